@@ -0,0 +1,602 @@
+// Archivage d'une année scolaire clôturée dans un fichier SQLite autonome, en lecture seule.
+// L'archive reprend le schéma de la base (sans triggers) pour que les requêtes du
+// frontend (bulletins, palmarès) puissent y être rejouées telles quelles.
+
+use crate::backup::{self, BackupEntry, BackupKind};
+use crate::db::DbPool;
+use crate::encryption;
+use crate::query;
+use chrono::Local;
+use log::{error, info};
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+const ARCHIVE_DIR: &str = "archives";
+
+// Tables copiées et filtre appliqué (?1 = id de l'année archivée, "1" = table entière).
+// Les tables de référence passent en premier pour que les clés étrangères soient satisfaites.
+const ARCHIVED_TABLES: [(&str, &str); 17] = [
+    // Données de référence nécessaires à la réimpression
+    ("domains", "1"),
+    ("options", "1"),
+    ("schema_migrations", "1"),
+    // Identité de l'école et critères de délibération, sans mot de passe ni licence
+    (
+        "settings",
+        "key LIKE 'school\\_%' ESCAPE '\\' OR key LIKE 'delib\\_%' ESCAPE '\\'",
+    ),
+    ("academic_years", "id = ?1"),
+    ("periods", "academic_year_id = ?1"),
+    ("deliberation_rule_sets", "academic_year_id = ?1"),
+    ("classes", "academic_year_id = ?1"),
+    (
+        "students",
+        "class_id IN (SELECT id FROM main.classes WHERE academic_year_id = ?1)",
+    ),
+    (
+        "subjects",
+        "class_id IN (SELECT id FROM main.classes WHERE academic_year_id = ?1)",
+    ),
+    (
+        "subject_period_max",
+        "subject_id IN (SELECT s.id FROM main.subjects s JOIN main.classes c ON s.class_id = c.id WHERE c.academic_year_id = ?1)",
+    ),
+    (
+        "evaluations",
+        "subject_id IN (SELECT s.id FROM main.subjects s JOIN main.classes c ON s.class_id = c.id WHERE c.academic_year_id = ?1)",
+    ),
+    (
+        "evaluation_scores",
+        "evaluation_id IN (SELECT e.id FROM main.evaluations e JOIN main.subjects s ON e.subject_id = s.id JOIN main.classes c ON s.class_id = c.id WHERE c.academic_year_id = ?1)",
+    ),
+    (
+        "grades",
+        "student_id IN (SELECT s.id FROM main.students s JOIN main.classes c ON s.class_id = c.id WHERE c.academic_year_id = ?1)",
+    ),
+    (
+        "repechages",
+        "student_id IN (SELECT s.id FROM main.students s JOIN main.classes c ON s.class_id = c.id WHERE c.academic_year_id = ?1)",
+    ),
+    ("notes", "academic_year_id = ?1"),
+    (
+        "custom_sorts",
+        "class_id IN (SELECT id FROM main.classes WHERE academic_year_id = ?1)",
+    ),
+];
+
+// Migration 18 : années retirées de la base locale. Le cloud les renvoie à chaque pull
+// tant qu'elles n'y ont pas été supprimées ; process_pull_data les ignore.
+pub fn create_schema(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS archived_years (
+            academic_year_id INTEGER PRIMARY KEY,
+            server_id TEXT,
+            name TEXT NOT NULL,
+            archived_at TEXT DEFAULT (datetime('now'))
+        );",
+    )
+}
+
+// Identifiants local et cloud des années retirées
+pub fn archived_years(conn: &Connection) -> Result<(HashSet<i64>, HashSet<String>), String> {
+    let mut stmt = conn
+        .prepare("SELECT academic_year_id, server_id FROM archived_years")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, Option<String>>(1)?))
+        })
+        .map_err(|e| e.to_string())?;
+    let mut ids = HashSet::new();
+    let mut server_ids = HashSet::new();
+    for row in rows {
+        let (id, server_id) = row.map_err(|e| e.to_string())?;
+        ids.insert(id);
+        server_ids.extend(server_id);
+    }
+    Ok((ids, server_ids))
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TableCount {
+    pub table: String,
+    pub rows: i64,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveResult {
+    pub file_name: String,
+    pub year_name: String,
+    pub counts: Vec<TableCount>,
+    pub removed_from_live: bool,
+    pub deleted_from_cloud: bool,
+    // Sauvegarde prise juste avant le retrait de l'année
+    pub backup: Option<BackupEntry>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveClass {
+    pub id: i64,
+    pub name: String,
+    pub students: i64,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveSummary {
+    pub file_name: String,
+    pub year_id: i64,
+    pub year_name: String,
+    pub start_date: String,
+    pub end_date: String,
+    pub archived_at: Option<String>,
+    pub size_bytes: u64,
+    pub classes: Vec<ArchiveClass>,
+}
+
+pub fn archives_dir(db_path: &Path) -> PathBuf {
+    let dir = db_path
+        .parent()
+        .map(|p| p.join(ARCHIVE_DIR))
+        .unwrap_or_else(|| PathBuf::from(ARCHIVE_DIR));
+    std::fs::create_dir_all(&dir).ok();
+    dir
+}
+
+fn archive_file_name(year_name: &str) -> String {
+    let slug: String = year_name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    format!(
+        "archive-{}-{}.db",
+        slug.trim_matches('-'),
+        Local::now().format("%Y%m%d-%H%M%S")
+    )
+}
+
+// Même garde-fou que pour les sauvegardes : pas de chemin hors du dossier archives
+fn resolve_archive(db_path: &Path, file_name: &str) -> Result<PathBuf, String> {
+    if !file_name.starts_with("archive-")
+        || !file_name.ends_with(".db")
+        || file_name.contains('/')
+        || file_name.contains('\\')
+    {
+        return Err("Nom d'archive invalide".to_string());
+    }
+    let path = archives_dir(db_path).join(file_name);
+    if !path.exists() {
+        return Err("Archive introuvable".to_string());
+    }
+    Ok(path)
+}
+
+// Seuls les filtres qui portent sur l'année attendent un paramètre
+fn year_params<'a>(condition: &str, year_id: &'a i64) -> Vec<&'a dyn rusqlite::ToSql> {
+    if condition.contains("?1") {
+        vec![year_id as &dyn rusqlite::ToSql]
+    } else {
+        Vec::new()
+    }
+}
+
+fn count_rows(conn: &Connection, schema: &str, year_id: i64) -> Result<Vec<TableCount>, String> {
+    ARCHIVED_TABLES
+        .iter()
+        .map(|(table, condition)| {
+            let sql = if schema == "main" {
+                format!("SELECT COUNT(*) FROM main.{table} WHERE {condition}")
+            } else {
+                format!("SELECT COUNT(*) FROM {schema}.{table}")
+            };
+            let bound = if schema == "main" {
+                year_params(condition, &year_id)
+            } else {
+                Vec::new()
+            };
+            conn.query_row(&sql, bound.as_slice(), |row| row.get(0))
+                .map(|rows| TableCount {
+                    table: table.to_string(),
+                    rows,
+                })
+                .map_err(|e| e.to_string())
+        })
+        .collect()
+}
+
+// `remove` retire l'année de la base locale uniquement ; `delete_from_cloud` (à confirmer
+// par l'utilisateur) propage en plus ces suppressions au cloud à la prochaine synchro.
+pub fn export_year(
+    conn: &mut Connection,
+    db_path: &Path,
+    year_id: i64,
+    remove: bool,
+    delete_from_cloud: bool,
+) -> Result<ArchiveResult, String> {
+    if delete_from_cloud && !remove {
+        return Err(
+            "La suppression dans le cloud suppose de retirer l'année de la base".to_string(),
+        );
+    }
+    let (year_name, is_active): (String, bool) = conn
+        .query_row(
+            "SELECT name, COALESCE(is_active, 0) FROM academic_years WHERE id = ?",
+            params![year_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Année scolaire introuvable".to_string())?;
+    if is_active {
+        return Err("L'année active ne peut pas être archivée".to_string());
+    }
+
+    let file_name = archive_file_name(&year_name);
+    let path = archives_dir(db_path).join(&file_name);
+    info!("Archiving year {} into {}", year_name, path.display());
+
+    // Sans clause KEY, SQLCipher chiffre la base attachée avec la clé de la base principale
+    conn.execute(
+        "ATTACH DATABASE ? AS archive",
+        params![path.to_string_lossy().to_string()],
+    )
+    .map_err(|e| e.to_string())?;
+
+    let copied = copy_year(conn, year_id);
+    let counts = copied.and_then(|_| {
+        let archived = verify_counts(conn, year_id)?;
+        conn.execute(
+            "INSERT INTO archive.settings (key, value) VALUES ('archived_at', datetime('now'))",
+            [],
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(archived)
+    });
+    conn.execute("DETACH DATABASE archive", [])
+        .map_err(|e| e.to_string())?;
+
+    let counts = match counts {
+        Ok(counts) => counts,
+        Err(e) => {
+            error!("Archive failed: {}", e);
+            std::fs::remove_file(&path).ok();
+            return Err(format!("Échec de l'archivage : {}", e));
+        }
+    };
+
+    if let Ok(metadata) = std::fs::metadata(&path) {
+        let mut permissions = metadata.permissions();
+        permissions.set_readonly(true);
+        std::fs::set_permissions(&path, permissions).ok();
+    }
+
+    let backup = if remove {
+        let backup = backup::create_backup(db_path, BackupKind::PreArchive)?;
+        remove_year(conn, year_id, delete_from_cloud)?;
+        Some(backup)
+    } else {
+        None
+    };
+
+    Ok(ArchiveResult {
+        file_name,
+        year_name,
+        counts,
+        removed_from_live: remove,
+        deleted_from_cloud: delete_from_cloud,
+        backup,
+    })
+}
+
+// Compare, table par table, les lignes de l'année dans la base et dans l'archive attachée
+fn verify_counts(conn: &Connection, year_id: i64) -> Result<Vec<TableCount>, String> {
+    let expected = count_rows(conn, "main", year_id)?;
+    let archived = count_rows(conn, "archive", year_id)?;
+    if expected
+        .iter()
+        .zip(&archived)
+        .any(|(e, a)| e.rows != a.rows)
+    {
+        return Err("Le contenu de l'archive ne correspond pas à la base".to_string());
+    }
+    Ok(archived)
+}
+
+fn copy_year(conn: &mut Connection, year_id: i64) -> Result<(), String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    for (table, condition) in ARCHIVED_TABLES {
+        let create_sql: String = tx
+            .query_row(
+                "SELECT sql FROM main.sqlite_master WHERE type = 'table' AND name = ?",
+                params![table],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        let create_sql = create_sql.replacen("CREATE TABLE ", "CREATE TABLE archive.", 1);
+        tx.execute(&create_sql, []).map_err(|e| e.to_string())?;
+        tx.execute(
+            &format!("INSERT INTO archive.{table} SELECT * FROM main.{table} WHERE {condition}"),
+            year_params(condition, &year_id).as_slice(),
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| e.to_string())
+}
+
+// Les suppressions passent par les triggers habituels ; les entrées de sync_deletions
+// qu'ils ajoutent ne sont gardées que si le retrait doit aussi être fait dans le cloud.
+// L'année est notée dans archived_years pour que le pull ne la réimporte pas.
+fn remove_year(conn: &mut Connection, year_id: i64, delete_from_cloud: bool) -> Result<(), String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let last_deletion: i64 = tx
+        .query_row(
+            "SELECT COALESCE(MAX(id), 0) FROM sync_deletions",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    tx.execute(
+        "INSERT OR REPLACE INTO archived_years (academic_year_id, server_id, name)
+         SELECT id, server_id, name FROM academic_years WHERE id = ?",
+        params![year_id],
+    )
+    .map_err(|e| e.to_string())?;
+    // Les notes ne sont pas supprimées en cascade (ON DELETE SET NULL)
+    tx.execute(
+        "DELETE FROM notes WHERE academic_year_id = ?",
+        params![year_id],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM academic_years WHERE id = ?", params![year_id])
+        .map_err(|e| e.to_string())?;
+    if !delete_from_cloud {
+        tx.execute(
+            "DELETE FROM sync_deletions WHERE id > ?",
+            params![last_deletion],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    info!(
+        "Archived year {} removed from live database (cloud: {})",
+        year_id, delete_from_cloud
+    );
+    Ok(())
+}
+
+fn read_summary(path: &Path, file_name: &str) -> Result<ArchiveSummary, String> {
+    let conn = encryption::open_read_only(path)?;
+    let (year_id, year_name, start_date, end_date) = conn
+        .query_row(
+            "SELECT id, name, start_date, end_date FROM academic_years LIMIT 1",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .map_err(|e| format!("Archive illisible : {}", e))?;
+    let archived_at: Option<String> = conn
+        .query_row(
+            "SELECT value FROM settings WHERE key = 'archived_at'",
+            [],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let classes = conn
+        .prepare(
+            "SELECT c.id, c.name, (SELECT COUNT(*) FROM students s WHERE s.class_id = c.id)
+             FROM classes c ORDER BY c.level, c.option, c.section",
+        )
+        .and_then(|mut stmt| {
+            stmt.query_map([], |row| {
+                Ok(ArchiveClass {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    students: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()
+        })
+        .map_err(|e| e.to_string())?;
+
+    Ok(ArchiveSummary {
+        file_name: file_name.to_string(),
+        year_id,
+        year_name,
+        start_date,
+        end_date,
+        archived_at,
+        size_bytes: std::fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+        classes,
+    })
+}
+
+pub fn list_archives(db_path: &Path) -> Result<Vec<ArchiveSummary>, String> {
+    let dir = archives_dir(db_path);
+    let mut names: Vec<String> = std::fs::read_dir(&dir)
+        .map_err(|e| e.to_string())?
+        .filter_map(Result::ok)
+        .filter_map(|e| e.file_name().to_str().map(|s| s.to_string()))
+        .filter(|name| name.starts_with("archive-") && name.ends_with(".db"))
+        .collect();
+    names.sort();
+
+    let mut archives = Vec::new();
+    for name in names {
+        match read_summary(&dir.join(&name), &name) {
+            Ok(summary) => archives.push(summary),
+            Err(e) => error!("Skipping archive {}: {}", name, e),
+        }
+    }
+    Ok(archives)
+}
+
+#[tauri::command]
+pub async fn archive_year(
+    pool: tauri::State<'_, DbPool>,
+    year_id: i64,
+    remove: Option<bool>,
+    delete_from_cloud: Option<bool>,
+) -> Result<ArchiveResult, String> {
+    let db_path = pool.path().to_path_buf();
+    pool.run(move |conn| {
+        export_year(
+            conn,
+            &db_path,
+            year_id,
+            remove.unwrap_or(false),
+            delete_from_cloud.unwrap_or(false),
+        )
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn archive_list(pool: tauri::State<'_, DbPool>) -> Result<Vec<ArchiveSummary>, String> {
+    pool.run_with_path(list_archives).await
+}
+
+#[tauri::command]
+pub async fn archive_open(
+    pool: tauri::State<'_, DbPool>,
+    file_name: String,
+) -> Result<ArchiveSummary, String> {
+    pool.run_with_path(move |path| {
+        let archive = resolve_archive(path, &file_name)?;
+        read_summary(&archive, &file_name)
+    })
+    .await
+}
+
+// Requête en lecture seule sur une archive (même format de résultat que db_select)
+#[tauri::command]
+pub async fn archive_select(
+    pool: tauri::State<'_, DbPool>,
+    file_name: String,
+    query: String,
+    values: Option<Vec<serde_json::Value>>,
+) -> Result<Vec<serde_json::Map<String, serde_json::Value>>, String> {
+    pool.run_with_path(move |path| {
+        let archive = resolve_archive(path, &file_name)?;
+        let conn = encryption::open_read_only(&archive)?;
+        query::select_rows(&conn, &query, values.unwrap_or_default())
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::TestDb;
+
+    fn count(conn: &Connection, sql: &str) -> i64 {
+        conn.query_row(sql, [], |row| row.get(0)).unwrap()
+    }
+
+    fn two_years() -> TestDb {
+        let db = TestDb::open();
+        db.conn()
+            .execute_batch(
+                "INSERT INTO academic_years (id, name, start_date, end_date, is_active) VALUES
+                   (1, '2023-2024', '2023-09-01', '2024-07-01', 0), (2, '2024-2025', '2024-09-01', '2025-07-01', 1);
+                 INSERT INTO classes (id, name, level, option, section, academic_year_id, server_id) VALUES
+                   (1, '7ème A', '7ème', 'EB', 'A', 1, 'c1'), (2, '8ème A', '8ème', 'EB', 'A', 2, 'c2');
+                 INSERT INTO students (id, first_name, last_name, gender, class_id, server_id) VALUES
+                   (1, 'Jean', 'Kabila', 'M', 1, 's1'), (2, 'Marie', 'Mbuyi', 'F', 2, 's2');
+                 INSERT INTO subjects (id, name, class_id, server_id) VALUES (1, 'Maths', 1, 'm1');
+                 INSERT INTO grades (student_id, subject_id, period, value, server_id) VALUES (1, 1, 'P1', 5, 'g1');
+                 INSERT INTO settings (key, value) VALUES ('school_name', 'Institut Umoja'), ('license_key', 'secret');",
+            )
+            .unwrap();
+        db
+    }
+
+    #[test]
+    fn copie_de_l_annee_et_verification_des_comptes() {
+        let db = two_years();
+        let mut conn = db.conn();
+        let path = db.dir.join("archive-test.db");
+        conn.execute(
+            "ATTACH DATABASE ? AS archive",
+            params![path.to_string_lossy().to_string()],
+        )
+        .unwrap();
+        copy_year(&mut conn, 1).unwrap();
+
+        let counts = verify_counts(&conn, 1).unwrap();
+        let rows = |table: &str| counts.iter().find(|c| c.table == table).unwrap().rows;
+        assert_eq!(
+            (rows("classes"), rows("students"), rows("grades")),
+            (1, 1, 1)
+        );
+        assert_eq!(rows("academic_years"), 1);
+        assert_eq!(rows("settings"), 1);
+
+        // Une ligne manquante dans l'archive fait échouer la vérification
+        conn.execute("DELETE FROM archive.grades", []).unwrap();
+        assert!(verify_counts(&conn, 1).is_err());
+        conn.execute("DETACH DATABASE archive", []).unwrap();
+    }
+
+    #[test]
+    fn archive_sans_retrait() {
+        let db = two_years();
+        let mut conn = db.conn();
+        assert!(export_year(&mut conn, &db.path, 2, false, false).is_err());
+        assert!(export_year(&mut conn, &db.path, 1, false, true).is_err());
+
+        let result = export_year(&mut conn, &db.path, 1, false, false).unwrap();
+        assert!(!result.removed_from_live);
+        assert!(result.backup.is_none());
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM grades"), 1);
+
+        let archives = list_archives(&db.path).unwrap();
+        assert_eq!(archives.len(), 1);
+        assert_eq!(archives[0].year_name, "2023-2024");
+        assert_eq!(archives[0].classes.len(), 1);
+        assert!(archives[0].archived_at.is_some());
+        let archive =
+            encryption::open_read_only(&resolve_archive(&db.path, &result.file_name).unwrap())
+                .unwrap();
+        let rows = query::select_rows(
+            &archive,
+            "SELECT value FROM grades WHERE period = ?",
+            vec![serde_json::json!("P1")],
+        )
+        .unwrap();
+        assert_eq!(rows.len(), 1);
+        assert!(archive.execute("DELETE FROM grades", []).is_err());
+    }
+
+    #[test]
+    fn retrait_local_avec_sauvegarde() {
+        let db = two_years();
+        let mut conn = db.conn();
+        let result = export_year(&mut conn, &db.path, 1, true, false).unwrap();
+        assert!(result.removed_from_live && !result.deleted_from_cloud);
+        let backup = result.backup.unwrap();
+        assert_eq!(backup.kind, "pre-archive");
+        assert!(backup::backups_dir(&db.path)
+            .join(&backup.file_name)
+            .exists());
+
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM students"), 1);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM grades"), 0);
+        // Le cloud garde l'année
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM sync_deletions"), 0);
+    }
+
+    #[test]
+    fn retrait_confirme_dans_le_cloud() {
+        let db = two_years();
+        let mut conn = db.conn();
+        let result = export_year(&mut conn, &db.path, 1, true, true).unwrap();
+        assert!(result.deleted_from_cloud);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM classes"), 1);
+        assert!(count(&conn, "SELECT COUNT(*) FROM sync_deletions") >= 4);
+    }
+}
@@ -0,0 +1,529 @@
+// Sauvegardes automatiques de la base de données
+// Les copies sont prises à chaud avec VACUUM INTO (instantané cohérent) et conservées
+// dans <app_data>/backups avec une rotation quotidienne / hebdomadaire.
+
+use crate::db::{self, DbPool};
+use crate::encryption;
+use chrono::{Datelike, Local, NaiveDateTime};
+use log::{error, info};
+use rusqlite::backup::Backup;
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const BACKUP_DIR: &str = "backups";
+const DEFAULT_KEEP_DAILY: usize = 7;
+const DEFAULT_KEEP_WEEKLY: usize = 4;
+// Nombre de copies conservées pour les sauvegardes ponctuelles (avant sync, restauration...)
+const KEEP_EVENT_BACKUPS: usize = 5;
+const SCHEDULER_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BackupKind {
+    Daily,
+    Weekly,
+    Manual,
+    PreSync,
+    PreRestore,
+    PreMigration,
+    PreRepair,
+    PreRollover,
+    PreArchive,
+}
+
+impl BackupKind {
+    const ALL: [BackupKind; 9] = [
+        BackupKind::Daily,
+        BackupKind::Weekly,
+        BackupKind::Manual,
+        BackupKind::PreSync,
+        BackupKind::PreRestore,
+        BackupKind::PreMigration,
+        BackupKind::PreRepair,
+        BackupKind::PreRollover,
+        BackupKind::PreArchive,
+    ];
+
+    fn prefix(self) -> &'static str {
+        match self {
+            BackupKind::Daily => "daily",
+            BackupKind::Weekly => "weekly",
+            BackupKind::Manual => "manual",
+            BackupKind::PreSync => "pre-sync",
+            BackupKind::PreRestore => "pre-restore",
+            BackupKind::PreMigration => "pre-migration",
+            BackupKind::PreRepair => "pre-repair",
+            BackupKind::PreRollover => "pre-rollover",
+            BackupKind::PreArchive => "pre-archive",
+        }
+    }
+
+    fn from_file_name(name: &str) -> Option<(BackupKind, NaiveDateTime)> {
+        let stem = name.strip_suffix(".db")?;
+        // Les préfixes composés ("pre-sync") sont testés avant les simples
+        let mut kinds = Self::ALL.to_vec();
+        kinds.sort_by_key(|k| std::cmp::Reverse(k.prefix().len()));
+        for kind in kinds {
+            if let Some(ts) = stem
+                .strip_prefix(kind.prefix())
+                .and_then(|rest| rest.strip_prefix('-'))
+            {
+                if let Ok(date) = NaiveDateTime::parse_from_str(ts, TIMESTAMP_FORMAT) {
+                    return Some((kind, date));
+                }
+            }
+        }
+        None
+    }
+
+    fn keep_count(self, conn: &Connection) -> usize {
+        match self {
+            BackupKind::Daily => read_keep_setting(conn, "backup_keep_daily", DEFAULT_KEEP_DAILY),
+            BackupKind::Weekly => {
+                read_keep_setting(conn, "backup_keep_weekly", DEFAULT_KEEP_WEEKLY)
+            }
+            _ => KEEP_EVENT_BACKUPS,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupEntry {
+    pub file_name: String,
+    pub kind: String,
+    pub created_at: String,
+    pub size_bytes: u64,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupVerification {
+    pub file_name: String,
+    pub valid: bool,
+    pub integrity: String,
+    pub schema_version: i64,
+    pub students: i64,
+    pub grades: i64,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreResult {
+    pub restored: String,
+    pub safety_backup: BackupEntry,
+}
+
+fn read_keep_setting(conn: &Connection, key: &str, default: usize) -> usize {
+    conn.query_row(
+        "SELECT value FROM settings WHERE key = ?",
+        params![key],
+        |row| row.get::<_, String>(0),
+    )
+    .ok()
+    .and_then(|v| v.trim().parse::<usize>().ok())
+    .filter(|n| *n > 0)
+    .unwrap_or(default)
+}
+
+pub fn backups_dir(db_path: &Path) -> PathBuf {
+    let dir = db_path
+        .parent()
+        .map(|p| p.join(BACKUP_DIR))
+        .unwrap_or_else(|| PathBuf::from(BACKUP_DIR));
+    std::fs::create_dir_all(&dir).ok();
+    dir
+}
+
+fn entry_for(path: &Path) -> Option<BackupEntry> {
+    let file_name = path.file_name()?.to_str()?.to_string();
+    let (kind, created_at) = BackupKind::from_file_name(&file_name)?;
+    let size_bytes = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    Some(BackupEntry {
+        file_name,
+        kind: kind.prefix().to_string(),
+        created_at: created_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+        size_bytes,
+    })
+}
+
+pub fn list_backups(db_path: &Path) -> Result<Vec<BackupEntry>, String> {
+    let dir = backups_dir(db_path);
+    let mut entries: Vec<BackupEntry> = std::fs::read_dir(&dir)
+        .map_err(|e| e.to_string())?
+        .filter_map(Result::ok)
+        .filter_map(|e| entry_for(&e.path()))
+        .collect();
+    // Du plus récent au plus ancien
+    entries.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    Ok(entries)
+}
+
+// Crée un instantané cohérent de la base, même si d'autres connexions sont ouvertes.
+pub fn create_backup(db_path: &Path, kind: BackupKind) -> Result<BackupEntry, String> {
+    let conn = db::open_connection(db_path)?;
+
+    let mut now = Local::now().naive_local();
+    let dir = backups_dir(db_path);
+    // Plusieurs sauvegardes dans la même seconde : on décale après la plus récente du même
+    // type, sans quoi la nouvelle passerait pour la plus ancienne et serait aussitôt supprimée
+    let latest = std::fs::read_dir(&dir)
+        .map_err(|e| e.to_string())?
+        .filter_map(Result::ok)
+        .filter_map(|e| BackupKind::from_file_name(e.file_name().to_str()?))
+        .filter(|(k, _)| *k == kind)
+        .map(|(_, date)| date)
+        .max();
+    if let Some(next) = latest.map(|date| date + chrono::Duration::seconds(1)) {
+        now = now.max(next);
+    }
+    let target = dir.join(format!(
+        "{}-{}.db",
+        kind.prefix(),
+        now.format(TIMESTAMP_FORMAT)
+    ));
+
+    info!("Creating {} backup: {}", kind.prefix(), target.display());
+    conn.execute(
+        "VACUUM INTO ?",
+        params![target.to_string_lossy().to_string()],
+    )
+    .map_err(|e| {
+        error!("Backup failed: {}", e);
+        format!("Échec de la sauvegarde : {}", e)
+    })?;
+
+    rotate_backups(&conn, db_path, kind)?;
+
+    entry_for(&target).ok_or_else(|| "Sauvegarde introuvable après création".to_string())
+}
+
+fn rotate_backups(conn: &Connection, db_path: &Path, kind: BackupKind) -> Result<(), String> {
+    let keep = kind.keep_count(conn);
+    let dir = backups_dir(db_path);
+    let prefix = kind.prefix();
+
+    let to_remove: Vec<BackupEntry> = list_backups(db_path)?
+        .into_iter()
+        .filter(|e| e.kind == prefix)
+        .skip(keep)
+        .collect();
+
+    for entry in to_remove {
+        info!("Removing old backup {}", entry.file_name);
+        if let Err(e) = std::fs::remove_file(dir.join(&entry.file_name)) {
+            error!("Failed to remove backup {}: {}", entry.file_name, e);
+        }
+    }
+    Ok(())
+}
+
+// Supprime toutes les sauvegardes (utilisé quand elles deviennent inutilisables ou sensibles)
+pub fn purge_backups(db_path: &Path) -> Result<usize, String> {
+    let dir = backups_dir(db_path);
+    let mut removed = 0;
+    for entry in list_backups(db_path)? {
+        match std::fs::remove_file(dir.join(&entry.file_name)) {
+            Ok(()) => removed += 1,
+            Err(e) => error!("Failed to remove backup {}: {}", entry.file_name, e),
+        }
+    }
+    Ok(removed)
+}
+
+// Sauvegarde quotidienne et hebdomadaire si elles n'ont pas encore été faites.
+pub fn run_scheduled_backups(db_path: &Path) -> Result<(), String> {
+    // Base chiffrée pas encore déverrouillée : rien n'est lisible pour l'instant
+    if encryption::is_enabled(db_path) && !encryption::is_unlocked() {
+        return Ok(());
+    }
+    let today = Local::now().date_naive();
+    let entries = list_backups(db_path)?;
+
+    let has_daily = entries.iter().any(|e| {
+        e.kind == BackupKind::Daily.prefix()
+            && e.created_at
+                .starts_with(&today.format("%Y-%m-%d").to_string())
+    });
+    if !has_daily {
+        create_backup(db_path, BackupKind::Daily)?;
+    }
+
+    let week = today.iso_week();
+    let has_weekly = entries.iter().any(|e| {
+        e.kind == BackupKind::Weekly.prefix()
+            && NaiveDateTime::parse_from_str(&e.created_at, "%Y-%m-%d %H:%M:%S")
+                .map(|d| d.date().iso_week() == week)
+                .unwrap_or(false)
+    });
+    if !has_weekly {
+        create_backup(db_path, BackupKind::Weekly)?;
+    }
+    Ok(())
+}
+
+pub fn start_scheduler(db_path: PathBuf) {
+    std::thread::spawn(move || loop {
+        if let Err(e) = run_scheduled_backups(&db_path) {
+            error!("Scheduled backup failed: {}", e);
+        }
+        std::thread::sleep(SCHEDULER_INTERVAL);
+    });
+}
+
+// Résout un nom de fichier de sauvegarde en refusant tout chemin hors du dossier backups
+fn resolve_backup(db_path: &Path, file_name: &str) -> Result<PathBuf, String> {
+    if BackupKind::from_file_name(file_name).is_none()
+        || file_name.contains('/')
+        || file_name.contains('\\')
+    {
+        return Err("Nom de sauvegarde invalide".to_string());
+    }
+    let path = backups_dir(db_path).join(file_name);
+    if !path.exists() {
+        return Err("Sauvegarde introuvable".to_string());
+    }
+    Ok(path)
+}
+
+pub fn verify_backup(db_path: &Path, file_name: &str) -> Result<BackupVerification, String> {
+    let path = resolve_backup(db_path, file_name)?;
+    let conn = encryption::open_read_only(&path)?;
+
+    let integrity: String = conn
+        .query_row("PRAGMA integrity_check", [], |row| row.get(0))
+        .unwrap_or_else(|e| e.to_string());
+    let schema_version: i64 = conn
+        .query_row(
+            "SELECT COALESCE(MAX(version), 0) FROM schema_migrations",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap_or(0);
+    let students: i64 = conn
+        .query_row("SELECT COUNT(*) FROM students", [], |row| row.get(0))
+        .unwrap_or(-1);
+    let grades: i64 = conn
+        .query_row("SELECT COUNT(*) FROM grades", [], |row| row.get(0))
+        .unwrap_or(-1);
+
+    Ok(BackupVerification {
+        file_name: file_name.to_string(),
+        valid: integrity == "ok" && students >= 0 && grades >= 0,
+        integrity,
+        schema_version,
+        students,
+        grades,
+    })
+}
+
+// Restaure une sauvegarde après avoir pris un instantané de l'état actuel. Le pool reste
+// fermé jusqu'à la fin de la remise à niveau du schéma : aucune connexion ne lit ni n'écrit
+// pendant que le fichier est remplacé, et aucune ne garde l'ancien contenu en cache.
+pub fn restore_backup(pool: &DbPool, file_name: &str) -> Result<RestoreResult, String> {
+    let db_path = pool.path();
+    let verification = verify_backup(db_path, file_name)?;
+    if !verification.valid {
+        return Err(format!(
+            "Sauvegarde corrompue, restauration annulée : {}",
+            verification.integrity
+        ));
+    }
+    let source = resolve_backup(db_path, file_name)?;
+
+    let _exclusive = pool.exclusive()?;
+    let safety_backup = create_backup(db_path, BackupKind::PreRestore)?;
+
+    info!("Restoring backup {}...", file_name);
+    {
+        let source_conn = encryption::open_read_only(&source)?;
+        let mut conn = db::open_connection(db_path)?;
+        Backup::new(&source_conn, &mut conn)
+            .and_then(|b| b.run_to_completion(100, Duration::ZERO, None))
+            .map_err(|e| format!("Échec de la restauration : {}", e))?;
+    }
+
+    // Une sauvegarde ancienne peut être à une version de schéma antérieure
+    db::initialize_db(db_path)?;
+
+    info!("Backup {} restored", file_name);
+    Ok(RestoreResult {
+        restored: file_name.to_string(),
+        safety_backup,
+    })
+}
+
+#[tauri::command]
+pub async fn backup_list(pool: tauri::State<'_, DbPool>) -> Result<Vec<BackupEntry>, String> {
+    pool.run_with_path(list_backups).await
+}
+
+#[tauri::command]
+pub async fn backup_create(pool: tauri::State<'_, DbPool>) -> Result<BackupEntry, String> {
+    pool.run_with_path(|path| create_backup(path, BackupKind::Manual))
+        .await
+}
+
+#[tauri::command]
+pub async fn backup_verify(
+    pool: tauri::State<'_, DbPool>,
+    file_name: String,
+) -> Result<BackupVerification, String> {
+    pool.run_with_path(move |path| verify_backup(path, &file_name))
+        .await
+}
+
+#[tauri::command]
+pub async fn backup_restore(
+    pool: tauri::State<'_, DbPool>,
+    file_name: String,
+) -> Result<RestoreResult, String> {
+    let pool = pool.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || restore_backup(&pool, &file_name))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::TestDb;
+    use std::sync::mpsc;
+    use std::thread;
+
+    fn count(conn: &Connection, sql: &str) -> i64 {
+        conn.query_row(sql, [], |row| row.get(0)).unwrap()
+    }
+
+    fn files_of(db: &TestDb, kind: BackupKind) -> Vec<String> {
+        list_backups(&db.path)
+            .unwrap()
+            .into_iter()
+            .filter(|e| e.kind == kind.prefix())
+            .map(|e| e.file_name)
+            .collect()
+    }
+
+    #[test]
+    fn rotation_par_type() {
+        let db = TestDb::open();
+        db.conn()
+            .execute(
+                "INSERT OR REPLACE INTO settings (key, value) VALUES ('backup_keep_daily', '2')",
+                [],
+            )
+            .unwrap();
+
+        let created: Vec<String> = (0..7)
+            .map(|_| {
+                create_backup(&db.path, BackupKind::Manual)
+                    .unwrap()
+                    .file_name
+            })
+            .collect();
+        for _ in 0..3 {
+            create_backup(&db.path, BackupKind::Daily).unwrap();
+        }
+        create_backup(&db.path, BackupKind::PreSync).unwrap();
+
+        // Les plus récentes sont gardées, chaque type selon sa propre limite
+        let mut manual = files_of(&db, BackupKind::Manual);
+        manual.reverse();
+        assert_eq!(manual, created[2..]);
+        assert_eq!(files_of(&db, BackupKind::Daily).len(), 2);
+        assert_eq!(files_of(&db, BackupKind::PreSync).len(), 1);
+
+        // Les sauvegardes planifiées du jour existent déjà ou sont créées une seule fois
+        run_scheduled_backups(&db.path).unwrap();
+        run_scheduled_backups(&db.path).unwrap();
+        assert_eq!(files_of(&db, BackupKind::Daily).len(), 2);
+        assert_eq!(files_of(&db, BackupKind::Weekly).len(), 1);
+    }
+
+    #[test]
+    fn verification_des_sauvegardes() {
+        let db = TestDb::open();
+        db.conn()
+            .execute_batch(
+                "INSERT INTO academic_years (id, name, start_date, end_date, is_active) VALUES (1, '2024-2025', '2024-09-01', '2025-07-01', 1);
+                 INSERT INTO classes (id, name, level, option, section, academic_year_id) VALUES (1, '2ème ETRO', '2ème', 'ELECTRONIQUE', 'A', 1);
+                 INSERT INTO students (id, first_name, last_name, gender, class_id) VALUES (1, 'Jean', 'Kabila', 'M', 1);",
+            )
+            .unwrap();
+        let entry = create_backup(&db.path, BackupKind::Manual).unwrap();
+
+        let verification = verify_backup(&db.path, &entry.file_name).unwrap();
+        assert!(verification.valid, "{:?}", verification);
+        assert_eq!(verification.students, 1);
+        assert!(verification.schema_version > 0);
+
+        // Fichier abîmé : signalé invalide et jamais restauré
+        let corrupt = "manual-20200101-000000.db";
+        std::fs::write(backups_dir(&db.path).join(corrupt), b"pas une base sqlite").unwrap();
+        assert!(!verify_backup(&db.path, corrupt).unwrap().valid);
+        assert!(restore_backup(&DbPool::new(db.path.clone()), corrupt).is_err());
+
+        // Seuls les noms de sauvegardes du dossier sont acceptés
+        assert!(verify_backup(&db.path, "../ecole.db").is_err());
+        assert!(verify_backup(&db.path, "manual-20200101-000001.db").is_err());
+    }
+
+    #[test]
+    fn restauration_avec_instantane_de_securite() {
+        let db = TestDb::open();
+        let pool = DbPool::new(db.path.clone());
+        let conn = db.conn();
+        conn.execute(
+            "INSERT INTO academic_years (id, name, start_date, end_date) VALUES (1, '2024-2025', '2024-09-01', '2025-07-01')",
+            [],
+        )
+        .unwrap();
+        let entry = create_backup(&db.path, BackupKind::Manual).unwrap();
+        conn.execute("DELETE FROM academic_years", []).unwrap();
+
+        let result = restore_backup(&pool, &entry.file_name).unwrap();
+        assert_eq!(result.restored, entry.file_name);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM academic_years"), 1);
+
+        // L'état d'avant la restauration reste récupérable
+        assert_eq!(result.safety_backup.kind, BackupKind::PreRestore.prefix());
+        restore_backup(&pool, &result.safety_backup.file_name).unwrap();
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM academic_years"), 0);
+    }
+
+    #[test]
+    fn restauration_apres_les_connexions_en_cours() {
+        let db = TestDb::open();
+        let pool = DbPool::new(db.path.clone());
+        let entry = create_backup(&db.path, BackupKind::Manual).unwrap();
+        let borrowed = pool.get().unwrap();
+        borrowed
+            .execute(
+                "INSERT INTO academic_years (name, start_date, end_date) VALUES ('2024-2025', '2024-09-01', '2025-07-01')",
+                [],
+            )
+            .unwrap();
+
+        let (sender, receiver) = mpsc::channel();
+        let restoring = pool.clone();
+        let file_name = entry.file_name.clone();
+        let handle = thread::spawn(move || {
+            let result = restore_backup(&restoring, &file_name);
+            sender.send(()).unwrap();
+            result
+        });
+        // Tant que la connexion n'est pas rendue, le fichier n'est pas remplacé
+        assert!(receiver.recv_timeout(Duration::from_millis(300)).is_err());
+        assert_eq!(count(&borrowed, "SELECT COUNT(*) FROM academic_years"), 1);
+        drop(borrowed);
+        receiver.recv_timeout(Duration::from_secs(10)).unwrap();
+        handle.join().unwrap().unwrap();
+
+        assert_eq!(
+            count(&pool.get().unwrap(), "SELECT COUNT(*) FROM academic_years"),
+            0
+        );
+    }
+}
@@ -0,0 +1,702 @@
+// Calcul des bulletins côté Rust : totaux par période, par semestre et pour l'année,
+// maxima, pourcentages et places, avec les mêmes conventions que les bulletins du
+// renderer (BulletinHumanitesContent.tsx, BulletinPrimaireContent.tsx). Les cotes
+// manquantes sont signalées explicitement au lieu d'être comptées comme zéro.
+
+use crate::db::DbPool;
+use crate::deliberation::is_education_de_base;
+use crate::gradebook::{Gradebook, SubjectInfo, WeightingMode};
+use crate::ranking::{self, Candidate, IncompletePolicy, Rank};
+use log::info;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+pub const ANNUAL_KEY: &str = "TG";
+pub const OTHER_DOMAIN_LABEL: &str = "Autres matières";
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ColumnKind {
+    Period,
+    Semester,
+    Year,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BulletinColumn {
+    // Code de période, « S1 », « S2 »… ou « TG » pour l'année
+    pub key: String,
+    pub label: String,
+    pub kind: ColumnKind,
+    pub period_codes: Vec<String>,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Score {
+    // Somme des cotes présentes, None si aucune cote n'est encodée
+    pub points: Option<f64>,
+    // Somme de tous les maxima de la colonne, cotes présentes ou non
+    pub max: f64,
+    // Maxima des seules cotes encodées
+    pub graded_max: f64,
+    // Une cote au moins manque sur une période notée
+    pub missing: bool,
+    // Calculé seulement quand la colonne est complète
+    pub percentage: Option<f64>,
+    // Une cote estimée (prédiction appliquée) au moins entre dans les points
+    pub estimated: bool,
+}
+
+impl Score {
+    fn empty() -> Self {
+        Score {
+            points: None,
+            max: 0.0,
+            graded_max: 0.0,
+            missing: false,
+            percentage: None,
+            estimated: false,
+        }
+    }
+
+    fn add_grade(&mut self, value: Option<f64>, max: f64, estimated: bool) {
+        self.max += max;
+        match value {
+            Some(v) => {
+                self.points = Some(self.points.unwrap_or(0.0) + v);
+                self.graded_max += max;
+                self.estimated |= estimated;
+            }
+            None => self.missing = true,
+        }
+    }
+
+    fn add(&mut self, other: &Score, weight: f64) {
+        self.max += other.max * weight;
+        self.graded_max += other.graded_max * weight;
+        self.missing |= other.missing;
+        self.estimated |= other.estimated;
+        if let Some(v) = other.points {
+            self.points = Some(self.points.unwrap_or(0.0) + v * weight);
+        }
+    }
+
+    fn finish(mut self) -> Self {
+        self.percentage = match self.points {
+            Some(points) if !self.missing && self.max > 0.0 => {
+                Some(round2(points * 100.0 / self.max))
+            }
+            _ => None,
+        };
+        self
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SubjectLine {
+    pub subject_id: i64,
+    pub code: String,
+    pub name: String,
+    pub domain_id: Option<i64>,
+    pub sub_domain: String,
+    pub coefficient: Option<f64>,
+    // Poids du cours dans les totaux et sous-totaux (1 en pondération par points)
+    pub weight: f64,
+    // Une entrée par colonne, dans l'ordre de ClassBulletin::columns
+    pub scores: Vec<Score>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DomainGroup {
+    pub domain_id: Option<i64>,
+    pub name: String,
+    pub subject_ids: Vec<i64>,
+    pub scores: Vec<Score>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct StudentBulletin {
+    pub student_id: i64,
+    pub student_name: String,
+    pub abandoned: bool,
+    pub subjects: Vec<SubjectLine>,
+    // Regroupement par domaine, seulement pour l'éducation de base
+    pub domains: Vec<DomainGroup>,
+    pub totals: Vec<Score>,
+    // None quand l'élève n'est pas classé dans la colonne (abandon, aucune cote,
+    // cotes incomplètes selon ranking_incomplete_policy)
+    pub ranks: Vec<Option<Rank>>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassBulletin {
+    pub class_id: i64,
+    pub class_name: String,
+    pub level: String,
+    pub education_de_base: bool,
+    pub weighting: WeightingMode,
+    pub columns: Vec<BulletinColumn>,
+    pub students: Vec<StudentBulletin>,
+}
+
+fn round2(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+fn semester_label(semester: i64) -> String {
+    if semester == 1 {
+        "1er semestre".to_string()
+    } else {
+        format!("{}ème semestre", semester)
+    }
+}
+
+// Colonnes du bulletin : chaque période, puis le total de son semestre après la
+// dernière période du semestre, puis le total général
+pub fn columns(book: &Gradebook) -> Vec<BulletinColumn> {
+    let mut columns = Vec::new();
+    for (index, period) in book.periods.iter().enumerate() {
+        columns.push(BulletinColumn {
+            key: period.code.clone(),
+            label: period.label.clone(),
+            kind: ColumnKind::Period,
+            period_codes: vec![period.code.clone()],
+        });
+        let closes_semester =
+            book.periods.get(index + 1).map(|next| next.semester) != Some(period.semester);
+        if closes_semester {
+            columns.push(BulletinColumn {
+                key: format!("S{}", period.semester),
+                label: semester_label(period.semester),
+                kind: ColumnKind::Semester,
+                period_codes: book
+                    .periods
+                    .iter()
+                    .filter(|p| p.semester == period.semester)
+                    .map(|p| p.code.clone())
+                    .collect(),
+            });
+        }
+    }
+    columns.push(BulletinColumn {
+        key: ANNUAL_KEY.to_string(),
+        label: "Total général".to_string(),
+        kind: ColumnKind::Year,
+        period_codes: book.periods.iter().map(|p| p.code.clone()).collect(),
+    });
+    columns
+}
+
+fn subject_score(
+    book: &Gradebook,
+    student_id: i64,
+    subject: &SubjectInfo,
+    column: &BulletinColumn,
+) -> Score {
+    let mut score = Score::empty();
+    for code in &column.period_codes {
+        let max = subject.max_for(code);
+        // Période non notée pour ce cours
+        if max == 0.0 {
+            continue;
+        }
+        score.add_grade(
+            book.grade(student_id, subject.id, code),
+            max,
+            book.is_estimated(student_id, subject.id, code),
+        );
+    }
+    score.finish()
+}
+
+fn sum_scores<'a>(count: usize, lines: impl Iterator<Item = &'a SubjectLine>) -> Vec<Score> {
+    let mut totals = vec![Score::empty(); count];
+    for line in lines {
+        for (total, score) in totals.iter_mut().zip(&line.scores) {
+            total.add(score, line.weight);
+        }
+    }
+    totals.into_iter().map(Score::finish).collect()
+}
+
+// Domaines dans leur ordre d'affichage, les cours sans domaine en dernier
+fn domain_groups(book: &Gradebook, lines: &[SubjectLine], count: usize) -> Vec<DomainGroup> {
+    // (sans domaine, ordre d'affichage, domaine) -> (libellé, cours)
+    type DomainKey = (bool, i64, Option<i64>);
+    let mut order: BTreeMap<DomainKey, (String, Vec<&SubjectLine>)> = BTreeMap::new();
+    for (subject, line) in book.subjects.iter().zip(lines) {
+        let (key, name) = match subject.domain_id {
+            Some(id) => (
+                (false, subject.domain_order, Some(id)),
+                subject.domain.clone(),
+            ),
+            None => ((true, 0, None), OTHER_DOMAIN_LABEL.to_string()),
+        };
+        order
+            .entry(key)
+            .or_insert_with(|| (name, Vec::new()))
+            .1
+            .push(line);
+    }
+    order
+        .into_iter()
+        .map(|((_, _, domain_id), (name, members))| DomainGroup {
+            domain_id,
+            name,
+            subject_ids: members.iter().map(|l| l.subject_id).collect(),
+            scores: sum_scores(count, members.into_iter()),
+        })
+        .collect()
+}
+
+fn assign_ranks(students: &mut [StudentBulletin], count: usize, policy: IncompletePolicy) {
+    for column in 0..count {
+        let candidates: Vec<Candidate> = students
+            .iter()
+            .map(|s| Candidate {
+                abandoned: s.abandoned,
+                score: &s.totals[column],
+            })
+            .collect();
+        let ranks = ranking::rank_candidates(&candidates, policy);
+        for (student, rank) in students.iter_mut().zip(ranks) {
+            student.ranks[column] = rank;
+        }
+    }
+}
+
+// Index de la colonne demandée ; accepte aussi les codes du palmarès (SEM1, SEM2, ANNUAL)
+pub fn column_index(columns: &[BulletinColumn], period: &str) -> Result<usize, String> {
+    let key = match period {
+        "ANNUAL" => ANNUAL_KEY.to_string(),
+        p => match p.strip_prefix("SEM") {
+            Some(semester) => format!("S{}", semester),
+            None => p.to_string(),
+        },
+    };
+    columns
+        .iter()
+        .position(|c| c.key == key)
+        .ok_or_else(|| format!("Période inconnue : {}", period))
+}
+
+pub fn compute(book: &Gradebook, class_id: i64, policy: IncompletePolicy) -> ClassBulletin {
+    let columns = columns(book);
+    let count = columns.len();
+    let education_de_base = is_education_de_base(&book.class.level);
+
+    let mut students: Vec<StudentBulletin> = book
+        .students
+        .iter()
+        .map(|student| {
+            let subjects: Vec<SubjectLine> = book
+                .subjects
+                .iter()
+                .map(|subject| SubjectLine {
+                    subject_id: subject.id,
+                    code: subject.code.clone(),
+                    name: subject.name.clone(),
+                    domain_id: subject.domain_id,
+                    sub_domain: subject.sub_domain.clone(),
+                    coefficient: subject.coefficient,
+                    weight: book.weight(subject),
+                    scores: columns
+                        .iter()
+                        .map(|column| subject_score(book, student.id, subject, column))
+                        .collect(),
+                })
+                .collect();
+            StudentBulletin {
+                student_id: student.id,
+                student_name: student.name.clone(),
+                abandoned: student.abandoned,
+                domains: if education_de_base {
+                    domain_groups(book, &subjects, count)
+                } else {
+                    Vec::new()
+                },
+                totals: sum_scores(count, subjects.iter()),
+                ranks: vec![None; count],
+                subjects,
+            }
+        })
+        .collect();
+    assign_ranks(&mut students, count, policy);
+
+    ClassBulletin {
+        class_id,
+        class_name: book.class.name.clone(),
+        level: book.class.level.clone(),
+        education_de_base,
+        weighting: book.weighting,
+        columns,
+        students,
+    }
+}
+
+#[tauri::command]
+pub async fn class_bulletins(
+    pool: tauri::State<'_, DbPool>,
+    class_id: i64,
+    // false : les cotes estimées sont retirées et comptent comme manquantes
+    include_estimated: Option<bool>,
+) -> Result<ClassBulletin, String> {
+    let result = pool
+        .run(move |conn| {
+            let mut book = Gradebook::load(conn, class_id)?;
+            if include_estimated == Some(false) {
+                book = book.without_estimated();
+            }
+            Ok(compute(
+                &book,
+                class_id,
+                IncompletePolicy::from_settings(conn),
+            ))
+        })
+        .await?;
+    info!(
+        "Bulletins de la classe {} : {} élève(s), {} colonne(s)",
+        class_id,
+        result.students.len(),
+        result.columns.len()
+    );
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gradebook::{ClassInfo, StudentInfo, GRADE_TRICHEUR_CODE};
+    use crate::periods::{Period, DEFAULT_PERIODS};
+    use std::collections::{HashMap, HashSet};
+
+    fn subject(id: i64, code: &str, domain: Option<(i64, &str, i64)>) -> SubjectInfo {
+        SubjectInfo {
+            id,
+            name: code.to_string(),
+            code: code.to_string(),
+            category: String::new(),
+            domain_id: domain.map(|d| d.0),
+            domain: domain.map(|d| d.1.to_string()).unwrap_or_default(),
+            domain_order: domain.map_or(0, |d| d.2),
+            sub_domain: String::new(),
+            coefficient: None,
+            maxima: DEFAULT_PERIODS
+                .iter()
+                .map(|(code, _, _, _, max)| (code.to_string(), *max as f64))
+                .collect(),
+        }
+    }
+
+    fn book(level: &str, subjects: Vec<SubjectInfo>, students: &[i64]) -> Gradebook {
+        Gradebook {
+            class: ClassInfo {
+                name: "Classe".to_string(),
+                level: level.to_string(),
+                academic_year_id: 1,
+            },
+            periods: DEFAULT_PERIODS
+                .iter()
+                .enumerate()
+                .map(|(i, (code, label, semester, is_exam, max))| Period {
+                    id: i as i64 + 1,
+                    academic_year_id: 1,
+                    code: code.to_string(),
+                    label: label.to_string(),
+                    semester: *semester,
+                    is_exam: *is_exam,
+                    display_order: i as i64,
+                    default_max: *max,
+                })
+                .collect(),
+            subjects,
+            students: students
+                .iter()
+                .map(|id| StudentInfo {
+                    id: *id,
+                    name: format!("Élève {}", id),
+                    gender: "F".to_string(),
+                    abandoned: false,
+                })
+                .collect(),
+            grades: HashMap::new(),
+            estimated: HashSet::new(),
+            repechages: HashMap::new(),
+            weighting: WeightingMode::Points,
+        }
+    }
+
+    // Cotes P1, P2, EXAM1, P3, P4, EXAM2 d'un élève dans un cours
+    fn fill(book: &mut Gradebook, student: i64, subject: i64, values: [Option<f64>; 6]) {
+        for ((code, ..), value) in DEFAULT_PERIODS.iter().zip(values) {
+            if let Some(v) = value {
+                book.grades.insert((student, subject, code.to_string()), v);
+            }
+        }
+    }
+
+    fn column(result: &ClassBulletin, key: &str) -> usize {
+        result.columns.iter().position(|c| c.key == key).unwrap()
+    }
+
+    #[test]
+    fn colonnes_periodes_semestres_et_annee() {
+        let result = compute(&book("1ère", vec![], &[]), 1, IncompletePolicy::Exclude);
+        let keys: Vec<&str> = result.columns.iter().map(|c| c.key.as_str()).collect();
+        assert_eq!(
+            keys,
+            ["P1", "P2", "EXAM1", "S1", "P3", "P4", "EXAM2", "S2", "TG"]
+        );
+        assert_eq!(result.columns[3].period_codes, ["P1", "P2", "EXAM1"]);
+        assert_eq!(result.columns[8].period_codes.len(), 6);
+    }
+
+    #[test]
+    fn totaux_et_pourcentages_complets() {
+        let mut book = book(
+            "1ère",
+            vec![subject(1, "FR", None), subject(2, "MATH", None)],
+            &[1],
+        );
+        fill(
+            &mut book,
+            1,
+            1,
+            [
+                Some(6.0),
+                Some(7.0),
+                Some(14.0),
+                Some(5.0),
+                Some(8.0),
+                Some(12.0),
+            ],
+        );
+        fill(
+            &mut book,
+            1,
+            2,
+            [
+                Some(4.0),
+                Some(5.0),
+                Some(10.0),
+                Some(6.0),
+                Some(5.0),
+                Some(10.0),
+            ],
+        );
+        let result = compute(&book, 1, IncompletePolicy::Exclude);
+        let student = &result.students[0];
+
+        let s1 = column(&result, "S1");
+        assert_eq!(student.subjects[0].scores[s1].points, Some(27.0));
+        assert_eq!(student.subjects[0].scores[s1].max, 40.0);
+        assert_eq!(student.subjects[0].scores[s1].percentage, Some(67.5));
+
+        let tg = column(&result, ANNUAL_KEY);
+        assert_eq!(student.totals[tg].points, Some(92.0));
+        assert_eq!(student.totals[tg].max, 160.0);
+        assert_eq!(student.totals[tg].percentage, Some(57.5));
+        assert!(!student.totals[tg].missing);
+        assert!(student.domains.is_empty());
+    }
+
+    #[test]
+    fn ponderation_par_coefficient() {
+        let mut fr = subject(1, "FR", None);
+        fr.coefficient = Some(2.0);
+        let mut book = book("1ère", vec![fr, subject(2, "MATH", None)], &[1]);
+        fill(&mut book, 1, 1, [Some(6.0), None, None, None, None, None]);
+        fill(&mut book, 1, 2, [Some(4.0), None, None, None, None, None]);
+
+        let result = compute(&book, 1, IncompletePolicy::Exclude);
+        let p1 = column(&result, "P1");
+        assert_eq!(result.students[0].totals[p1].percentage, Some(50.0));
+
+        book.weighting = WeightingMode::Coefficient;
+        let result = compute(&book, 1, IncompletePolicy::Exclude);
+        let totals = result.students[0].totals[p1];
+        assert_eq!(totals.points, Some(16.0));
+        assert_eq!(totals.max, 30.0);
+        assert_eq!(totals.percentage, Some(53.33));
+        // Le pourcentage d'un cours n'est pas touché par son coefficient
+        assert_eq!(
+            result.students[0].subjects[0].scores[p1].percentage,
+            Some(60.0)
+        );
+    }
+
+    #[test]
+    fn cote_manquante_signalee_sans_pourcentage() {
+        let mut book = book(
+            "1ère",
+            vec![subject(1, "FR", None), subject(2, "MATH", None)],
+            &[1],
+        );
+        fill(
+            &mut book,
+            1,
+            1,
+            [Some(6.0), None, Some(14.0), None, None, None],
+        );
+        fill(
+            &mut book,
+            1,
+            2,
+            [Some(4.0), Some(5.0), Some(10.0), None, None, None],
+        );
+        let result = compute(&book, 1, IncompletePolicy::Exclude);
+        let student = &result.students[0];
+
+        let p2 = column(&result, "P2");
+        assert_eq!(student.subjects[0].scores[p2].points, None);
+        assert!(student.subjects[0].scores[p2].missing);
+        // Les maxima restent comptés même quand la cote manque
+        assert_eq!(student.totals[p2].max, 20.0);
+        assert_eq!(student.totals[p2].points, Some(5.0));
+        assert_eq!(student.totals[p2].percentage, None);
+
+        let s1 = column(&result, "S1");
+        assert_eq!(student.subjects[0].scores[s1].points, Some(20.0));
+        assert_eq!(student.subjects[0].scores[s1].percentage, None);
+        assert_eq!(student.subjects[1].scores[s1].percentage, Some(47.5));
+        assert!(student.totals[s1].missing);
+
+        // Aucune cote au 2ème semestre : ni points ni place
+        let s2 = column(&result, "S2");
+        assert_eq!(student.totals[s2].points, None);
+        assert_eq!(student.ranks[s2], None);
+    }
+
+    #[test]
+    fn tricheur_compte_zero() {
+        let mut book = book("1ère", vec![subject(1, "FR", None)], &[1]);
+        fill(
+            &mut book,
+            1,
+            1,
+            [
+                Some(GRADE_TRICHEUR_CODE),
+                Some(8.0),
+                Some(10.0),
+                None,
+                None,
+                None,
+            ],
+        );
+        let result = compute(&book, 1, IncompletePolicy::Exclude);
+        let s1 = column(&result, "S1");
+        assert_eq!(result.students[0].totals[s1].points, Some(18.0));
+        assert_eq!(result.students[0].totals[s1].percentage, Some(45.0));
+    }
+
+    #[test]
+    fn places_ex_aequo_sans_les_abandons() {
+        let mut book = book("1ère", vec![subject(1, "FR", None)], &[1, 2, 3, 4, 5]);
+        book.students[3].abandoned = true;
+        fill(&mut book, 1, 1, [Some(7.0), None, None, None, None, None]);
+        fill(&mut book, 2, 1, [Some(9.0), None, None, None, None, None]);
+        fill(&mut book, 3, 1, [Some(7.0), None, None, None, None, None]);
+        fill(&mut book, 4, 1, [Some(10.0), None, None, None, None, None]);
+        let result = compute(&book, 1, IncompletePolicy::Exclude);
+        let p1 = column(&result, "P1");
+        let places: Vec<Option<(usize, bool)>> = result
+            .students
+            .iter()
+            .map(|s| s.ranks[p1].map(|r| (r.place, r.ex_aequo)))
+            .collect();
+        assert_eq!(
+            places,
+            [
+                Some((2, true)),
+                Some((1, false)),
+                Some((2, true)),
+                None,
+                None
+            ]
+        );
+        assert_eq!(result.students[1].ranks[p1].unwrap().out_of, 3);
+    }
+
+    #[test]
+    fn cotes_incompletes_selon_le_parametre() {
+        let mut book = book("1ère", vec![subject(1, "FR", None)], &[1, 2, 3]);
+        fill(
+            &mut book,
+            1,
+            1,
+            [Some(5.0), Some(5.0), Some(10.0), None, None, None],
+        );
+        fill(
+            &mut book,
+            2,
+            1,
+            [Some(6.0), Some(6.0), Some(12.0), None, None, None],
+        );
+        // 9/10 sur les cotes encodées, l'examen manque
+        fill(
+            &mut book,
+            3,
+            1,
+            [Some(9.0), Some(9.0), None, None, None, None],
+        );
+        let places = |policy| {
+            let result = compute(&book, 1, policy);
+            let s1 = column(&result, "S1");
+            result
+                .students
+                .iter()
+                .map(|s| s.ranks[s1].map(|r| r.place))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(places(IncompletePolicy::Exclude), [Some(2), Some(1), None]);
+        assert_eq!(
+            places(IncompletePolicy::Partial),
+            [Some(3), Some(2), Some(1)]
+        );
+        assert_eq!(
+            places(IncompletePolicy::RankLast),
+            [Some(2), Some(1), Some(3)]
+        );
+    }
+
+    #[test]
+    fn education_de_base_regroupe_par_domaine() {
+        let langues = Some((10, "Langues", 1));
+        let sciences = Some((20, "Sciences", 2));
+        let mut book = book(
+            "7ème",
+            vec![
+                subject(1, "SCI", sciences),
+                subject(2, "FR", langues),
+                subject(3, "EPS", None),
+                subject(4, "ANG", langues),
+            ],
+            &[1],
+        );
+        fill(&mut book, 1, 1, [Some(5.0); 6]);
+        fill(&mut book, 1, 2, [Some(6.0); 6]);
+        fill(&mut book, 1, 3, [Some(7.0); 6]);
+        fill(&mut book, 1, 4, [Some(8.0), None, None, None, None, None]);
+        let result = compute(&book, 1, IncompletePolicy::Exclude);
+        assert!(result.education_de_base);
+
+        let domains = &result.students[0].domains;
+        let names: Vec<&str> = domains.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(names, ["Langues", "Sciences", OTHER_DOMAIN_LABEL]);
+        assert_eq!(domains[0].subject_ids, [2, 4]);
+
+        let p1 = column(&result, "P1");
+        assert_eq!(domains[0].scores[p1].points, Some(14.0));
+        assert_eq!(domains[0].scores[p1].max, 20.0);
+        assert_eq!(domains[0].scores[p1].percentage, Some(70.0));
+        let p2 = column(&result, "P2");
+        assert_eq!(domains[0].scores[p2].percentage, None);
+        assert_eq!(domains[1].scores[p2].percentage, Some(50.0));
+    }
+}
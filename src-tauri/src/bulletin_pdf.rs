@@ -0,0 +1,818 @@
+// Bulletins en PDF générés côté Rust, pour une mise en page identique d'une machine à
+// l'autre (l'impression par la webview change les sauts de page et les polices).
+// Un élève ou toute la classe dans un seul fichier, sans connexion : les polices
+// standard du PDF (Helvetica) suffisent et n'ont pas à être embarquées.
+//
+// Contenu repris des bulletins du renderer : en-tête de l'école (clés school_* de
+// settings), cotes groupées par domaine avec les maxima, totaux, pourcentages, places,
+// conduite annuelle et décision de la délibération.
+
+use crate::bulletin::{self, ClassBulletin, Score, StudentBulletin, OTHER_DOMAIN_LABEL};
+use crate::db::DbPool;
+use crate::deliberation::{self, DelibMode, StudentDeliberation};
+use crate::gradebook::Gradebook;
+use crate::ranking::IncompletePolicy;
+use log::info;
+use printpdf::{
+    BuiltinFont, IndirectFontRef, Line, Mm, PdfDocument, PdfDocumentReference, PdfLayerReference,
+    Point,
+};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+
+// A4 portrait, en millimètres
+const PAGE_WIDTH: f32 = 210.0;
+const PAGE_HEIGHT: f32 = 297.0;
+const MARGIN: f32 = 12.0;
+const ROW_HEIGHT: f32 = 5.0;
+const FONT_SIZE: f32 = 8.0;
+const NAME_MIN_WIDTH: f32 = 50.0;
+const COLUMN_MAX_WIDTH: f32 = 16.0;
+// Place gardée en bas de page pour la décision et les signatures
+const SUMMARY_HEIGHT: f32 = 48.0;
+// Largeur moyenne d'un caractère d'Helvetica, en fraction de la taille de police
+const CHAR_WIDTH: f32 = 0.52;
+const PT_TO_MM: f32 = 0.3528;
+
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SchoolHeader {
+    pub name: String,
+    pub city: String,
+    pub pobox: String,
+}
+
+impl SchoolHeader {
+    pub fn from_settings(conn: &Connection) -> Result<Self, String> {
+        let get = |key: &str| -> Result<String, String> {
+            conn.query_row(
+                "SELECT value FROM settings WHERE key = ?",
+                params![key],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()
+            .map(|v| v.unwrap_or_default().trim().to_string())
+            .map_err(|e| e.to_string())
+        };
+        Ok(SchoolHeader {
+            name: get("school_name")?,
+            city: get("school_city")?,
+            pobox: get("school_pobox")?,
+        })
+    }
+}
+
+// Données d'un lot de bulletins, déjà calculées
+pub struct BulletinPdfInput<'a> {
+    pub school: &'a SchoolHeader,
+    pub year: &'a str,
+    pub book: &'a Gradebook,
+    pub bulletin: &'a ClassBulletin,
+    pub deliberations: &'a [StudentDeliberation],
+}
+
+pub struct RenderedPdf {
+    pub bytes: Vec<u8>,
+    pub pages: usize,
+    pub students: usize,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PdfExport {
+    pub path: String,
+    pub pages: usize,
+    pub students: usize,
+}
+
+fn text_width(text: &str, size: f32) -> f32 {
+    text.chars().count() as f32 * size * CHAR_WIDTH * PT_TO_MM
+}
+
+// Coupe un libellé trop long pour sa cellule
+fn fit(text: &str, width: f32, size: f32) -> String {
+    if text_width(text, size) <= width {
+        return text.to_string();
+    }
+    let keep = (width / (size * CHAR_WIDTH * PT_TO_MM)) as usize;
+    let mut cut: String = text.chars().take(keep.saturating_sub(1)).collect();
+    cut.push('.');
+    cut
+}
+
+fn number(value: f64) -> String {
+    let text = format!("{:.2}", (value * 100.0).round() / 100.0);
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+// Points d'une cellule : vide si la colonne n'est pas notée, « - » pour une cote manquante
+fn points_cell(score: &Score) -> String {
+    if score.max == 0.0 {
+        return String::new();
+    }
+    match score.points {
+        Some(points) if !score.missing => {
+            let mut text = number(points);
+            if score.estimated {
+                text.push('*');
+            }
+            text
+        }
+        _ => "-".to_string(),
+    }
+}
+
+fn max_cell(score: &Score) -> String {
+    if score.max == 0.0 {
+        String::new()
+    } else {
+        number(score.max)
+    }
+}
+
+// Libellé court d'une colonne du bulletin
+fn column_header(key: &str) -> String {
+    if let Some(semester) = key.strip_prefix('S').filter(|s| s.parse::<u32>().is_ok()) {
+        format!("TOT.S{}", semester)
+    } else if key == bulletin::ANNUAL_KEY {
+        "T.G.".to_string()
+    } else {
+        key.replace("EXAM", "EX.")
+    }
+}
+
+struct Writer {
+    doc: PdfDocumentReference,
+    regular: IndirectFontRef,
+    bold: IndirectFontRef,
+    layer: PdfLayerReference,
+    pages: usize,
+    // Ordonnée courante depuis le bas de la page
+    y: f32,
+    // Abscisses des bords de colonnes du tableau
+    edges: Vec<f32>,
+}
+
+impl Writer {
+    fn new(title: &str, columns: usize) -> Result<Self, String> {
+        let (doc, page, layer) =
+            PdfDocument::new(title, Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Bulletin");
+        let regular = doc
+            .add_builtin_font(BuiltinFont::Helvetica)
+            .map_err(|e| e.to_string())?;
+        let bold = doc
+            .add_builtin_font(BuiltinFont::HelveticaBold)
+            .map_err(|e| e.to_string())?;
+        let layer = doc.get_page(page).get_layer(layer);
+
+        let table_width = PAGE_WIDTH - 2.0 * MARGIN;
+        let column_width =
+            ((table_width - NAME_MIN_WIDTH) / columns.max(1) as f32).min(COLUMN_MAX_WIDTH);
+        let name_width = table_width - column_width * columns as f32;
+        let mut edges = vec![MARGIN, MARGIN + name_width];
+        for i in 1..=columns {
+            edges.push(MARGIN + name_width + column_width * i as f32);
+        }
+        Ok(Writer {
+            doc,
+            regular,
+            bold,
+            layer,
+            pages: 1,
+            y: PAGE_HEIGHT - MARGIN,
+            edges,
+        })
+    }
+
+    // La première page vient avec le document
+    fn next_page(&mut self) {
+        let (page, layer) = self
+            .doc
+            .add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Bulletin");
+        self.layer = self.doc.get_page(page).get_layer(layer);
+        self.pages += 1;
+        self.y = PAGE_HEIGHT - MARGIN;
+    }
+
+    fn text(&self, text: &str, size: f32, x: f32, y: f32, bold: bool) {
+        if text.is_empty() {
+            return;
+        }
+        let font = if bold { &self.bold } else { &self.regular };
+        self.layer.use_text(text, size, Mm(x), Mm(y), font);
+    }
+
+    fn centered(&self, text: &str, size: f32, left: f32, right: f32, y: f32, bold: bool) {
+        let x = left + ((right - left) - text_width(text, size)) / 2.0;
+        self.text(text, size, x.max(left), y, bold);
+    }
+
+    fn line(&self, x1: f32, y1: f32, x2: f32, y2: f32) {
+        self.layer.add_line(Line {
+            points: vec![
+                (Point::new(Mm(x1), Mm(y1)), false),
+                (Point::new(Mm(x2), Mm(y2)), false),
+            ],
+            is_closed: false,
+        });
+    }
+
+    // Ligne de texte libre, puis descente
+    fn paragraph(&mut self, text: &str, size: f32, bold: bool) {
+        self.y -= size * PT_TO_MM + 1.5;
+        self.text(text, size, MARGIN, self.y, bold);
+    }
+
+    // Ligne du tableau : libellé puis une cellule par colonne, bordures comprises
+    fn row(&mut self, label: &str, cells: &[String], bold: bool) {
+        let top = self.y;
+        let bottom = top - ROW_HEIGHT;
+        let baseline = bottom + 1.5;
+        let (left, right) = (self.edges[0], *self.edges.last().unwrap_or(&MARGIN));
+        self.line(left, top, right, top);
+        self.line(left, bottom, right, bottom);
+        for x in &self.edges {
+            self.line(*x, top, *x, bottom);
+        }
+        let name_width = self.edges[1] - self.edges[0] - 2.0;
+        self.text(
+            &fit(label, name_width, FONT_SIZE),
+            FONT_SIZE,
+            left + 1.0,
+            baseline,
+            bold,
+        );
+        for (i, cell) in cells.iter().enumerate() {
+            let (l, r) = (self.edges[i + 1], self.edges[i + 2]);
+            let cell = fit(cell, r - l - 1.0, FONT_SIZE);
+            self.centered(&cell, FONT_SIZE, l, r, baseline, bold);
+        }
+        self.y = bottom;
+    }
+}
+
+// Groupes de cours par domaine, dans l'ordre des domaines ; les cours sans domaine à la fin
+fn domain_groups<'a>(
+    book: &Gradebook,
+    student: &'a StudentBulletin,
+) -> Vec<(String, Option<i64>, Vec<&'a bulletin::SubjectLine>)> {
+    let mut order: Vec<(i64, String, Option<i64>)> = Vec::new();
+    for subject in &book.subjects {
+        let key = (
+            subject.domain_order,
+            subject.domain.clone(),
+            subject.domain_id,
+        );
+        if subject.domain_id.is_some() && !order.contains(&key) {
+            order.push(key);
+        }
+    }
+    order.sort();
+    let mut groups: Vec<(String, Option<i64>, Vec<&bulletin::SubjectLine>)> = order
+        .into_iter()
+        .map(|(_, name, id)| {
+            let lines = student
+                .subjects
+                .iter()
+                .filter(|line| line.domain_id == id)
+                .collect();
+            (name, id, lines)
+        })
+        .collect();
+    let others: Vec<&bulletin::SubjectLine> = student
+        .subjects
+        .iter()
+        .filter(|line| line.domain_id.is_none())
+        .collect();
+    if !others.is_empty() {
+        groups.push((OTHER_DOMAIN_LABEL.to_string(), None, others));
+    }
+    groups.retain(|group| !group.2.is_empty());
+    groups
+}
+
+fn student_header(writer: &mut Writer, input: &BulletinPdfInput, student: &StudentBulletin) {
+    let right = PAGE_WIDTH - MARGIN;
+    writer.y -= 4.0;
+    writer.centered(
+        "RÉPUBLIQUE DÉMOCRATIQUE DU CONGO",
+        10.0,
+        MARGIN,
+        right,
+        writer.y,
+        true,
+    );
+    writer.y -= 4.5;
+    writer.centered(
+        "MINISTÈRE DE L'ÉDUCATION NATIONALE ET NOUVELLE CITOYENNETÉ",
+        8.0,
+        MARGIN,
+        right,
+        writer.y,
+        false,
+    );
+    writer.y -= 2.5;
+    writer.line(MARGIN, writer.y, right, writer.y);
+
+    let school = input.school;
+    let left_lines = [
+        format!("ÉCOLE : {}", school.name),
+        format!("VILLE : {}", school.city),
+        format!("B.P. : {}", school.pobox),
+    ];
+    let right_lines = [
+        format!("ÉLÈVE : {}", student.student_name),
+        format!("CLASSE : {}", input.bulletin.class_name),
+        format!("ANNÉE SCOLAIRE : {}", input.year),
+    ];
+    let middle = PAGE_WIDTH / 2.0;
+    for (left, right_text) in left_lines.iter().zip(&right_lines) {
+        writer.y -= 4.5;
+        writer.text(left, 9.0, MARGIN, writer.y, false);
+        writer.text(right_text, 9.0, middle, writer.y, false);
+    }
+    writer.y -= 7.0;
+    writer.centered(
+        &format!(
+            "BULLETIN DE LA {}",
+            input.bulletin.class_name.to_uppercase()
+        ),
+        11.0,
+        MARGIN,
+        right,
+        writer.y,
+        true,
+    );
+    writer.y -= 3.0;
+}
+
+fn table_header(writer: &mut Writer, input: &BulletinPdfInput) {
+    let headers: Vec<String> = input
+        .bulletin
+        .columns
+        .iter()
+        .map(|c| column_header(&c.key))
+        .collect();
+    writer.row("BRANCHES", &headers, true);
+}
+
+// Passe à la page suivante quand la ligne ne tient plus
+fn ensure_room(
+    writer: &mut Writer,
+    input: &BulletinPdfInput,
+    student: &StudentBulletin,
+    height: f32,
+) {
+    if writer.y - height >= MARGIN {
+        return;
+    }
+    writer.next_page();
+    writer.paragraph(
+        &format!(
+            "{} - {} (suite)",
+            student.student_name, input.bulletin.class_name
+        ),
+        9.0,
+        true,
+    );
+    writer.y -= 2.0;
+    table_header(writer, input);
+}
+
+fn student_pages(writer: &mut Writer, input: &BulletinPdfInput, student: &StudentBulletin) {
+    student_header(writer, input, student);
+    table_header(writer, input);
+    let cells = |scores: &[Score], f: fn(&Score) -> String| -> Vec<String> {
+        scores.iter().map(f).collect()
+    };
+
+    let groups = domain_groups(input.book, student);
+    let show_domains = groups.len() > 1 || groups.iter().any(|g| g.1.is_some());
+    let mut estimated = false;
+    for (name, domain_id, lines) in &groups {
+        if show_domains {
+            ensure_room(writer, input, student, 2.0 * ROW_HEIGHT);
+            writer.row(&name.to_uppercase(), &[], true);
+        }
+        let mut maxima: Option<Vec<String>> = None;
+        for line in lines {
+            let line_maxima = cells(&line.scores, max_cell);
+            if maxima.as_ref() != Some(&line_maxima) {
+                ensure_room(writer, input, student, 2.0 * ROW_HEIGHT);
+                writer.row("MAXIMA", &line_maxima, true);
+                maxima = Some(line_maxima);
+            }
+            ensure_room(writer, input, student, ROW_HEIGHT);
+            writer.row(&line.name, &cells(&line.scores, points_cell), false);
+            estimated |= line.scores.iter().any(|s| s.estimated);
+        }
+        // Sous-total du domaine, calculé pour l'éducation de base
+        if let Some(group) = student
+            .domains
+            .iter()
+            .find(|d| domain_id.is_some() && d.domain_id == *domain_id)
+        {
+            ensure_room(writer, input, student, ROW_HEIGHT);
+            writer.row("Sous-total", &cells(&group.scores, points_cell), true);
+        }
+    }
+
+    ensure_room(writer, input, student, 4.0 * ROW_HEIGHT);
+    writer.row("MAXIMA GÉNÉRAUX", &cells(&student.totals, max_cell), true);
+    writer.row("TOTAUX", &cells(&student.totals, points_cell), true);
+    let percentages: Vec<String> = student
+        .totals
+        .iter()
+        .map(|s| s.percentage.map(number).unwrap_or_default())
+        .collect();
+    writer.row("POURCENTAGE", &percentages, false);
+    let ranks: Vec<String> = student
+        .ranks
+        .iter()
+        .map(|rank| match rank {
+            Some(r) if r.ex_aequo => format!("{}e/{}", r.place, r.out_of),
+            Some(r) => format!("{}/{}", r.place, r.out_of),
+            None => String::new(),
+        })
+        .collect();
+    writer.row("PLACE / NBRE D'ÉLÈVES", &ranks, false);
+    if estimated {
+        writer.paragraph("* cote estimée, à confirmer", 7.0, false);
+    }
+
+    if writer.y - SUMMARY_HEIGHT < MARGIN {
+        writer.next_page();
+    }
+    let deliberation = input
+        .deliberations
+        .iter()
+        .find(|d| d.student_id == student.student_id);
+    writer.y -= 3.0;
+    writer.paragraph(
+        &format!(
+            "CONDUITE : {}",
+            deliberation
+                .and_then(|d| d.conduct.as_deref())
+                .unwrap_or("-")
+        ),
+        9.0,
+        true,
+    );
+    if let Some(d) = deliberation {
+        let decision = match d.percentage {
+            Some(p) => format!("DÉCISION : {} ({} %)", d.verdict_label, number(p)),
+            None => format!("DÉCISION : {}", d.verdict_label),
+        };
+        writer.paragraph(&decision, 9.0, true);
+        if !d.remaining_failures.is_empty() {
+            writer.paragraph(
+                &format!("Échecs : {}", d.remaining_failures.join(", ")),
+                8.0,
+                false,
+            );
+        }
+    }
+    writer.y -= 4.0;
+    writer.paragraph(
+        &format!(
+            "Fait à {}, le ......../......../20........",
+            if input.school.city.is_empty() {
+                ".........................."
+            } else {
+                &input.school.city
+            }
+        ),
+        8.0,
+        false,
+    );
+    writer.y -= 3.0;
+    let signatures = writer.y - 4.0;
+    writer.text("Signature du responsable", 8.0, MARGIN, signatures, true);
+    writer.centered(
+        "Sceau de l'École",
+        8.0,
+        MARGIN,
+        PAGE_WIDTH - MARGIN,
+        signatures,
+        true,
+    );
+    let chef = "Le Chef d'Établissement";
+    writer.text(
+        chef,
+        8.0,
+        PAGE_WIDTH - MARGIN - text_width(chef, 8.0),
+        signatures,
+        true,
+    );
+    writer.text(
+        "Le bulletin est sans valeur s'il est raturé ou surchargé.",
+        7.0,
+        MARGIN,
+        MARGIN,
+        false,
+    );
+}
+
+// Un bulletin par élève demandé (tous si None), chacun sur une nouvelle page
+pub fn render(input: &BulletinPdfInput, student_id: Option<i64>) -> Result<RenderedPdf, String> {
+    let students: Vec<&StudentBulletin> = input
+        .bulletin
+        .students
+        .iter()
+        .filter(|s| student_id.is_none() || student_id == Some(s.student_id))
+        .collect();
+    if students.is_empty() {
+        return Err("Aucun élève à imprimer".to_string());
+    }
+    let title = match students.as_slice() {
+        [one] => format!("Bulletin - {}", one.student_name),
+        _ => format!("Bulletins - {}", input.bulletin.class_name),
+    };
+    let mut writer = Writer::new(&title, input.bulletin.columns.len())?;
+    for (i, student) in students.iter().enumerate() {
+        if i > 0 {
+            writer.next_page();
+        }
+        student_pages(&mut writer, input, student);
+    }
+    let pages = writer.pages;
+    let bytes = writer.doc.save_to_bytes().map_err(|e| e.to_string())?;
+    Ok(RenderedPdf {
+        bytes,
+        pages,
+        students: students.len(),
+    })
+}
+
+pub fn class_pdf(
+    conn: &Connection,
+    class_id: i64,
+    student_id: Option<i64>,
+    mode: DelibMode,
+) -> Result<RenderedPdf, String> {
+    let book = Gradebook::load(conn, class_id)?;
+    let school = SchoolHeader::from_settings(conn)?;
+    let year: String = conn
+        .query_row(
+            "SELECT name FROM academic_years WHERE id = ?",
+            params![book.class.academic_year_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .unwrap_or_default();
+    let result = bulletin::compute(&book, class_id, IncompletePolicy::from_settings(conn));
+    let deliberations = deliberation::deliberate(conn, class_id, mode)?.students;
+    render(
+        &BulletinPdfInput {
+            school: &school,
+            year: &year,
+            book: &book,
+            bulletin: &result,
+            deliberations: &deliberations,
+        },
+        student_id,
+    )
+}
+
+#[tauri::command]
+pub async fn bulletins_pdf(
+    pool: tauri::State<'_, DbPool>,
+    class_id: i64,
+    // None : toute la classe
+    student_id: Option<i64>,
+    mode: Option<DelibMode>,
+    path: String,
+) -> Result<PdfExport, String> {
+    let mode = mode.unwrap_or_default();
+    let rendered = pool
+        .run(move |conn| class_pdf(conn, class_id, student_id, mode))
+        .await?;
+    std::fs::write(&path, &rendered.bytes).map_err(|e| e.to_string())?;
+    info!(
+        "Bulletins PDF de la classe {} : {} élève(s), {} page(s) -> {}",
+        class_id, rendered.students, rendered.pages, path
+    );
+    Ok(PdfExport {
+        path,
+        pages: rendered.pages,
+        students: rendered.students,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deliberation::DelibConfig;
+    use crate::gradebook::{ClassInfo, StudentInfo, SubjectInfo, WeightingMode};
+    use crate::periods::{Period, DEFAULT_PERIODS};
+    use printpdf::lopdf;
+    use std::collections::{HashMap, HashSet};
+
+    fn subject(id: i64, name: &str, domain: Option<(i64, &str, i64)>) -> SubjectInfo {
+        SubjectInfo {
+            id,
+            name: name.to_string(),
+            code: name.to_uppercase(),
+            category: String::new(),
+            domain_id: domain.map(|d| d.0),
+            domain: domain.map(|d| d.1.to_string()).unwrap_or_default(),
+            domain_order: domain.map_or(0, |d| d.2),
+            sub_domain: String::new(),
+            coefficient: None,
+            maxima: DEFAULT_PERIODS
+                .iter()
+                .map(|(code, _, _, _, max)| (code.to_string(), *max as f64))
+                .collect(),
+        }
+    }
+
+    // Toutes les cotes à la même fraction du maximum
+    fn book(subjects: Vec<SubjectInfo>, students: &[(i64, &str, f64)]) -> Gradebook {
+        let mut grades = HashMap::new();
+        for (student, _, share) in students {
+            for subject in &subjects {
+                for (code, _, _, _, max) in DEFAULT_PERIODS {
+                    grades.insert((*student, subject.id, code.to_string()), max as f64 * share);
+                }
+            }
+        }
+        Gradebook {
+            class: ClassInfo {
+                name: "6ème Scientifique".to_string(),
+                level: "6ème".to_string(),
+                academic_year_id: 1,
+            },
+            periods: DEFAULT_PERIODS
+                .iter()
+                .enumerate()
+                .map(|(i, (code, label, semester, is_exam, max))| Period {
+                    id: i as i64 + 1,
+                    academic_year_id: 1,
+                    code: code.to_string(),
+                    label: label.to_string(),
+                    semester: *semester,
+                    is_exam: *is_exam,
+                    display_order: i as i64,
+                    default_max: *max,
+                })
+                .collect(),
+            subjects,
+            students: students
+                .iter()
+                .map(|(id, name, _)| StudentInfo {
+                    id: *id,
+                    name: name.to_string(),
+                    gender: "F".to_string(),
+                    abandoned: false,
+                })
+                .collect(),
+            grades,
+            estimated: HashSet::new(),
+            repechages: HashMap::new(),
+            weighting: WeightingMode::Points,
+        }
+    }
+
+    fn school() -> SchoolHeader {
+        SchoolHeader {
+            name: "Complexe Scolaire Lumiere".to_string(),
+            city: "Kinshasa".to_string(),
+            pobox: "1234".to_string(),
+        }
+    }
+
+    fn render_book(book: &Gradebook, student_id: Option<i64>) -> RenderedPdf {
+        let bulletin = bulletin::compute(book, 1, IncompletePolicy::Exclude);
+        let config = DelibConfig::for_level(&book.class.level);
+        let deliberations =
+            deliberation::deliberate_gradebook(book, &config, DelibMode::FirstSession);
+        render(
+            &BulletinPdfInput {
+                school: &school(),
+                year: "2025-2026",
+                book,
+                bulletin: &bulletin,
+                deliberations: &deliberations,
+            },
+            student_id,
+        )
+        .unwrap()
+    }
+
+    // Nombre de pages et texte de chaque page, relus depuis le PDF produit
+    fn read(bytes: &[u8]) -> (usize, Vec<String>) {
+        let doc = lopdf::Document::load_mem(bytes).unwrap();
+        let pages: Vec<u32> = doc.get_pages().keys().copied().collect();
+        let texts = pages
+            .iter()
+            .map(|page| doc.extract_text(&[*page]).unwrap())
+            .collect();
+        (pages.len(), texts)
+    }
+
+    fn class_book() -> Gradebook {
+        book(
+            vec![
+                subject(1, "Mathematiques", Some((1, "Sciences", 1))),
+                subject(2, "Physique", Some((1, "Sciences", 1))),
+                subject(3, "Francais", Some((2, "Langues", 2))),
+                subject(4, "Dessin", None),
+            ],
+            &[
+                (1, "KABILA Jean", 0.7),
+                (2, "MBUYI Marie", 0.4),
+                (3, "TSHALA Paul", 0.55),
+            ],
+        )
+    }
+
+    #[test]
+    fn une_page_par_eleve_pour_la_classe() {
+        let rendered = render_book(&class_book(), None);
+        assert_eq!(rendered.students, 3);
+        assert_eq!(rendered.pages, 3);
+        let (pages, texts) = read(&rendered.bytes);
+        assert_eq!(pages, 3);
+        assert!(texts[0].contains("KABILA Jean"));
+        assert!(texts[1].contains("MBUYI Marie"));
+        assert!(texts[2].contains("TSHALA Paul"));
+        assert!(!texts[0].contains("MBUYI Marie"));
+    }
+
+    #[test]
+    fn contenu_du_bulletin() {
+        let rendered = render_book(&class_book(), Some(1));
+        let (pages, texts) = read(&rendered.bytes);
+        assert_eq!(pages, 1);
+        let text = &texts[0];
+        for expected in [
+            "Complexe Scolaire Lumiere",
+            "Kinshasa",
+            "2025-2026",
+            "KABILA Jean",
+            "BRANCHES",
+            "SCIENCES",
+            "LANGUES",
+            "Mathematiques",
+            "Francais",
+            "Dessin",
+            "TOTAUX",
+            "POURCENTAGE",
+            "70",
+            "1/3",
+            "Admis",
+        ] {
+            assert!(
+                text.contains(expected),
+                "« {} » absent de :\n{}",
+                expected,
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn decision_d_echec() {
+        let rendered = render_book(&class_book(), Some(2));
+        let (_, texts) = read(&rendered.bytes);
+        assert!(texts[0].contains("MBUYI Marie"));
+        assert!(texts[0].contains("3/3"));
+        assert!(!texts[0].contains("Admis"));
+    }
+
+    #[test]
+    fn tableau_long_sur_plusieurs_pages() {
+        let subjects = (1..=60)
+            .map(|id| subject(id, &format!("Cours {}", id), Some((1, "Sciences", 1))))
+            .collect();
+        let book = book(
+            subjects,
+            &[(1, "KABILA Jean", 0.7), (2, "MBUYI Marie", 0.6)],
+        );
+        let single = render_book(&book, Some(1));
+        assert!(single.pages > 1);
+        let (pages, texts) = read(&single.bytes);
+        assert_eq!(pages, single.pages);
+        assert!(texts[1].contains("(suite)"));
+        assert!(texts.last().unwrap().contains("Cours 60"));
+
+        let class = render_book(&book, None);
+        assert_eq!(class.pages, 2 * single.pages);
+        assert_eq!(read(&class.bytes).0, class.pages);
+    }
+
+    #[test]
+    fn eleve_inconnu() {
+        let book = class_book();
+        let bulletin = bulletin::compute(&book, 1, IncompletePolicy::Exclude);
+        let input = BulletinPdfInput {
+            school: &school(),
+            year: "",
+            book: &book,
+            bulletin: &bulletin,
+            deliberations: &[],
+        };
+        assert!(render(&input, Some(99)).is_err());
+    }
+}
@@ -0,0 +1,707 @@
+// Conduite des élèves : échelle configurable (mentions ou lettres, clé conduct_scale de
+// settings), saisie contrôlée par période dans les colonnes conduite_p1..p4 de students,
+// conduite annuelle calculée sur les périodes (la colonne conduite garde une décision
+// du conseil de classe, prioritaire) et refus de promotion pour une conduite bloquante
+// quand les règles de délibération le prévoient.
+//
+// Les colonnes restent du texte libre pour la synchronisation : on y écrit le code du
+// niveau, et les anciennes saisies (« elute », « mediocre »…) sont reconnues par alias.
+
+use crate::db::DbPool;
+use crate::deliberation::{DelibConfig, DelibMode, StudentDeliberation, Verdict};
+use log::info;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+const SCALE_KEY: &str = "conduct_scale";
+// Période réservée à la conduite annuelle décidée par le conseil de classe
+pub const ANNUAL_PERIOD: &str = "ANNUEL";
+pub const PERIOD_COLUMNS: [(&str, &str); 4] = [
+    ("P1", "conduite_p1"),
+    ("P2", "conduite_p2"),
+    ("P3", "conduite_p3"),
+    ("P4", "conduite_p4"),
+];
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ConductLevel {
+    // Valeur écrite dans la base
+    pub code: String,
+    pub label: String,
+    pub abbreviation: String,
+    // Anciennes saisies libres reconnues comme ce niveau
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    // Refuse la promotion si les règles de délibération le prévoient
+    #[serde(default)]
+    pub blocks_promotion: bool,
+}
+
+// Niveaux du meilleur au moins bon
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ConductScale {
+    pub name: String,
+    pub levels: Vec<ConductLevel>,
+}
+
+fn level(
+    code: &str,
+    label: &str,
+    abbreviation: &str,
+    aliases: &[&str],
+    blocks_promotion: bool,
+) -> ConductLevel {
+    ConductLevel {
+        code: code.to_string(),
+        label: label.to_string(),
+        abbreviation: abbreviation.to_string(),
+        aliases: aliases.iter().map(|a| a.to_string()).collect(),
+        blocks_promotion,
+    }
+}
+
+// Sans casse, sans accents ni ponctuation : « T. Bon » et « tres bon » se valent
+fn normalize(value: &str) -> String {
+    value
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'é' | 'è' | 'ê' | 'ë' => 'e',
+            'à' | 'â' => 'a',
+            'î' | 'ï' => 'i',
+            'ô' => 'o',
+            'ù' | 'û' => 'u',
+            'ç' => 'c',
+            '.' | '-' | '_' => ' ',
+            other => other,
+        })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl Default for ConductScale {
+    fn default() -> Self {
+        ConductScale::mentions()
+    }
+}
+
+impl ConductScale {
+    // Échelle des bulletins ; les codes reprennent les valeurs du formulaire élève
+    pub fn mentions() -> Self {
+        ConductScale {
+            name: "Mentions".to_string(),
+            levels: vec![
+                level("elite", "Élite", "E", &["elute"], false),
+                level("tres bon", "Très bon", "TB", &["t bon"], false),
+                level("bon", "Bon", "B", &[], false),
+                level("assez bon", "Assez bon", "AB", &["mediocre"], false),
+                level(
+                    "mauvais",
+                    "Mauvais",
+                    "Ma",
+                    &["mauvaise", "insuffisant"],
+                    true,
+                ),
+            ],
+        }
+    }
+
+    pub fn letters() -> Self {
+        ConductScale {
+            name: "Lettres".to_string(),
+            levels: ["A", "B", "C", "D", "E"]
+                .iter()
+                .enumerate()
+                .map(|(i, letter)| level(letter, letter, letter, &[], i == 4))
+                .collect(),
+        }
+    }
+
+    pub fn load(conn: &Connection) -> Result<Self, String> {
+        let stored: Option<String> = conn
+            .query_row(
+                "SELECT value FROM settings WHERE key = ?",
+                params![SCALE_KEY],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?;
+        let Some(json) = stored else {
+            return Ok(ConductScale::default());
+        };
+        let mut scale: ConductScale = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+        scale.validate()?;
+        Ok(scale)
+    }
+
+    pub fn validate(&mut self) -> Result<(), String> {
+        if self.levels.len() < 2 {
+            return Err("L'échelle de conduite doit compter au moins deux niveaux".to_string());
+        }
+        let mut seen = HashSet::new();
+        for level in self.levels.iter_mut() {
+            level.code = level.code.trim().to_string();
+            level.label = level.label.trim().to_string();
+            if level.code.is_empty() || level.label.is_empty() {
+                return Err(
+                    "Chaque niveau de conduite doit avoir un code et un libellé".to_string()
+                );
+            }
+            let names = [&level.code, &level.label, &level.abbreviation]
+                .into_iter()
+                .chain(level.aliases.iter())
+                .map(|n| normalize(n))
+                .filter(|n| !n.is_empty())
+                .collect::<HashSet<_>>();
+            for name in names {
+                if !seen.insert(name.clone()) {
+                    return Err(format!("« {} » désigne deux niveaux de conduite", name));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Rang du niveau (0 = le meilleur) correspondant à une saisie, libre ou codée
+    pub fn parse(&self, value: &str) -> Option<usize> {
+        let wanted = normalize(value);
+        if wanted.is_empty() {
+            return None;
+        }
+        self.levels.iter().position(|level| {
+            [&level.code, &level.label, &level.abbreviation]
+                .into_iter()
+                .chain(level.aliases.iter())
+                .any(|n| normalize(n) == wanted)
+        })
+    }
+
+    // Conduite annuelle : moyenne des rangs des périodes, arrondie vers le niveau inférieur
+    // (rang supérieur) : [Élite, Élite, Élite, Très bon] donne Très bon
+    pub fn annual(&self, periods: &[usize]) -> Option<usize> {
+        if periods.is_empty() {
+            return None;
+        }
+        let mean = periods.iter().sum::<usize>().div_ceil(periods.len());
+        Some(mean.min(self.levels.len().saturating_sub(1)))
+    }
+
+    // Poids dans le signalement des élèves à risque : les deux derniers niveaux comptent
+    pub fn risk_weight(&self, rank: usize) -> u32 {
+        match self.levels.len().saturating_sub(rank + 1) {
+            0 => 2,
+            1 => 1,
+            _ => 0,
+        }
+    }
+
+    fn expected(&self) -> String {
+        self.levels
+            .iter()
+            .map(|l| l.label.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PeriodConduct {
+    pub period: String,
+    // Valeur de la base telle quelle
+    pub raw: String,
+    // None si vide ou absente de l'échelle
+    pub code: Option<String>,
+    pub label: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct StudentConduct {
+    pub student_id: i64,
+    pub student_name: String,
+    pub periods: Vec<PeriodConduct>,
+    // Calculée sur les périodes reconnues
+    pub computed: Option<String>,
+    // Décision du conseil si elle existe, sinon la conduite calculée
+    pub annual: Option<String>,
+    pub annual_label: Option<String>,
+    pub overridden: bool,
+    pub blocks_promotion: bool,
+    // Saisies hors échelle, à corriger
+    pub invalid: Vec<String>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassConduct {
+    pub class_id: i64,
+    pub class_name: String,
+    pub scale: ConductScale,
+    pub students: Vec<StudentConduct>,
+}
+
+fn student_conduct(
+    scale: &ConductScale,
+    student_id: i64,
+    student_name: String,
+    annual_raw: &str,
+    period_raws: Vec<String>,
+) -> StudentConduct {
+    let mut invalid = Vec::new();
+    let mut ranks = Vec::new();
+    let periods = PERIOD_COLUMNS
+        .iter()
+        .zip(period_raws)
+        .map(|((period, _), raw)| {
+            let rank = scale.parse(&raw);
+            match rank {
+                Some(r) => ranks.push(r),
+                None if !raw.trim().is_empty() => invalid.push(format!("{} : {}", period, raw)),
+                None => {}
+            }
+            PeriodConduct {
+                period: period.to_string(),
+                code: rank.map(|r| scale.levels[r].code.clone()),
+                label: rank.map(|r| scale.levels[r].label.clone()),
+                raw,
+            }
+        })
+        .collect();
+
+    let computed = scale.annual(&ranks);
+    let decided = scale.parse(annual_raw);
+    if decided.is_none() && !annual_raw.trim().is_empty() {
+        invalid.push(format!("{} : {}", ANNUAL_PERIOD, annual_raw));
+    }
+    let annual = decided.or(computed).map(|r| &scale.levels[r]);
+    StudentConduct {
+        student_id,
+        student_name,
+        periods,
+        computed: computed.map(|r| scale.levels[r].code.clone()),
+        annual: annual.map(|l| l.code.clone()),
+        annual_label: annual.map(|l| l.label.clone()),
+        overridden: decided.is_some(),
+        blocks_promotion: annual.is_some_and(|l| l.blocks_promotion),
+        invalid,
+    }
+}
+
+fn conduct_select(filter: &str) -> String {
+    format!(
+        "SELECT id, COALESCE(last_name, ''), COALESCE(post_name, ''), COALESCE(first_name, ''),
+                COALESCE(conduite, ''), {}
+         FROM students WHERE {} ORDER BY last_name, post_name, first_name",
+        PERIOD_COLUMNS
+            .iter()
+            .map(|(_, column)| format!("COALESCE({}, '')", column))
+            .collect::<Vec<_>>()
+            .join(", "),
+        filter
+    )
+}
+
+fn read_conduct(scale: &ConductScale, row: &rusqlite::Row) -> rusqlite::Result<StudentConduct> {
+    // Même nom affiché que le carnet de cotes
+    let name = [row.get::<_, String>(1)?, row.get(2)?, row.get(3)?]
+        .iter()
+        .filter(|s| !s.is_empty())
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(" ");
+    let annual_raw: String = row.get(4)?;
+    let period_raws = (0..PERIOD_COLUMNS.len())
+        .map(|i| row.get(i + 5))
+        .collect::<rusqlite::Result<Vec<String>>>()?;
+    Ok(student_conduct(
+        scale,
+        row.get(0)?,
+        name,
+        &annual_raw,
+        period_raws,
+    ))
+}
+
+pub fn class_conduct(conn: &Connection, class_id: i64) -> Result<ClassConduct, String> {
+    let class_name: String = conn
+        .query_row(
+            "SELECT name FROM classes WHERE id = ?",
+            params![class_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Classe introuvable".to_string())?;
+    let scale = ConductScale::load(conn)?;
+    let students = conn
+        .prepare(&conduct_select("class_id = ?"))
+        .and_then(|mut stmt| {
+            stmt.query_map(params![class_id], |row| read_conduct(&scale, row))?
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .map_err(|e| e.to_string())?;
+    Ok(ClassConduct {
+        class_id,
+        class_name,
+        scale,
+        students,
+    })
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ConductUpdate {
+    pub student_id: i64,
+    // P1 à P4, ou ANNUEL pour la décision du conseil de classe
+    pub period: String,
+    // Vide ou absent : efface la conduite
+    #[serde(default)]
+    pub value: Option<String>,
+}
+
+// Écrit une conduite contrôlée contre l'échelle ; renvoie le code enregistré
+pub fn set_conduct(
+    conn: &Connection,
+    scale: &ConductScale,
+    update: &ConductUpdate,
+) -> Result<String, String> {
+    let period = update.period.trim().to_uppercase();
+    let column = if period == ANNUAL_PERIOD {
+        "conduite"
+    } else {
+        PERIOD_COLUMNS
+            .iter()
+            .find(|(p, _)| *p == period)
+            .map(|(_, column)| *column)
+            .ok_or_else(|| format!("Période de conduite inconnue : {}", update.period))?
+    };
+    let raw = update.value.as_deref().unwrap_or("").trim();
+    let code = if raw.is_empty() {
+        String::new()
+    } else {
+        let rank = scale.parse(raw).ok_or_else(|| {
+            format!(
+                "Conduite « {} » absente de l'échelle ({})",
+                raw,
+                scale.expected()
+            )
+        })?;
+        scale.levels[rank].code.clone()
+    };
+    let changed = conn
+        .execute(
+            &format!(
+                "UPDATE students SET {} = ?, is_dirty = 1, last_modified_at = datetime('now') WHERE id = ?",
+                column
+            ),
+            params![code, update.student_id],
+        )
+        .map_err(|e| e.to_string())?;
+    if changed == 0 {
+        return Err(format!("Élève {} introuvable", update.student_id));
+    }
+    Ok(code)
+}
+
+// Plusieurs saisies d'un coup (téléphone du titulaire) : tout ou rien
+pub fn set_conduct_batch(
+    conn: &mut Connection,
+    updates: &[ConductUpdate],
+) -> Result<Vec<ConductUpdate>, String> {
+    let scale = ConductScale::load(conn)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let mut saved = Vec::new();
+    for update in updates {
+        let code = set_conduct(&tx, &scale, update)?;
+        saved.push(ConductUpdate {
+            student_id: update.student_id,
+            period: update.period.trim().to_uppercase(),
+            value: (!code.is_empty()).then_some(code),
+        });
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(saved)
+}
+
+// Conduite annuelle de chaque élève de la classe
+pub fn annual_levels(
+    conn: &Connection,
+    class_id: i64,
+) -> Result<HashMap<i64, ConductLevel>, String> {
+    let result = class_conduct(conn, class_id)?;
+    Ok(result
+        .students
+        .into_iter()
+        .filter_map(|student| {
+            let code = student.annual?;
+            let level = result.scale.levels.iter().find(|l| l.code == code)?;
+            Some((student.student_id, level.clone()))
+        })
+        .collect())
+}
+
+// Une conduite bloquante transforme une réussite en échec quand les règles le prévoient
+pub fn apply_to_deliberation(
+    conn: &Connection,
+    class_id: i64,
+    config: &DelibConfig,
+    mode: DelibMode,
+    results: &mut [StudentDeliberation],
+) -> Result<(), String> {
+    let levels = annual_levels(conn, class_id)?;
+    for result in results.iter_mut() {
+        let Some(level) = levels.get(&result.student_id) else {
+            continue;
+        };
+        result.conduct = Some(level.label.clone());
+        if !config.conduite_bloque_promotion || !level.blocks_promotion {
+            continue;
+        }
+        if matches!(result.verdict, Verdict::Admis | Verdict::DeuxiemeSession) {
+            result.reasoning.push(format!(
+                "Conduite annuelle « {} » : promotion refusée",
+                level.label
+            ));
+            result.verdict = Verdict::Echec;
+            result.verdict_label = Verdict::Echec.label(mode).to_string();
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn conduct_scale_get(pool: tauri::State<'_, DbPool>) -> Result<ConductScale, String> {
+    pool.run(|conn| ConductScale::load(conn)).await
+}
+
+#[tauri::command]
+pub fn conduct_scale_presets() -> Vec<ConductScale> {
+    vec![ConductScale::mentions(), ConductScale::letters()]
+}
+
+#[tauri::command]
+pub async fn conduct_scale_set(
+    pool: tauri::State<'_, DbPool>,
+    mut scale: ConductScale,
+) -> Result<ConductScale, String> {
+    scale.validate()?;
+    let json = serde_json::to_string(&scale).map_err(|e| e.to_string())?;
+    pool.run(move |conn| {
+        conn.execute(
+            "INSERT OR REPLACE INTO settings (key, value) VALUES (?, ?)",
+            params![SCALE_KEY, json],
+        )
+        .map_err(|e| e.to_string())
+    })
+    .await?;
+    info!(
+        "Échelle de conduite « {} » : {} niveau(x)",
+        scale.name,
+        scale.levels.len()
+    );
+    Ok(scale)
+}
+
+#[tauri::command]
+pub async fn class_conduct_report(
+    pool: tauri::State<'_, DbPool>,
+    class_id: i64,
+) -> Result<ClassConduct, String> {
+    pool.run(move |conn| class_conduct(conn, class_id)).await
+}
+
+#[tauri::command]
+pub async fn student_conduct_set(
+    pool: tauri::State<'_, DbPool>,
+    updates: Vec<ConductUpdate>,
+) -> Result<Vec<ConductUpdate>, String> {
+    let saved = pool
+        .run(move |conn| set_conduct_batch(conn, &updates))
+        .await?;
+    info!("Conduite : {} saisie(s) enregistrée(s)", saved.len());
+    Ok(saved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::TestDb;
+    use crate::deliberation::{self, Verdict};
+
+    #[test]
+    fn saisies_libres_reconnues() {
+        let scale = ConductScale::mentions();
+        assert_eq!(scale.parse("Élite"), Some(0));
+        assert_eq!(scale.parse("elute"), Some(0));
+        assert_eq!(scale.parse("T. Bon"), Some(1));
+        assert_eq!(scale.parse("  TRÈS   bon "), Some(1));
+        assert_eq!(scale.parse("AB"), Some(3));
+        assert_eq!(scale.parse("Médiocre"), Some(3));
+        assert_eq!(scale.parse("mauvaise"), Some(4));
+        assert_eq!(scale.parse(""), None);
+        assert_eq!(scale.parse("n'importe"), None);
+        assert_eq!(ConductScale::letters().parse("c"), Some(2));
+    }
+
+    #[test]
+    fn conduite_annuelle_arrondie_vers_le_niveau_inferieur() {
+        let scale = ConductScale::mentions();
+        assert_eq!(scale.annual(&[]), None);
+        assert_eq!(scale.annual(&[2]), Some(2));
+        assert_eq!(scale.annual(&[0, 0, 0, 1]), Some(1));
+        assert_eq!(scale.annual(&[1, 2]), Some(2));
+        assert_eq!(scale.annual(&[0, 0, 0, 0]), Some(0));
+        assert_eq!(scale.annual(&[4, 4, 3, 4]), Some(4));
+        assert_eq!(
+            [0, 1, 2, 3, 4].map(|rank| scale.risk_weight(rank)),
+            [0, 0, 0, 1, 2]
+        );
+    }
+
+    #[test]
+    fn echelle_invalide_refusee() {
+        let mut empty = ConductScale {
+            name: "Vide".to_string(),
+            levels: Vec::new(),
+        };
+        assert!(empty.validate().is_err());
+        let mut duplicate = ConductScale::mentions();
+        duplicate.levels[1].aliases.push("Bon".to_string());
+        assert!(duplicate.validate().is_err());
+
+        // Une échelle enregistrée invalide n'est pas utilisée telle quelle
+        let db = TestDb::open();
+        let conn = db.conn();
+        assert_eq!(ConductScale::load(&conn).unwrap(), ConductScale::mentions());
+        conn.execute(
+            "INSERT INTO settings (key, value) VALUES (?, '{\"name\": \"Vide\", \"levels\": []}')",
+            params![SCALE_KEY],
+        )
+        .unwrap();
+        assert!(ConductScale::load(&conn).is_err());
+    }
+
+    fn class_db() -> TestDb {
+        let db = TestDb::open();
+        db.conn()
+            .execute_batch(
+                "INSERT INTO academic_years (id, name, start_date, end_date) VALUES (1, '2024-2025', '2024-09-01', '2025-07-01');
+                 INSERT INTO classes (id, name, level, option, section, academic_year_id) VALUES (1, '2ème ETRO', '2ème', 'ELECTRONIQUE', 'A', 1);
+                 INSERT INTO subjects (id, name, code, class_id) VALUES (1, 'Maths', 'MATH', 1);
+                 INSERT INTO students (id, first_name, last_name, gender, class_id, conduite_p1) VALUES
+                   (1, 'Jean', 'Kabila', 'M', 1, 'elute'), (2, 'Marie', 'Mbuyi', 'F', 1, 'n''importe');
+                 -- 80 % partout : les deux élèves sont admis sur les cotes
+                 INSERT INTO grades (student_id, subject_id, period, value)
+                   SELECT s.id, 1, p.code, p.default_max * 0.8 FROM students s, periods p WHERE p.academic_year_id = 1;",
+            )
+            .unwrap();
+        db
+    }
+
+    fn update(student_id: i64, period: &str, value: Option<&str>) -> ConductUpdate {
+        ConductUpdate {
+            student_id,
+            period: period.to_string(),
+            value: value.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn saisie_groupee_tout_ou_rien() {
+        let db = class_db();
+        let mut conn = db.conn();
+        let report = class_conduct(&conn, 1).unwrap();
+        assert_eq!(report.students[0].annual.as_deref(), Some("elite"));
+        assert_eq!(report.students[1].invalid, ["P1 : n'importe"]);
+
+        // Une valeur hors échelle ou une période inconnue annule tout le lot
+        assert!(set_conduct_batch(
+            &mut conn,
+            &[
+                update(1, "P2", Some("T. Bon")),
+                update(2, "P9", Some("bon"))
+            ]
+        )
+        .is_err());
+        assert!(set_conduct_batch(&mut conn, &[update(1, "p2", Some("zzz"))]).is_err());
+        assert!(set_conduct_batch(&mut conn, &[update(99, "P1", Some("bon"))]).is_err());
+        let p2: String = conn
+            .query_row("SELECT conduite_p2 FROM students WHERE id = 1", [], |r| {
+                r.get(0)
+            })
+            .unwrap();
+        assert_eq!(p2, "");
+
+        let saved = set_conduct_batch(
+            &mut conn,
+            &[
+                update(1, "p2", Some("T. Bon")),
+                update(2, "P1", Some("mauvaise")),
+                update(2, "P2", Some("Médiocre")),
+                update(1, "ANNUEL", None),
+            ],
+        )
+        .unwrap();
+        assert_eq!(saved[0].period, "P2");
+        assert_eq!(saved[0].value.as_deref(), Some("tres bon"));
+        assert_eq!(saved[3].value, None);
+
+        let report = class_conduct(&conn, 1).unwrap();
+        assert_eq!(report.students[0].annual.as_deref(), Some("tres bon"));
+        assert!(!report.students[0].overridden);
+        assert_eq!(report.students[1].annual.as_deref(), Some("mauvais"));
+        assert!(report.students[1].blocks_promotion);
+        assert!(report.students[1].invalid.is_empty());
+
+        // Décision du conseil de classe, prioritaire sur le calcul
+        set_conduct_batch(&mut conn, &[update(2, "ANNUEL", Some("Bon"))]).unwrap();
+        let report = class_conduct(&conn, 1).unwrap();
+        assert_eq!(report.students[1].annual.as_deref(), Some("bon"));
+        assert_eq!(report.students[1].computed.as_deref(), Some("mauvais"));
+        assert!(report.students[1].overridden);
+    }
+
+    #[test]
+    fn conduite_bloquante_selon_les_parametres() {
+        let db = class_db();
+        let mut conn = db.conn();
+        set_conduct_batch(
+            &mut conn,
+            &[
+                update(2, "P1", Some("Mauvais")),
+                update(2, "P2", Some("Mauvais")),
+            ],
+        )
+        .unwrap();
+
+        let result = deliberation::deliberate(&conn, 1, DelibMode::FirstSession).unwrap();
+        assert_eq!(result.students[1].conduct.as_deref(), Some("Mauvais"));
+        assert!(result.students.iter().all(|s| s.verdict == Verdict::Admis));
+
+        // Interrupteur de la page de paramètres
+        conn.execute(
+            "INSERT OR REPLACE INTO settings (key, value) VALUES ('delib_conduiteBloquePromotion', 'true')",
+            [],
+        )
+        .unwrap();
+        let result = deliberation::deliberate(&conn, 1, DelibMode::FirstSession).unwrap();
+        assert_eq!(result.students[0].verdict, Verdict::Admis);
+        assert_eq!(result.students[0].conduct.as_deref(), Some("Élite"));
+        let blocked = &result.students[1];
+        assert_eq!(blocked.verdict, Verdict::Echec);
+        assert_eq!(
+            blocked.verdict_label,
+            Verdict::Echec.label(DelibMode::FirstSession)
+        );
+        assert_eq!(
+            blocked.reasoning.last().map(String::as_str),
+            Some("Conduite annuelle « Mauvais » : promotion refusée")
+        );
+    }
+}
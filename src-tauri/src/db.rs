@@ -1,12 +1,229 @@
+use crate::backup::{self, BackupKind};
+use crate::encryption;
+use crate::migrations;
 use log::info;
+use rusqlite::types::ValueRef;
 use rusqlite::Connection;
-use std::path::Path;
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
 
-pub fn initialize_db(db_path: &Path) -> Result<(), String> {
+// Réglages appliqués à chaque connexion ouverte par le backend
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+const STATEMENT_CACHE_CAPACITY: usize = 64;
+// Connexions gardées ouvertes au repos (le serveur mobile crée un thread par requête)
+const MAX_IDLE_CONNECTIONS: usize = 8;
+// Attente maximale des connexions en cours avant un accès exclusif (remplacement du fichier)
+const EXCLUSIVE_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Ouvre une connexion configurée : WAL, busy_timeout commun, clés étrangères actives.
+// Une base chiffrée n'est accessible qu'une fois déverrouillée par le mot de passe local.
+// Une base restée en clair s'ouvre sans clé, même une fois la base principale déverrouillée.
+pub fn open_connection(db_path: &Path) -> Result<Connection, String> {
+    let encrypted = encryption::is_enabled(db_path);
+    if encrypted && !encryption::is_unlocked() {
+        return Err(encryption::LOCKED_ERROR.to_string());
+    }
     let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
-    println!("Database path: {}", db_path.display());
+    if encrypted {
+        encryption::apply_key(&conn)?;
+    }
+    conn.busy_timeout(BUSY_TIMEOUT).map_err(|e| e.to_string())?;
+    conn.query_row("PRAGMA journal_mode = WAL", [], |row| {
+        row.get::<_, String>(0)
+    })
+    .map_err(|e| e.to_string())?;
     conn.execute("PRAGMA foreign_keys = ON", [])
         .map_err(|e| e.to_string())?;
+    conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE_CAPACITY);
+    Ok(conn)
+}
+
+// Conversion d'une valeur SQLite en JSON (quarantaine, requêtes du frontend)
+pub fn value_to_json(value: ValueRef) -> serde_json::Value {
+    match value {
+        ValueRef::Null => serde_json::Value::Null,
+        ValueRef::Integer(v) => serde_json::json!(v),
+        ValueRef::Real(v) => serde_json::json!(v),
+        ValueRef::Text(v) => serde_json::json!(String::from_utf8_lossy(v)),
+        ValueRef::Blob(v) => serde_json::json!(v),
+    }
+}
+
+// Pool de connexions partagé, stocké dans l'état Tauri (app.manage)
+#[derive(Clone)]
+pub struct DbPool {
+    inner: Arc<PoolInner>,
+}
+
+struct PoolInner {
+    path: PathBuf,
+    idle: Mutex<Vec<Connection>>,
+    checkouts: Mutex<Checkouts>,
+    checkouts_changed: Condvar,
+}
+
+#[derive(Default)]
+struct Checkouts {
+    // Connexions actuellement prêtées par get()
+    active: usize,
+    // Accès exclusif en cours : get() attend qu'il se termine
+    exclusive: bool,
+}
+
+// Tant qu'il est gardé, plus aucune connexion n'est prêtée par le pool
+pub struct ExclusiveAccess {
+    pool: Arc<PoolInner>,
+}
+
+pub struct PooledConnection {
+    conn: Option<Connection>,
+    pool: Arc<PoolInner>,
+}
+
+impl DbPool {
+    pub fn new(path: PathBuf) -> Self {
+        DbPool {
+            inner: Arc::new(PoolInner {
+                path,
+                idle: Mutex::new(Vec::new()),
+                checkouts: Mutex::new(Checkouts::default()),
+                checkouts_changed: Condvar::new(),
+            }),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.inner.path
+    }
+
+    // Ferme les connexions au repos (avant de remplacer le fichier de la base)
+    pub fn clear_idle(&self) {
+        self.inner.idle.lock().unwrap().clear();
+    }
+
+    // Attend que les connexions prêtées soient rendues, puis bloque les nouvelles
+    // jusqu'à la fin de l'accès exclusif (chiffrement de la base en place)
+    pub fn exclusive(&self) -> Result<ExclusiveAccess, String> {
+        let inner = &self.inner;
+        let checkouts = inner
+            .checkouts_changed
+            .wait_while(inner.checkouts.lock().unwrap(), |c| c.exclusive)
+            .unwrap();
+        let mut checkouts = checkouts;
+        checkouts.exclusive = true;
+        let (mut checkouts, timeout) = inner
+            .checkouts_changed
+            .wait_timeout_while(checkouts, EXCLUSIVE_TIMEOUT, |c| c.active > 0)
+            .unwrap();
+        if timeout.timed_out() {
+            checkouts.exclusive = false;
+            inner.checkouts_changed.notify_all();
+            return Err("La base est occupée, réessayez dans un instant".to_string());
+        }
+        drop(checkouts);
+        self.clear_idle();
+        Ok(ExclusiveAccess {
+            pool: inner.clone(),
+        })
+    }
+
+    pub fn get(&self) -> Result<PooledConnection, String> {
+        let inner = &self.inner;
+        inner
+            .checkouts_changed
+            .wait_while(inner.checkouts.lock().unwrap(), |c| c.exclusive)
+            .unwrap()
+            .active += 1;
+        let idle = inner.idle.lock().unwrap().pop();
+        let conn = match idle.map(Ok).unwrap_or_else(|| open_connection(&inner.path)) {
+            Ok(conn) => conn,
+            Err(e) => {
+                inner.release_checkout();
+                return Err(e);
+            }
+        };
+        Ok(PooledConnection {
+            conn: Some(conn),
+            pool: self.inner.clone(),
+        })
+    }
+
+    // Exécute un travail SQLite sur un thread bloquant, hors du runtime async
+    pub async fn run<T, F>(&self, f: F) -> Result<T, String>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> Result<T, String> + Send + 'static,
+    {
+        let pool = self.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            let mut conn = pool.get()?;
+            f(&mut conn)
+        })
+        .await
+        .map_err(|e| e.to_string())?
+    }
+
+    // Variante pour les traitements qui ont besoin du chemin (sauvegardes, restauration...)
+    pub async fn run_with_path<T, F>(&self, f: F) -> Result<T, String>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Path) -> Result<T, String> + Send + 'static,
+    {
+        let path = self.inner.path.clone();
+        tauri::async_runtime::spawn_blocking(move || f(&path))
+            .await
+            .map_err(|e| e.to_string())?
+    }
+}
+
+impl PoolInner {
+    fn release_checkout(&self) {
+        self.checkouts.lock().unwrap().active -= 1;
+        self.checkouts_changed.notify_all();
+    }
+}
+
+impl Drop for ExclusiveAccess {
+    fn drop(&mut self) {
+        self.pool.checkouts.lock().unwrap().exclusive = false;
+        self.pool.checkouts_changed.notify_all();
+    }
+}
+
+impl Deref for PooledConnection {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for PooledConnection {
+    fn deref_mut(&mut self) -> &mut Connection {
+        self.conn.as_mut().unwrap()
+    }
+}
+
+impl Drop for PooledConnection {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            // Une connexion restée dans une transaction n'est pas réutilisable
+            if conn.is_autocommit() {
+                let mut idle = self.pool.idle.lock().unwrap();
+                if idle.len() < MAX_IDLE_CONNECTIONS {
+                    idle.push(conn);
+                }
+            }
+        }
+        self.pool.release_checkout();
+    }
+}
+
+pub fn initialize_db(db_path: &Path) -> Result<(), String> {
+    let mut conn = open_connection(db_path)?;
+    println!("Database path: {}", db_path.display());
 
     // Base schema
     conn.execute_batch(
@@ -215,80 +432,21 @@ pub fn initialize_db(db_path: &Path) -> Result<(), String> {
     )
     .map_err(|e| e.to_string())?;
 
-    // Migration logic for existing databases
-    let _ = conn.execute("ALTER TABLE notes ADD COLUMN tags TEXT DEFAULT ''", []);
-    let _ = conn.execute(
-        "ALTER TABLE sync_deletions ADD COLUMN server_id TEXT NOT NULL DEFAULT ''",
-        [],
-    );
-
-    // Normalize legacy NULL text values so older databases can sync cleanly.
-    let _ = conn.execute(
-        "UPDATE students SET first_name = COALESCE(first_name, ''), last_name = COALESCE(last_name, ''), post_name = COALESCE(post_name, ''), gender = COALESCE(gender, ''), birth_date = birth_date, birthplace = COALESCE(birthplace, ''), conduite = COALESCE(conduite, ''), conduite_p1 = COALESCE(conduite_p1, ''), conduite_p2 = COALESCE(conduite_p2, ''), conduite_p3 = COALESCE(conduite_p3, ''), conduite_p4 = COALESCE(conduite_p4, ''), abandon_reason = COALESCE(abandon_reason, '')",
-        [],
-    );
-
-    let sync_tables = vec![
-        "academic_years",
-        "classes",
-        "students",
-        "subjects",
-        "grades",
-        "notes",
-        "domains",
-        "repechages",
-        "options",
-        "custom_sorts",
-    ];
-
-    for table in sync_tables {
-        let _ = conn.execute(
-            &format!("ALTER TABLE {table} ADD COLUMN server_id TEXT"),
-            [],
-        );
-        let _ = conn.execute(
-            &format!("ALTER TABLE {table} ADD COLUMN is_dirty INTEGER DEFAULT 1"),
-            [],
-        );
-        let _ = conn.execute(&format!("ALTER TABLE {table} ADD COLUMN last_modified_at TEXT DEFAULT '1970-01-01 00:00:00'"), []);
-        let _ = conn.execute(
-            &format!(
-                "ALTER TABLE {table} ADD COLUMN updated_at TEXT DEFAULT '1970-01-01 00:00:00'"
-            ),
-            [],
-        );
+    // Migrations versionnées (voir migrations.rs)
+    // Instantané de sécurité avant de toucher au schéma d'une base existante
+    let has_data: bool = conn
+        .query_row("SELECT EXISTS(SELECT 1 FROM academic_years)", [], |r| {
+            r.get(0)
+        })
+        .map_err(|e| e.to_string())?;
+    if has_data && !migrations::pending_migrations(&conn)?.is_empty() {
+        backup::create_backup(db_path, BackupKind::PreMigration)?;
     }
-
-    let _ = conn.execute(
-        "ALTER TABLE subjects ADD COLUMN category TEXT DEFAULT ''",
-        [],
-    );
-    let _ = conn.execute(
-        "ALTER TABLE subjects ADD COLUMN sub_domain TEXT DEFAULT ''",
-        [],
-    );
-    let _ = conn.execute("ALTER TABLE subjects ADD COLUMN domain_id INTEGER", []);
-    // Migration: ordre d'affichage des matières (bulletin, grille)
-    let _ = conn.execute(
-        "ALTER TABLE subjects ADD COLUMN display_order INTEGER DEFAULT 0",
-        [],
-    );
-
-    // Migration: Ajout du marqueur "Voir Bureau" pour les repêchages (dette élève)
-    let _ = conn.execute(
-        "ALTER TABLE repechages ADD COLUMN voir_bureau INTEGER NOT NULL DEFAULT 0",
-        [],
-    );
-
-    // Migration: Ajouter les colonnes manquantes à la table options (pour les bases existantes)
-    let _ = conn.execute(
-        "ALTER TABLE options ADD COLUMN value TEXT NOT NULL DEFAULT ''",
-        [],
-    );
-    let _ = conn.execute(
-        "ALTER TABLE options ADD COLUMN short TEXT NOT NULL DEFAULT ''",
-        [],
-    );
+    let applied = migrations::run_pending(&mut conn)?;
+    if !applied.is_empty() {
+        info!("Applied migrations: {:?}", applied);
+    }
+    info!("Schema version: {}", migrations::current_version(&conn)?);
 
     // Setup triggers for all sync tables
     let sync_tables = vec![
@@ -302,6 +460,9 @@ pub fn initialize_db(db_path: &Path) -> Result<(), String> {
         "repechages",
         "options",
         "custom_sorts",
+        "periods",
+        "evaluations",
+        "evaluation_scores",
     ];
 
     for table in sync_tables {
@@ -404,3 +565,89 @@ pub fn initialize_db(db_path: &Path) -> Result<(), String> {
     info!("Database initialized and schema verified.");
     Ok(())
 }
+
+#[cfg(test)]
+pub mod test_support {
+    use super::{initialize_db, open_connection};
+    use rusqlite::Connection;
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static NEXT_DB: AtomicUsize = AtomicUsize::new(0);
+
+    // Base de test créée par initialize_db comme au démarrage, dans un dossier propre au
+    // test (les sauvegardes et archives s'écrivent à côté), supprimé à la fin du test
+    pub struct TestDb {
+        pub dir: PathBuf,
+        pub path: PathBuf,
+    }
+
+    impl TestDb {
+        pub fn open() -> Self {
+            let dir = std::env::temp_dir().join(format!(
+                "schoolab_test_{}_{}",
+                std::process::id(),
+                NEXT_DB.fetch_add(1, Ordering::SeqCst)
+            ));
+            let _ = std::fs::remove_dir_all(&dir);
+            std::fs::create_dir_all(&dir).unwrap();
+            let path = dir.join("ecole.db");
+            initialize_db(&path).unwrap();
+            TestDb { dir, path }
+        }
+
+        pub fn conn(&self) -> Connection {
+            open_connection(&self.path).unwrap()
+        }
+    }
+
+    impl Drop for TestDb {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_support::TestDb;
+    use super::*;
+    use std::sync::mpsc;
+    use std::thread;
+
+    #[test]
+    fn acces_exclusif_apres_les_connexions_en_cours() {
+        let db = TestDb::open();
+        let pool = DbPool::new(db.path.clone());
+        let borrowed = pool.get().unwrap();
+
+        let (sender, receiver) = mpsc::channel();
+        let waiting = pool.clone();
+        let handle = thread::spawn(move || {
+            let access = waiting.exclusive().unwrap();
+            sender.send(()).unwrap();
+            access
+        });
+        // La connexion prêtée doit être rendue avant l'accès exclusif
+        assert!(receiver.recv_timeout(Duration::from_millis(200)).is_err());
+        drop(borrowed);
+        receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        let access = handle.join().unwrap();
+        assert!(pool.inner.idle.lock().unwrap().is_empty());
+
+        // Pendant l'accès exclusif, get() attend
+        let (sender, receiver) = mpsc::channel();
+        let blocked = pool.clone();
+        let handle = thread::spawn(move || {
+            let conn = blocked.get().unwrap();
+            sender.send(()).unwrap();
+            conn.query_row("SELECT 1", [], |row| row.get::<_, i64>(0))
+                .unwrap()
+        });
+        assert!(receiver.recv_timeout(Duration::from_millis(200)).is_err());
+        drop(access);
+        receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(handle.join().unwrap(), 1);
+        assert_eq!(pool.inner.checkouts.lock().unwrap().active, 0);
+    }
+}
@@ -0,0 +1,531 @@
+// Jeux de règles de délibération par année scolaire, niveau et option. Chaque année
+// garde sa propre copie : une délibération rejouée sur une ancienne année applique
+// les règles de cette année-là, même après un changement ministériel.
+//
+// Pour une classe, le jeu le plus précis l'emporte : niveau et option, puis niveau,
+// puis option, puis le jeu général (niveau et option vides). Tant qu'aucun jeu n'a été
+// enregistré pour la classe, les clés delib_* de settings servent de règles : la page de
+// paramètres et le palmarès du frontend restent alors alignés sur le moteur Rust.
+
+use crate::db::DbPool;
+use crate::deliberation::DelibConfig;
+use crate::rollover::LEVELS;
+use log::info;
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleSet {
+    pub id: i64,
+    pub academic_year_id: i64,
+    pub level: String,
+    pub option: String,
+    pub name: String,
+    pub rules: DelibConfig,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleSetInput {
+    pub id: Option<i64>,
+    #[serde(default)]
+    pub level: String,
+    #[serde(default)]
+    pub option: String,
+    #[serde(default)]
+    pub name: String,
+    pub rules: DelibConfig,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolvedRules {
+    // Absent quand les règles viennent des paramètres généraux
+    pub rule_set_id: Option<i64>,
+    pub name: String,
+    pub rules: DelibConfig,
+}
+
+// Jeux créés pour une année sans règles : éducation de base et humanités
+fn default_rule_sets(conn: &Connection) -> Vec<(String, String, String, DelibConfig)> {
+    vec![
+        (
+            "7ème".to_string(),
+            String::new(),
+            "Éducation de base (7ème)".to_string(),
+            DelibConfig::from_settings(conn, "7ème"),
+        ),
+        (
+            "8ème".to_string(),
+            String::new(),
+            "Éducation de base (8ème)".to_string(),
+            DelibConfig::from_settings(conn, "8ème"),
+        ),
+        (
+            String::new(),
+            String::new(),
+            "Humanités".to_string(),
+            DelibConfig::from_settings(conn, "1ère"),
+        ),
+    ]
+}
+
+pub fn create_schema(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS deliberation_rule_sets (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            academic_year_id INTEGER NOT NULL,
+            level TEXT NOT NULL DEFAULT '',
+            option TEXT NOT NULL DEFAULT '',
+            name TEXT NOT NULL DEFAULT '',
+            -- DelibConfig sérialisé (seuils, règles de rachat, limites d'échecs)
+            rules TEXT NOT NULL,
+            created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+            updated_at TEXT DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (academic_year_id) REFERENCES academic_years(id) ON DELETE CASCADE,
+            UNIQUE(academic_year_id, level, option)
+        );",
+    )?;
+
+    // Les années existantes figent les critères actuellement enregistrés
+    let defaults = default_rule_sets(tx);
+    let years: Vec<i64> = tx
+        .prepare("SELECT id FROM academic_years")?
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+    {
+        let mut stmt = tx.prepare(
+            "INSERT OR IGNORE INTO deliberation_rule_sets (academic_year_id, level, option, name, rules)
+             VALUES (?, ?, ?, ?, ?)",
+        )?;
+        for year_id in years {
+            for (level, option, name, rules) in &defaults {
+                let json = serde_json::to_string(rules)
+                    .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+                stmt.execute(params![year_id, level, option, name, json])?;
+            }
+        }
+    }
+
+    // Une nouvelle année reprend les règles de la dernière année qui en a, ou les
+    // règles par défaut si c'est la première
+    let values: Vec<String> = defaults
+        .iter()
+        .map(|(level, option, name, rules)| {
+            let json = serde_json::to_string(rules).unwrap_or_default();
+            format!(
+                "('{}', '{}', '{}', '{}')",
+                level,
+                option,
+                name.replace('\'', "''"),
+                json.replace('\'', "''")
+            )
+        })
+        .collect();
+    tx.execute_batch(&format!(
+        "
+        CREATE TRIGGER IF NOT EXISTS trg_academic_years_rule_sets
+        AFTER INSERT ON academic_years
+        FOR EACH ROW
+        BEGIN
+            INSERT OR IGNORE INTO deliberation_rule_sets (academic_year_id, level, option, name, rules)
+            SELECT NEW.id, level, option, name, rules FROM deliberation_rule_sets
+            WHERE academic_year_id = (
+                SELECT MAX(academic_year_id) FROM deliberation_rule_sets WHERE academic_year_id <> NEW.id
+            );
+            INSERT OR IGNORE INTO deliberation_rule_sets (academic_year_id, level, option, name, rules)
+            SELECT NEW.id, column1, column2, column3, column4 FROM (VALUES {values})
+            WHERE NOT EXISTS (SELECT 1 FROM deliberation_rule_sets WHERE academic_year_id = NEW.id);
+        END;
+        ",
+        values = values.join(", ")
+    ))
+}
+
+// Migration 16 : les jeux semés par la migration 12 et par son trigger figeaient les
+// clés delib_* au moment de la migration, si bien que la page de paramètres n'atteignait
+// plus la délibération Rust. Les jeux jamais modifiés sont retirés (l'année retombe sur
+// settings) et une nouvelle année ne reprend que les jeux enregistrés de l'année qui la
+// précède (par date de début).
+pub fn settings_until_saved(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "DROP TRIGGER IF EXISTS trg_academic_years_rule_sets;
+
+        DELETE FROM deliberation_rule_sets
+        WHERE updated_at = created_at
+          AND (level, option, name) IN (
+              VALUES ('7ème', '', 'Éducation de base (7ème)'),
+                     ('8ème', '', 'Éducation de base (8ème)'),
+                     ('', '', 'Humanités'));
+
+        CREATE TRIGGER IF NOT EXISTS trg_academic_years_rule_sets
+        AFTER INSERT ON academic_years
+        FOR EACH ROW
+        BEGIN
+            INSERT OR IGNORE INTO deliberation_rule_sets (academic_year_id, level, option, name, rules)
+            SELECT NEW.id, level, option, name, rules FROM deliberation_rule_sets
+            WHERE academic_year_id = (
+                SELECT id FROM academic_years
+                WHERE id <> NEW.id AND start_date < NEW.start_date
+                ORDER BY start_date DESC, id DESC LIMIT 1
+            );
+        END;",
+    )
+}
+
+fn read_rule_set(row: &rusqlite::Row) -> rusqlite::Result<RuleSet> {
+    let json: String = row.get(5)?;
+    let rules = serde_json::from_str(&json).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(5, rusqlite::types::Type::Text, Box::new(e))
+    })?;
+    Ok(RuleSet {
+        id: row.get(0)?,
+        academic_year_id: row.get(1)?,
+        level: row.get(2)?,
+        option: row.get(3)?,
+        name: row.get(4)?,
+        rules,
+    })
+}
+
+pub fn list_rule_sets(conn: &Connection, year_id: i64) -> Result<Vec<RuleSet>, String> {
+    conn.prepare_cached(
+        "SELECT id, academic_year_id, level, option, name, rules
+         FROM deliberation_rule_sets WHERE academic_year_id = ? ORDER BY level, option, id",
+    )
+    .and_then(|mut stmt| {
+        stmt.query_map(params![year_id], read_rule_set)?
+            .collect::<Result<Vec<_>, _>>()
+    })
+    .map_err(|e| e.to_string())
+}
+
+pub fn rules_for(
+    conn: &Connection,
+    year_id: i64,
+    level: &str,
+    option: &str,
+) -> Result<ResolvedRules, String> {
+    let best = list_rule_sets(conn, year_id)?
+        .into_iter()
+        .filter(|r| {
+            (r.level.is_empty() || r.level == level) && (r.option.is_empty() || r.option == option)
+        })
+        .max_by_key(|r| (!r.level.is_empty() as u8) * 2 + !r.option.is_empty() as u8);
+    Ok(match best {
+        Some(rule_set) => ResolvedRules {
+            rule_set_id: Some(rule_set.id),
+            name: rule_set.name,
+            rules: rule_set.rules,
+        },
+        None => ResolvedRules {
+            rule_set_id: None,
+            name: "Paramètres généraux".to_string(),
+            rules: DelibConfig::from_settings(conn, level),
+        },
+    })
+}
+
+pub fn rules_for_class(conn: &Connection, class_id: i64) -> Result<ResolvedRules, String> {
+    let (year_id, level, option): (i64, String, String) = conn
+        .query_row(
+            "SELECT academic_year_id, level, option FROM classes WHERE id = ?",
+            params![class_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Classe introuvable".to_string())?;
+    rules_for(conn, year_id, &level, &option)
+}
+
+pub fn save_rule_set(
+    conn: &Connection,
+    year_id: i64,
+    mut input: RuleSetInput,
+) -> Result<RuleSet, String> {
+    input.rules.validate()?;
+    let level = input.level.trim().to_string();
+    let option = input.option.trim().to_string();
+    if !level.is_empty() && !LEVELS.contains(&level.as_str()) {
+        return Err(format!("Niveau inconnu : {}", level));
+    }
+    if !option.is_empty() {
+        let known: bool = conn
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM options WHERE value = ?)",
+                params![option],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        if !known {
+            return Err(format!("Option inconnue : {}", option));
+        }
+    }
+    let year_exists: bool = conn
+        .query_row(
+            "SELECT EXISTS(SELECT 1 FROM academic_years WHERE id = ?)",
+            params![year_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if !year_exists {
+        return Err("Année scolaire introuvable".to_string());
+    }
+
+    let duplicate: Option<i64> = conn
+        .query_row(
+            "SELECT id FROM deliberation_rule_sets WHERE academic_year_id = ? AND level = ? AND option = ?",
+            params![year_id, level, option],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    if duplicate.is_some_and(|id| Some(id) != input.id) {
+        return Err("Un jeu de règles existe déjà pour ce niveau et cette option".to_string());
+    }
+
+    let json = serde_json::to_string(&input.rules).map_err(|e| e.to_string())?;
+    let id = match input.id {
+        Some(id) => {
+            let updated = conn
+                .execute(
+                    "UPDATE deliberation_rule_sets
+                     SET level = ?, option = ?, name = ?, rules = ?, updated_at = CURRENT_TIMESTAMP
+                     WHERE id = ? AND academic_year_id = ?",
+                    params![level, option, input.name.trim(), json, id, year_id],
+                )
+                .map_err(|e| e.to_string())?;
+            if updated == 0 {
+                return Err("Jeu de règles introuvable pour cette année".to_string());
+            }
+            id
+        }
+        None => {
+            conn.execute(
+                "INSERT INTO deliberation_rule_sets (academic_year_id, level, option, name, rules)
+                 VALUES (?, ?, ?, ?, ?)",
+                params![year_id, level, option, input.name.trim(), json],
+            )
+            .map_err(|e| e.to_string())?;
+            conn.last_insert_rowid()
+        }
+    };
+
+    conn.query_row(
+        "SELECT id, academic_year_id, level, option, name, rules FROM deliberation_rule_sets WHERE id = ?",
+        params![id],
+        read_rule_set,
+    )
+    .map_err(|e| e.to_string())
+}
+
+// Remplace les règles d'une nouvelle année par celles d'une année existante
+pub fn copy_rule_sets(conn: &Connection, from_year_id: i64, to_year_id: i64) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM deliberation_rule_sets WHERE academic_year_id = ?",
+        params![to_year_id],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO deliberation_rule_sets (academic_year_id, level, option, name, rules)
+         SELECT ?, level, option, name, rules FROM deliberation_rule_sets WHERE academic_year_id = ?",
+        params![to_year_id, from_year_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn delib_rule_sets_list(
+    pool: tauri::State<'_, DbPool>,
+    academic_year_id: i64,
+) -> Result<Vec<RuleSet>, String> {
+    pool.run(move |conn| list_rule_sets(conn, academic_year_id))
+        .await
+}
+
+#[tauri::command]
+pub async fn delib_rule_set_save(
+    pool: tauri::State<'_, DbPool>,
+    academic_year_id: i64,
+    rule_set: RuleSetInput,
+) -> Result<RuleSet, String> {
+    let saved = pool
+        .run(move |conn| save_rule_set(conn, academic_year_id, rule_set))
+        .await?;
+    info!(
+        "Saved deliberation rules '{}' ({} / {}) for year {}",
+        saved.name, saved.level, saved.option, academic_year_id
+    );
+    Ok(saved)
+}
+
+#[tauri::command]
+pub async fn delib_rule_set_delete(pool: tauri::State<'_, DbPool>, id: i64) -> Result<(), String> {
+    pool.run(move |conn| {
+        conn.execute(
+            "DELETE FROM deliberation_rule_sets WHERE id = ?",
+            params![id],
+        )
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn delib_rules_for_class(
+    pool: tauri::State<'_, DbPool>,
+    class_id: i64,
+) -> Result<ResolvedRules, String> {
+    pool.run(move |conn| rules_for_class(conn, class_id)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::TestDb;
+
+    fn humanites(name: &str, seuil: f64) -> RuleSetInput {
+        RuleSetInput {
+            id: None,
+            level: String::new(),
+            option: String::new(),
+            name: name.to_string(),
+            rules: DelibConfig {
+                seuil_reussite_global: seuil,
+                ..DelibConfig::for_level("1ère")
+            },
+        }
+    }
+
+    fn set_setting(conn: &Connection, key: &str, value: &str) {
+        conn.execute(
+            "INSERT OR REPLACE INTO settings (key, value) VALUES (?, ?)",
+            params![key, value],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn parametres_generaux_tant_qu_aucun_jeu_n_est_enregistre() {
+        let db = TestDb::open();
+        let conn = db.conn();
+        conn.execute_batch(
+            "INSERT INTO academic_years (id, name, start_date, end_date) VALUES (1, '2024-2025', '2024-09-01', '2025-07-01');
+             INSERT INTO classes (id, name, level, option, section, academic_year_id) VALUES (1, '2ème ETRO', '2ème', 'ELECTRONIQUE', 'A', 1);",
+        )
+        .unwrap();
+        assert!(list_rule_sets(&conn, 1).unwrap().is_empty());
+        let resolved = rules_for_class(&conn, 1).unwrap();
+        assert_eq!(resolved.rule_set_id, None);
+        assert_eq!(resolved.rules.seuil_reussite_global, 50.0);
+
+        // Une modification de la page de paramètres atteint la délibération Rust
+        set_setting(&conn, "delib_seuilReussiteGlobal", "60");
+        set_setting(&conn, "delib_conduiteBloquePromotion", "true");
+        let resolved = rules_for_class(&conn, 1).unwrap();
+        assert_eq!(resolved.rules.seuil_reussite_global, 60.0);
+        assert!(resolved.rules.conduite_bloque_promotion);
+
+        // Un jeu enregistré explicitement l'emporte ensuite sur settings
+        let saved = save_rule_set(&conn, 1, humanites("Humanités 2024", 55.0)).unwrap();
+        set_setting(&conn, "delib_seuilReussiteGlobal", "70");
+        let resolved = rules_for_class(&conn, 1).unwrap();
+        assert_eq!(resolved.rule_set_id, Some(saved.id));
+        assert_eq!(resolved.rules.seuil_reussite_global, 55.0);
+    }
+
+    #[test]
+    fn jeu_le_plus_precis_et_validation() {
+        let db = TestDb::open();
+        let conn = db.conn();
+        conn.execute_batch(
+            "INSERT INTO academic_years (id, name, start_date, end_date) VALUES (1, '2024-2025', '2024-09-01', '2025-07-01');
+             INSERT INTO classes (id, name, level, option, section, academic_year_id) VALUES
+               (1, '2ème ETRO', '2ème', 'ELECTRONIQUE', 'A', 1), (2, '2ème ELEC', '2ème', 'ELECTRICITE', 'A', 1);",
+        )
+        .unwrap();
+        save_rule_set(&conn, 1, humanites("Humanités", 50.0)).unwrap();
+        let etro = save_rule_set(
+            &conn,
+            1,
+            RuleSetInput {
+                level: "2ème".to_string(),
+                option: "ELECTRONIQUE".to_string(),
+                ..humanites("ETRO 2", 55.0)
+            },
+        )
+        .unwrap();
+        assert_eq!(rules_for_class(&conn, 1).unwrap().rule_set_id, Some(etro.id));
+        assert_eq!(rules_for_class(&conn, 2).unwrap().name, "Humanités");
+
+        let unknown_level = RuleSetInput {
+            level: "9ème".to_string(),
+            ..humanites("?", 50.0)
+        };
+        assert!(save_rule_set(&conn, 1, unknown_level).is_err());
+        assert!(save_rule_set(&conn, 1, humanites("Doublon", 50.0)).is_err());
+        assert!(save_rule_set(&conn, 1, humanites("Hors bornes", 120.0)).is_err());
+    }
+
+    #[test]
+    fn nouvelle_annee_reprend_l_annee_precedente_par_date() {
+        let db = TestDb::open();
+        let conn = db.conn();
+        conn.execute(
+            "INSERT INTO academic_years (id, name, start_date, end_date) VALUES (1, '2024-2025', '2024-09-01', '2025-07-01')",
+            [],
+        )
+        .unwrap();
+        save_rule_set(&conn, 1, humanites("Règles 2024", 55.0)).unwrap();
+
+        // Année antérieure saisie après coup : rien ne la précède
+        conn.execute(
+            "INSERT INTO academic_years (id, name, start_date, end_date) VALUES (2, '2022-2023', '2022-09-01', '2023-07-01')",
+            [],
+        )
+        .unwrap();
+        assert!(list_rule_sets(&conn, 2).unwrap().is_empty());
+        save_rule_set(&conn, 2, humanites("Règles 2022", 45.0)).unwrap();
+
+        // L'année suivante reprend 2024-2025 et non la dernière année créée
+        conn.execute(
+            "INSERT INTO academic_years (id, name, start_date, end_date) VALUES (3, '2025-2026', '2025-09-01', '2026-07-01')",
+            [],
+        )
+        .unwrap();
+        let copied = list_rule_sets(&conn, 3).unwrap();
+        assert_eq!(copied.len(), 1);
+        assert_eq!(copied[0].name, "Règles 2024");
+        assert_eq!(copied[0].rules.seuil_reussite_global, 55.0);
+    }
+
+    #[test]
+    fn migration_retire_les_jeux_semes() {
+        let db = TestDb::open();
+        let mut conn = db.conn();
+        conn.execute(
+            "INSERT INTO academic_years (id, name, start_date, end_date) VALUES (1, '2024-2025', '2024-09-01', '2025-07-01')",
+            [],
+        )
+        .unwrap();
+        save_rule_set(&conn, 1, humanites("Règles de l'école", 55.0)).unwrap();
+        // Jeux tels que les semait la migration 12
+        let json = serde_json::to_string(&DelibConfig::for_level("7ème")).unwrap();
+        conn.execute(
+            "INSERT INTO deliberation_rule_sets (academic_year_id, level, option, name, rules)
+             VALUES (1, '7ème', '', 'Éducation de base (7ème)', ?)",
+            params![json],
+        )
+        .unwrap();
+        let tx = conn.transaction().unwrap();
+        settings_until_saved(&tx).unwrap();
+        tx.commit().unwrap();
+        let sets = list_rule_sets(&conn, 1).unwrap();
+        assert_eq!(sets.len(), 1);
+        assert_eq!(sets[0].name, "Règles de l'école");
+    }
+}
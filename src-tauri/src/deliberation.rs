@@ -0,0 +1,1133 @@
+// Moteur de délibération : applique les critères de promotion de l'école (criteres.md)
+// à partir des cotes annuelles. Les seuils viennent des clés delib_* de settings,
+// partagées avec la page de paramètres et le palmarès du frontend.
+//
+// Première délibération :
+//  1. admis avec 50 % et plus, sans échec ;
+//  2. 2ème session avec 50 % au moins et au plus 5 échecs, dont 1 au plus dans les
+//     branches principales (7ème, 8ème) ou 3 au plus dans les branches spécifiques
+//     (humanités) ;
+//  3. un cours en échec est relevable si les points manquants restent sous la limite
+//     de son maximum (6 pts jusqu'à 80, 8 jusqu'à 160, ...) ; il est alors racheté par
+//     le surplus des cours où l'élève dépasse la moyenne, sinon il reste à repêcher.
+
+use crate::conduct;
+use crate::db::DbPool;
+use crate::delib_rules::{self, ResolvedRules};
+use crate::gradebook::Gradebook;
+use log::info;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+// Tolérance sur les comparaisons de points (cotes décimales)
+const EPSILON: f64 = 1e-9;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RachatRule {
+    pub max_points_limite: f64,
+    pub points_manquants_max: f64,
+}
+
+// Limite d'échecs sur un groupe de cours (branches principales, spécifiques...).
+// Un cours en fait partie si sa catégorie, son domaine ou son code figure dans la liste.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BranchLimit {
+    pub label: String,
+    #[serde(default)]
+    pub categories: Vec<String>,
+    #[serde(default)]
+    pub domains: Vec<String>,
+    #[serde(default)]
+    pub subject_codes: Vec<String>,
+    pub max_failures: usize,
+}
+
+impl BranchLimit {
+    pub fn matches(&self, subject: &SubjectScore) -> bool {
+        let found = |list: &[String], value: &str| {
+            !value.trim().is_empty()
+                && list
+                    .iter()
+                    .any(|v| v.trim().eq_ignore_ascii_case(value.trim()))
+        };
+        found(&self.categories, &subject.category)
+            || found(&self.domains, &subject.domain)
+            || found(&self.subject_codes, &subject.code)
+    }
+}
+
+// Règles de délibération d'un niveau (voir delib_rules.rs pour leur stockage par année)
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DelibConfig {
+    pub seuil_reussite_global: f64,
+    pub seuil_echec_matiere: f64,
+    // Triées par maximum croissant
+    pub rachat_rules: Vec<RachatRule>,
+    pub max_echecs_repechage: usize,
+    pub manque_cotes_double_en_final: bool,
+    #[serde(default)]
+    pub branch_limits: Vec<BranchLimit>,
+    // Une conduite annuelle bloquante (voir conduct.rs) refuse la promotion
+    #[serde(default)]
+    pub conduite_bloque_promotion: bool,
+}
+
+impl Default for DelibConfig {
+    fn default() -> Self {
+        DelibConfig {
+            seuil_reussite_global: 50.0,
+            seuil_echec_matiere: 50.0,
+            rachat_rules: [
+                (80.0, 6.0),
+                (160.0, 8.0),
+                (300.0, 10.0),
+                (320.0, 12.0),
+                (500.0, 15.0),
+            ]
+            .into_iter()
+            .map(|(max_points_limite, points_manquants_max)| RachatRule {
+                max_points_limite,
+                points_manquants_max,
+            })
+            .collect(),
+            max_echecs_repechage: 5,
+            manque_cotes_double_en_final: true,
+            branch_limits: Vec::new(),
+            conduite_bloque_promotion: false,
+        }
+    }
+}
+
+impl DelibConfig {
+    // Règles par défaut d'un niveau : 1 échec au plus dans les branches principales en
+    // 7ème et 8ème, 3 dans les branches spécifiques (cours techniques) ensuite
+    pub fn for_level(level: &str) -> Self {
+        let branch_limit = if is_education_de_base(level) {
+            BranchLimit {
+                label: "branches principales".to_string(),
+                categories: Vec::new(),
+                domains: Vec::new(),
+                subject_codes: ["FR", "MATH", "ALG", "ARITH", "GEO", "STAT"]
+                    .iter()
+                    .map(|s| s.to_string())
+                    .collect(),
+                max_failures: 1,
+            }
+        } else {
+            BranchLimit {
+                label: "branches spécifiques".to_string(),
+                categories: vec!["Techniques".to_string()],
+                domains: Vec::new(),
+                subject_codes: Vec::new(),
+                max_failures: 3,
+            }
+        };
+        DelibConfig {
+            branch_limits: vec![branch_limit],
+            ..DelibConfig::default()
+        }
+    }
+
+    // Règles d'un niveau tirées des clés delib_* de settings
+    pub fn from_settings(conn: &Connection, level: &str) -> Self {
+        let get = |key: &str| -> Option<String> {
+            conn.query_row(
+                "SELECT value FROM settings WHERE key = ?",
+                params![format!("delib_{key}")],
+                |row| row.get(0),
+            )
+            .ok()
+        };
+        let number = |key: &str| get(key).and_then(|v| v.parse::<f64>().ok());
+        let list = |key: &str| get(key).and_then(|v| serde_json::from_str::<Vec<String>>(&v).ok());
+
+        let mut config = DelibConfig::for_level(level);
+        if let Some(mut rules) =
+            get("rachatRules").and_then(|v| serde_json::from_str::<Vec<RachatRule>>(&v).ok())
+        {
+            rules.sort_by(|a, b| a.max_points_limite.total_cmp(&b.max_points_limite));
+            config.rachat_rules = rules;
+        }
+        if let Some(v) = number("seuilReussiteGlobal") {
+            config.seuil_reussite_global = v;
+        }
+        if let Some(v) = number("seuilEchecMatiere") {
+            config.seuil_echec_matiere = v;
+        }
+        if let Some(v) = number("maxEchecsRepechage") {
+            config.max_echecs_repechage = v as usize;
+        }
+        if let Some(v) = get("manqueCotesDoubleEnFinal") {
+            config.manque_cotes_double_en_final = v == "true";
+        }
+        if let Some(v) = get("conduiteBloquePromotion") {
+            config.conduite_bloque_promotion = v == "true";
+        }
+        for limit in config.branch_limits.iter_mut() {
+            if is_education_de_base(level) {
+                if let Some(codes) = list("branchesPrincipales") {
+                    limit.subject_codes = codes;
+                }
+                if let Some(v) = number("maxEchecsPrincipales") {
+                    limit.max_failures = v as usize;
+                }
+            } else {
+                if let Some(categories) = list("categoriesSpecifiques") {
+                    limit.categories = categories;
+                }
+                if let Some(v) = number("maxEchecsSpecifiques") {
+                    limit.max_failures = v as usize;
+                }
+            }
+        }
+        config
+    }
+
+    // Contrôle d'un jeu de règles saisi par l'utilisateur ; trie les règles de rachat
+    pub fn validate(&mut self) -> Result<(), String> {
+        for (label, value) in [
+            ("seuil de réussite", self.seuil_reussite_global),
+            ("seuil d'échec par cours", self.seuil_echec_matiere),
+        ] {
+            if !(value > 0.0 && value <= 100.0) {
+                return Err(format!("Le {} doit être compris entre 0 et 100", label));
+            }
+        }
+        for rule in &self.rachat_rules {
+            if !rule.max_points_limite.is_finite() || rule.max_points_limite <= 0.0 {
+                return Err("Chaque règle de rachat doit porter sur un maximum positif".to_string());
+            }
+            if !rule.points_manquants_max.is_finite() || rule.points_manquants_max < 0.0 {
+                return Err(format!(
+                    "Points rachetables invalides pour les cours de {} points",
+                    rule.max_points_limite
+                ));
+            }
+        }
+        self.rachat_rules
+            .sort_by(|a, b| a.max_points_limite.total_cmp(&b.max_points_limite));
+        if let Some(pair) = self
+            .rachat_rules
+            .windows(2)
+            .find(|w| w[0].max_points_limite == w[1].max_points_limite)
+        {
+            return Err(format!(
+                "Deux règles de rachat pour les cours de {} points",
+                pair[0].max_points_limite
+            ));
+        }
+        for limit in &self.branch_limits {
+            if limit.label.trim().is_empty() {
+                return Err("Chaque limite d'échecs doit avoir un libellé".to_string());
+            }
+            if limit.categories.is_empty()
+                && limit.domains.is_empty()
+                && limit.subject_codes.is_empty()
+            {
+                return Err(format!(
+                    "Aucune catégorie, aucun domaine ni cours pour les {}",
+                    limit.label
+                ));
+            }
+            if limit.max_failures > self.max_echecs_repechage {
+                return Err(format!(
+                    "La limite des {} ({}) dépasse le nombre d'échecs admis ({})",
+                    limit.label, limit.max_failures, self.max_echecs_repechage
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    // Points manquants qu'un cours de ce maximum peut racheter (None au-delà de la dernière règle)
+    pub fn relevable_limit(&self, max_points: f64) -> Option<f64> {
+        self.rachat_rules
+            .iter()
+            .find(|r| max_points <= r.max_points_limite + EPSILON)
+            .map(|r| r.points_manquants_max)
+    }
+
+    fn branches_of(&self, subject: &SubjectScore) -> Vec<String> {
+        self.branch_limits
+            .iter()
+            .filter(|l| l.matches(subject))
+            .map(|l| l.label.clone())
+            .collect()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum DelibMode {
+    // Première délibération, sur les cotes de l'année
+    #[default]
+    FirstSession,
+    // Délibération finale, après intégration des résultats de la 2ème session
+    Final,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Verdict {
+    Admis,
+    // En première délibération : admis à la 2ème session ; en final : réussite après 2ème session
+    DeuxiemeSession,
+    Echec,
+    Abandon,
+    // Cotes manquantes, décision laissée au conseil
+    Incomplet,
+}
+
+impl Verdict {
+    pub fn label(&self, mode: DelibMode) -> &'static str {
+        match (self, mode) {
+            (Verdict::Admis, _) => "Admis",
+            (Verdict::DeuxiemeSession, DelibMode::FirstSession) => "Admis à la 2ème session",
+            (Verdict::DeuxiemeSession, DelibMode::Final) => "Réussite après 2ème session",
+            (Verdict::Echec, _) => "Échec",
+            (Verdict::Abandon, _) => "Abandon",
+            (Verdict::Incomplet, _) => "Cotes manquantes : décision à prendre",
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum SubjectStatus {
+    Reussi,
+    Rachete,
+    Echec,
+    Incomplet,
+}
+
+// Points annuels d'un cours pour un élève, entrée du calcul
+#[derive(Clone, Debug)]
+pub struct SubjectScore {
+    pub subject_id: i64,
+    pub code: String,
+    pub name: String,
+    pub category: String,
+    pub domain: String,
+    pub points: f64,
+    pub max_points: f64,
+    pub missing: bool,
+    // Pourcentage obtenu à la 2ème session, s'il y en a une
+    pub repechage: Option<f64>,
+    // Poids dans le pourcentage global (1 hors pondération par coefficient)
+    pub weight: f64,
+}
+
+impl SubjectScore {
+    fn label(&self) -> String {
+        if self.code.is_empty() {
+            self.name.clone()
+        } else {
+            self.code.clone()
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SubjectDeliberation {
+    pub subject_id: i64,
+    pub code: String,
+    pub name: String,
+    pub points: f64,
+    pub max_points: f64,
+    pub percentage: Option<f64>,
+    pub status: SubjectStatus,
+    // Groupes de cours (branches principales...) dont ce cours fait partie
+    pub branches: Vec<String>,
+    pub repechage_applied: bool,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RachatSource {
+    pub subject_id: i64,
+    pub code: String,
+    pub points: f64,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RachatDecision {
+    pub subject_id: i64,
+    pub code: String,
+    pub max_points: f64,
+    pub missing_points: f64,
+    // Limite de la règle applicable, absente si le cours dépasse la dernière règle
+    pub limit: Option<f64>,
+    pub relevable: bool,
+    pub accepted: bool,
+    // Cours dans lesquels les points ont été puisés
+    pub sources: Vec<RachatSource>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct StudentDeliberation {
+    pub student_id: i64,
+    pub student_name: String,
+    pub percentage: Option<f64>,
+    pub total_points: f64,
+    pub total_max: f64,
+    pub subjects: Vec<SubjectDeliberation>,
+    // Échecs avant rachat
+    pub failures: Vec<String>,
+    pub relevable: Vec<String>,
+    pub rachats: Vec<RachatDecision>,
+    // Échecs restants après rachat (cours à repêcher)
+    pub remaining_failures: Vec<String>,
+    pub missing_subjects: Vec<String>,
+    pub verdict: Verdict,
+    pub verdict_label: String,
+    pub reasoning: Vec<String>,
+    // Conduite annuelle, renseignée par conduct::apply_to_deliberation
+    pub conduct: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassDeliberation {
+    pub class_id: i64,
+    pub class_name: String,
+    pub level: String,
+    pub mode: DelibMode,
+    // Règles appliquées (celles de l'année de la classe)
+    pub rules: ResolvedRules,
+    pub students: Vec<StudentDeliberation>,
+}
+
+fn round2(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+// 7ème et 8ème : éducation de base ; les autres niveaux suivent la règle des humanités
+pub fn is_education_de_base(level: &str) -> bool {
+    matches!(level, "7ème" | "8ème")
+}
+
+// Critère 3 : rachat des échecs relevables par le surplus des autres cours, dans l'ordre
+// des cours comme le palmarès. Retourne les décisions et les échecs restants.
+fn apply_rachat(
+    config: &DelibConfig,
+    scores: &[SubjectScore],
+    failed: &[usize],
+    allow: bool,
+) -> (Vec<RachatDecision>, Vec<usize>) {
+    let ratio = config.seuil_echec_matiere / 100.0;
+    let mut surplus: Vec<(usize, f64)> = scores
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| !s.missing)
+        .map(|(i, s)| (i, s.points - s.max_points * ratio))
+        .filter(|(_, extra)| *extra > EPSILON)
+        .collect();
+
+    let mut decisions = Vec::new();
+    let mut remaining = Vec::new();
+    for &index in failed {
+        let subject = &scores[index];
+        let missing_points = subject.max_points * ratio - subject.points;
+        let limit = config.relevable_limit(subject.max_points);
+        let relevable = limit.is_some_and(|l| missing_points <= l + EPSILON);
+        let available: f64 = surplus.iter().map(|(_, extra)| extra).sum();
+        let accepted = allow && relevable && available + EPSILON >= missing_points;
+
+        let mut sources = Vec::new();
+        if accepted {
+            let mut needed = missing_points;
+            for (source, extra) in surplus.iter_mut() {
+                if needed <= EPSILON {
+                    break;
+                }
+                let taken = extra.min(needed);
+                if taken <= EPSILON {
+                    continue;
+                }
+                *extra -= taken;
+                needed -= taken;
+                sources.push(RachatSource {
+                    subject_id: scores[*source].subject_id,
+                    code: scores[*source].label(),
+                    points: round2(taken),
+                });
+            }
+        } else {
+            remaining.push(index);
+        }
+
+        decisions.push(RachatDecision {
+            subject_id: subject.subject_id,
+            code: subject.label(),
+            max_points: subject.max_points,
+            missing_points: round2(missing_points),
+            limit,
+            relevable,
+            accepted,
+            sources,
+        });
+    }
+    (decisions, remaining)
+}
+
+// Délibération d'un élève à partir de ses totaux annuels par cours
+pub fn deliberate_student(
+    config: &DelibConfig,
+    mode: DelibMode,
+    student_id: i64,
+    student_name: &str,
+    abandoned: bool,
+    scores: &[SubjectScore],
+) -> StudentDeliberation {
+    let ratio = config.seuil_echec_matiere / 100.0;
+    let mut reasoning = Vec::new();
+
+    // En final, le résultat de la 2ème session remplace les points s'il est meilleur
+    let mut adjusted: Vec<SubjectScore> = scores.to_vec();
+    let mut repechage_applied = vec![false; scores.len()];
+    if mode == DelibMode::Final {
+        for (i, subject) in adjusted.iter_mut().enumerate() {
+            if let Some(rep) = subject.repechage.filter(|p| *p > 0.0) {
+                let points = rep / 100.0 * subject.max_points;
+                if !subject.missing && points > subject.points {
+                    reasoning.push(format!(
+                        "{} : 2ème session à {}%, {} → {} / {}",
+                        subject.label(),
+                        round2(rep),
+                        round2(subject.points),
+                        round2(points),
+                        subject.max_points
+                    ));
+                    subject.points = points;
+                    repechage_applied[i] = true;
+                }
+            }
+        }
+    }
+
+    let missing_subjects: Vec<String> = adjusted
+        .iter()
+        .filter(|s| s.missing)
+        .map(|s| s.label())
+        .collect();
+    let complete = missing_subjects.is_empty();
+    let total_points: f64 = adjusted
+        .iter()
+        .filter(|s| !s.missing)
+        .map(|s| s.points * s.weight)
+        .sum();
+    let total_max: f64 = adjusted
+        .iter()
+        .filter(|s| !s.missing)
+        .map(|s| s.max_points * s.weight)
+        .sum();
+    let percentage = (complete && total_max > 0.0).then(|| total_points / total_max * 100.0);
+
+    // Échecs de la première délibération, sur les points d'origine
+    let failed_indices = |list: &[SubjectScore]| -> Vec<usize> {
+        list.iter()
+            .enumerate()
+            .filter(|(_, s)| !s.missing && s.max_points > 0.0)
+            .filter(|(_, s)| s.points + EPSILON < s.max_points * ratio)
+            .map(|(i, _)| i)
+            .collect()
+    };
+    let first_percentage = {
+        let points: f64 = scores
+            .iter()
+            .filter(|s| !s.missing)
+            .map(|s| s.points * s.weight)
+            .sum();
+        (complete && total_max > 0.0).then(|| points / total_max * 100.0)
+    };
+    let first_failed = failed_indices(scores);
+    let passes_first =
+        first_percentage.is_some_and(|p| p + EPSILON >= config.seuil_reussite_global);
+    let (rachats, first_remaining) = apply_rachat(config, scores, &first_failed, passes_first);
+
+    let final_remaining = if mode == DelibMode::Final {
+        let after_failed = failed_indices(&adjusted);
+        first_remaining
+            .iter()
+            .copied()
+            .filter(|i| after_failed.contains(i))
+            .collect()
+    } else {
+        first_remaining.clone()
+    };
+
+    let labels =
+        |indices: &[usize]| -> Vec<String> { indices.iter().map(|i| scores[*i].label()).collect() };
+    let failures = labels(&first_failed);
+    let remaining_failures = labels(&final_remaining);
+    let relevable: Vec<String> = rachats
+        .iter()
+        .filter(|r| r.relevable)
+        .map(|r| r.code.clone())
+        .collect();
+
+    let subjects = adjusted
+        .iter()
+        .enumerate()
+        .map(|(i, s)| SubjectDeliberation {
+            subject_id: s.subject_id,
+            code: s.code.clone(),
+            name: s.name.clone(),
+            points: round2(s.points),
+            max_points: s.max_points,
+            percentage: (!s.missing && s.max_points > 0.0)
+                .then(|| round2(s.points / s.max_points * 100.0)),
+            status: if s.missing {
+                SubjectStatus::Incomplet
+            } else if final_remaining.contains(&i) {
+                SubjectStatus::Echec
+            } else if first_failed.contains(&i) && !repechage_applied[i] {
+                SubjectStatus::Rachete
+            } else {
+                SubjectStatus::Reussi
+            },
+            branches: config.branches_of(s),
+            repechage_applied: repechage_applied[i],
+        })
+        .collect();
+
+    let verdict = if abandoned {
+        reasoning.push("Élève en abandon".to_string());
+        Verdict::Abandon
+    } else if !complete {
+        reasoning.push(format!(
+            "Cotes manquantes : {}",
+            missing_subjects.join(", ")
+        ));
+        if mode == DelibMode::Final && config.manque_cotes_double_en_final {
+            reasoning.push("Les cotes manquantes entraînent le redoublement".to_string());
+            Verdict::Echec
+        } else {
+            Verdict::Incomplet
+        }
+    } else {
+        let first = first_percentage.unwrap_or(0.0);
+        reasoning.push(format!(
+            "Pourcentage annuel : {}% (seuil {}%)",
+            round2(first),
+            config.seuil_reussite_global
+        ));
+        for rachat in &rachats {
+            let detail = match (rachat.limit, rachat.relevable, rachat.accepted) {
+                (_, true, true) => format!(
+                    "{} : {} pts manquants rachetés sur le surplus de {}",
+                    rachat.code,
+                    rachat.missing_points,
+                    rachat
+                        .sources
+                        .iter()
+                        .map(|s| format!("{} ({} pts)", s.code, s.points))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                (Some(limit), true, false) if passes_first => format!(
+                    "{} : relevable ({} pts manquants ≤ {}) mais surplus insuffisant, à repêcher",
+                    rachat.code, rachat.missing_points, limit
+                ),
+                (Some(limit), true, false) => format!(
+                    "{} : relevable ({} pts manquants ≤ {}), pas de rachat sous le seuil global",
+                    rachat.code, rachat.missing_points, limit
+                ),
+                (Some(limit), false, _) => format!(
+                    "{} : {} pts manquants, au-delà de la limite de {} pts",
+                    rachat.code, rachat.missing_points, limit
+                ),
+                (None, _, _) => format!(
+                    "{} : {} pts manquants, aucune règle de rachat pour {} pts",
+                    rachat.code, rachat.missing_points, rachat.max_points
+                ),
+            };
+            reasoning.push(detail);
+        }
+
+        if !passes_first {
+            reasoning.push("Pourcentage sous le seuil de réussite".to_string());
+            Verdict::Echec
+        } else if first_remaining.is_empty() {
+            reasoning.push("Aucun échec restant".to_string());
+            Verdict::Admis
+        } else {
+            let within_limits =
+                second_session_allowed(config, scores, &first_remaining, &mut reasoning);
+            match (within_limits, mode) {
+                (false, _) => Verdict::Echec,
+                (true, DelibMode::FirstSession) => Verdict::DeuxiemeSession,
+                (true, DelibMode::Final) => {
+                    // Comme le palmarès final, la réussite après 2ème session suit le pourcentage
+                    let after = percentage.unwrap_or(0.0);
+                    reasoning.push(format!(
+                        "Pourcentage après 2ème session : {}%",
+                        round2(after)
+                    ));
+                    if after + EPSILON >= config.seuil_reussite_global {
+                        if !remaining_failures.is_empty() {
+                            reasoning.push(format!(
+                                "Échecs maintenus après 2ème session : {}",
+                                remaining_failures.join(", ")
+                            ));
+                        }
+                        Verdict::DeuxiemeSession
+                    } else {
+                        Verdict::Echec
+                    }
+                }
+            }
+        }
+    };
+
+    StudentDeliberation {
+        student_id,
+        student_name: student_name.to_string(),
+        percentage: percentage.map(round2),
+        total_points: round2(total_points),
+        total_max,
+        subjects,
+        failures,
+        relevable,
+        rachats,
+        remaining_failures,
+        missing_subjects,
+        verdict,
+        verdict_label: verdict.label(mode).to_string(),
+        reasoning,
+        conduct: None,
+    }
+}
+
+// Critère 2 : nombre d'échecs admis pour la 2ème session et limites par groupe de cours
+fn second_session_allowed(
+    config: &DelibConfig,
+    scores: &[SubjectScore],
+    remaining: &[usize],
+    reasoning: &mut Vec<String>,
+) -> bool {
+    let count = remaining.len();
+    if count > config.max_echecs_repechage {
+        reasoning.push(format!(
+            "{} échecs restants, plus que les {} admis pour la 2ème session",
+            count, config.max_echecs_repechage
+        ));
+        return false;
+    }
+    for limit in &config.branch_limits {
+        let failures = remaining
+            .iter()
+            .filter(|i| limit.matches(&scores[**i]))
+            .count();
+        if failures > limit.max_failures {
+            reasoning.push(format!(
+                "{} échecs dans les {} (maximum {})",
+                failures, limit.label, limit.max_failures
+            ));
+            return false;
+        }
+    }
+    reasoning.push(format!("{} échec(s) à présenter en 2ème session", count));
+    true
+}
+
+// Totaux annuels (toutes les périodes de l'année) de chaque cours pour un élève
+pub fn annual_scores(book: &Gradebook, student_id: i64) -> Vec<SubjectScore> {
+    let codes = book.all_period_codes();
+    book.subjects
+        .iter()
+        .map(|subject| {
+            let total = book.subject_total(student_id, subject, &codes);
+            SubjectScore {
+                subject_id: subject.id,
+                code: subject.code.clone(),
+                name: subject.name.clone(),
+                category: subject.category.clone(),
+                domain: subject.domain.clone(),
+                points: total.points,
+                max_points: total.max,
+                missing: total.missing,
+                repechage: book.repechage(student_id, subject.id),
+                weight: book.weight(subject),
+            }
+        })
+        // Un cours sans maximum sur l'année (pas noté) ne compte pas
+        .filter(|s| s.max_points > 0.0 || s.missing)
+        .collect()
+}
+
+pub fn deliberate_gradebook(
+    book: &Gradebook,
+    config: &DelibConfig,
+    mode: DelibMode,
+) -> Vec<StudentDeliberation> {
+    book.students
+        .iter()
+        .map(|student| {
+            deliberate_student(
+                config,
+                mode,
+                student.id,
+                &student.name,
+                student.abandoned,
+                &annual_scores(book, student.id),
+            )
+        })
+        .collect()
+}
+
+pub fn deliberate(
+    conn: &Connection,
+    class_id: i64,
+    mode: DelibMode,
+) -> Result<ClassDeliberation, String> {
+    let book = Gradebook::load(conn, class_id)?;
+    let resolved = delib_rules::rules_for_class(conn, class_id)?;
+    let mut students = deliberate_gradebook(&book, &resolved.rules, mode);
+    conduct::apply_to_deliberation(conn, class_id, &resolved.rules, mode, &mut students)?;
+    Ok(ClassDeliberation {
+        class_id,
+        class_name: book.class.name.clone(),
+        level: book.class.level.clone(),
+        mode,
+        rules: resolved,
+        students,
+    })
+}
+
+#[tauri::command]
+pub async fn deliberate_class(
+    pool: tauri::State<'_, DbPool>,
+    class_id: i64,
+    mode: Option<DelibMode>,
+) -> Result<ClassDeliberation, String> {
+    let mode = mode.unwrap_or_default();
+    let result = pool
+        .run(move |conn| deliberate(conn, class_id, mode))
+        .await?;
+    info!(
+        "Délibération de la classe {} ({:?}) : {} élève(s)",
+        class_id,
+        mode,
+        result.students.len()
+    );
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(code: &str, category: &str, points: f64, max_points: f64) -> SubjectScore {
+        SubjectScore {
+            subject_id: code.bytes().map(i64::from).sum(),
+            code: code.to_string(),
+            name: code.to_string(),
+            category: category.to_string(),
+            domain: String::new(),
+            points,
+            max_points,
+            missing: false,
+            repechage: None,
+            weight: 1.0,
+        }
+    }
+
+    fn run(level: &str, scores: &[SubjectScore]) -> StudentDeliberation {
+        deliberate_student(
+            &DelibConfig::for_level(level),
+            DelibMode::FirstSession,
+            1,
+            "Élève",
+            false,
+            scores,
+        )
+    }
+
+    #[test]
+    fn admis_a_cinquante_pourcent_sans_echec() {
+        let result = run(
+            "1ère",
+            &[
+                score("FR", "Langues", 40.0, 80.0),
+                score("HIST", "", 80.0, 160.0),
+            ],
+        );
+        assert_eq!(result.percentage, Some(50.0));
+        assert!(result.failures.is_empty());
+        assert_eq!(result.verdict, Verdict::Admis);
+    }
+
+    #[test]
+    fn echec_sous_cinquante_pourcent() {
+        let result = run(
+            "2ème",
+            &[score("FR", "", 30.0, 80.0), score("HIST", "", 70.0, 160.0)],
+        );
+        assert_eq!(result.verdict, Verdict::Echec);
+        // Pas de rachat sous le seuil global, même si le cours est relevable
+        assert!(result.rachats.iter().all(|r| !r.accepted));
+    }
+
+    #[test]
+    fn limites_de_rachat_selon_le_maximum() {
+        let config = DelibConfig::default();
+        assert_eq!(config.relevable_limit(80.0), Some(6.0));
+        assert_eq!(config.relevable_limit(40.0), Some(6.0));
+        assert_eq!(config.relevable_limit(160.0), Some(8.0));
+        assert_eq!(config.relevable_limit(240.0), Some(10.0));
+        assert_eq!(config.relevable_limit(300.0), Some(10.0));
+        assert_eq!(config.relevable_limit(320.0), Some(12.0));
+        assert_eq!(config.relevable_limit(400.0), Some(15.0));
+        assert_eq!(config.relevable_limit(500.0), Some(15.0));
+        assert_eq!(config.relevable_limit(600.0), None);
+    }
+
+    #[test]
+    fn trente_cinq_sur_quatre_vingts_rachete_par_le_surplus() {
+        // 35/80 : 5 points manquants (≤ 6), repris sur le surplus de 20 pts en histoire
+        let result = run(
+            "2ème",
+            &[
+                score("ANG", "Langues", 35.0, 80.0),
+                score("HIST", "", 100.0, 160.0),
+            ],
+        );
+        assert_eq!(result.failures, vec!["ANG"]);
+        assert_eq!(result.relevable, vec!["ANG"]);
+        assert!(result.remaining_failures.is_empty());
+        let rachat = &result.rachats[0];
+        assert!(rachat.accepted);
+        assert_eq!(rachat.missing_points, 5.0);
+        assert_eq!(
+            rachat.sources,
+            vec![RachatSource {
+                subject_id: score("HIST", "", 0.0, 0.0).subject_id,
+                code: "HIST".to_string(),
+                points: 5.0
+            }]
+        );
+        assert_eq!(result.verdict, Verdict::Admis);
+        assert_eq!(result.subjects[0].status, SubjectStatus::Rachete);
+    }
+
+    #[test]
+    fn surplus_insuffisant_le_cours_reste_a_repecher() {
+        // Avec les seuils par défaut, 50 % au total couvre toujours les points manquants :
+        // le cas ne se présente que si l'école abaisse le seuil global.
+        let config = DelibConfig {
+            seuil_reussite_global: 45.0,
+            ..DelibConfig::for_level("2ème")
+        };
+        // 35/80 relevable, mais seulement 3 pts de surplus ailleurs (83/160)
+        let result = deliberate_student(
+            &config,
+            DelibMode::FirstSession,
+            1,
+            "Élève",
+            false,
+            &[
+                score("ANG", "Langues", 35.0, 80.0),
+                score("HIST", "", 83.0, 160.0),
+            ],
+        );
+        assert!(result.percentage.unwrap() >= 45.0);
+        assert_eq!(result.relevable, vec!["ANG"]);
+        assert!(!result.rachats[0].accepted);
+        assert_eq!(result.remaining_failures, vec!["ANG"]);
+        assert_eq!(result.verdict, Verdict::DeuxiemeSession);
+    }
+
+    #[test]
+    fn points_manquants_au_dela_de_la_limite() {
+        // 70/160 : 10 pts manquants > 8, non relevable malgré un large surplus
+        let result = run(
+            "3ème",
+            &[
+                score("PHYS", "", 70.0, 160.0),
+                score("FR", "", 380.0, 400.0),
+            ],
+        );
+        assert!(result.relevable.is_empty());
+        assert_eq!(result.rachats[0].limit, Some(8.0));
+        assert_eq!(result.verdict, Verdict::DeuxiemeSession);
+
+        // 74/160 : 6 pts manquants ≤ 8, racheté
+        let result = run(
+            "3ème",
+            &[
+                score("PHYS", "", 74.0, 160.0),
+                score("FR", "", 380.0, 400.0),
+            ],
+        );
+        assert_eq!(result.verdict, Verdict::Admis);
+    }
+
+    #[test]
+    fn grands_cours_relevables_jusqu_a_quinze_points() {
+        // 400 pts : 15 manquants rachetés, 16 non
+        let big = |points: f64| {
+            run(
+                "1ère",
+                &[
+                    score("MATH", "", points, 400.0),
+                    score("FR", "", 400.0, 400.0),
+                ],
+            )
+        };
+        assert_eq!(big(185.0).verdict, Verdict::Admis);
+        assert_eq!(big(184.0).verdict, Verdict::DeuxiemeSession);
+
+        // 300 pts : 10 manquants ; 320 pts : 12 manquants
+        let result = run(
+            "1ère",
+            &[
+                score("ETRO", "Techniques", 140.0, 300.0),
+                score("ELEC", "Techniques", 148.0, 320.0),
+                score("FR", "", 400.0, 400.0),
+            ],
+        );
+        assert!(result.remaining_failures.is_empty());
+        assert_eq!(result.verdict, Verdict::Admis);
+    }
+
+    #[test]
+    fn education_de_base_un_seul_echec_en_branche_principale() {
+        let others = [
+            score("REL", "", 200.0, 200.0),
+            score("HIST", "", 200.0, 200.0),
+        ];
+        // Échecs lourds (non relevables) en français et en anglais : 2ème session
+        let mut scores = vec![score("FR", "", 100.0, 400.0), score("ANG", "", 20.0, 120.0)];
+        scores.extend(others.iter().cloned());
+        assert_eq!(run("7ème", &scores).verdict, Verdict::DeuxiemeSession);
+
+        // Français et algèbre : deux branches principales, échec
+        let mut scores = vec![score("FR", "", 100.0, 400.0), score("ALG", "", 60.0, 320.0)];
+        scores.extend(others.iter().cloned());
+        let result = run("8ème", &scores);
+        assert_eq!(result.verdict, Verdict::Echec);
+        assert!(result
+            .reasoning
+            .iter()
+            .any(|r| r.contains("branches principales")));
+    }
+
+    #[test]
+    fn humanites_trois_echecs_en_branches_specifiques() {
+        let mut scores = vec![
+            score("ETRO", "Techniques", 20.0, 160.0),
+            score("ELEC", "Techniques", 20.0, 160.0),
+            score("MECA", "Techniques", 20.0, 160.0),
+            score("FR", "", 40.0, 160.0),
+            score("MATH", "Sciences", 1000.0, 1000.0),
+        ];
+        assert_eq!(run("2ème", &scores).verdict, Verdict::DeuxiemeSession);
+
+        scores.insert(0, score("DT", "Techniques", 20.0, 160.0));
+        let result = run("2ème", &scores);
+        assert_eq!(result.remaining_failures.len(), 5);
+        assert_eq!(result.verdict, Verdict::Echec);
+    }
+
+    #[test]
+    fn plus_de_cinq_echecs_entraine_l_echec() {
+        let mut scores: Vec<SubjectScore> = ["A", "B", "C", "D", "E", "F"]
+            .iter()
+            .map(|code| score(code, "", 20.0, 160.0))
+            .collect();
+        scores.push(score("MATH", "", 2000.0, 2000.0));
+        let result = run("1ère", &scores);
+        assert_eq!(result.remaining_failures.len(), 6);
+        assert_eq!(result.verdict, Verdict::Echec);
+
+        scores.remove(0);
+        assert_eq!(run("1ère", &scores).verdict, Verdict::DeuxiemeSession);
+    }
+
+    #[test]
+    fn cotes_manquantes_et_abandon() {
+        let mut incomplete = score("FR", "", 0.0, 0.0);
+        incomplete.missing = true;
+        let scores = [incomplete, score("HIST", "", 100.0, 160.0)];
+        assert_eq!(run("1ère", &scores).verdict, Verdict::Incomplet);
+
+        let config = DelibConfig::for_level("1ère");
+        let final_result =
+            deliberate_student(&config, DelibMode::Final, 1, "Élève", false, &scores);
+        assert_eq!(final_result.verdict, Verdict::Echec);
+
+        let abandoned =
+            deliberate_student(&config, DelibMode::FirstSession, 1, "Élève", true, &scores);
+        assert_eq!(abandoned.verdict, Verdict::Abandon);
+    }
+
+    #[test]
+    fn deliberation_finale_integre_la_deuxieme_session() {
+        let config = DelibConfig::for_level("2ème");
+        let mut failed = score("PHYS", "", 60.0, 160.0);
+        failed.repechage = Some(55.0);
+        let scores = [failed, score("FR", "", 300.0, 400.0)];
+
+        let first =
+            deliberate_student(&config, DelibMode::FirstSession, 1, "Élève", false, &scores);
+        assert_eq!(first.verdict, Verdict::DeuxiemeSession);
+
+        let final_result =
+            deliberate_student(&config, DelibMode::Final, 1, "Élève", false, &scores);
+        assert_eq!(final_result.verdict, Verdict::DeuxiemeSession);
+        assert!(final_result.remaining_failures.is_empty());
+        assert!(final_result.subjects[0].repechage_applied);
+        assert_eq!(final_result.subjects[0].points, 88.0);
+    }
+
+    #[test]
+    fn limite_par_domaine_et_validation() {
+        let mut config = DelibConfig {
+            branch_limits: vec![BranchLimit {
+                label: "branches du domaine des langues".to_string(),
+                categories: Vec::new(),
+                domains: vec!["Domaine des Langues".to_string()],
+                subject_codes: Vec::new(),
+                max_failures: 1,
+            }],
+            ..DelibConfig::default()
+        };
+        let mut fr = score("FR", "", 100.0, 400.0);
+        fr.domain = "Domaine des Langues".to_string();
+        let mut ang = score("ANG", "", 20.0, 120.0);
+        ang.domain = "Domaine des Langues".to_string();
+        let result = deliberate_student(
+            &config,
+            DelibMode::FirstSession,
+            1,
+            "Élève",
+            false,
+            &[fr, ang, score("MATH", "", 400.0, 400.0)],
+        );
+        assert_eq!(
+            result.subjects[0].branches,
+            vec!["branches du domaine des langues"]
+        );
+        assert_eq!(result.verdict, Verdict::Echec);
+
+        assert!(config.validate().is_ok());
+        config.branch_limits[0].max_failures = 6;
+        assert!(config.validate().is_err());
+        config.branch_limits[0].max_failures = 1;
+        config.rachat_rules.push(RachatRule {
+            max_points_limite: 80.0,
+            points_manquants_max: 4.0,
+        });
+        assert!(config.validate().is_err());
+        config.rachat_rules.pop();
+        config.seuil_reussite_global = 120.0;
+        assert!(config.validate().is_err());
+    }
+}
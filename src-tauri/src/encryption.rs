@@ -0,0 +1,493 @@
+// Chiffrement optionnel de la base au repos (SQLCipher).
+// Une clé de données aléatoire chiffre le fichier ; elle est conservée dans un fichier
+// <base>.keys, protégée par une clé dérivée du mot de passe local et, en secours,
+// par un code de récupération remis une seule fois à l'utilisateur.
+
+use crate::backup::{self, BackupKind};
+use crate::db::{self, DbPool};
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use lazy_static::lazy_static;
+use log::{info, warn};
+use rand::RngCore;
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+pub const LOCKED_ERROR: &str = "DATABASE_LOCKED";
+pub const INVALID_SECRET_ERROR: &str = "INVALID_PASSWORD";
+const KEY_FILE_SUFFIX: &str = ".keys";
+// Enregistré dans le fichier de clés ; réduit en test, où la dérivation n'est pas optimisée
+const KDF_ITERATIONS: u32 = if cfg!(test) { 1_000 } else { 210_000 };
+const SQLITE_HEADER: &[u8; 16] = b"SQLite format 3\0";
+
+lazy_static! {
+    // Clé de données (hex) une fois la base déverrouillée
+    static ref ACTIVE_KEY: RwLock<Option<String>> = RwLock::new(None);
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WrappedKey {
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct KeyFile {
+    version: u32,
+    kdf_iterations: u32,
+    password: WrappedKey,
+    recovery: WrappedKey,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct EncryptionStatus {
+    pub enabled: bool,
+    pub unlocked: bool,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct EncryptionResult {
+    pub recovery_code: String,
+    pub removed_backups: usize,
+}
+
+fn sibling(db_path: &Path, suffix: &str) -> PathBuf {
+    let mut name = db_path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+fn key_file_path(db_path: &Path) -> PathBuf {
+    sibling(db_path, KEY_FILE_SUFFIX)
+}
+
+// Un fichier SQLite en clair commence toujours par le même en-tête
+fn is_plaintext(db_path: &Path) -> bool {
+    let mut header = [0u8; 16];
+    std::fs::File::open(db_path)
+        .and_then(|mut f| f.read_exact(&mut header))
+        .map(|_| &header == SQLITE_HEADER)
+        .unwrap_or(false)
+}
+
+// Un fichier de clés à côté d'une base restée en clair provient d'un chiffrement
+// interrompu : il est ignoré.
+pub fn is_enabled(db_path: &Path) -> bool {
+    key_file_path(db_path).exists() && !is_plaintext(db_path)
+}
+
+pub fn is_unlocked() -> bool {
+    ACTIVE_KEY.read().unwrap().is_some()
+}
+
+// Doit être appelé avant toute autre requête sur la connexion
+pub fn apply_key(conn: &rusqlite::Connection) -> Result<(), String> {
+    if let Some(key) = ACTIVE_KEY.read().unwrap().as_ref() {
+        conn.execute_batch(&format!("PRAGMA key = \"x'{key}'\";"))
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+// Ouverture en lecture seule d'une copie (sauvegarde, archive) : une copie antérieure
+// au chiffrement est restée en clair et s'ouvre sans clé.
+pub fn open_read_only(path: &Path) -> Result<rusqlite::Connection, String> {
+    let conn =
+        rusqlite::Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .map_err(|e| e.to_string())?;
+    if !is_plaintext(path) {
+        apply_key(&conn)?;
+    }
+    conn.execute_batch("PRAGMA query_only = ON;")
+        .map_err(|e| e.to_string())?;
+    Ok(conn)
+}
+
+fn random_bytes<const N: usize>() -> [u8; N] {
+    let mut bytes = [0u8; N];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    bytes
+}
+
+fn cipher_for(secret: &str, salt: &[u8], iterations: u32) -> Aes256Gcm {
+    let mut kek = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(secret.as_bytes(), salt, iterations, &mut kek);
+    Aes256Gcm::new((&kek).into())
+}
+
+fn wrap_key(secret: &str, data_key: &[u8], iterations: u32) -> Result<WrappedKey, String> {
+    let salt = random_bytes::<16>();
+    let nonce = random_bytes::<12>();
+    let ciphertext = cipher_for(secret, &salt, iterations)
+        .encrypt(Nonce::from_slice(&nonce), data_key)
+        .map_err(|_| "Échec du chiffrement de la clé".to_string())?;
+    Ok(WrappedKey {
+        salt: hex::encode(salt),
+        nonce: hex::encode(nonce),
+        ciphertext: hex::encode(ciphertext),
+    })
+}
+
+// Un secret incorrect est détecté par l'authentification AES-GCM
+fn unwrap_key(secret: &str, wrapped: &WrappedKey, iterations: u32) -> Result<Vec<u8>, String> {
+    let salt = hex::decode(&wrapped.salt).map_err(|e| e.to_string())?;
+    let nonce = hex::decode(&wrapped.nonce).map_err(|e| e.to_string())?;
+    let ciphertext = hex::decode(&wrapped.ciphertext).map_err(|e| e.to_string())?;
+    if nonce.len() != 12 {
+        return Err("Fichier de clés invalide".to_string());
+    }
+    cipher_for(secret, &salt, iterations)
+        .decrypt(Nonce::from_slice(&nonce), ciphertext.as_ref())
+        .map_err(|_| INVALID_SECRET_ERROR.to_string())
+}
+
+fn read_key_file(db_path: &Path) -> Result<KeyFile, String> {
+    let content = std::fs::read_to_string(key_file_path(db_path))
+        .map_err(|e| format!("Fichier de clés illisible : {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Fichier de clés invalide : {}", e))
+}
+
+// Écriture atomique : un fichier de clés tronqué rendrait la base illisible
+fn write_key_file(db_path: &Path, key_file: &KeyFile) -> Result<(), String> {
+    let path = key_file_path(db_path);
+    let tmp = sibling(&path, ".tmp");
+    let content = serde_json::to_string_pretty(key_file).map_err(|e| e.to_string())?;
+    std::fs::write(&tmp, content).map_err(|e| e.to_string())?;
+    std::fs::rename(&tmp, &path).map_err(|e| e.to_string())
+}
+
+fn generate_recovery_code() -> String {
+    let code = hex::encode_upper(random_bytes::<16>());
+    code.as_bytes()
+        .chunks(4)
+        .map(|c| String::from_utf8_lossy(c).to_string())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+// Tolère les tirets, espaces et minuscules lors de la saisie
+fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_hexdigit())
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+pub fn unlock(db_path: &Path, password: &str) -> Result<(), String> {
+    let key_file = read_key_file(db_path)?;
+    let data_key = unwrap_key(password, &key_file.password, key_file.kdf_iterations)?;
+    *ACTIVE_KEY.write().unwrap() = Some(hex::encode(data_key));
+    info!("Database unlocked");
+    Ok(())
+}
+
+// Nouveau mot de passe : seule la clé de données est re-chiffrée, pas la base
+pub fn change_password(
+    db_path: &Path,
+    old_password: &str,
+    new_password: &str,
+) -> Result<(), String> {
+    let mut key_file = read_key_file(db_path)?;
+    let data_key = unwrap_key(old_password, &key_file.password, key_file.kdf_iterations)?;
+    key_file.password = wrap_key(new_password, &data_key, key_file.kdf_iterations)?;
+    write_key_file(db_path, &key_file)
+}
+
+// Mot de passe oublié : le code de récupération redonne accès à la clé de données
+pub fn recover(db_path: &Path, recovery_code: &str, new_password: &str) -> Result<(), String> {
+    let mut key_file = read_key_file(db_path)?;
+    let data_key = unwrap_key(
+        &normalize_recovery_code(recovery_code),
+        &key_file.recovery,
+        key_file.kdf_iterations,
+    )?;
+    key_file.password = wrap_key(new_password, &data_key, key_file.kdf_iterations)?;
+    write_key_file(db_path, &key_file)?;
+    *ACTIVE_KEY.write().unwrap() = Some(hex::encode(data_key));
+    info!("Database access recovered with recovery code");
+    Ok(())
+}
+
+// Chiffre une base existante en place. Le frontend doit avoir fermé sa propre
+// connexion (plugin sql) avant l'appel : le fichier est remplacé.
+pub fn encrypt_database(pool: &DbPool, password: &str) -> Result<EncryptionResult, String> {
+    let db_path = pool.path().to_path_buf();
+    if is_enabled(&db_path) {
+        return Err("La base est déjà chiffrée".to_string());
+    }
+
+    // Plus aucune connexion du pool pendant le remplacement du fichier : celles en cours
+    // sont attendues, les nouvelles patientent jusqu'à la fin du chiffrement
+    let _exclusive = pool.exclusive()?;
+
+    let data_key = random_bytes::<32>();
+    let key_hex = hex::encode(data_key);
+    let recovery_code = generate_recovery_code();
+    let encrypted_path = sibling(&db_path, ".encrypting");
+    std::fs::remove_file(&encrypted_path).ok();
+
+    {
+        let conn = db::open_connection(&db_path)?;
+        let stored_hash: String = conn
+            .query_row(
+                "SELECT value FROM settings WHERE key = 'local_password_hash'",
+                [],
+                |r| r.get(0),
+            )
+            .map_err(|_| "NO_PASSWORD_SET".to_string())?;
+        if stored_hash != crate::simple_hash(password) {
+            return Err(INVALID_SECRET_ERROR.to_string());
+        }
+
+        info!("Encrypting database into {}...", encrypted_path.display());
+        conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))
+            .map_err(|e| e.to_string())?;
+        conn.execute(
+            "ATTACH DATABASE ? AS encrypted KEY ?",
+            params![
+                encrypted_path.to_string_lossy().to_string(),
+                format!("x'{key_hex}'")
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+        let exported = conn
+            .query_row("SELECT sqlcipher_export('encrypted')", [], |_| Ok(()))
+            .map_err(|e| format!("Échec du chiffrement : {}", e));
+        conn.execute("DETACH DATABASE encrypted", [])
+            .map_err(|e| e.to_string())?;
+        exported?;
+    }
+
+    let key_file = KeyFile {
+        version: 1,
+        kdf_iterations: KDF_ITERATIONS,
+        password: wrap_key(password, &data_key, KDF_ITERATIONS)?,
+        recovery: wrap_key(
+            &normalize_recovery_code(&recovery_code),
+            &data_key,
+            KDF_ITERATIONS,
+        )?,
+    };
+
+    // Le fichier de clés est écrit avant le remplacement : si l'opération est interrompue,
+    // la base reste en clair et is_enabled ignore ce fichier.
+    write_key_file(&db_path, &key_file)?;
+    std::fs::rename(&encrypted_path, &db_path)
+        .map_err(|e| format!("Impossible de remplacer la base : {}", e))?;
+    for suffix in ["-wal", "-shm"] {
+        std::fs::remove_file(sibling(&db_path, suffix)).ok();
+    }
+    *ACTIVE_KEY.write().unwrap() = Some(key_hex);
+
+    // Les anciennes sauvegardes sont en clair : on les supprime et on repart d'une copie chiffrée
+    let removed_backups = backup::purge_backups(&db_path)?;
+    if removed_backups > 0 {
+        warn!("{} plaintext backups removed", removed_backups);
+    }
+    backup::create_backup(&db_path, BackupKind::Manual)?;
+
+    info!("Database encrypted");
+    Ok(EncryptionResult {
+        recovery_code,
+        removed_backups,
+    })
+}
+
+#[tauri::command]
+pub async fn db_encryption_status(
+    pool: tauri::State<'_, DbPool>,
+) -> Result<EncryptionStatus, String> {
+    pool.run_with_path(|path| {
+        Ok(EncryptionStatus {
+            enabled: is_enabled(path),
+            unlocked: is_unlocked(),
+        })
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn db_encrypt(
+    pool: tauri::State<'_, DbPool>,
+    password: String,
+) -> Result<EncryptionResult, String> {
+    let pool = pool.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || encrypt_database(&pool, &password))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub async fn db_recover_access(
+    pool: tauri::State<'_, DbPool>,
+    recovery_code: String,
+    new_password: String,
+) -> Result<(), String> {
+    pool.run_with_path(move |path| {
+        recover(path, &recovery_code, &new_password)?;
+        db::initialize_db(path)?;
+        let conn = db::open_connection(path)?;
+        crate::save_password_hash(&conn, &new_password)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::TestDb;
+    use std::sync::Mutex;
+
+    // La clé active est globale au processus : un seul test de chiffrement à la fois
+    static ACTIVE_KEY_TESTS: Mutex<()> = Mutex::new(());
+
+    fn school_name(conn: &rusqlite::Connection) -> rusqlite::Result<String> {
+        conn.query_row(
+            "SELECT value FROM settings WHERE key = 'school_name'",
+            [],
+            |row| row.get(0),
+        )
+    }
+
+    // Base en clair avec un mot de passe local et le nom de l'école
+    fn plain_db(password: &str) -> TestDb {
+        let db = TestDb::open();
+        let conn = db.conn();
+        crate::save_password_hash(&conn, password).unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO settings (key, value) VALUES ('school_name', 'Institut Umoja')",
+            [],
+        )
+        .unwrap();
+        db
+    }
+
+    fn encrypted_db(password: &str) -> (TestDb, EncryptionResult) {
+        let db = plain_db(password);
+        let result = encrypt_database(&DbPool::new(db.path.clone()), password).unwrap();
+        (db, result)
+    }
+
+    fn lock() {
+        *ACTIVE_KEY.write().unwrap() = None;
+    }
+
+    #[test]
+    fn chiffrement_en_place() {
+        let _serial = ACTIVE_KEY_TESTS.lock().unwrap_or_else(|e| e.into_inner());
+        let db = plain_db("secret");
+        backup::create_backup(&db.path, BackupKind::Manual).unwrap();
+        let pool = DbPool::new(db.path.clone());
+
+        assert_eq!(
+            encrypt_database(&pool, "faux").unwrap_err(),
+            INVALID_SECRET_ERROR
+        );
+        assert!(!is_enabled(&db.path));
+
+        let result = encrypt_database(&pool, "secret").unwrap();
+        assert!(is_enabled(&db.path));
+        assert_eq!(normalize_recovery_code(&result.recovery_code).len(), 32);
+
+        // Illisible sans la clé
+        let plain = rusqlite::Connection::open(&db.path).unwrap();
+        assert!(school_name(&plain).is_err());
+        // Données intactes avec la clé
+        assert_eq!(school_name(&pool.get().unwrap()).unwrap(), "Institut Umoja");
+
+        // La sauvegarde en clair est remplacée par une copie chiffrée
+        assert_eq!(result.removed_backups, 1);
+        let backups = backup::list_backups(&db.path).unwrap();
+        assert_eq!(backups.len(), 1);
+        let copy = backup::backups_dir(&db.path).join(&backups[0].file_name);
+        assert!(!is_plaintext(&copy));
+        assert!(
+            backup::verify_backup(&db.path, &backups[0].file_name)
+                .unwrap()
+                .valid
+        );
+
+        assert!(encrypt_database(&pool, "secret").is_err());
+    }
+
+    #[test]
+    fn detection_du_fichier_de_cles() {
+        let _serial = ACTIVE_KEY_TESTS.lock().unwrap_or_else(|e| e.into_inner());
+        let db = TestDb::open();
+        assert!(!is_enabled(&db.path));
+        // Chiffrement interrompu : fichier de clés à côté d'une base restée en clair
+        std::fs::write(key_file_path(&db.path), "{}").unwrap();
+        assert!(!is_enabled(&db.path));
+        std::fs::remove_file(key_file_path(&db.path)).unwrap();
+
+        let (db, _) = encrypted_db("secret");
+        assert!(is_enabled(&db.path));
+        std::fs::remove_file(key_file_path(&db.path)).unwrap();
+        assert!(!is_enabled(&db.path));
+    }
+
+    #[test]
+    fn deverrouillage_par_mot_de_passe() {
+        let _serial = ACTIVE_KEY_TESTS.lock().unwrap_or_else(|e| e.into_inner());
+        let (db, _) = encrypted_db("secret");
+
+        lock();
+        assert_eq!(db::open_connection(&db.path).unwrap_err(), LOCKED_ERROR);
+        assert_eq!(unlock(&db.path, "faux").unwrap_err(), INVALID_SECRET_ERROR);
+        assert!(!is_unlocked());
+
+        unlock(&db.path, "secret").unwrap();
+        assert_eq!(school_name(&db.conn()).unwrap(), "Institut Umoja");
+    }
+
+    #[test]
+    fn changement_de_mot_de_passe() {
+        let _serial = ACTIVE_KEY_TESTS.lock().unwrap_or_else(|e| e.into_inner());
+        let (db, _) = encrypted_db("secret");
+
+        assert_eq!(
+            change_password(&db.path, "faux", "nouveau").unwrap_err(),
+            INVALID_SECRET_ERROR
+        );
+        change_password(&db.path, "secret", "nouveau").unwrap();
+
+        lock();
+        assert_eq!(
+            unlock(&db.path, "secret").unwrap_err(),
+            INVALID_SECRET_ERROR
+        );
+        unlock(&db.path, "nouveau").unwrap();
+        assert_eq!(school_name(&db.conn()).unwrap(), "Institut Umoja");
+    }
+
+    #[test]
+    fn recuperation_par_code() {
+        let _serial = ACTIVE_KEY_TESTS.lock().unwrap_or_else(|e| e.into_inner());
+        let (db, result) = encrypted_db("secret");
+        let data_key = ACTIVE_KEY.read().unwrap().clone();
+
+        lock();
+        assert_eq!(
+            recover(&db.path, "0000-0000", "nouveau").unwrap_err(),
+            INVALID_SECRET_ERROR
+        );
+        // Saisie tolérante : minuscules et espaces
+        let typed = result.recovery_code.to_lowercase().replace('-', " ");
+        recover(&db.path, &typed, "nouveau").unwrap();
+        assert_eq!(*ACTIVE_KEY.read().unwrap(), data_key);
+        assert_eq!(school_name(&db.conn()).unwrap(), "Institut Umoja");
+
+        lock();
+        unlock(&db.path, "nouveau").unwrap();
+        assert_eq!(*ACTIVE_KEY.read().unwrap(), data_key);
+    }
+}
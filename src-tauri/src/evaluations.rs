@@ -0,0 +1,649 @@
+// Évaluation continue : interrogations nommées à l'intérieur d'une période, chacune avec
+// son maximum et les points de chaque élève. La cote de période (table grades) est
+// recalculée à partir de ces interrogations dès qu'une d'elles change et marquée
+// is_computed. Une saisie directe reste en place tant que l'élève n'a aucun point et est
+// remplacée dès qu'il en a ; seule une cote calculée disparaît avec les points de l'élève.
+
+use crate::db::DbPool;
+use crate::gradebook::GRADE_TRICHEUR_CODE;
+use crate::periods;
+use log::info;
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+const RULE_SETTING_KEY: &str = "eval_aggregation_rule";
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AggregationRule {
+    // Total des points ramené au maximum de la période
+    Sum,
+    // Moyenne des pourcentages de chaque interrogation
+    Mean,
+}
+
+impl AggregationRule {
+    fn as_str(&self) -> &'static str {
+        match self {
+            AggregationRule::Sum => "sum",
+            AggregationRule::Mean => "mean",
+        }
+    }
+
+    pub fn load(conn: &Connection) -> Self {
+        let value: Option<String> = conn
+            .query_row(
+                "SELECT value FROM settings WHERE key = ?",
+                params![RULE_SETTING_KEY],
+                |row| row.get(0),
+            )
+            .ok();
+        match value.as_deref() {
+            Some("mean") => AggregationRule::Mean,
+            _ => AggregationRule::Sum,
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Evaluation {
+    pub id: i64,
+    pub subject_id: i64,
+    pub period_code: String,
+    pub name: String,
+    pub max_points: f64,
+    pub eval_date: Option<String>,
+    pub display_order: i64,
+    pub scores: Vec<EvaluationScore>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct EvaluationScore {
+    pub student_id: i64,
+    // None efface les points de l'élève (absence justifiée)
+    pub value: Option<f64>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct EvaluationInput {
+    pub id: Option<i64>,
+    pub subject_id: i64,
+    pub period_code: String,
+    pub name: String,
+    pub max_points: f64,
+    pub eval_date: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ComputedGrade {
+    pub student_id: i64,
+    pub subject_id: i64,
+    pub period: String,
+    pub value: f64,
+}
+
+// Migration 11
+pub fn create_schema(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS evaluations (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            subject_id INTEGER NOT NULL,
+            period_code TEXT NOT NULL,
+            name TEXT NOT NULL,
+            max_points REAL NOT NULL,
+            eval_date TEXT,
+            display_order INTEGER NOT NULL DEFAULT 0,
+            server_id TEXT,
+            is_dirty INTEGER DEFAULT 1,
+            created_at TEXT DEFAULT '1970-01-01 00:00:00',
+            updated_at TEXT DEFAULT '1970-01-01 00:00:00',
+            last_modified_at TEXT DEFAULT '1970-01-01 00:00:00',
+            FOREIGN KEY (subject_id) REFERENCES subjects(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS evaluation_scores (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            evaluation_id INTEGER NOT NULL,
+            student_id INTEGER NOT NULL,
+            value REAL NOT NULL,
+            server_id TEXT,
+            is_dirty INTEGER DEFAULT 1,
+            created_at TEXT DEFAULT '1970-01-01 00:00:00',
+            updated_at TEXT DEFAULT '1970-01-01 00:00:00',
+            last_modified_at TEXT DEFAULT '1970-01-01 00:00:00',
+            FOREIGN KEY (evaluation_id) REFERENCES evaluations(id) ON DELETE CASCADE,
+            FOREIGN KEY (student_id) REFERENCES students(id) ON DELETE CASCADE,
+            UNIQUE(evaluation_id, student_id)
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_evaluations_subject_period ON evaluations(subject_id, period_code);",
+    )
+}
+
+// Cote de période à partir des (points, maximum) de chaque interrogation passée
+pub fn aggregate(rule: AggregationRule, scores: &[(f64, f64)], period_max: f64) -> Option<f64> {
+    let scores: Vec<(f64, f64)> = scores
+        .iter()
+        .filter(|(_, max)| *max > 0.0)
+        .map(|(value, max)| {
+            let value = if *value == GRADE_TRICHEUR_CODE {
+                0.0
+            } else {
+                *value
+            };
+            (value, *max)
+        })
+        .collect();
+    if scores.is_empty() {
+        return None;
+    }
+    let ratio = match rule {
+        AggregationRule::Sum => {
+            let points: f64 = scores.iter().map(|(v, _)| v).sum();
+            let max: f64 = scores.iter().map(|(_, m)| m).sum();
+            points / max
+        }
+        AggregationRule::Mean => {
+            scores.iter().map(|(v, m)| v / m).sum::<f64>() / scores.len() as f64
+        }
+    };
+    Some((ratio.clamp(0.0, 1.0) * period_max * 100.0).round() / 100.0)
+}
+
+// Recalcule les cotes de période d'un cours pour les élèves ayant au moins une
+// interrogation notée ; les saisies directes des autres élèves ne sont pas touchées.
+pub fn recompute(
+    conn: &Connection,
+    subject_id: i64,
+    period_code: &str,
+) -> Result<Vec<ComputedGrade>, String> {
+    let rule = AggregationRule::load(conn);
+    let period_max = periods::max_points(conn, subject_id, period_code)?;
+
+    let mut by_student: HashMap<i64, Vec<(f64, f64)>> = HashMap::new();
+    {
+        let mut stmt = conn
+            .prepare_cached(
+                "SELECT sc.student_id, sc.value, e.max_points
+                 FROM evaluation_scores sc JOIN evaluations e ON sc.evaluation_id = e.id
+                 WHERE e.subject_id = ? AND e.period_code = ?",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![subject_id, period_code], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get(1)?, row.get(2)?))
+            })
+            .map_err(|e| e.to_string())?;
+        for row in rows {
+            let (student_id, value, max) = row.map_err(|e| e.to_string())?;
+            by_student.entry(student_id).or_default().push((value, max));
+        }
+    }
+
+    let mut computed = Vec::new();
+    let mut stmt = conn
+        .prepare_cached(
+            "INSERT INTO grades (student_id, subject_id, period, value, is_computed, is_dirty, last_modified_at)
+             VALUES (?1, ?2, ?3, ?4, 1, 1, datetime('now'))
+             ON CONFLICT(student_id, subject_id, period)
+             DO UPDATE SET value = ?4, is_estimated = 0, is_computed = 1, is_dirty = 1, last_modified_at = datetime('now')
+             WHERE value != ?4 OR is_estimated != 0 OR is_computed != 1",
+        )
+        .map_err(|e| e.to_string())?;
+    for (student_id, scores) in by_student {
+        if let Some(value) = aggregate(rule, &scores, period_max) {
+            stmt.execute(params![student_id, subject_id, period_code, value])
+                .map_err(|e| e.to_string())?;
+            computed.push(ComputedGrade {
+                student_id,
+                subject_id,
+                period: period_code.to_string(),
+                value,
+            });
+        }
+    }
+    // Cotes calculées devenues orphelines (points effacés, interrogation supprimée). Créer ou
+    // modifier une interrogation ne change aucun point : rien n'est supprimé dans ce cas.
+    conn.execute(
+        "DELETE FROM grades
+         WHERE subject_id = ?1 AND period = ?2 AND is_computed = 1
+           AND student_id NOT IN (
+               SELECT sc.student_id FROM evaluation_scores sc JOIN evaluations e ON sc.evaluation_id = e.id
+               WHERE e.subject_id = ?1 AND e.period_code = ?2)",
+        params![subject_id, period_code],
+    )
+    .map_err(|e| e.to_string())?;
+    computed.sort_by_key(|g| g.student_id);
+    Ok(computed)
+}
+
+fn evaluation_target(conn: &Connection, evaluation_id: i64) -> Result<(i64, String), String> {
+    conn.query_row(
+        "SELECT subject_id, period_code FROM evaluations WHERE id = ?",
+        params![evaluation_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )
+    .optional()
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| "Interrogation introuvable".to_string())
+}
+
+pub fn list_evaluations(
+    conn: &Connection,
+    subject_id: i64,
+    period_code: &str,
+) -> Result<Vec<Evaluation>, String> {
+    let mut evaluations: Vec<Evaluation> = conn
+        .prepare_cached(
+            "SELECT id, subject_id, period_code, name, max_points, eval_date, display_order
+             FROM evaluations WHERE subject_id = ? AND period_code = ? ORDER BY display_order, id",
+        )
+        .and_then(|mut stmt| {
+            stmt.query_map(params![subject_id, period_code], |row| {
+                Ok(Evaluation {
+                    id: row.get(0)?,
+                    subject_id: row.get(1)?,
+                    period_code: row.get(2)?,
+                    name: row.get(3)?,
+                    max_points: row.get(4)?,
+                    eval_date: row.get(5)?,
+                    display_order: row.get(6)?,
+                    scores: Vec::new(),
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare_cached("SELECT student_id, value FROM evaluation_scores WHERE evaluation_id = ?")
+        .map_err(|e| e.to_string())?;
+    for evaluation in evaluations.iter_mut() {
+        evaluation.scores = stmt
+            .query_map(params![evaluation.id], |row| {
+                Ok(EvaluationScore {
+                    student_id: row.get(0)?,
+                    value: row.get(1)?,
+                })
+            })
+            .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(evaluations)
+}
+
+pub fn save_evaluation(
+    conn: &mut Connection,
+    input: &EvaluationInput,
+) -> Result<(i64, Vec<ComputedGrade>), String> {
+    if input.name.trim().is_empty() {
+        return Err("Le nom de l'interrogation est obligatoire".to_string());
+    }
+    if input.max_points <= 0.0 {
+        return Err("Le maximum doit être positif".to_string());
+    }
+    // Vérifie que la période existe pour l'année du cours
+    periods::max_points(conn, input.subject_id, &input.period_code)?;
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let id = match input.id {
+        Some(id) => {
+            let (subject_id, period_code) = evaluation_target(&tx, id)?;
+            if subject_id != input.subject_id || period_code != input.period_code {
+                return Err(
+                    "Une interrogation ne peut pas changer de cours ni de période".to_string(),
+                );
+            }
+            tx.execute(
+                "UPDATE evaluations SET name = ?, max_points = ?, eval_date = ?, is_dirty = 1, last_modified_at = (datetime('now')) WHERE id = ?",
+                params![input.name.trim(), input.max_points, input.eval_date, id],
+            )
+            .map_err(|e| e.to_string())?;
+            id
+        }
+        None => {
+            tx.execute(
+                "INSERT INTO evaluations (subject_id, period_code, name, max_points, eval_date, display_order, is_dirty, last_modified_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, (SELECT COALESCE(MAX(display_order), 0) + 1 FROM evaluations WHERE subject_id = ?1 AND period_code = ?2), 1, (datetime('now')))",
+                params![input.subject_id, input.period_code, input.name.trim(), input.max_points, input.eval_date],
+            )
+            .map_err(|e| e.to_string())?;
+            tx.last_insert_rowid()
+        }
+    };
+    let computed = recompute(&tx, input.subject_id, &input.period_code)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok((id, computed))
+}
+
+pub fn delete_evaluation(
+    conn: &mut Connection,
+    evaluation_id: i64,
+) -> Result<Vec<ComputedGrade>, String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let (subject_id, period_code) = evaluation_target(&tx, evaluation_id)?;
+    // Les points sont supprimés explicitement pour que leurs suppressions soient synchronisées
+    tx.execute(
+        "DELETE FROM evaluation_scores WHERE evaluation_id = ?",
+        params![evaluation_id],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.execute(
+        "DELETE FROM evaluations WHERE id = ?",
+        params![evaluation_id],
+    )
+    .map_err(|e| e.to_string())?;
+    let computed = recompute(&tx, subject_id, &period_code)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(computed)
+}
+
+// Enregistre des points sans transaction propre (utilisé aussi par le serveur mobile)
+pub fn write_scores(
+    conn: &Connection,
+    evaluation_id: i64,
+    scores: &[EvaluationScore],
+) -> Result<(i64, String), String> {
+    let target = evaluation_target(conn, evaluation_id)?;
+    let max_points: f64 = conn
+        .query_row(
+            "SELECT max_points FROM evaluations WHERE id = ?",
+            params![evaluation_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+
+    for score in scores {
+        match score.value {
+            Some(value) => {
+                if value != GRADE_TRICHEUR_CODE && !(0.0..=max_points).contains(&value) {
+                    return Err(format!(
+                        "Points hors limites ({} sur {})",
+                        value, max_points
+                    ));
+                }
+                conn.execute(
+                    "INSERT INTO evaluation_scores (evaluation_id, student_id, value, is_dirty, last_modified_at)
+                     VALUES (?1, ?2, ?3, 1, datetime('now'))
+                     ON CONFLICT(evaluation_id, student_id)
+                     DO UPDATE SET value = ?3, is_dirty = 1, last_modified_at = datetime('now')",
+                    params![evaluation_id, score.student_id, value],
+                )
+                .map_err(|e| e.to_string())?;
+            }
+            None => {
+                conn.execute(
+                    "DELETE FROM evaluation_scores WHERE evaluation_id = ? AND student_id = ?",
+                    params![evaluation_id, score.student_id],
+                )
+                .map_err(|e| e.to_string())?;
+            }
+        }
+    }
+    Ok(target)
+}
+
+pub fn save_scores(
+    conn: &mut Connection,
+    evaluation_id: i64,
+    scores: &[EvaluationScore],
+) -> Result<Vec<ComputedGrade>, String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let (subject_id, period_code) = write_scores(&tx, evaluation_id, scores)?;
+    let computed = recompute(&tx, subject_id, &period_code)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(computed)
+}
+
+// Changer de règle recalcule toutes les cotes issues d'interrogations
+pub fn set_rule(conn: &mut Connection, rule: AggregationRule) -> Result<usize, String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    tx.execute(
+        "INSERT OR REPLACE INTO settings (key, value) VALUES (?, ?)",
+        params![RULE_SETTING_KEY, rule.as_str()],
+    )
+    .map_err(|e| e.to_string())?;
+    let targets: Vec<(i64, String)> = tx
+        .prepare("SELECT DISTINCT subject_id, period_code FROM evaluations")
+        .and_then(|mut stmt| {
+            stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .map_err(|e| e.to_string())?;
+    let mut updated = 0;
+    for (subject_id, period_code) in &targets {
+        updated += recompute(&tx, *subject_id, period_code)?.len();
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    info!(
+        "Evaluation rule set to {}: {} grades recomputed",
+        rule.as_str(),
+        updated
+    );
+    Ok(updated)
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedEvaluation {
+    pub id: i64,
+    pub grades: Vec<ComputedGrade>,
+}
+
+#[tauri::command]
+pub async fn evaluations_list(
+    pool: tauri::State<'_, DbPool>,
+    subject_id: i64,
+    period_code: String,
+) -> Result<Vec<Evaluation>, String> {
+    pool.run(move |conn| list_evaluations(conn, subject_id, &period_code))
+        .await
+}
+
+#[tauri::command]
+pub async fn evaluation_save(
+    pool: tauri::State<'_, DbPool>,
+    evaluation: EvaluationInput,
+) -> Result<SavedEvaluation, String> {
+    pool.run(move |conn| {
+        save_evaluation(conn, &evaluation).map(|(id, grades)| SavedEvaluation { id, grades })
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn evaluation_delete(
+    pool: tauri::State<'_, DbPool>,
+    evaluation_id: i64,
+) -> Result<Vec<ComputedGrade>, String> {
+    pool.run(move |conn| delete_evaluation(conn, evaluation_id))
+        .await
+}
+
+#[tauri::command]
+pub async fn evaluation_scores_save(
+    pool: tauri::State<'_, DbPool>,
+    evaluation_id: i64,
+    scores: Vec<EvaluationScore>,
+) -> Result<Vec<ComputedGrade>, String> {
+    pool.run(move |conn| save_scores(conn, evaluation_id, &scores))
+        .await
+}
+
+#[tauri::command]
+pub async fn evaluation_rule_get(
+    pool: tauri::State<'_, DbPool>,
+) -> Result<AggregationRule, String> {
+    pool.run(|conn| Ok(AggregationRule::load(conn))).await
+}
+
+#[tauri::command]
+pub async fn evaluation_rule_set(
+    pool: tauri::State<'_, DbPool>,
+    rule: AggregationRule,
+) -> Result<usize, String> {
+    pool.run(move |conn| set_rule(conn, rule)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::TestDb;
+
+    #[test]
+    fn somme_et_moyenne() {
+        // 5/10 et 5/5 : 10 points sur 15 contre 50 % et 100 %
+        let scores = [(5.0, 10.0), (5.0, 5.0)];
+        assert_eq!(aggregate(AggregationRule::Sum, &scores, 20.0), Some(13.33));
+        assert_eq!(aggregate(AggregationRule::Mean, &scores, 20.0), Some(15.0));
+        assert_eq!(aggregate(AggregationRule::Sum, &[], 20.0), None);
+        // Une interrogation sans maximum est ignorée
+        assert_eq!(aggregate(AggregationRule::Sum, &[(3.0, 0.0)], 20.0), None);
+    }
+
+    #[test]
+    fn ramene_au_maximum_de_la_periode() {
+        assert_eq!(
+            aggregate(AggregationRule::Sum, &[(7.0, 10.0)], 40.0),
+            Some(28.0)
+        );
+        assert_eq!(
+            aggregate(AggregationRule::Mean, &[(7.0, 10.0)], 50.0),
+            Some(35.0)
+        );
+        // Bornée entre 0 et le maximum
+        assert_eq!(
+            aggregate(AggregationRule::Sum, &[(12.0, 10.0)], 20.0),
+            Some(20.0)
+        );
+        assert_eq!(
+            aggregate(AggregationRule::Mean, &[(-3.0, 10.0)], 20.0),
+            Some(0.0)
+        );
+    }
+
+    #[test]
+    fn tricheur_compte_zero() {
+        let scores = [(GRADE_TRICHEUR_CODE, 10.0), (10.0, 10.0)];
+        assert_eq!(aggregate(AggregationRule::Sum, &scores, 20.0), Some(10.0));
+        assert_eq!(
+            aggregate(AggregationRule::Mean, &[(GRADE_TRICHEUR_CODE, 10.0)], 20.0),
+            Some(0.0)
+        );
+    }
+
+    fn score(student_id: i64, value: Option<f64>) -> EvaluationScore {
+        EvaluationScore { student_id, value }
+    }
+
+    fn input(name: &str, period_code: &str, max_points: f64) -> EvaluationInput {
+        EvaluationInput {
+            id: None,
+            subject_id: 1,
+            period_code: period_code.to_string(),
+            name: name.to_string(),
+            max_points,
+            eval_date: None,
+        }
+    }
+
+    fn grade(conn: &Connection, student_id: i64) -> Option<f64> {
+        conn.query_row(
+            "SELECT value FROM grades WHERE student_id = ? AND subject_id = 1 AND period = 'P1'",
+            params![student_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .unwrap()
+    }
+
+    #[test]
+    fn cotes_recalculees_et_orphelines_supprimees() {
+        let db = TestDb::open();
+        let mut conn = db.conn();
+        conn.execute_batch(
+            "INSERT INTO academic_years (id, name, start_date, end_date) VALUES (1, '2024-2025', '2024-09-01', '2025-07-01');
+             INSERT INTO classes (id, name, level, option, section, academic_year_id) VALUES (1, '7ème A', '7ème', 'EB', 'A', 1);
+             INSERT INTO subjects (id, name, class_id, max_p1) VALUES (1, 'Maths', 1, 20);
+             INSERT INTO students (id, first_name, last_name, gender, class_id) VALUES
+               (1, 'Jean', 'Kabila', 'M', 1), (2, 'Marie', 'Mbuyi', 'F', 1), (3, 'Paul', 'Ilunga', 'M', 1);
+             -- Saisie directe, sans interrogation
+             INSERT INTO grades (student_id, subject_id, period, value) VALUES (3, 1, 'P1', 12);",
+        )
+        .unwrap();
+
+        assert!(save_evaluation(&mut conn, &input("I1", "ZZ", 10.0)).is_err());
+        let (i1, _) = save_evaluation(&mut conn, &input("I1", "P1", 10.0)).unwrap();
+        let (i2, _) = save_evaluation(&mut conn, &input("I2", "P1", 5.0)).unwrap();
+        save_scores(&mut conn, i1, &[score(1, Some(5.0)), score(2, Some(10.0))]).unwrap();
+        let computed = save_scores(&mut conn, i2, &[score(1, Some(5.0))]).unwrap();
+        assert_eq!(computed.len(), 2);
+        assert_eq!(computed[0].value, 13.33);
+        assert_eq!(computed[1].value, 20.0);
+        // L'élève sans points garde sa saisie directe
+        assert_eq!(grade(&conn, 3), Some(12.0));
+        assert!(save_scores(&mut conn, i2, &[score(1, Some(6.0))]).is_err());
+
+        assert_eq!(set_rule(&mut conn, AggregationRule::Mean).unwrap(), 2);
+        assert_eq!(grade(&conn, 1), Some(15.0));
+
+        // Points effacés : la cote de l'élève disparaît au lieu de rester figée
+        let computed = save_scores(&mut conn, i1, &[score(2, None)]).unwrap();
+        assert_eq!(computed.len(), 1);
+        assert_eq!(grade(&conn, 2), None);
+
+        let computed = delete_evaluation(&mut conn, i2).unwrap();
+        assert_eq!(computed[0].value, 10.0);
+        assert_eq!(list_evaluations(&conn, 1, "P1").unwrap().len(), 1);
+
+        // Dernière interrogation supprimée : plus aucune cote calculée
+        assert!(delete_evaluation(&mut conn, i1).unwrap().is_empty());
+        assert_eq!(grade(&conn, 1), None);
+        assert_eq!(grade(&conn, 3), Some(12.0));
+    }
+
+    #[test]
+    fn saisie_directe_conservee_jusqu_aux_points() {
+        let db = TestDb::open();
+        let mut conn = db.conn();
+        conn.execute_batch(
+            "INSERT INTO academic_years (id, name, start_date, end_date) VALUES (1, '2024-2025', '2024-09-01', '2025-07-01');
+             INSERT INTO classes (id, name, level, option, section, academic_year_id) VALUES (1, '7ème A', '7ème', 'EB', 'A', 1);
+             INSERT INTO subjects (id, name, class_id, max_p1) VALUES (1, 'Maths', 1, 20);
+             INSERT INTO students (id, first_name, last_name, gender, class_id) VALUES
+               (1, 'Jean', 'Kabila', 'M', 1), (2, 'Marie', 'Mbuyi', 'F', 1);
+             INSERT INTO grades (student_id, subject_id, period, value, server_id) VALUES
+               (1, 1, 'P1', 7, 'g1'), (2, 1, 'P1', 9, 'g2');",
+        )
+        .unwrap();
+        let deletions = |conn: &Connection| -> i64 {
+            conn.query_row("SELECT COUNT(*) FROM sync_deletions", [], |row| row.get(0))
+                .unwrap()
+        };
+
+        // Création et modification d'une interrogation sans points : rien n'est supprimé
+        let (i1, computed) = save_evaluation(&mut conn, &input("I1", "P1", 10.0)).unwrap();
+        assert!(computed.is_empty());
+        let mut edited = input("Interrogation 1", "P1", 20.0);
+        edited.id = Some(i1);
+        save_evaluation(&mut conn, &edited).unwrap();
+        assert_eq!((grade(&conn, 1), grade(&conn, 2)), (Some(7.0), Some(9.0)));
+        assert_eq!(deletions(&conn), 0);
+
+        // Remplacée seulement pour l'élève qui a des points
+        save_scores(&mut conn, i1, &[score(1, Some(15.0))]).unwrap();
+        assert_eq!((grade(&conn, 1), grade(&conn, 2)), (Some(15.0), Some(9.0)));
+
+        // Ses points effacés, sa cote calculée disparaît ; la saisie directe reste
+        save_scores(&mut conn, i1, &[score(1, None)]).unwrap();
+        assert_eq!((grade(&conn, 1), grade(&conn, 2)), (None, Some(9.0)));
+        delete_evaluation(&mut conn, i1).unwrap();
+        assert_eq!(grade(&conn, 2), Some(9.0));
+        assert_eq!(deletions(&conn), 1);
+    }
+}
@@ -0,0 +1,256 @@
+// Contraintes sur les cotes au niveau de la base : une cote doit porter sur une période
+// de l'année de sa classe et rester entre 0 et le maximum du cours pour cette période
+// (le code tricheur -1 excepté). Les lignes antérieures aux triggers se repèrent avec
+// grades_audit.
+
+use crate::db::DbPool;
+use crate::gradebook::GRADE_TRICHEUR_CODE;
+use log::info;
+use rusqlite::{params, Connection, Transaction};
+use serde::Serialize;
+
+pub const UNKNOWN_PERIOD_MESSAGE: &str = "Période inconnue pour ce cours";
+pub const OUT_OF_RANGE_MESSAGE: &str =
+    "Cote hors limites : elle doit être comprise entre 0 et le maximum de la période";
+
+// Maximum effectif, même règle que periods::max_points
+fn max_expr(row: &str) -> String {
+    format!(
+        "COALESCE(
+            (SELECT max_points FROM subject_period_max WHERE subject_id = {row}.subject_id AND period_code = {row}.period),
+            (SELECT p.default_max FROM subjects s JOIN classes c ON c.id = s.class_id
+             JOIN periods p ON p.academic_year_id = c.academic_year_id
+             WHERE s.id = {row}.subject_id AND p.code = {row}.period)
+        )"
+    )
+}
+
+fn period_known_expr(row: &str) -> String {
+    format!(
+        "EXISTS(SELECT 1 FROM subjects s JOIN classes c ON c.id = s.class_id
+            JOIN periods p ON p.academic_year_id = c.academic_year_id
+            WHERE s.id = {row}.subject_id AND p.code = {row}.period)"
+    )
+}
+
+pub fn create_schema(tx: &Transaction) -> rusqlite::Result<()> {
+    let checks = format!(
+        "SELECT RAISE(ABORT, '{unknown}') WHERE NOT {known};
+            SELECT RAISE(ABORT, '{range}')
+            WHERE NEW.value != {tricheur} AND (NEW.value < 0 OR NEW.value > {max});",
+        unknown = UNKNOWN_PERIOD_MESSAGE,
+        range = OUT_OF_RANGE_MESSAGE.replace('\'', "''"),
+        known = period_known_expr("NEW"),
+        tricheur = GRADE_TRICHEUR_CODE,
+        max = max_expr("NEW"),
+    );
+    tx.execute_batch(&format!(
+        "
+        CREATE TRIGGER IF NOT EXISTS trg_grades_validate_insert
+        BEFORE INSERT ON grades
+        FOR EACH ROW
+        BEGIN
+            {checks}
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS trg_grades_validate_update
+        BEFORE UPDATE OF subject_id, period, value ON grades
+        FOR EACH ROW
+        BEGIN
+            {checks}
+        END;
+        "
+    ))
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum GradeViolationKind {
+    UnknownPeriod,
+    Negative,
+    AboveMax,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct GradeViolation {
+    pub grade_id: i64,
+    pub kind: GradeViolationKind,
+    pub class_id: Option<i64>,
+    pub class_name: String,
+    pub student_id: i64,
+    pub student_name: String,
+    pub subject_id: i64,
+    pub subject_name: String,
+    pub period: String,
+    pub value: f64,
+    pub max_points: Option<f64>,
+}
+
+pub fn audit(conn: &Connection) -> Result<Vec<GradeViolation>, String> {
+    let sql = format!(
+        "SELECT g.id, g.student_id, g.subject_id, g.period, g.value,
+                {known}, {max},
+                c.id, COALESCE(c.name, ''),
+                TRIM(COALESCE(st.last_name, '') || ' ' || COALESCE(st.post_name, '') || ' ' || COALESCE(st.first_name, '')),
+                COALESCE(s.name, '')
+         FROM grades g
+         LEFT JOIN subjects s ON s.id = g.subject_id
+         LEFT JOIN classes c ON c.id = s.class_id
+         LEFT JOIN students st ON st.id = g.student_id
+         WHERE NOT {known}
+            OR (g.value != ?1 AND (g.value < 0 OR g.value > {max}))
+         ORDER BY c.name, st.last_name, s.display_order, g.period",
+        known = period_known_expr("g"),
+        max = max_expr("g"),
+    );
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![GRADE_TRICHEUR_CODE], |row| {
+            let value: f64 = row.get(4)?;
+            let known: bool = row.get(5)?;
+            let max_points: Option<f64> = row.get(6)?;
+            let kind = if !known {
+                GradeViolationKind::UnknownPeriod
+            } else if value < 0.0 {
+                GradeViolationKind::Negative
+            } else {
+                GradeViolationKind::AboveMax
+            };
+            Ok(GradeViolation {
+                grade_id: row.get(0)?,
+                kind,
+                class_id: row.get(7)?,
+                class_name: row.get(8)?,
+                student_id: row.get(1)?,
+                student_name: row.get(9)?,
+                subject_id: row.get(2)?,
+                subject_name: row.get(10)?,
+                period: row.get(3)?,
+                value,
+                max_points,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn grades_audit(pool: tauri::State<'_, DbPool>) -> Result<Vec<GradeViolation>, String> {
+    let violations = pool.run(|conn| audit(conn)).await?;
+    info!(
+        "Audit des cotes : {} ligne(s) hors règles",
+        violations.len()
+    );
+    Ok(violations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::TestDb;
+
+    fn class_db() -> TestDb {
+        let db = TestDb::open();
+        db.conn()
+            .execute_batch(
+                "INSERT INTO academic_years (id, name, start_date, end_date, is_active) VALUES (1, '2024-2025', '2024-09-01', '2025-07-01', 1);
+                 INSERT INTO classes (id, name, level, option, section, academic_year_id) VALUES (1, '2ème ETRO', '2ème', 'ELECTRONIQUE', 'A', 1);
+                 INSERT INTO subjects (id, name, code, class_id) VALUES (1, 'Maths', 'MATH', 1);
+                 INSERT INTO students (id, first_name, last_name, gender, class_id) VALUES (1, 'Jean', 'Kabila', 'M', 1);",
+            )
+            .unwrap();
+        db
+    }
+
+    fn save(conn: &Connection, period: &str, value: f64) -> rusqlite::Result<usize> {
+        conn.execute(
+            "INSERT INTO grades (student_id, subject_id, period, value) VALUES (1, 1, ?, ?)
+             ON CONFLICT(student_id, subject_id, period) DO UPDATE SET value = excluded.value",
+            params![period, value],
+        )
+    }
+
+    #[test]
+    fn cotes_hors_limites_refusees() {
+        let db = class_db();
+        let conn = db.conn();
+
+        // Bornes incluses et code tricheur acceptés
+        save(&conn, "P1", 0.0).unwrap();
+        save(&conn, "P1", 10.0).unwrap();
+        save(&conn, "EXAM1", 20.0).unwrap();
+        save(&conn, "P2", GRADE_TRICHEUR_CODE).unwrap();
+
+        // Au-dessus du maximum, à l'insertion comme à la modification
+        let error = save(&conn, "P3", 10.5).unwrap_err();
+        assert_eq!(
+            error.sqlite_error_code(),
+            Some(rusqlite::ErrorCode::ConstraintViolation)
+        );
+        assert!(error.to_string().contains(OUT_OF_RANGE_MESSAGE));
+        assert!(save(&conn, "P1", 10.5).is_err());
+        // Négatif autre que le code tricheur
+        assert!(save(&conn, "P4", -2.0).is_err());
+        assert!(conn
+            .execute("UPDATE grades SET value = -0.5 WHERE period = 'P1'", [])
+            .is_err());
+
+        // Le maximum propre au cours l'emporte sur celui de la période
+        conn.execute(
+            "UPDATE subject_period_max SET max_points = 20 WHERE subject_id = 1 AND period_code = 'P3'",
+            [],
+        )
+        .unwrap();
+        save(&conn, "P3", 15.0).unwrap();
+
+        let value: f64 = conn
+            .query_row("SELECT value FROM grades WHERE period = 'P1'", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(value, 10.0);
+        assert!(audit(&conn).unwrap().is_empty());
+    }
+
+    #[test]
+    fn periode_inconnue_refusee() {
+        let db = class_db();
+        let conn = db.conn();
+
+        let error = save(&conn, "P9", 1.0).unwrap_err();
+        assert!(error.to_string().contains(UNKNOWN_PERIOD_MESSAGE));
+        save(&conn, "P1", 5.0).unwrap();
+        assert!(conn
+            .execute("UPDATE grades SET period = 'P9' WHERE period = 'P1'", [])
+            .is_err());
+    }
+
+    #[test]
+    fn audit_des_lignes_anterieures_aux_triggers() {
+        let db = class_db();
+        let conn = db.conn();
+        conn.execute_batch(
+            "DROP TRIGGER trg_grades_validate_insert;
+             INSERT INTO grades (student_id, subject_id, period, value) VALUES
+               (1, 1, 'P9', 3), (1, 1, 'P3', 11), (1, 1, 'P4', -3), (1, 1, 'P1', -1), (1, 1, 'P2', 7);",
+        )
+        .unwrap();
+
+        let mut kinds: Vec<_> = audit(&conn)
+            .unwrap()
+            .into_iter()
+            .map(|v| (v.period, v.kind, v.max_points))
+            .collect();
+        kinds.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            kinds,
+            vec![
+                ("P3".to_string(), GradeViolationKind::AboveMax, Some(10.0)),
+                ("P4".to_string(), GradeViolationKind::Negative, Some(10.0)),
+                ("P9".to_string(), GradeViolationKind::UnknownPeriod, None),
+            ]
+        );
+    }
+}
@@ -0,0 +1,301 @@
+// Chargement en une fois de tout ce qu'il faut pour calculer les résultats d'une classe :
+// périodes, cours et leurs maxima, élèves, cotes et repêchages. Les calculs (délibération,
+// bulletins, classements) travaillent ensuite en mémoire sans retourner en base.
+
+use crate::periods::{self, Period};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+pub const GRADE_TRICHEUR_CODE: f64 = -1.0;
+
+// Pondération des totaux, paramètre d'école grade_weighting_mode
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WeightingMode {
+    // Points bruts : chaque cours pèse son maximum
+    #[default]
+    Points,
+    // Points et maxima multipliés par le coefficient du cours
+    Coefficient,
+}
+
+impl WeightingMode {
+    pub fn from_settings(conn: &Connection) -> Self {
+        conn.query_row(
+            "SELECT value FROM settings WHERE key = 'grade_weighting_mode'",
+            params![],
+            |row| row.get::<_, String>(0),
+        )
+        .ok()
+        .and_then(|v| serde_json::from_value(serde_json::Value::String(v)).ok())
+        .unwrap_or_default()
+    }
+}
+
+pub struct ClassInfo {
+    pub name: String,
+    pub level: String,
+    pub academic_year_id: i64,
+}
+
+pub struct SubjectInfo {
+    pub id: i64,
+    pub name: String,
+    pub code: String,
+    pub category: String,
+    // Domaine et sous-domaine (classes de l'éducation de base)
+    pub domain_id: Option<i64>,
+    pub domain: String,
+    pub domain_order: i64,
+    pub sub_domain: String,
+    // NULL en base : coefficient 1
+    pub coefficient: Option<f64>,
+    // code de période -> maximum
+    pub maxima: HashMap<String, f64>,
+}
+
+impl SubjectInfo {
+    pub fn max_for(&self, period_code: &str) -> f64 {
+        self.maxima.get(period_code).copied().unwrap_or(0.0)
+    }
+}
+
+pub struct StudentInfo {
+    pub id: i64,
+    pub name: String,
+    pub gender: String,
+    pub abandoned: bool,
+}
+
+// Points d'un cours sur un ensemble de périodes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SubjectTotal {
+    pub points: f64,
+    pub max: f64,
+    // Une cote au moins manque sur une période notée
+    pub missing: bool,
+}
+
+pub struct Gradebook {
+    pub class: ClassInfo,
+    pub periods: Vec<Period>,
+    pub subjects: Vec<SubjectInfo>,
+    pub students: Vec<StudentInfo>,
+    // (élève, cours, période) -> valeur brute (le code tricheur -1 compris)
+    pub grades: HashMap<(i64, i64, String), f64>,
+    // Cotes issues d'une prédiction appliquée (grades.is_estimated)
+    pub estimated: HashSet<(i64, i64, String)>,
+    // (élève, cours) -> pourcentage obtenu à la 2ème session
+    pub repechages: HashMap<(i64, i64), f64>,
+    pub weighting: WeightingMode,
+}
+
+impl Gradebook {
+    pub fn load(conn: &Connection, class_id: i64) -> Result<Self, String> {
+        let class = conn
+            .query_row(
+                "SELECT name, level, academic_year_id FROM classes WHERE id = ?",
+                params![class_id],
+                |row| {
+                    Ok(ClassInfo {
+                        name: row.get(0)?,
+                        level: row.get(1)?,
+                        academic_year_id: row.get(2)?,
+                    })
+                },
+            )
+            .optional()
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "Classe introuvable".to_string())?;
+
+        let periods = periods::load_periods(conn, class.academic_year_id)?;
+        let mut maxima = periods::class_maxima(conn, class_id)?;
+
+        let subjects = conn
+            .prepare_cached(
+                "SELECT s.id, s.name, s.code, COALESCE(s.category, ''), s.domain_id,
+                        COALESCE(d.name, ''), COALESCE(d.display_order, 0), COALESCE(s.sub_domain, ''),
+                        s.coefficient
+                 FROM subjects s LEFT JOIN domains d ON d.id = s.domain_id
+                 WHERE s.class_id = ? ORDER BY s.display_order, s.id",
+            )
+            .and_then(|mut stmt| {
+                stmt.query_map(params![class_id], |row| {
+                    Ok(SubjectInfo {
+                        id: row.get(0)?,
+                        name: row.get(1)?,
+                        code: row.get(2)?,
+                        category: row.get(3)?,
+                        domain_id: row.get(4)?,
+                        domain: row.get(5)?,
+                        domain_order: row.get(6)?,
+                        sub_domain: row.get(7)?,
+                        coefficient: row.get(8)?,
+                        maxima: HashMap::new(),
+                    })
+                })?
+                .collect::<Result<Vec<_>, _>>()
+            })
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .map(|mut subject| {
+                subject.maxima = maxima.remove(&subject.id).unwrap_or_default();
+                subject
+            })
+            .collect();
+
+        let students = conn
+            .prepare_cached(
+                "SELECT id, last_name, COALESCE(post_name, ''), COALESCE(first_name, ''),
+                        COALESCE(is_abandoned, 0) != 0 OR COALESCE(abandon_reason, '') != '',
+                        COALESCE(gender, '')
+                 FROM students WHERE class_id = ? ORDER BY last_name, post_name, first_name",
+            )
+            .and_then(|mut stmt| {
+                stmt.query_map(params![class_id], |row| {
+                    let name = [row.get::<_, String>(1)?, row.get(2)?, row.get(3)?]
+                        .iter()
+                        .filter(|s| !s.is_empty())
+                        .cloned()
+                        .collect::<Vec<_>>()
+                        .join(" ");
+                    Ok(StudentInfo {
+                        id: row.get(0)?,
+                        name,
+                        gender: row.get(5)?,
+                        abandoned: row.get(4)?,
+                    })
+                })?
+                .collect::<Result<Vec<_>, _>>()
+            })
+            .map_err(|e| e.to_string())?;
+
+        let mut grades = HashMap::new();
+        let mut estimated = HashSet::new();
+        {
+            let mut stmt = conn
+                .prepare_cached(
+                    "SELECT g.student_id, g.subject_id, g.period, g.value, COALESCE(g.is_estimated, 0)
+                     FROM grades g JOIN subjects s ON g.subject_id = s.id
+                     WHERE s.class_id = ?",
+                )
+                .map_err(|e| e.to_string())?;
+            let rows = stmt
+                .query_map(params![class_id], |row| {
+                    Ok((
+                        (row.get(0)?, row.get(1)?, row.get::<_, String>(2)?),
+                        row.get::<_, f64>(3)?,
+                        row.get::<_, bool>(4)?,
+                    ))
+                })
+                .map_err(|e| e.to_string())?;
+            for row in rows {
+                let (key, value, is_estimated) = row.map_err(|e| e.to_string())?;
+                if is_estimated {
+                    estimated.insert(key.clone());
+                }
+                grades.insert(key, value);
+            }
+        }
+
+        let mut repechages = HashMap::new();
+        {
+            let mut stmt = conn
+                .prepare_cached(
+                    "SELECT r.student_id, r.subject_id, r.percentage
+                     FROM repechages r JOIN subjects s ON r.subject_id = s.id
+                     WHERE s.class_id = ?",
+                )
+                .map_err(|e| e.to_string())?;
+            let rows = stmt
+                .query_map(params![class_id], |row| {
+                    Ok(((row.get(0)?, row.get(1)?), row.get::<_, f64>(2)?))
+                })
+                .map_err(|e| e.to_string())?;
+            for row in rows {
+                let (key, percentage) = row.map_err(|e| e.to_string())?;
+                repechages.insert(key, percentage);
+            }
+        }
+
+        Ok(Gradebook {
+            class,
+            periods,
+            subjects,
+            students,
+            grades,
+            estimated,
+            repechages,
+            weighting: WeightingMode::from_settings(conn),
+        })
+    }
+
+    // Valeur utilisable en calcul : le code tricheur vaut zéro
+    pub fn grade(&self, student_id: i64, subject_id: i64, period_code: &str) -> Option<f64> {
+        self.grades
+            .get(&(student_id, subject_id, period_code.to_string()))
+            .map(|v| if *v == GRADE_TRICHEUR_CODE { 0.0 } else { *v })
+    }
+
+    pub fn is_estimated(&self, student_id: i64, subject_id: i64, period_code: &str) -> bool {
+        self.estimated
+            .contains(&(student_id, subject_id, period_code.to_string()))
+    }
+
+    // Les cotes estimées redeviennent manquantes
+    pub fn without_estimated(mut self) -> Self {
+        for key in self.estimated.drain() {
+            self.grades.remove(&key);
+        }
+        self
+    }
+
+    pub fn all_period_codes(&self) -> Vec<&str> {
+        self.periods.iter().map(|p| p.code.as_str()).collect()
+    }
+
+    // Total d'un cours sur les périodes données. Comme le palmarès, on s'arrête à la
+    // première cote manquante : le cours est alors incomplet.
+    pub fn subject_total(
+        &self,
+        student_id: i64,
+        subject: &SubjectInfo,
+        period_codes: &[&str],
+    ) -> SubjectTotal {
+        let mut total = SubjectTotal {
+            points: 0.0,
+            max: 0.0,
+            missing: false,
+        };
+        for code in period_codes {
+            let max = subject.max_for(code);
+            if max == 0.0 {
+                continue;
+            }
+            match self.grade(student_id, subject.id, code) {
+                Some(value) => {
+                    total.points += value;
+                    total.max += max;
+                }
+                None => {
+                    total.missing = true;
+                    break;
+                }
+            }
+        }
+        total
+    }
+
+    // Poids d'un cours dans les totaux selon le mode de pondération
+    pub fn weight(&self, subject: &SubjectInfo) -> f64 {
+        match self.weighting {
+            WeightingMode::Points => 1.0,
+            WeightingMode::Coefficient => subject.coefficient.filter(|c| *c > 0.0).unwrap_or(1.0),
+        }
+    }
+
+    pub fn repechage(&self, student_id: i64, subject_id: i64) -> Option<f64> {
+        self.repechages.get(&(student_id, subject_id)).copied()
+    }
+}
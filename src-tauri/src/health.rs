@@ -0,0 +1,409 @@
+// Contrôle d'intégrité de la base et réparation des incohérences
+// (orphelins laissés par des connexions sans foreign_keys, drapeaux de sync contradictoires).
+
+use crate::backup::{self, BackupEntry, BackupKind};
+use crate::db::{self, DbPool};
+use crate::migrations::SYNC_TABLES;
+use log::{info, warn};
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use serde::Serialize;
+use std::path::Path;
+
+// Requêtes de détection des orphelins : (table, raison, condition WHERE)
+const ORPHAN_CHECKS: [(&str, &str, &str); 6] = [
+    (
+        "grades",
+        "Élève inexistant",
+        "student_id NOT IN (SELECT id FROM students)",
+    ),
+    (
+        "grades",
+        "Matière inexistante",
+        "subject_id NOT IN (SELECT id FROM subjects)",
+    ),
+    (
+        "repechages",
+        "Élève inexistant",
+        "student_id NOT IN (SELECT id FROM students)",
+    ),
+    (
+        "repechages",
+        "Matière inexistante",
+        "subject_id NOT IN (SELECT id FROM subjects)",
+    ),
+    (
+        "subjects",
+        "Classe inexistante",
+        "class_id NOT IN (SELECT id FROM classes)",
+    ),
+    (
+        "custom_sorts",
+        "Classe inexistante",
+        "class_id NOT IN (SELECT id FROM classes)",
+    ),
+];
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ForeignKeyViolation {
+    pub table: String,
+    pub row_id: Option<i64>,
+    pub parent: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthIssue {
+    pub table: String,
+    pub row_id: i64,
+    pub reason: String,
+}
+
+#[derive(Serialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthReport {
+    pub integrity_ok: bool,
+    pub integrity_messages: Vec<String>,
+    pub foreign_key_violations: Vec<ForeignKeyViolation>,
+    pub orphans: Vec<HealthIssue>,
+    pub sync_contradictions: Vec<HealthIssue>,
+    pub repaired: bool,
+    pub quarantined: usize,
+    pub resynced: usize,
+    pub backup: Option<BackupEntry>,
+}
+
+impl HealthReport {
+    pub fn is_healthy(&self) -> bool {
+        self.integrity_ok
+            && self.foreign_key_violations.is_empty()
+            && self.orphans.is_empty()
+            && self.sync_contradictions.is_empty()
+    }
+}
+
+fn check_integrity(conn: &Connection) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare("PRAGMA integrity_check(100)")
+        .map_err(|e| e.to_string())?;
+    let messages = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| e.to_string())?;
+    Ok(messages)
+}
+
+fn check_foreign_keys(conn: &Connection) -> Result<Vec<ForeignKeyViolation>, String> {
+    let mut stmt = conn
+        .prepare("PRAGMA foreign_key_check")
+        .map_err(|e| e.to_string())?;
+    let violations = stmt
+        .query_map([], |row| {
+            Ok(ForeignKeyViolation {
+                table: row.get(0)?,
+                row_id: row.get(1)?,
+                parent: row.get(2)?,
+            })
+        })
+        .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| e.to_string())?;
+    Ok(violations)
+}
+
+fn find_orphans(conn: &Connection) -> Result<Vec<HealthIssue>, String> {
+    let mut issues: Vec<HealthIssue> = Vec::new();
+    for (table, reason, condition) in ORPHAN_CHECKS {
+        let mut stmt = conn
+            .prepare(&format!("SELECT id FROM {table} WHERE {condition}"))
+            .map_err(|e| e.to_string())?;
+        let ids = stmt
+            .query_map([], |row| row.get::<_, i64>(0))
+            .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| e.to_string())?;
+        for row_id in ids {
+            // Une note peut être orpheline à la fois de l'élève et de la matière
+            if !issues
+                .iter()
+                .any(|i| i.table == table && i.row_id == row_id)
+            {
+                issues.push(HealthIssue {
+                    table: table.to_string(),
+                    row_id,
+                    reason: reason.to_string(),
+                });
+            }
+        }
+    }
+    Ok(issues)
+}
+
+// Une ligne marquée comme synchronisée (is_dirty = 0) sans server_id ne sera jamais envoyée.
+fn find_sync_contradictions(conn: &Connection) -> Result<Vec<HealthIssue>, String> {
+    let mut issues = Vec::new();
+    for table in SYNC_TABLES {
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT id FROM {table} WHERE is_dirty = 0 AND (server_id IS NULL OR server_id = '')"
+            ))
+            .map_err(|e| e.to_string())?;
+        let ids = stmt
+            .query_map([], |row| row.get::<_, i64>(0))
+            .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| e.to_string())?;
+        issues.extend(ids.into_iter().map(|row_id| HealthIssue {
+            table: table.to_string(),
+            row_id,
+            reason: "Marquée synchronisée sans server_id".to_string(),
+        }));
+    }
+    Ok(issues)
+}
+
+fn row_to_json(tx: &Transaction, table: &str, row_id: i64) -> rusqlite::Result<String> {
+    let mut stmt = tx.prepare(&format!("SELECT * FROM {table} WHERE id = ?"))?;
+    let columns: Vec<String> = stmt.column_names().iter().map(|c| c.to_string()).collect();
+    stmt.query_row(params![row_id], |row| {
+        let mut map = serde_json::Map::new();
+        for (i, name) in columns.iter().enumerate() {
+            map.insert(name.clone(), db::value_to_json(row.get_ref(i)?));
+        }
+        Ok(serde_json::Value::Object(map).to_string())
+    })
+}
+
+// Tables dont les lignes sont supprimées en cascade avec une ligne de `table` : (table, colonne)
+fn cascade_children(tx: &Transaction, table: &str) -> rusqlite::Result<Vec<(String, String)>> {
+    let mut stmt = tx.prepare(
+        "SELECT m.name, f.\"from\" FROM sqlite_master m, pragma_foreign_key_list(m.name) f
+         WHERE m.type = 'table' AND f.\"table\" = ? AND f.on_delete = 'CASCADE'",
+    )?;
+    let children = stmt
+        .query_map(params![table], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(children)
+}
+
+// Met une ligne en quarantaine avec, d'abord, les lignes qui en dépendent : sans cela le
+// DELETE emporterait en cascade les cotes, repêchages, maxima... d'une matière orpheline.
+// Renvoie le nombre de lignes déplacées (0 si la ligne a déjà été traitée).
+fn quarantine_row(
+    tx: &Transaction,
+    table: &str,
+    row_id: i64,
+    reason: &str,
+) -> rusqlite::Result<usize> {
+    let row_data = match row_to_json(tx, table, row_id).optional()? {
+        Some(data) => data,
+        None => return Ok(0),
+    };
+    let mut moved = 0;
+    for (child, column) in cascade_children(tx, table)? {
+        let ids: Vec<i64> = tx
+            .prepare(&format!("SELECT id FROM {child} WHERE {column} = ?"))?
+            .query_map(params![row_id], |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+        let child_reason = format!("{reason} (dépend de {table} {row_id})");
+        for id in ids {
+            moved += quarantine_row(tx, &child, id, &child_reason)?;
+        }
+    }
+    tx.execute(
+        "INSERT INTO quarantine (table_name, row_id, row_data, reason) VALUES (?, ?, ?, ?)",
+        params![table, row_id, row_data, reason],
+    )?;
+    tx.execute(
+        &format!("DELETE FROM {table} WHERE id = ?"),
+        params![row_id],
+    )?;
+    Ok(moved + 1)
+}
+
+// Déplace les orphelins dans la table quarantine et remet en file de sync les lignes contradictoires.
+// La quarantaine est locale : les suppressions ne sont pas propagées au cloud.
+fn repair(conn: &mut Connection, report: &mut HealthReport) -> Result<(), String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let last_deletion: i64 = tx
+        .query_row(
+            "SELECT COALESCE(MAX(id), 0) FROM sync_deletions",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+
+    for issue in &report.orphans {
+        report.quarantined += quarantine_row(&tx, &issue.table, issue.row_id, &issue.reason)
+            .map_err(|e| e.to_string())?;
+    }
+    // Entrées ajoutées par les triggers trg_*_deleted pendant la mise en quarantaine
+    tx.execute(
+        "DELETE FROM sync_deletions WHERE id > ?",
+        params![last_deletion],
+    )
+    .map_err(|e| e.to_string())?;
+
+    for issue in &report.sync_contradictions {
+        report.resynced += tx
+            .execute(
+                &format!("UPDATE {} SET is_dirty = 1 WHERE id = ?", issue.table),
+                params![issue.row_id],
+            )
+            .map_err(|e| e.to_string())?;
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+    report.repaired = true;
+    Ok(())
+}
+
+pub fn run_health_check(
+    conn: &mut Connection,
+    db_path: &Path,
+    repair_mode: bool,
+) -> Result<HealthReport, String> {
+    let integrity_messages = check_integrity(conn)?;
+    let mut report = HealthReport {
+        integrity_ok: integrity_messages.len() == 1 && integrity_messages[0] == "ok",
+        integrity_messages,
+        foreign_key_violations: check_foreign_keys(conn)?,
+        orphans: find_orphans(conn)?,
+        sync_contradictions: find_sync_contradictions(conn)?,
+        ..Default::default()
+    };
+
+    info!(
+        "Health check: integrity_ok={}, fk_violations={}, orphans={}, sync_contradictions={}",
+        report.integrity_ok,
+        report.foreign_key_violations.len(),
+        report.orphans.len(),
+        report.sync_contradictions.len()
+    );
+
+    if repair_mode && !report.is_healthy() {
+        // Écrire dans un fichier corrompu peut aggraver les dégâts : il faut restaurer une sauvegarde
+        if !report.integrity_ok {
+            warn!("Repair skipped: database file is corrupted");
+            return Ok(report);
+        }
+        report.backup = Some(backup::create_backup(db_path, BackupKind::PreRepair)?);
+        repair(conn, &mut report)?;
+        info!(
+            "Repair done: {} quarantined, {} resynced",
+            report.quarantined, report.resynced
+        );
+    }
+
+    Ok(report)
+}
+
+#[tauri::command]
+pub async fn db_health_check(
+    pool: tauri::State<'_, DbPool>,
+    repair: Option<bool>,
+) -> Result<HealthReport, String> {
+    let db_path = pool.path().to_path_buf();
+    pool.run(move |conn| run_health_check(conn, &db_path, repair.unwrap_or(false)))
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::TestDb;
+
+    fn count(conn: &Connection, sql: &str) -> i64 {
+        conn.query_row(sql, [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn matiere_orpheline_mise_en_quarantaine_avec_ses_cotes() {
+        let db = TestDb::open();
+        let mut conn = db.conn();
+        conn.execute_batch(
+            "INSERT INTO academic_years (id, name, start_date, end_date) VALUES (1, '2024-2025', '2024-09-01', '2025-07-01');
+             INSERT INTO classes (id, name, level, option, section, academic_year_id) VALUES (1, '7ème A', '7ème', 'EB', 'A', 1), (2, '7ème B', '7ème', 'EB', 'B', 1);
+             INSERT INTO students (id, first_name, last_name, gender, class_id) VALUES (1, 'Jean', 'Kabila', 'M', 2);
+             INSERT INTO subjects (id, name, code, class_id, server_id) VALUES (1, 'Maths', 'MATH', 1, 'sub-1');
+             INSERT INTO grades (student_id, subject_id, period, value, server_id) VALUES (1, 1, 'P1', 7, 'g-1'), (1, 1, 'P2', 8, 'g-2');
+             INSERT INTO repechages (student_id, subject_id, value, percentage, server_id) VALUES (1, 1, 30, 60, 'r-1');
+             INSERT INTO evaluations (id, subject_id, period_code, name, max_points) VALUES (1, 1, 'P1', 'Interro 1', 10);
+             INSERT INTO evaluation_scores (evaluation_id, student_id, value) VALUES (1, 1, 7);
+             PRAGMA foreign_keys = OFF;
+             DELETE FROM classes WHERE id = 1;
+             PRAGMA foreign_keys = ON;",
+        )
+        .unwrap();
+        let maxima = count(
+            &conn,
+            "SELECT COUNT(*) FROM subject_period_max WHERE subject_id = 1",
+        );
+        assert!(maxima > 0);
+
+        let report = run_health_check(&mut conn, &db.path, false).unwrap();
+        assert_eq!(report.orphans.len(), 1);
+        assert_eq!(report.orphans[0].table, "subjects");
+
+        let report = run_health_check(&mut conn, &db.path, true).unwrap();
+        assert!(report.backup.is_some());
+        // matière, 2 cotes, repêchage, interrogation et sa cote, maxima par période
+        assert_eq!(report.quarantined as i64, 6 + maxima);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM quarantine"), 6 + maxima);
+        assert_eq!(
+            count(
+                &conn,
+                "SELECT COUNT(*) FROM quarantine WHERE table_name = 'grades'"
+            ),
+            2
+        );
+        assert_eq!(
+            count(
+                &conn,
+                "SELECT COUNT(*) FROM quarantine WHERE table_name = 'evaluation_scores'"
+            ),
+            1
+        );
+        let reason: String = conn
+            .query_row(
+                "SELECT reason FROM quarantine WHERE table_name = 'repechages'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(reason, "Classe inexistante (dépend de subjects 1)");
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM grades"), 0);
+        // La quarantaine n'est pas propagée au cloud
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM sync_deletions"), 0);
+
+        let report = run_health_check(&mut conn, &db.path, false).unwrap();
+        assert!(report.orphans.is_empty());
+        assert!(report.foreign_key_violations.is_empty());
+    }
+
+    #[test]
+    fn cote_orpheline_et_contradiction_de_sync() {
+        let db = TestDb::open();
+        let mut conn = db.conn();
+        conn.execute_batch(
+            "INSERT INTO academic_years (id, name, start_date, end_date) VALUES (1, '2024-2025', '2024-09-01', '2025-07-01');
+             INSERT INTO classes (id, name, level, option, section, academic_year_id) VALUES (1, '7ème A', '7ème', 'EB', 'A', 1);
+             INSERT INTO students (id, first_name, last_name, gender, class_id) VALUES (1, 'Jean', 'Kabila', 'M', 1), (2, 'Marie', 'Mbuyi', 'F', 1);
+             INSERT INTO subjects (id, name, code, class_id) VALUES (1, 'Maths', 'MATH', 1);
+             INSERT INTO grades (student_id, subject_id, period, value, server_id) VALUES (2, 1, 'P1', 7, 'g-1');
+             UPDATE students SET is_dirty = 0 WHERE id = 1;
+             PRAGMA foreign_keys = OFF;
+             DELETE FROM students WHERE id = 2;
+             PRAGMA foreign_keys = ON;",
+        )
+        .unwrap();
+        let report = run_health_check(&mut conn, &db.path, true).unwrap();
+        assert_eq!((report.quarantined, report.resynced), (1, 1));
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM grades"), 0);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM sync_deletions"), 0);
+        assert_eq!(
+            count(&conn, "SELECT is_dirty FROM students WHERE id = 1"),
+            1
+        );
+        assert!(run_health_check(&mut conn, &db.path, false)
+            .unwrap()
+            .is_healthy());
+    }
+}
@@ -0,0 +1,353 @@
+// Journal des opérations (table operation_log), même format que historyService.ts :
+// l'état avant et après de chaque ligne modifiée, en JSON, pour l'historique et l'annulation.
+// Les opérations en masse sont regroupées sous un point de reprise (table checkpoints)
+// et s'annulent ensemble.
+
+use crate::db::DbPool;
+use log::info;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ActionType {
+    Create,
+    Update,
+    Delete,
+}
+
+impl ActionType {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ActionType::Create => "CREATE",
+            ActionType::Update => "UPDATE",
+            ActionType::Delete => "DELETE",
+        }
+    }
+
+    fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "CREATE" => Ok(ActionType::Create),
+            "UPDATE" => Ok(ActionType::Update),
+            "DELETE" => Ok(ActionType::Delete),
+            other => Err(format!("Type d'opération inconnu : {}", other)),
+        }
+    }
+
+    fn inverse(&self) -> Self {
+        match self {
+            ActionType::Create => ActionType::Delete,
+            ActionType::Update => ActionType::Update,
+            ActionType::Delete => ActionType::Create,
+        }
+    }
+}
+
+// État d'une cote (entité « grade »), celui que relit undoRedoService.ts
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct GradeState {
+    pub id: i64,
+    pub student_id: i64,
+    pub subject_id: i64,
+    pub period: String,
+    pub value: f64,
+    // Absent des états écrits par le renderer : cote saisie
+    #[serde(default)]
+    pub estimated: bool,
+}
+
+// Maximum d'un cours pour une période (entité « subject_period_max »)
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SubjectMaxState {
+    pub subject_id: i64,
+    pub period_code: String,
+    pub max_points: i64,
+}
+
+pub fn log_operation<T: Serialize>(
+    conn: &Connection,
+    entity_type: &str,
+    entity_id: i64,
+    action: ActionType,
+    previous_state: Option<&T>,
+    new_state: Option<&T>,
+    description: &str,
+) -> Result<i64, String> {
+    let to_json = |state: Option<&T>| -> Result<Option<String>, String> {
+        state
+            .map(|s| serde_json::to_string(s).map_err(|e| e.to_string()))
+            .transpose()
+    };
+    conn.execute(
+        "INSERT INTO operation_log (entity_type, entity_id, action_type, previous_state, new_state, description)
+         VALUES (?, ?, ?, ?, ?, ?)",
+        params![
+            entity_type,
+            entity_id,
+            action.as_str(),
+            to_json(previous_state)?,
+            to_json(new_state)?,
+            description
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(conn.last_insert_rowid())
+}
+
+// Opérations rattachées à un même point de reprise
+pub struct Batch<'a> {
+    conn: &'a Connection,
+    pub checkpoint_id: i64,
+}
+
+impl<'a> Batch<'a> {
+    pub fn start(conn: &'a Connection, name: &str, description: &str) -> Result<Self, String> {
+        conn.execute(
+            "INSERT INTO checkpoints (name, description) VALUES (?, ?)",
+            params![name, description],
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(Batch {
+            conn,
+            checkpoint_id: conn.last_insert_rowid(),
+        })
+    }
+
+    pub fn log<T: Serialize>(
+        &self,
+        entity_type: &str,
+        entity_id: i64,
+        action: ActionType,
+        previous_state: Option<&T>,
+        new_state: Option<&T>,
+        description: &str,
+    ) -> Result<i64, String> {
+        let id = log_operation(
+            self.conn,
+            entity_type,
+            entity_id,
+            action,
+            previous_state,
+            new_state,
+            description,
+        )?;
+        self.conn
+            .execute(
+                "UPDATE operation_log SET checkpoint_id = ? WHERE id = ?",
+                params![self.checkpoint_id, id],
+            )
+            .map_err(|e| e.to_string())?;
+        Ok(id)
+    }
+}
+
+pub fn find_grade(conn: &Connection, id: i64) -> Result<Option<GradeState>, String> {
+    conn.query_row(
+        "SELECT id, student_id, subject_id, period, value, COALESCE(is_estimated, 0)
+         FROM grades WHERE id = ?",
+        params![id],
+        |row| {
+            Ok(GradeState {
+                id: row.get(0)?,
+                student_id: row.get(1)?,
+                subject_id: row.get(2)?,
+                period: row.get(3)?,
+                value: row.get(4)?,
+                estimated: row.get(5)?,
+            })
+        },
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+pub fn find_subject_max(conn: &Connection, id: i64) -> Result<Option<SubjectMaxState>, String> {
+    conn.query_row(
+        "SELECT subject_id, period_code, max_points FROM subject_period_max WHERE id = ?",
+        params![id],
+        |row| {
+            Ok(SubjectMaxState {
+                subject_id: row.get(0)?,
+                period_code: row.get(1)?,
+                max_points: row.get(2)?,
+            })
+        },
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+fn parse_state<T: for<'de> Deserialize<'de>>(json: Option<String>) -> Result<Option<T>, String> {
+    json.map(|s| serde_json::from_str(&s).map_err(|e| e.to_string()))
+        .transpose()
+}
+
+fn restore_grade(
+    batch: &Batch,
+    entity_id: i64,
+    action: ActionType,
+    expected: Option<GradeState>,
+    target: Option<GradeState>,
+) -> Result<(), String> {
+    let current = find_grade(batch.conn, entity_id)?;
+    if current != expected {
+        return Err(format!(
+            "La cote {} a été modifiée depuis : annulation impossible",
+            entity_id
+        ));
+    }
+    match &target {
+        Some(grade) => batch
+            .conn
+            .execute(
+                "INSERT INTO grades (id, student_id, subject_id, period, value, is_estimated, is_dirty, last_modified_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, 1, datetime('now'))
+                 ON CONFLICT(id) DO UPDATE SET value = ?5, period = ?4, is_estimated = ?6, is_dirty = 1,
+                    last_modified_at = datetime('now')",
+                params![
+                    grade.id,
+                    grade.student_id,
+                    grade.subject_id,
+                    grade.period,
+                    grade.value,
+                    grade.estimated
+                ],
+            )
+            .map_err(|e| e.to_string())?,
+        None => batch
+            .conn
+            .execute("DELETE FROM grades WHERE id = ?", params![entity_id])
+            .map_err(|e| e.to_string())?,
+    };
+    batch.log(
+        "grade",
+        entity_id,
+        action.inverse(),
+        current.as_ref(),
+        target.as_ref(),
+        "Annulation",
+    )?;
+    Ok(())
+}
+
+fn restore_subject_max(
+    batch: &Batch,
+    entity_id: i64,
+    expected: Option<SubjectMaxState>,
+    target: Option<SubjectMaxState>,
+) -> Result<(), String> {
+    let current = find_subject_max(batch.conn, entity_id)?;
+    if current != expected {
+        return Err("Le maximum a été modifié depuis : annulation impossible".to_string());
+    }
+    let target =
+        target.ok_or_else(|| "Maximum sans état précédent : annulation impossible".to_string())?;
+    batch
+        .conn
+        .execute(
+            "UPDATE subject_period_max SET max_points = ? WHERE id = ?",
+            params![target.max_points, entity_id],
+        )
+        .map_err(|e| e.to_string())?;
+    batch.log(
+        "subject_period_max",
+        entity_id,
+        ActionType::Update,
+        current.as_ref(),
+        Some(&target),
+        "Annulation",
+    )?;
+    Ok(())
+}
+
+// (entity_type, entity_id, action_type, previous_state, new_state)
+type LoggedOperation = (String, i64, String, Option<String>, Option<String>);
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UndoReport {
+    // Point de reprise des opérations inverses, lui-même annulable
+    pub checkpoint_id: i64,
+    pub restored: usize,
+}
+
+// Annule toutes les opérations d'un point de reprise, de la plus récente à la plus ancienne.
+// Refuse si une ligne a changé depuis pour ne pas écraser une saisie ultérieure.
+pub fn undo_checkpoint(conn: &mut Connection, checkpoint_id: i64) -> Result<UndoReport, String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let name: String = tx
+        .query_row(
+            "SELECT name FROM checkpoints WHERE id = ?",
+            params![checkpoint_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Point de reprise introuvable".to_string())?;
+
+    let operations: Vec<LoggedOperation> = tx
+        .prepare(
+            "SELECT entity_type, entity_id, action_type, previous_state, new_state
+             FROM operation_log WHERE checkpoint_id = ? ORDER BY id DESC",
+        )
+        .and_then(|mut stmt| {
+            stmt.query_map(params![checkpoint_id], |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()
+        })
+        .map_err(|e| e.to_string())?;
+    if operations.is_empty() {
+        return Err("Aucune opération à annuler".to_string());
+    }
+
+    let restored = operations.len();
+    let report_checkpoint = {
+        let batch = Batch::start(&tx, &format!("Annulation : {}", name), "")?;
+        for (entity_type, entity_id, action, previous, new) in operations {
+            let action = ActionType::parse(&action)?;
+            match entity_type.as_str() {
+                "grade" => restore_grade(
+                    &batch,
+                    entity_id,
+                    action,
+                    parse_state(new)?,
+                    parse_state(previous)?,
+                )?,
+                "subject_period_max" => restore_subject_max(
+                    &batch,
+                    entity_id,
+                    parse_state(new)?,
+                    parse_state(previous)?,
+                )?,
+                other => return Err(format!("Opération « {} » non réversible", other)),
+            }
+        }
+        batch.checkpoint_id
+    };
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(UndoReport {
+        checkpoint_id: report_checkpoint,
+        restored,
+    })
+}
+
+#[tauri::command]
+pub async fn history_undo_checkpoint(
+    pool: tauri::State<'_, DbPool>,
+    checkpoint_id: i64,
+) -> Result<UndoReport, String> {
+    let report = pool
+        .run(move |conn| undo_checkpoint(conn, checkpoint_id))
+        .await?;
+    info!(
+        "Point de reprise {} annulé : {} opération(s)",
+        checkpoint_id, report.restored
+    );
+    Ok(report)
+}
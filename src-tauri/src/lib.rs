@@ -1,12 +1,37 @@
+mod archive;
+mod backup;
+mod bulletin;
+mod bulletin_pdf;
+mod conduct;
 mod db;
+mod delib_rules;
+mod deliberation;
+mod encryption;
+mod evaluations;
+mod grade_validation;
+mod gradebook;
+mod health;
+mod history;
+mod migrations;
+mod periods;
+mod prediction;
+mod query;
+mod ranking;
+mod repechage;
+mod risk;
+mod rollover;
+mod scaling;
 mod server;
+mod statistics;
 mod sync;
+mod targets;
 
 use chrono::{DateTime, Duration, Utc};
 use log::{error, info};
 use reqwest::header::{AUTHORIZATION, CONTENT_TYPE};
 use rusqlite::{params, Connection};
 use serde::{Deserialize, Serialize};
+use db::DbPool;
 use std::path::PathBuf;
 use tauri::Manager;
 
@@ -25,7 +50,7 @@ pub struct LicenseInfo {
     pub plan: String,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct ActivationResult {
     pub success: bool,
     pub error: Option<String>,
@@ -35,7 +60,7 @@ pub struct ActivationResult {
     pub school: Option<SchoolInfo>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct SchoolInfo {
     pub id: String,
     pub name: String,
@@ -90,10 +115,11 @@ fn get_hwid() -> String {
 }
 
 #[tauri::command]
-async fn get_license_info(app_handle: tauri::AppHandle) -> Result<LicenseInfo, String> {
-    let db_path = get_db_path(&app_handle);
-    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+async fn get_license_info(pool: tauri::State<'_, DbPool>) -> Result<LicenseInfo, String> {
+    pool.run(read_license_info).await
+}
 
+fn read_license_info(conn: &mut Connection) -> Result<LicenseInfo, String> {
     let get_setting = |key: &str| -> Option<String> {
         conn.query_row(
             "SELECT value FROM settings WHERE key = ?",
@@ -189,63 +215,89 @@ pub struct AuthResult {
 }
 
 #[tauri::command]
-async fn auth_check(app_handle: tauri::AppHandle) -> Result<AuthCheckResult, String> {
-    let db_path = get_db_path(&app_handle);
-    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
-
-    let hash: Option<String> = conn
-        .query_row(
-            "SELECT value FROM settings WHERE key = 'local_password_hash'",
-            [],
-            |r| r.get(0),
-        )
-        .ok();
+async fn auth_check(pool: tauri::State<'_, DbPool>) -> Result<AuthCheckResult, String> {
+    if encryption::is_enabled(pool.path()) {
+        return Ok(AuthCheckResult { hasPassword: true });
+    }
 
-    Ok(AuthCheckResult {
-        hasPassword: hash.is_some(),
+    pool.run(|conn| {
+        let hash: Option<String> = conn
+            .query_row(
+                "SELECT value FROM settings WHERE key = 'local_password_hash'",
+                [],
+                |r| r.get(0),
+            )
+            .ok();
+
+        Ok(AuthCheckResult {
+            hasPassword: hash.is_some(),
+        })
     })
+    .await
 }
 
 #[tauri::command]
-async fn auth_create(app_handle: tauri::AppHandle, password: String) -> Result<AuthResult, String> {
+async fn auth_create(pool: tauri::State<'_, DbPool>, password: String) -> Result<AuthResult, String> {
     info!("Creating local password...");
-    let db_path = get_db_path(&app_handle);
-    info!("Database path: {:?}", db_path);
-    let conn = Connection::open(db_path).map_err(|e| {
-        error!("Failed to open DB for auth_create: {}", e);
-        e.to_string()
-    })?;
+    info!("Database path: {:?}", pool.path());
 
-    let hash = simple_hash(&password);
     info!("Hashing password and saving to settings...");
+    pool.run(move |conn| save_password_hash(conn, &password))
+        .await?;
+
+    info!("Local password created successfully.");
+    Ok(AuthResult {
+        success: true,
+        valid: true,
+    })
+}
+
+fn save_password_hash(conn: &Connection, password: &str) -> Result<(), String> {
+    let hash = simple_hash(password);
     conn.execute(
         "INSERT OR REPLACE INTO settings (key, value) VALUES ('local_password_hash', ?)",
         params![hash],
     )
     .map_err(|e| {
-        error!("SQL error in auth_create: {}", e);
+        error!("SQL error while saving password hash: {}", e);
         e.to_string()
     })?;
-
-    info!("Local password created successfully.");
-    Ok(AuthResult {
-        success: true,
-        valid: true,
-    })
+    Ok(())
 }
 
 #[tauri::command]
-async fn auth_verify(app_handle: tauri::AppHandle, password: String) -> Result<AuthResult, String> {
-    let db_path = get_db_path(&app_handle);
-    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
-
-    let stored_hash: String = conn
-        .query_row(
-            "SELECT value FROM settings WHERE key = 'local_password_hash'",
-            [],
-            |r| r.get(0),
-        )
-        .map_err(|_| "NO_PASSWORD_SET".to_string())?;
+async fn auth_verify(pool: tauri::State<'_, DbPool>, password: String) -> Result<AuthResult, String> {
+    // Base chiffrée : le mot de passe sert à déverrouiller la clé, la table settings est illisible avant
+    if encryption::is_enabled(pool.path()) {
+        let valid = pool
+            .run_with_path(move |path| match encryption::unlock(path, &password) {
+                Ok(()) => {
+                    db::initialize_db(path)?;
+                    if let Err(e) = backup::run_scheduled_backups(path) {
+                        error!("Scheduled backup failed: {}", e);
+                    }
+                    Ok(true)
+                }
+                Err(e) if e == encryption::INVALID_SECRET_ERROR => Ok(false),
+                Err(e) => Err(e),
+            })
+            .await?;
+        return Ok(AuthResult {
+            success: true,
+            valid,
+        });
+    }
+
+    let stored_hash: String = pool
+        .run(|conn| {
+            conn.query_row(
+                "SELECT value FROM settings WHERE key = 'local_password_hash'",
+                [],
+                |r| r.get(0),
+            )
+            .map_err(|_| "NO_PASSWORD_SET".to_string())
+        })
+        .await?;
 
     let input_hash = simple_hash(&password);
 
@@ -255,6 +307,42 @@ async fn auth_verify(app_handle: tauri::AppHandle, password: String) -> Result<A
     })
 }
 
+#[tauri::command]
+async fn auth_change_password(
+    pool: tauri::State<'_, DbPool>,
+    old_password: String,
+    new_password: String,
+) -> Result<AuthResult, String> {
+    let db_path = pool.path().to_path_buf();
+    pool.run(move |conn| {
+        let stored_hash: String = conn
+            .query_row(
+                "SELECT value FROM settings WHERE key = 'local_password_hash'",
+                [],
+                |r| r.get(0),
+            )
+            .map_err(|_| "NO_PASSWORD_SET".to_string())?;
+        if stored_hash != simple_hash(&old_password) {
+            return Ok(AuthResult {
+                success: false,
+                valid: false,
+            });
+        }
+
+        // La clé de la base doit suivre le mot de passe
+        if encryption::is_enabled(&db_path) {
+            encryption::change_password(&db_path, &old_password, &new_password)?;
+        }
+        save_password_hash(conn, &new_password)?;
+        info!("Local password changed.");
+        Ok(AuthResult {
+            success: true,
+            valid: true,
+        })
+    })
+    .await
+}
+
 #[allow(non_snake_case)]
 #[derive(Serialize)]
 pub struct SyncStatusResult {
@@ -263,10 +351,11 @@ pub struct SyncStatusResult {
 }
 
 #[tauri::command]
-async fn check_sync_status(app_handle: tauri::AppHandle) -> Result<SyncStatusResult, String> {
-    let db_path = get_db_path(&app_handle);
-    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+async fn check_sync_status(pool: tauri::State<'_, DbPool>) -> Result<SyncStatusResult, String> {
+    pool.run(read_sync_status).await
+}
 
+fn read_sync_status(conn: &mut Connection) -> Result<SyncStatusResult, String> {
     let tables = vec![
         "academic_years",
         "classes",
@@ -309,7 +398,7 @@ async fn check_sync_status(app_handle: tauri::AppHandle) -> Result<SyncStatusRes
 
 #[tauri::command]
 async fn activate_license(
-    app_handle: tauri::AppHandle,
+    pool: tauri::State<'_, DbPool>,
     key: String,
     password: Option<String>,
 ) -> Result<ActivationResult, String> {
@@ -345,86 +434,94 @@ async fn activate_license(
 
     if result.success {
         info!("License activated successfully!");
-        let db_path = get_db_path(&app_handle);
-        let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+        let result = result.clone();
+        pool.run(move |conn| {
+            save_activation(conn, &key, &result);
+            Ok(())
+        })
+        .await?;
+    }
+
+    Ok(result)
+}
 
+// Enregistre la licence et les informations de l'école renvoyées par le cloud
+fn save_activation(conn: &Connection, key: &str, result: &ActivationResult) {
+    conn.execute(
+        "INSERT OR REPLACE INTO settings (key, value) VALUES ('license_key', ?)",
+        params![key],
+    )
+    .ok();
+    if let Some(token) = &result.token {
         conn.execute(
-            "INSERT OR REPLACE INTO settings (key, value) VALUES ('license_key', ?)",
-            params![key],
+            "INSERT OR REPLACE INTO settings (key, value) VALUES ('license_token', ?)",
+            params![token],
         )
         .ok();
-        if let Some(token) = &result.token {
-            conn.execute(
-                "INSERT OR REPLACE INTO settings (key, value) VALUES ('license_token', ?)",
-                params![token],
-            )
-            .ok();
-        }
-        if let Some(exp) = &result.expires_at {
-            conn.execute(
-                "INSERT OR REPLACE INTO settings (key, value) VALUES ('license_expires_at', ?)",
-                params![exp],
-            )
-            .ok();
-        }
-        if let Some(plan) = &result.plan {
-            conn.execute(
-                "INSERT OR REPLACE INTO settings (key, value) VALUES ('license_plan', ?)",
-                params![plan],
-            )
-            .ok();
-        }
-        if let Some(school) = &result.school {
-            conn.execute(
-                "INSERT OR REPLACE INTO settings (key, value) VALUES ('school_id', ?)",
-                params![school.id],
-            )
-            .ok();
-            conn.execute(
-                "INSERT OR REPLACE INTO settings (key, value) VALUES ('school_name', ?)",
-                params![school.name],
-            )
-            .ok();
+    }
+    if let Some(exp) = &result.expires_at {
+        conn.execute(
+            "INSERT OR REPLACE INTO settings (key, value) VALUES ('license_expires_at', ?)",
+            params![exp],
+        )
+        .ok();
+    }
+    if let Some(plan) = &result.plan {
+        conn.execute(
+            "INSERT OR REPLACE INTO settings (key, value) VALUES ('license_plan', ?)",
+            params![plan],
+        )
+        .ok();
+    }
+    if let Some(school) = &result.school {
+        conn.execute(
+            "INSERT OR REPLACE INTO settings (key, value) VALUES ('school_id', ?)",
+            params![school.id],
+        )
+        .ok();
+        conn.execute(
+            "INSERT OR REPLACE INTO settings (key, value) VALUES ('school_name', ?)",
+            params![school.name],
+        )
+        .ok();
+        conn.execute(
+            "INSERT OR REPLACE INTO settings (key, value) VALUES ('school_city', ?)",
+            params![school.city],
+        )
+        .ok();
+        if let Some(pobox) = &school.pobox {
             conn.execute(
-                "INSERT OR REPLACE INTO settings (key, value) VALUES ('school_city', ?)",
-                params![school.city],
+                "INSERT OR REPLACE INTO settings (key, value) VALUES ('school_pobox', ?)",
+                params![pobox],
             )
             .ok();
-            if let Some(pobox) = &school.pobox {
-                conn.execute(
-                    "INSERT OR REPLACE INTO settings (key, value) VALUES ('school_pobox', ?)",
-                    params![pobox],
-                )
-                .ok();
-            }
         }
     }
-
-    Ok(result)
 }
 
 #[tauri::command]
-async fn refresh_remote_license(app_handle: tauri::AppHandle) -> Result<serde_json::Value, String> {
-    let db_path = get_db_path(&app_handle);
-
-    let (school_id, token) = {
-        let conn = Connection::open(&db_path).map_err(|e| e.to_string())?;
-        let sid = conn
-            .query_row(
-                "SELECT value FROM settings WHERE key = 'school_id'",
-                [],
-                |row| row.get::<_, String>(0),
-            )
-            .map_err(|_| "NOT_LINKED")?;
-        let tok = conn
-            .query_row(
-                "SELECT value FROM settings WHERE key = 'license_token'",
-                [],
-                |row| row.get::<_, String>(0),
-            )
-            .map_err(|_| "NOT_LINKED")?;
-        (sid, tok)
-    };
+async fn refresh_remote_license(
+    pool: tauri::State<'_, DbPool>,
+) -> Result<serde_json::Value, String> {
+    let (school_id, token) = pool
+        .run(|conn| {
+            let sid = conn
+                .query_row(
+                    "SELECT value FROM settings WHERE key = 'school_id'",
+                    [],
+                    |row| row.get::<_, String>(0),
+                )
+                .map_err(|_| "NOT_LINKED")?;
+            let tok = conn
+                .query_row(
+                    "SELECT value FROM settings WHERE key = 'license_token'",
+                    [],
+                    |row| row.get::<_, String>(0),
+                )
+                .map_err(|_| "NOT_LINKED")?;
+            Ok((sid, tok))
+        })
+        .await?;
 
     let client = reqwest::Client::new();
     let url = format!(
@@ -444,73 +541,78 @@ async fn refresh_remote_license(app_handle: tauri::AppHandle) -> Result<serde_js
     let result: serde_json::Value = res.json().await.map_err(|e| e.to_string())?;
 
     if result["success"].as_bool().unwrap_or(false) {
-        let conn = Connection::open(&db_path).map_err(|e| e.to_string())?;
-        if result["license"]["active"].as_bool().unwrap_or(false) {
-            if let Some(exp) = result["license"]["expiresAt"].as_str() {
-                conn.execute(
-                    "INSERT OR REPLACE INTO settings (key, value) VALUES ('license_expires_at', ?)",
-                    params![exp],
-                )
-                .ok();
-            }
-            if let Some(plan) = result["license"]["plan"].as_str() {
-                conn.execute(
-                    "INSERT OR REPLACE INTO settings (key, value) VALUES ('license_plan', ?)",
-                    params![plan],
-                )
-                .ok();
-            }
-        } else {
-            let past_date = Utc::now() - Duration::days(1);
+        let info = pool
+            .run(move |conn| {
+                save_remote_license(conn, &result);
+                read_license_info(conn)
+            })
+            .await?;
+        Ok(serde_json::json!({ "success": true, "info": info }))
+    } else {
+        Ok(serde_json::json!({ "success": false, "error": "Invalid server response" }))
+    }
+}
+
+fn save_remote_license(conn: &Connection, result: &serde_json::Value) {
+    if result["license"]["active"].as_bool().unwrap_or(false) {
+        if let Some(exp) = result["license"]["expiresAt"].as_str() {
             conn.execute(
                 "INSERT OR REPLACE INTO settings (key, value) VALUES ('license_expires_at', ?)",
-                params![past_date.to_rfc3339()],
+                params![exp],
             )
             .ok();
         }
-
-        // Mise à jour des informations de l'école (Nouveau)
-        if let Some(school) = result["school"].as_object() {
-            if let Some(name) = school.get("name").and_then(|v| v.as_str()) {
-                conn.execute(
-                    "INSERT OR REPLACE INTO settings (key, value) VALUES ('school_name', ?)",
-                    params![name],
-                )
-                .ok();
-            }
-            if let Some(city) = school.get("city").and_then(|v| v.as_str()) {
-                conn.execute(
-                    "INSERT OR REPLACE INTO settings (key, value) VALUES ('school_city', ?)",
-                    params![city],
-                )
-                .ok();
-            }
-            if let Some(pobox) = school.get("pobox").and_then(|v| v.as_str()) {
-                conn.execute(
-                    "INSERT OR REPLACE INTO settings (key, value) VALUES ('school_pobox', ?)",
-                    params![pobox],
-                )
-                .ok();
-            }
+        if let Some(plan) = result["license"]["plan"].as_str() {
+            conn.execute(
+                "INSERT OR REPLACE INTO settings (key, value) VALUES ('license_plan', ?)",
+                params![plan],
+            )
+            .ok();
         }
-
-        let info = get_license_info(app_handle).await?;
-        Ok(serde_json::json!({ "success": true, "info": info }))
     } else {
-        Ok(serde_json::json!({ "success": false, "error": "Invalid server response" }))
+        let past_date = Utc::now() - Duration::days(1);
+        conn.execute(
+            "INSERT OR REPLACE INTO settings (key, value) VALUES ('license_expires_at', ?)",
+            params![past_date.to_rfc3339()],
+        )
+        .ok();
+    }
+
+    // Mise à jour des informations de l'école (Nouveau)
+    if let Some(school) = result["school"].as_object() {
+        if let Some(name) = school.get("name").and_then(|v| v.as_str()) {
+            conn.execute(
+                "INSERT OR REPLACE INTO settings (key, value) VALUES ('school_name', ?)",
+                params![name],
+            )
+            .ok();
+        }
+        if let Some(city) = school.get("city").and_then(|v| v.as_str()) {
+            conn.execute(
+                "INSERT OR REPLACE INTO settings (key, value) VALUES ('school_city', ?)",
+                params![city],
+            )
+            .ok();
+        }
+        if let Some(pobox) = school.get("pobox").and_then(|v| v.as_str()) {
+            conn.execute(
+                "INSERT OR REPLACE INTO settings (key, value) VALUES ('school_pobox', ?)",
+                params![pobox],
+            )
+            .ok();
+        }
     }
 }
 
 #[tauri::command]
-async fn start_web_server(app_handle: tauri::AppHandle) -> Result<server::ServerInfo, String> {
+async fn start_web_server(
+    app_handle: tauri::AppHandle,
+    pool: tauri::State<'_, DbPool>,
+) -> Result<server::ServerInfo, String> {
     // En mode dev, on remonte d'un niveau depuis src-tauri pour atteindre dist-mobile
     // En production, dist-mobile sera dans le bundle Tauri
-    // NB: tiny-server resolve logic is in server.rs, we just passed db_path
-
-    // We need db_path here.
-    let db_path = get_db_path(&app_handle);
-
-    server::start_web_server(app_handle.clone(), db_path)
+    // NB: tiny-server resolve logic is in server.rs, we just pass the shared pool
+    server::start_web_server(app_handle.clone(), pool.inner().clone())
 }
 
 #[tauri::command]
@@ -518,360 +620,6 @@ fn get_web_server_info() -> Option<server::ServerInfo> {
     server::get_server_info()
 }
 
-// Grade prediction structures and functions
-#[derive(Serialize, Deserialize, Debug, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct PredictionResult {
-    pub student_id: i32,
-    pub student_name: String,
-    pub subject_id: i32,
-    pub subject_name: String,
-    pub period: String,
-    pub predicted_grade: f64,
-    pub max_grade: f64,
-    pub confidence: u8,
-    pub reasoning: String,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(rename_all = "camelCase")]
-pub struct PredictionParams {
-    pub class_id: i32,
-    pub student_ids: Option<Vec<i32>>,
-    pub subject_ids: Option<Vec<i32>>,
-    pub periods: Option<Vec<String>>,
-    pub confidence_threshold: u8,
-}
-
-fn calculate_student_performance(
-    conn: &Connection,
-    student_id: i32,
-    excluded_subject_id: i32,
-    period: &str,
-) -> Result<f64, String> {
-    info!("  [student_perf] student_id={}, excluded_subject={}, period={}", student_id, excluded_subject_id, period);
-
-    let max_col = match period {
-        "P1" => "max_p1",
-        "P2" => "max_p2",
-        "EXAM1" => "max_exam1",
-        "P3" => "max_p3",
-        "P4" => "max_p4",
-        "EXAM2" => "max_exam2",
-        _ => "max_p1",
-    };
-
-    let query = format!(
-        "SELECT g.value, s.{} as max_val
-         FROM grades g
-         JOIN subjects s ON g.subject_id = s.id
-         WHERE g.student_id = ? AND g.subject_id != ? AND g.period = ?
-         AND g.value IS NOT NULL AND g.value >= 0",
-        max_col
-    );
-
-    let mut stmt = conn.prepare(&query).map_err(|e| {
-        error!("  [student_perf] Failed to prepare: {}", e);
-        e.to_string()
-    })?;
-
-    let grades_iter = stmt
-        .query_map(rusqlite::params![student_id, excluded_subject_id, period], |row| {
-            let value: f64 = row.get(0)?;
-            let max: f64 = row.get(1)?;
-            Ok((value, max))
-        })
-        .map_err(|e| {
-            error!("  [student_perf] Failed to query: {}", e);
-            e.to_string()
-        })?;
-
-    let mut percentages = Vec::new();
-    for grade_result in grades_iter {
-        let (value, max) = grade_result.map_err(|e| {
-            error!("  [student_perf] Failed to get row: {}", e);
-            e.to_string()
-        })?;
-        if max > 0.0 {
-            percentages.push(value / max);
-        }
-    }
-
-    if percentages.is_empty() {
-        info!("  [student_perf] No grades found, returning 0.5");
-        return Ok(0.5);
-    }
-
-    let avg = percentages.iter().sum::<f64>() / percentages.len() as f64;
-    info!("  [student_perf] Found {} grades, avg: {:.4}", percentages.len(), avg);
-    Ok(avg.min(1.0).max(0.0))
-}
-
-fn calculate_class_performance(
-    conn: &Connection,
-    class_id: i32,
-    subject_id: i32,
-    period: &str,
-) -> Result<f64, String> {
-    info!("  [class_perf] class_id={}, subject_id={}, period={}", class_id, subject_id, period);
-
-    let max_col = match period {
-        "P1" => "max_p1",
-        "P2" => "max_p2",
-        "EXAM1" => "max_exam1",
-        "P3" => "max_p3",
-        "P4" => "max_p4",
-        "EXAM2" => "max_exam2",
-        _ => "max_p1",
-    };
-
-    let query = format!(
-        "SELECT AVG(CAST(g.value AS FLOAT) / s.{}) as avg_percentage
-         FROM grades g
-         JOIN subjects s ON g.subject_id = s.id
-         JOIN students st ON g.student_id = st.id
-         WHERE st.class_id = ? AND g.subject_id = ? AND g.period = ?
-         AND g.value IS NOT NULL AND g.value >= 0",
-        max_col
-    );
-
-    let mut stmt = conn.prepare(&query).map_err(|e| {
-        error!("  [class_perf] Failed to prepare: {}", e);
-        e.to_string()
-    })?;
-
-    let avg_percentage: f64 = stmt
-        .query_row(rusqlite::params![class_id, subject_id, period], |row| {
-            row.get(0)
-        })
-        .unwrap_or_else(|e| {
-            info!("  [class_perf] Query returned no rows or error: {}, using 0.5", e);
-            0.5
-        });
-
-    info!("  [class_perf] Result: {:.4}", avg_percentage);
-    Ok(avg_percentage.min(1.0).max(0.0))
-}
-
-fn get_max_for_period(conn: &Connection, subject_id: i32, period: &str) -> Result<f64, String> {
-    info!("  [max_for_period] subject_id={}, period={}", subject_id, period);
-
-    let column = match period {
-        "P1" => "max_p1",
-        "P2" => "max_p2",
-        "EXAM1" => "max_exam1",
-        "P3" => "max_p3",
-        "P4" => "max_p4",
-        "EXAM2" => "max_exam2",
-        _ => "max_p1",
-    };
-
-    let query = format!("SELECT {} FROM subjects WHERE id = ?", column);
-    let mut stmt = conn.prepare(&query).map_err(|e| {
-        error!("  [max_for_period] Failed to prepare: {}", e);
-        e.to_string()
-    })?;
-
-    let max: f64 = stmt
-        .query_row(rusqlite::params![subject_id], |row| row.get(0))
-        .map_err(|e| {
-            error!("  [max_for_period] Failed to query: {}", e);
-            e.to_string()
-        })?;
-
-    info!("  [max_for_period] Result: {:.0}", max);
-    Ok(max)
-}
-
-#[tauri::command]
-async fn predict_missing_grades(
-    app_handle: tauri::AppHandle,
-    params: PredictionParams,
-) -> Result<Vec<PredictionResult>, String> {
-    info!("=== PREDICT_MISSING_GRADES START ===");
-    info!("Params: class_id={}, confidence_threshold={}", params.class_id, params.confidence_threshold);
-
-    let db_path = get_db_path(&app_handle);
-    let conn = Connection::open(db_path).map_err(|e| {
-        error!("Failed to open DB: {}", e);
-        e.to_string()
-    })?;
-
-    let mut results = Vec::new();
-    let periods = params.periods.unwrap_or_else(|| {
-        vec![
-            "P1".to_string(),
-            "P2".to_string(),
-            "EXAM1".to_string(),
-            "P3".to_string(),
-            "P4".to_string(),
-            "EXAM2".to_string(),
-        ]
-    });
-
-    info!("Periods: {:?}", periods);
-
-    // Get all students in class
-    let mut student_stmt = conn
-        .prepare("SELECT id, first_name, last_name FROM students WHERE class_id = ?")
-        .map_err(|e| {
-            error!("Failed to prepare student query: {}", e);
-            e.to_string()
-        })?;
-
-    let students_iter = student_stmt
-        .query_map(rusqlite::params![params.class_id], |row| {
-            Ok((row.get::<_, i32>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?))
-        })
-        .map_err(|e| {
-            error!("Failed to query students: {}", e);
-            e.to_string()
-        })?;
-
-    for student_result in students_iter {
-        let (student_id, first_name, last_name) = student_result.map_err(|e| {
-            error!("Failed to get student row: {}", e);
-            e.to_string()
-        })?;
-
-        info!("Processing student: {} {} (id={})", first_name, last_name, student_id);
-
-        if let Some(ref student_ids) = params.student_ids {
-            if !student_ids.contains(&student_id) {
-                continue;
-            }
-        }
-
-        // Get all subjects for this class
-        let mut subject_stmt = conn
-            .prepare("SELECT id, name FROM subjects WHERE class_id = ?")
-            .map_err(|e| {
-                error!("Failed to prepare subject query: {}", e);
-                e.to_string()
-            })?;
-
-        let subjects_iter = subject_stmt
-            .query_map(rusqlite::params![params.class_id], |row| {
-                Ok((row.get::<_, i32>(0)?, row.get::<_, String>(1)?))
-            })
-            .map_err(|e| {
-                error!("Failed to query subjects: {}", e);
-                e.to_string()
-            })?;
-
-        for subject_result in subjects_iter {
-            let (subject_id, subject_name) = subject_result.map_err(|e| {
-                error!("Failed to get subject row: {}", e);
-                e.to_string()
-            })?;
-
-            if let Some(ref subject_ids) = params.subject_ids {
-                if !subject_ids.contains(&subject_id) {
-                    continue;
-                }
-            }
-
-            for period in &periods {
-                // Check if grade exists
-                let existing: Option<f64> = conn
-                    .query_row(
-                        "SELECT value FROM grades WHERE student_id = ? AND subject_id = ? AND period = ?",
-                        rusqlite::params![student_id, subject_id, period],
-                        |row| row.get(0),
-                    )
-                    .ok();
-
-                if existing.is_some() {
-                    continue;
-                }
-
-                // Log pour déboguer : on utilise first_name et last_name (pas de variable student_name)
-                info!("Calculating prediction for {} {} / {} / {}", first_name, last_name, subject_name, period);
-
-                // Calculate prediction
-                let student_perf = match calculate_student_performance(&conn, student_id, subject_id, period) {
-                    Ok(perf) => {
-                        info!("  Student perf: {:.2}", perf);
-                        perf
-                    },
-                    Err(e) => {
-                        error!("  Failed to calculate student perf: {}", e);
-                        continue;
-                    }
-                };
-
-                let class_perf = match calculate_class_performance(&conn, params.class_id, subject_id, period) {
-                    Ok(perf) => {
-                        info!("  Class perf: {:.2}", perf);
-                        perf
-                    },
-                    Err(e) => {
-                        error!("  Failed to calculate class perf: {}", e);
-                        continue;
-                    }
-                };
-
-                let max_grade = match get_max_for_period(&conn, subject_id, period) {
-                    Ok(max) => {
-                        info!("  Max grade: {:.2}", max);
-                        max
-                    },
-                    Err(e) => {
-                        error!("  Failed to get max grade: {}", e);
-                        continue;
-                    }
-                };
-
-                let ecart = student_perf - class_perf;
-                let predicted_percentage = if ecart > 0.15 {
-                    class_perf.min(0.95)
-                } else if ecart < -0.15 {
-                    (class_perf - 0.1).max(0.0)
-                } else {
-                    (class_perf * 0.7 + student_perf * 0.3).min(1.0)
-                };
-
-                let predicted_grade = predicted_percentage * max_grade;
-
-                let mut confidence = 80u8;
-                if (ecart.abs()) > 0.25 {
-                    confidence = confidence.saturating_sub(20);
-                }
-                if student_perf < 0.3 {
-                    confidence = confidence.saturating_sub(15);
-                }
-                if predicted_percentage < 0.3 {
-                    confidence = confidence.saturating_sub(10);
-                }
-
-                info!("  Predicted: {:.2}/{:.0} (conf: {}%)", predicted_grade, max_grade, confidence);
-
-                if confidence >= params.confidence_threshold {
-                    results.push(PredictionResult {
-                        student_id,
-                        student_name: format!("{} {}", first_name, last_name),
-                        subject_id,
-                        subject_name: subject_name.clone(),
-                        period: period.clone(),
-                        predicted_grade: (predicted_grade * 100.0).round() / 100.0,
-                        max_grade,
-                        confidence,
-                        reasoning: format!(
-                            "Perf élève: {:.0}% vs classe: {:.0}% (écart: {:.0}%)",
-                            student_perf * 100.0,
-                            class_perf * 100.0,
-                            ecart * 100.0
-                        ),
-                    });
-                }
-            }
-        }
-    }
-
-    info!("=== PREDICT_MISSING_GRADES END: {} results ===", results.len());
-    Ok(results)
-}
-
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
@@ -897,9 +645,16 @@ pub fn run() {
             let db_path = get_db_path(app.handle());
             info!("Initializing application...");
             info!("Database path: {:?}", db_path);
-            db::initialize_db(&db_path).expect("Failed to initialize database");
+            // Base chiffrée : l'initialisation attend le déverrouillage (auth_verify)
+            if encryption::is_enabled(&db_path) {
+                info!("Database is encrypted, waiting for unlock");
+            } else {
+                db::initialize_db(&db_path).expect("Failed to initialize database");
+            }
+            backup::start_scheduler(db_path.clone());
 
-            // Pas besoin d'initialiser AppState serveur ici, tiny-http gère le sien
+            // Pool partagé par les commandes et le serveur mobile
+            app.manage(DbPool::new(db_path));
 
             Ok(())
         })
@@ -914,11 +669,62 @@ pub fn run() {
             auth_check,
             auth_create,
             auth_verify,
+            auth_change_password,
             check_sync_status,
             start_web_server,
             get_web_server_info,
             server::broadcast_db_change,
-            predict_missing_grades
+            prediction::predict_missing_grades,
+            prediction::apply_predictions,
+            migrations::get_schema_status,
+            backup::backup_list,
+            backup::backup_create,
+            backup::backup_verify,
+            backup::backup_restore,
+            health::db_health_check,
+            encryption::db_encryption_status,
+            encryption::db_encrypt,
+            encryption::db_recover_access,
+            query::db_select,
+            query::db_execute,
+            rollover::rollover_preview,
+            rollover::rollover_apply,
+            archive::archive_year,
+            archive::archive_list,
+            archive::archive_open,
+            archive::archive_select,
+            periods::periods_list,
+            periods::periods_save,
+            periods::subject_period_max_list,
+            periods::subject_period_max_set,
+            evaluations::evaluations_list,
+            evaluations::evaluation_save,
+            evaluations::evaluation_delete,
+            evaluations::evaluation_scores_save,
+            evaluations::evaluation_rule_get,
+            evaluations::evaluation_rule_set,
+            deliberation::deliberate_class,
+            delib_rules::delib_rule_sets_list,
+            delib_rules::delib_rule_set_save,
+            delib_rules::delib_rule_set_delete,
+            delib_rules::delib_rules_for_class,
+            repechage::repechages_generate,
+            repechage::repechage_results_save,
+            bulletin::class_bulletins,
+            bulletin_pdf::bulletins_pdf,
+            ranking::class_ranking,
+            statistics::class_statistics,
+            scaling::subject_max_change,
+            scaling::grades_curve,
+            history::history_undo_checkpoint,
+            grade_validation::grades_audit,
+            risk::class_risk_report,
+            targets::student_target_simulation,
+            conduct::conduct_scale_get,
+            conduct::conduct_scale_presets,
+            conduct::conduct_scale_set,
+            conduct::class_conduct_report,
+            conduct::student_conduct_set
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
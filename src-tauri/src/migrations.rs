@@ -0,0 +1,440 @@
+// Migrations versionnées du schéma SQLite.
+// Chaque migration est appliquée une seule fois, dans sa propre transaction,
+// et enregistrée dans la table schema_migrations.
+
+use crate::archive;
+use crate::db::DbPool;
+use crate::delib_rules;
+use crate::evaluations;
+use crate::grade_validation;
+use crate::periods;
+use log::{error, info};
+use rusqlite::{params, Connection, Transaction};
+use serde::Serialize;
+
+pub struct Migration {
+    pub version: i64,
+    pub name: &'static str,
+    pub up: fn(&Transaction) -> rusqlite::Result<()>,
+}
+
+// Tables synchronisées avec le cloud (colonnes server_id / is_dirty / horodatage)
+pub const SYNC_TABLES: [&str; 10] = [
+    "academic_years",
+    "classes",
+    "students",
+    "subjects",
+    "grades",
+    "notes",
+    "domains",
+    "repechages",
+    "options",
+    "custom_sorts",
+];
+
+// Liste ordonnée des migrations. Ne jamais modifier ni renuméroter une migration
+// déjà publiée : ajouter une nouvelle entrée à la fin.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "notes_tags",
+        up: |tx| add_column_if_missing(tx, "notes", "tags", "TEXT DEFAULT ''"),
+    },
+    Migration {
+        version: 2,
+        name: "sync_deletions_server_id",
+        up: |tx| {
+            add_column_if_missing(
+                tx,
+                "sync_deletions",
+                "server_id",
+                "TEXT NOT NULL DEFAULT ''",
+            )
+        },
+    },
+    Migration {
+        version: 3,
+        name: "normalize_legacy_student_nulls",
+        up: |tx| {
+            // Normalize legacy NULL text values so older databases can sync cleanly.
+            tx.execute(
+                "UPDATE students SET first_name = COALESCE(first_name, ''), last_name = COALESCE(last_name, ''), post_name = COALESCE(post_name, ''), gender = COALESCE(gender, ''), birthplace = COALESCE(birthplace, ''), conduite = COALESCE(conduite, ''), conduite_p1 = COALESCE(conduite_p1, ''), conduite_p2 = COALESCE(conduite_p2, ''), conduite_p3 = COALESCE(conduite_p3, ''), conduite_p4 = COALESCE(conduite_p4, ''), abandon_reason = COALESCE(abandon_reason, '')",
+                [],
+            )?;
+            Ok(())
+        },
+    },
+    Migration {
+        version: 4,
+        name: "sync_tracking_columns",
+        up: |tx| {
+            for table in SYNC_TABLES {
+                add_column_if_missing(tx, table, "server_id", "TEXT")?;
+                add_column_if_missing(tx, table, "is_dirty", "INTEGER DEFAULT 1")?;
+                add_column_if_missing(
+                    tx,
+                    table,
+                    "last_modified_at",
+                    "TEXT DEFAULT '1970-01-01 00:00:00'",
+                )?;
+                add_column_if_missing(
+                    tx,
+                    table,
+                    "updated_at",
+                    "TEXT DEFAULT '1970-01-01 00:00:00'",
+                )?;
+            }
+            Ok(())
+        },
+    },
+    Migration {
+        version: 5,
+        name: "subjects_category_domain",
+        up: |tx| {
+            add_column_if_missing(tx, "subjects", "category", "TEXT DEFAULT ''")?;
+            add_column_if_missing(tx, "subjects", "sub_domain", "TEXT DEFAULT ''")?;
+            add_column_if_missing(tx, "subjects", "domain_id", "INTEGER")
+        },
+    },
+    Migration {
+        version: 6,
+        name: "subjects_display_order",
+        // Ordre d'affichage des matières (bulletin, grille)
+        up: |tx| add_column_if_missing(tx, "subjects", "display_order", "INTEGER DEFAULT 0"),
+    },
+    Migration {
+        version: 7,
+        name: "repechages_voir_bureau",
+        // Marqueur "Voir Bureau" pour les repêchages (dette élève)
+        up: |tx| {
+            add_column_if_missing(
+                tx,
+                "repechages",
+                "voir_bureau",
+                "INTEGER NOT NULL DEFAULT 0",
+            )
+        },
+    },
+    Migration {
+        version: 8,
+        name: "options_value_short",
+        up: |tx| {
+            add_column_if_missing(tx, "options", "value", "TEXT NOT NULL DEFAULT ''")?;
+            add_column_if_missing(tx, "options", "short", "TEXT NOT NULL DEFAULT ''")
+        },
+    },
+    Migration {
+        version: 9,
+        name: "quarantine",
+        // Lignes écartées par db_health_check (orphelins), conservées pour inspection
+        up: |tx| {
+            tx.execute_batch(
+                "CREATE TABLE IF NOT EXISTS quarantine (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    table_name TEXT NOT NULL,
+                    row_id INTEGER NOT NULL,
+                    row_data TEXT NOT NULL,
+                    reason TEXT NOT NULL,
+                    quarantined_at TEXT DEFAULT (datetime('now'))
+                );",
+            )
+        },
+    },
+    Migration {
+        version: 10,
+        name: "configurable_periods",
+        // Périodes par année et maxima par cours, repris des colonnes max_p1..max_exam2
+        up: periods::create_schema,
+    },
+    Migration {
+        version: 11,
+        name: "evaluations",
+        // Interrogations d'une période et points des élèves
+        up: evaluations::create_schema,
+    },
+    Migration {
+        version: 12,
+        name: "deliberation_rule_sets",
+        // Critères de délibération par année, niveau et option
+        up: delib_rules::create_schema,
+    },
+    Migration {
+        version: 13,
+        name: "subjects_coefficient",
+        // Coefficient facultatif du cours (NULL = 1), utilisé si grade_weighting_mode = coefficient
+        up: |tx| add_column_if_missing(tx, "subjects", "coefficient", "REAL"),
+    },
+    Migration {
+        version: 14,
+        name: "grade_validation",
+        // Cotes refusées hors [0, maximum] ou sur une période inconnue
+        up: grade_validation::create_schema,
+    },
+    Migration {
+        version: 15,
+        name: "grades_is_estimated",
+        // Cote insérée depuis une prédiction acceptée (apply_predictions), remise à 0 à la saisie
+        up: |tx| add_column_if_missing(tx, "grades", "is_estimated", "INTEGER NOT NULL DEFAULT 0"),
+    },
+    Migration {
+        version: 16,
+        name: "deliberation_rule_sets_explicit",
+        // Règles tirées de settings tant qu'aucun jeu n'est enregistré pour l'année
+        up: delib_rules::settings_until_saved,
+    },
+    Migration {
+        version: 17,
+        name: "grades_is_computed",
+        // Cote de période produite par le calcul des interrogations (evaluations::recompute),
+        // remise à 0 à la saisie : seules ces cotes sont supprimées quand leurs points disparaissent
+        up: |tx| add_column_if_missing(tx, "grades", "is_computed", "INTEGER NOT NULL DEFAULT 0"),
+    },
+    Migration {
+        version: 18,
+        name: "archived_years",
+        // Années archivées et retirées de la base, ignorées au pull
+        up: archive::create_schema,
+    },
+];
+
+fn column_exists(tx: &Transaction, table: &str, column: &str) -> rusqlite::Result<bool> {
+    let mut stmt = tx.prepare(&format!("PRAGMA table_info({table})"))?;
+    let names = stmt.query_map([], |row| row.get::<_, String>(1))?;
+    for name in names {
+        if name? == column {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+// Ajoute une colonne seulement si elle n'existe pas encore.
+// Toute autre erreur SQL est remontée au lieu d'être ignorée.
+pub fn add_column_if_missing(
+    tx: &Transaction,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> rusqlite::Result<()> {
+    if !column_exists(tx, table, column)? {
+        tx.execute(
+            &format!("ALTER TABLE {table} ADD COLUMN {column} {definition}"),
+            [],
+        )?;
+    }
+    Ok(())
+}
+
+fn ensure_migrations_table(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS schema_migrations (
+            version INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            applied_at TEXT DEFAULT (datetime('now'))
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn latest_version() -> i64 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+pub fn current_version(conn: &Connection) -> Result<i64, String> {
+    ensure_migrations_table(conn)?;
+    conn.query_row(
+        "SELECT COALESCE(MAX(version), 0) FROM schema_migrations",
+        [],
+        |row| row.get(0),
+    )
+    .map_err(|e| e.to_string())
+}
+
+pub fn pending_migrations(conn: &Connection) -> Result<Vec<&'static Migration>, String> {
+    let current = current_version(conn)?;
+    Ok(MIGRATIONS.iter().filter(|m| m.version > current).collect())
+}
+
+// Applique toutes les migrations en attente, dans l'ordre.
+// Une migration qui échoue est annulée et l'erreur est remontée : le démarrage doit s'arrêter.
+pub fn run_pending(conn: &mut Connection) -> Result<Vec<i64>, String> {
+    let pending: Vec<&Migration> = pending_migrations(conn)?;
+    let mut applied = Vec::new();
+
+    for migration in pending {
+        info!(
+            "Applying migration {} ({})...",
+            migration.version, migration.name
+        );
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        let result = (migration.up)(&tx).and_then(|_| {
+            tx.execute(
+                "INSERT INTO schema_migrations (version, name) VALUES (?, ?)",
+                params![migration.version, migration.name],
+            )
+        });
+
+        if let Err(e) = result {
+            error!(
+                "Migration {} ({}) failed: {}",
+                migration.version, migration.name, e
+            );
+            // La transaction est annulée au drop
+            return Err(format!(
+                "Échec de la migration {} ({}) : {}",
+                migration.version, migration.name, e
+            ));
+        }
+
+        tx.commit().map_err(|e| {
+            format!(
+                "Échec de la migration {} ({}) : {}",
+                migration.version, migration.name, e
+            )
+        })?;
+        applied.push(migration.version);
+    }
+
+    Ok(applied)
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationEntry {
+    pub version: i64,
+    pub name: String,
+    pub applied_at: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SchemaStatus {
+    pub current_version: i64,
+    pub latest_version: i64,
+    pub applied: Vec<MigrationEntry>,
+    pub pending: Vec<MigrationEntry>,
+}
+
+#[tauri::command]
+pub async fn get_schema_status(pool: tauri::State<'_, DbPool>) -> Result<SchemaStatus, String> {
+    pool.run(read_schema_status).await
+}
+
+fn read_schema_status(conn: &mut Connection) -> Result<SchemaStatus, String> {
+    let current_version = current_version(conn)?;
+
+    let applied: Vec<MigrationEntry> = conn
+        .prepare("SELECT version, name, applied_at FROM schema_migrations ORDER BY version")
+        .and_then(|mut stmt| {
+            stmt.query_map([], |row| {
+                Ok(MigrationEntry {
+                    version: row.get(0)?,
+                    name: row.get(1)?,
+                    applied_at: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()
+        })
+        .map_err(|e| e.to_string())?;
+
+    let pending = pending_migrations(conn)?
+        .into_iter()
+        .map(|m| MigrationEntry {
+            version: m.version,
+            name: m.name.to_string(),
+            applied_at: None,
+        })
+        .collect();
+
+    Ok(SchemaStatus {
+        current_version,
+        latest_version: latest_version(),
+        applied,
+        pending,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{self, test_support::TestDb};
+
+    fn count(conn: &Connection, sql: &str) -> i64 {
+        conn.query_row(sql, [], |row| row.get(0)).unwrap()
+    }
+
+    fn recorded_versions(conn: &Connection) -> Vec<i64> {
+        conn.prepare("SELECT version FROM schema_migrations ORDER BY version")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    fn all_versions() -> Vec<i64> {
+        MIGRATIONS.iter().map(|m| m.version).collect()
+    }
+
+    #[test]
+    fn versions_croissantes() {
+        assert!(all_versions().windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn base_neuve_a_jour_et_relance_sans_effet() {
+        let db = TestDb::open();
+        let mut conn = db.conn();
+        assert_eq!(current_version(&conn).unwrap(), latest_version());
+        assert_eq!(recorded_versions(&conn), all_versions());
+
+        assert!(run_pending(&mut conn).unwrap().is_empty());
+        db::initialize_db(&db.path).unwrap();
+        assert_eq!(recorded_versions(&conn), all_versions());
+        assert!(pending_migrations(&conn).unwrap().is_empty());
+    }
+
+    #[test]
+    fn base_anterieure_aux_migrations() {
+        let db = TestDb::open();
+        let mut conn = db.conn();
+        conn.execute_batch(
+            "INSERT INTO academic_years (id, name, start_date, end_date, is_active) VALUES (1, '2024-2025', '2024-09-01', '2025-07-01', 1);
+             INSERT INTO classes (id, name, level, option, section, academic_year_id) VALUES (1, '2ème ETRO', '2ème', 'ELECTRONIQUE', 'A', 1);
+             INSERT INTO subjects (id, name, code, class_id) VALUES (1, 'Maths', 'MATH', 1);
+             INSERT INTO students (id, first_name, last_name, gender, class_id) VALUES (1, 'Jean', 'Kabila', 'M', 1);
+             INSERT INTO grades (student_id, subject_id, period, value) VALUES (1, 1, 'P1', 7);
+             ALTER TABLE notes DROP COLUMN tags;
+             DROP TABLE schema_migrations;",
+        )
+        .unwrap();
+
+        // Schéma déjà en place mais aucune version enregistrée : tout est rejoué sans erreur
+        assert_eq!(current_version(&conn).unwrap(), 0);
+        assert_eq!(run_pending(&mut conn).unwrap(), all_versions());
+        assert_eq!(recorded_versions(&conn), all_versions());
+        assert!(run_pending(&mut conn).unwrap().is_empty());
+
+        // Colonne de la migration 1 recréée, données intactes
+        assert_eq!(
+            count(
+                &conn,
+                "SELECT COUNT(*) FROM pragma_table_info('notes') WHERE name = 'tags'"
+            ),
+            1
+        );
+        assert_eq!(
+            count(&conn, "SELECT COUNT(*) FROM grades WHERE value = 7"),
+            1
+        );
+        assert_eq!(
+            count(
+                &conn,
+                "SELECT COUNT(*) FROM subject_period_max WHERE subject_id = 1"
+            ),
+            6
+        );
+    }
+}
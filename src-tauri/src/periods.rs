@@ -0,0 +1,612 @@
+// Périodes d'évaluation configurables par année scolaire (P1..EXAM2 par défaut,
+// trimestres ou interrogations supplémentaires possibles) et maxima par cours.
+// Les colonnes historiques max_p1..max_exam2 de subjects restent alimentées par
+// triggers pour le frontend et le cloud qui les lisent encore.
+
+use crate::db::DbPool;
+use log::info;
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// (code, libellé, semestre, examen, maximum par défaut)
+pub const DEFAULT_PERIODS: [(&str, &str, i64, bool, i64); 6] = [
+    ("P1", "1ère période", 1, false, 10),
+    ("P2", "2ème période", 1, false, 10),
+    ("EXAM1", "Examen 1er semestre", 1, true, 20),
+    ("P3", "3ème période", 2, false, 10),
+    ("P4", "4ème période", 2, false, 10),
+    ("EXAM2", "Examen 2ème semestre", 2, true, 20),
+];
+
+// Colonne historique de subjects correspondant à un code de période
+pub const LEGACY_COLUMNS: [(&str, &str); 6] = [
+    ("P1", "max_p1"),
+    ("P2", "max_p2"),
+    ("EXAM1", "max_exam1"),
+    ("P3", "max_p3"),
+    ("P4", "max_p4"),
+    ("EXAM2", "max_exam2"),
+];
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Period {
+    pub id: i64,
+    pub academic_year_id: i64,
+    pub code: String,
+    pub label: String,
+    pub semester: i64,
+    pub is_exam: bool,
+    pub display_order: i64,
+    pub default_max: i64,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PeriodInput {
+    pub code: String,
+    pub label: String,
+    pub semester: i64,
+    pub is_exam: bool,
+    pub default_max: i64,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SubjectPeriodMax {
+    pub subject_id: i64,
+    pub period_code: String,
+    pub max_points: i64,
+}
+
+// Valeur de la colonne historique pour chaque code connu, maximum par défaut sinon
+fn legacy_max_case(row: &str, default: &str) -> String {
+    let branches: Vec<String> = LEGACY_COLUMNS
+        .iter()
+        .map(|(code, column)| format!("WHEN '{code}' THEN {row}.{column}"))
+        .collect();
+    format!("CASE p.code {} ELSE {default} END", branches.join(" "))
+}
+
+// Schéma, reprise des colonnes historiques et triggers de cohérence (migration 10)
+pub fn create_schema(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS periods (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            academic_year_id INTEGER NOT NULL,
+            code TEXT NOT NULL,
+            label TEXT NOT NULL,
+            semester INTEGER NOT NULL DEFAULT 1,
+            is_exam INTEGER NOT NULL DEFAULT 0,
+            display_order INTEGER NOT NULL DEFAULT 0,
+            default_max INTEGER NOT NULL DEFAULT 10,
+            server_id TEXT,
+            is_dirty INTEGER DEFAULT 1,
+            created_at TEXT DEFAULT '1970-01-01 00:00:00',
+            updated_at TEXT DEFAULT '1970-01-01 00:00:00',
+            last_modified_at TEXT DEFAULT '1970-01-01 00:00:00',
+            FOREIGN KEY (academic_year_id) REFERENCES academic_years(id) ON DELETE CASCADE,
+            UNIQUE(academic_year_id, code)
+        );
+
+        CREATE TABLE IF NOT EXISTS subject_period_max (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            subject_id INTEGER NOT NULL,
+            period_code TEXT NOT NULL,
+            max_points INTEGER NOT NULL,
+            FOREIGN KEY (subject_id) REFERENCES subjects(id) ON DELETE CASCADE,
+            UNIQUE(subject_id, period_code)
+        );",
+    )?;
+
+    let years: Vec<i64> = tx
+        .prepare("SELECT id FROM academic_years")?
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+    for year_id in years {
+        insert_defaults(tx, year_id)?;
+    }
+    tx.execute(
+        &format!(
+            "INSERT OR IGNORE INTO subject_period_max (subject_id, period_code, max_points)
+             SELECT s.id, p.code, {}
+             FROM subjects s
+             JOIN classes c ON s.class_id = c.id
+             JOIN periods p ON p.academic_year_id = c.academic_year_id",
+            legacy_max_case("s", "p.default_max")
+        ),
+        [],
+    )?;
+
+    let defaults: Vec<String> = DEFAULT_PERIODS
+        .iter()
+        .enumerate()
+        .map(|(i, (code, label, semester, is_exam, default_max))| {
+            format!(
+                "(NEW.id, '{code}', '{}', {semester}, {}, {}, {default_max})",
+                label.replace('\'', "''"),
+                *is_exam as i32,
+                i + 1
+            )
+        })
+        .collect();
+    let legacy_sets: Vec<String> = LEGACY_COLUMNS
+        .iter()
+        .map(|(code, column)| {
+            format!("{column} = CASE WHEN NEW.period_code = '{code}' THEN NEW.max_points ELSE {column} END")
+        })
+        .collect();
+    let legacy_columns: Vec<&str> = LEGACY_COLUMNS.iter().map(|(_, c)| *c).collect();
+
+    tx.execute_batch(&format!(
+        "
+        -- Une nouvelle année reçoit le découpage par défaut
+        CREATE TRIGGER IF NOT EXISTS trg_academic_years_default_periods
+        AFTER INSERT ON academic_years
+        FOR EACH ROW
+        BEGIN
+            INSERT OR IGNORE INTO periods (academic_year_id, code, label, semester, is_exam, display_order, default_max)
+            VALUES {defaults};
+        END;
+
+        -- Un nouveau cours reçoit un maximum pour chaque période de son année
+        CREATE TRIGGER IF NOT EXISTS trg_subjects_period_max_insert
+        AFTER INSERT ON subjects
+        FOR EACH ROW
+        BEGIN
+            INSERT OR IGNORE INTO subject_period_max (subject_id, period_code, max_points)
+            SELECT NEW.id, p.code, {new_case}
+            FROM periods p JOIN classes c ON c.academic_year_id = p.academic_year_id
+            WHERE c.id = NEW.class_id;
+        END;
+
+        -- Colonnes historiques modifiées par l'ancien frontend
+        CREATE TRIGGER IF NOT EXISTS trg_subjects_legacy_max_update
+        AFTER UPDATE OF {columns} ON subjects
+        FOR EACH ROW
+        BEGIN
+            UPDATE subject_period_max SET max_points = {legacy_case}
+            WHERE subject_id = NEW.id AND period_code IN ({codes});
+        END;
+
+        -- Et dans l'autre sens ; la mise à jour de subjects marque aussi le cours à synchroniser
+        CREATE TRIGGER IF NOT EXISTS trg_subject_period_max_insert
+        AFTER INSERT ON subject_period_max
+        FOR EACH ROW
+        BEGIN
+            UPDATE subjects SET {sets} WHERE id = NEW.subject_id;
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS trg_subject_period_max_update
+        AFTER UPDATE OF max_points ON subject_period_max
+        FOR EACH ROW
+        BEGIN
+            UPDATE subjects SET {sets} WHERE id = NEW.subject_id;
+        END;
+        ",
+        defaults = defaults.join(", "),
+        new_case = legacy_max_case("NEW", "p.default_max"),
+        columns = legacy_columns.join(", "),
+        legacy_case = legacy_max_case("NEW", "max_points").replace("p.code", "period_code"),
+        codes = LEGACY_COLUMNS
+            .iter()
+            .map(|(code, _)| format!("'{code}'"))
+            .collect::<Vec<_>>()
+            .join(", "),
+        sets = legacy_sets.join(", "),
+    ))
+}
+
+fn insert_defaults(conn: &Connection, year_id: i64) -> rusqlite::Result<()> {
+    let mut stmt = conn.prepare_cached(
+        "INSERT OR IGNORE INTO periods (academic_year_id, code, label, semester, is_exam, display_order, default_max) VALUES (?, ?, ?, ?, ?, ?, ?)",
+    )?;
+    for (i, (code, label, semester, is_exam, default_max)) in DEFAULT_PERIODS.iter().enumerate() {
+        stmt.execute(params![
+            year_id,
+            code,
+            label,
+            semester,
+            is_exam,
+            i as i64 + 1,
+            default_max
+        ])?;
+    }
+    Ok(())
+}
+
+fn read_period(row: &rusqlite::Row) -> rusqlite::Result<Period> {
+    Ok(Period {
+        id: row.get(0)?,
+        academic_year_id: row.get(1)?,
+        code: row.get(2)?,
+        label: row.get(3)?,
+        semester: row.get(4)?,
+        is_exam: row.get(5)?,
+        display_order: row.get(6)?,
+        default_max: row.get(7)?,
+    })
+}
+
+pub fn load_periods(conn: &Connection, year_id: i64) -> Result<Vec<Period>, String> {
+    conn.prepare_cached(
+        "SELECT id, academic_year_id, code, label, semester, is_exam, display_order, default_max
+         FROM periods WHERE academic_year_id = ? ORDER BY display_order, id",
+    )
+    .and_then(|mut stmt| {
+        stmt.query_map(params![year_id], read_period)?
+            .collect::<Result<Vec<_>, _>>()
+    })
+    .map_err(|e| e.to_string())
+}
+
+pub fn periods_for_class(conn: &Connection, class_id: i64) -> Result<Vec<Period>, String> {
+    let year_id: i64 = conn
+        .query_row(
+            "SELECT academic_year_id FROM classes WHERE id = ?",
+            params![class_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Classe introuvable".to_string())?;
+    load_periods(conn, year_id)
+}
+
+// Maximum d'un cours pour une période ; à défaut de ligne dédiée, celui de la période
+pub fn max_points(conn: &Connection, subject_id: i64, period_code: &str) -> Result<f64, String> {
+    conn.prepare_cached(
+        "SELECT COALESCE(
+            (SELECT max_points FROM subject_period_max WHERE subject_id = s.id AND period_code = ?2),
+            (SELECT p.default_max FROM periods p JOIN classes c ON c.academic_year_id = p.academic_year_id
+             WHERE c.id = s.class_id AND p.code = ?2)
+         )
+         FROM subjects s WHERE s.id = ?1",
+    )
+    .and_then(|mut stmt| {
+        stmt.query_row(params![subject_id, period_code], |row| {
+            row.get::<_, Option<f64>>(0)
+        })
+    })
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Période {} inconnue pour ce cours", period_code))
+}
+
+// Maxima de tous les cours d'une classe : subject_id -> (code -> max)
+pub fn class_maxima(
+    conn: &Connection,
+    class_id: i64,
+) -> Result<HashMap<i64, HashMap<String, f64>>, String> {
+    let mut maxima: HashMap<i64, HashMap<String, f64>> = HashMap::new();
+    let mut stmt = conn
+        .prepare_cached(
+            "SELECT s.id, p.code, COALESCE(m.max_points, p.default_max)
+             FROM subjects s
+             JOIN classes c ON s.class_id = c.id
+             JOIN periods p ON p.academic_year_id = c.academic_year_id
+             LEFT JOIN subject_period_max m ON m.subject_id = s.id AND m.period_code = p.code
+             WHERE s.class_id = ?",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![class_id], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get(2)?))
+        })
+        .map_err(|e| e.to_string())?;
+    for row in rows {
+        let (subject_id, code, max) = row.map_err(|e| e.to_string())?;
+        maxima.entry(subject_id).or_default().insert(code, max);
+    }
+    Ok(maxima)
+}
+
+// Remplace le découpage d'une année. Une période qui porte déjà des cotes ne peut pas disparaître.
+pub fn save_periods(
+    conn: &mut Connection,
+    year_id: i64,
+    periods: &[PeriodInput],
+) -> Result<Vec<Period>, String> {
+    if periods.is_empty() {
+        return Err("Au moins une période est requise".to_string());
+    }
+    for (i, period) in periods.iter().enumerate() {
+        let code = period.code.trim();
+        if code.is_empty() || period.label.trim().is_empty() {
+            return Err("Chaque période doit avoir un code et un libellé".to_string());
+        }
+        if periods[..i].iter().any(|p| p.code.trim() == code) {
+            return Err(format!("Le code {} est utilisé deux fois", code));
+        }
+        if period.default_max <= 0 {
+            return Err(format!("Le maximum de {} doit être positif", code));
+        }
+    }
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let existing: Vec<String> = tx
+        .prepare("SELECT code FROM periods WHERE academic_year_id = ?")
+        .and_then(|mut stmt| {
+            stmt.query_map(params![year_id], |row| row.get(0))?
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .map_err(|e| e.to_string())?;
+
+    for code in existing
+        .iter()
+        .filter(|c| !periods.iter().any(|p| p.code.trim() == c.as_str()))
+    {
+        let graded: i64 = tx
+            .query_row(
+                "SELECT COUNT(*) FROM grades g
+                 JOIN subjects s ON g.subject_id = s.id
+                 JOIN classes c ON s.class_id = c.id
+                 WHERE c.academic_year_id = ? AND g.period = ?",
+                params![year_id, code],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        if graded > 0 {
+            return Err(format!(
+                "La période {} contient {} cote(s) et ne peut pas être supprimée",
+                code, graded
+            ));
+        }
+        tx.execute(
+            "DELETE FROM subject_period_max WHERE period_code = ?1
+             AND subject_id IN (SELECT s.id FROM subjects s JOIN classes c ON s.class_id = c.id WHERE c.academic_year_id = ?2)",
+            params![code, year_id],
+        )
+        .map_err(|e| e.to_string())?;
+        tx.execute(
+            "DELETE FROM periods WHERE academic_year_id = ? AND code = ?",
+            params![year_id, code],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    for (i, period) in periods.iter().enumerate() {
+        tx.execute(
+            "INSERT INTO periods (academic_year_id, code, label, semester, is_exam, display_order, default_max, is_dirty, last_modified_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 1, (datetime('now')))
+             ON CONFLICT(academic_year_id, code) DO UPDATE SET
+                label = ?3, semester = ?4, is_exam = ?5, display_order = ?6, default_max = ?7,
+                is_dirty = 1, last_modified_at = (datetime('now'))",
+            params![
+                year_id,
+                period.code.trim(),
+                period.label.trim(),
+                period.semester,
+                period.is_exam,
+                i as i64 + 1,
+                period.default_max
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+        // Les cours existants reçoivent le maximum par défaut de la nouvelle période
+        tx.execute(
+            "INSERT OR IGNORE INTO subject_period_max (subject_id, period_code, max_points)
+             SELECT s.id, ?2, ?3 FROM subjects s JOIN classes c ON s.class_id = c.id
+             WHERE c.academic_year_id = ?1",
+            params![year_id, period.code.trim(), period.default_max],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+
+    info!("Saved {} periods for year {}", periods.len(), year_id);
+    load_periods(conn, year_id)
+}
+
+// Remplace le découpage d'une nouvelle année par celui d'une année existante
+pub fn copy_periods(conn: &Connection, from_year_id: i64, to_year_id: i64) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM periods WHERE academic_year_id = ?",
+        params![to_year_id],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO periods (academic_year_id, code, label, semester, is_exam, display_order, default_max, is_dirty, last_modified_at)
+         SELECT ?, code, label, semester, is_exam, display_order, default_max, 1, (datetime('now'))
+         FROM periods WHERE academic_year_id = ?",
+        params![to_year_id, from_year_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn set_subject_max(
+    conn: &Connection,
+    subject_id: i64,
+    period_code: &str,
+    max_points: i64,
+) -> Result<(), String> {
+    if max_points <= 0 {
+        return Err("Le maximum doit être positif".to_string());
+    }
+    let known: bool = conn
+        .query_row(
+            "SELECT EXISTS(SELECT 1 FROM periods p JOIN classes c ON c.academic_year_id = p.academic_year_id
+             JOIN subjects s ON s.class_id = c.id WHERE s.id = ? AND p.code = ?)",
+            params![subject_id, period_code],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if !known {
+        return Err(format!("Période {} inconnue pour ce cours", period_code));
+    }
+    conn.execute(
+        "INSERT INTO subject_period_max (subject_id, period_code, max_points) VALUES (?1, ?2, ?3)
+         ON CONFLICT(subject_id, period_code) DO UPDATE SET max_points = ?3",
+        params![subject_id, period_code, max_points],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn list_subject_maxima(conn: &Connection, class_id: i64) -> Result<Vec<SubjectPeriodMax>, String> {
+    let mut rows: Vec<SubjectPeriodMax> = class_maxima(conn, class_id)?
+        .into_iter()
+        .flat_map(|(subject_id, maxima)| {
+            maxima
+                .into_iter()
+                .map(move |(period_code, max)| SubjectPeriodMax {
+                    subject_id,
+                    period_code,
+                    max_points: max as i64,
+                })
+        })
+        .collect();
+    rows.sort_by(|a, b| {
+        a.subject_id
+            .cmp(&b.subject_id)
+            .then(a.period_code.cmp(&b.period_code))
+    });
+    Ok(rows)
+}
+
+#[tauri::command]
+pub async fn periods_list(
+    pool: tauri::State<'_, DbPool>,
+    academic_year_id: i64,
+) -> Result<Vec<Period>, String> {
+    pool.run(move |conn| load_periods(conn, academic_year_id))
+        .await
+}
+
+#[tauri::command]
+pub async fn periods_save(
+    pool: tauri::State<'_, DbPool>,
+    academic_year_id: i64,
+    periods: Vec<PeriodInput>,
+) -> Result<Vec<Period>, String> {
+    pool.run(move |conn| save_periods(conn, academic_year_id, &periods))
+        .await
+}
+
+#[tauri::command]
+pub async fn subject_period_max_list(
+    pool: tauri::State<'_, DbPool>,
+    class_id: i64,
+) -> Result<Vec<SubjectPeriodMax>, String> {
+    pool.run(move |conn| list_subject_maxima(conn, class_id))
+        .await
+}
+
+#[tauri::command]
+pub async fn subject_period_max_set(
+    pool: tauri::State<'_, DbPool>,
+    subject_id: i64,
+    period_code: String,
+    max_points: i64,
+) -> Result<(), String> {
+    pool.run(move |conn| set_subject_max(conn, subject_id, &period_code, max_points))
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::TestDb;
+
+    fn class_db() -> TestDb {
+        let db = TestDb::open();
+        db.conn()
+            .execute_batch(
+                "INSERT INTO academic_years (id, name, start_date, end_date, is_active) VALUES (1, '2024-2025', '2024-09-01', '2025-07-01', 1);
+                 INSERT INTO classes (id, name, level, option, section, academic_year_id) VALUES (1, '7ème A', '7ème', 'EB', 'A', 1);
+                 INSERT INTO subjects (id, name, code, class_id, max_p1, max_exam1) VALUES (1, 'Maths', 'MATH', 1, 20, 40);
+                 INSERT INTO students (id, first_name, last_name, gender, class_id) VALUES (1, 'Jean', 'Kabila', 'M', 1);
+                 UPDATE subjects SET is_dirty = 0;",
+            )
+            .unwrap();
+        db
+    }
+
+    fn legacy_max(conn: &Connection, column: &str) -> (i64, bool) {
+        conn.query_row(
+            &format!("SELECT {column}, is_dirty FROM subjects WHERE id = 1"),
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .unwrap()
+    }
+
+    fn inputs(codes: &[&str]) -> Vec<PeriodInput> {
+        codes
+            .iter()
+            .map(|code| PeriodInput {
+                code: code.to_string(),
+                label: code.to_string(),
+                semester: 1,
+                is_exam: false,
+                default_max: 25,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn colonnes_historiques_synchronisees() {
+        let db = class_db();
+        let conn = db.conn();
+
+        // Reprise des colonnes historiques à la création du cours, défauts ailleurs
+        assert_eq!(load_periods(&conn, 1).unwrap().len(), 6);
+        assert_eq!(max_points(&conn, 1, "P1").unwrap(), 20.0);
+        assert_eq!(max_points(&conn, 1, "EXAM1").unwrap(), 40.0);
+        assert_eq!(max_points(&conn, 1, "P2").unwrap(), 10.0);
+
+        // Nouveau maximum : colonne historique à jour et cours à synchroniser
+        set_subject_max(&conn, 1, "P2", 15).unwrap();
+        assert_eq!(legacy_max(&conn, "max_p2"), (15, true));
+
+        // Ancien frontend : la colonne historique alimente subject_period_max
+        conn.execute("UPDATE subjects SET max_p3 = 30 WHERE id = 1", [])
+            .unwrap();
+        assert_eq!(max_points(&conn, 1, "P3").unwrap(), 30.0);
+        assert_eq!(class_maxima(&conn, 1).unwrap()[&1]["P3"], 30.0);
+
+        assert!(set_subject_max(&conn, 1, "P9", 10).is_err());
+        assert!(set_subject_max(&conn, 1, "P1", 0).is_err());
+    }
+
+    #[test]
+    fn periode_notee_non_supprimable() {
+        let db = class_db();
+        let mut conn = db.conn();
+        conn.execute(
+            "INSERT INTO grades (student_id, subject_id, period, value) VALUES (1, 1, 'P1', 5)",
+            [],
+        )
+        .unwrap();
+
+        let error = save_periods(&mut conn, 1, &inputs(&["T1", "T2", "T3"])).unwrap_err();
+        assert!(error.contains("P1"), "{error}");
+        // Rien n'a changé
+        let codes: Vec<String> = load_periods(&conn, 1)
+            .unwrap()
+            .into_iter()
+            .map(|p| p.code)
+            .collect();
+        assert_eq!(codes, ["P1", "P2", "EXAM1", "P3", "P4", "EXAM2"]);
+        assert_eq!(max_points(&conn, 1, "P2").unwrap(), 10.0);
+
+        // La période notée gardée, les autres remplacées
+        let saved = save_periods(&mut conn, 1, &inputs(&["P1", "T2", "T3"])).unwrap();
+        assert_eq!(saved.len(), 3);
+        assert_eq!(max_points(&conn, 1, "P1").unwrap(), 20.0);
+        assert_eq!(max_points(&conn, 1, "T2").unwrap(), 25.0);
+        assert!(max_points(&conn, 1, "P2").is_err());
+
+        // Un cours créé ensuite reçoit le nouveau découpage
+        conn.execute(
+            "INSERT INTO subjects (id, name, class_id) VALUES (2, 'Français', 1)",
+            [],
+        )
+        .unwrap();
+        assert_eq!(class_maxima(&conn, 1).unwrap()[&2].len(), 3);
+
+        assert!(save_periods(&mut conn, 1, &inputs(&["T1", "T1"])).is_err());
+        assert!(save_periods(&mut conn, 1, &[]).is_err());
+    }
+}
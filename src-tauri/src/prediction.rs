@@ -0,0 +1,1000 @@
+// Prédiction des cotes manquantes d'une classe. Chaque cote est exprimée en pourcentage
+// du maximum de sa période et expliquée par deux variables : l'historique de l'élève dans
+// le même cours aux périodes précédentes et ses résultats de la même période dans les
+// autres cours, pondérés par la corrélation de ces cours avec le cours prédit. Une
+// régression linéaire est ajustée par cours (ou sur toute la classe quand le cours a trop
+// peu de cotes). La confiance vient des erreurs commises sur des cotes connues mises de
+// côté (validation croisée par élève), pas d'un barème fixe. Tout se calcule sur le
+// Gradebook chargé en une fois : aucune requête par élève, cours ou période.
+// Les prédictions acceptées s'insèrent comme cotes estimées sous un point de reprise,
+// annulable d'un bloc avec history_undo_checkpoint.
+
+use crate::db::DbPool;
+use crate::gradebook::{Gradebook, GRADE_TRICHEUR_CODE};
+use crate::history::{ActionType, Batch, GradeState};
+use log::info;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// Écart toléré entre prédiction et cote réelle, en fraction du maximum
+pub const TOLERANCE: f64 = 0.10;
+// Nombre de groupes d'élèves de la validation croisée
+const FOLDS: usize = 5;
+// En dessous, la régression d'un cours cède la place à celle de la classe
+const MIN_SAMPLES: usize = 8;
+// Erreurs de validation minimales pour estimer la confiance d'un modèle
+const MIN_RESIDUALS: usize = 5;
+// Régularisation des pentes : évite les coefficients extrêmes sur de petits effectifs
+const RIDGE: f64 = 1e-3;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PredictionResult {
+    pub student_id: i32,
+    pub student_name: String,
+    pub subject_id: i32,
+    pub subject_name: String,
+    pub period: String,
+    pub predicted_grade: f64,
+    pub max_grade: f64,
+    pub confidence: u8,
+    pub reasoning: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PredictionParams {
+    pub class_id: i32,
+    pub student_ids: Option<Vec<i32>>,
+    pub subject_ids: Option<Vec<i32>>,
+    pub periods: Option<Vec<String>>,
+    pub confidence_threshold: u8,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Backtest {
+    // Cotes connues prédites sans les cotes de leur élève
+    pub samples: usize,
+    // En points de pourcentage
+    pub mean_absolute_error: Option<f64>,
+    // Part des cotes retrouvées à la tolérance près, en %
+    pub within_tolerance: Option<f64>,
+    pub tolerance: f64,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PredictionReport {
+    pub class_id: i32,
+    pub predictions: Vec<PredictionResult>,
+    pub backtest: Backtest,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Features {
+    Both,
+    History,
+    Cross,
+    Intercept,
+}
+
+impl Features {
+    // Du plus informatif au moins informatif, selon les variables disponibles
+    fn candidates(sample: &Sample) -> &'static [Features] {
+        match (sample.history, sample.cross) {
+            (Some(_), Some(_)) => &[
+                Features::Both,
+                Features::History,
+                Features::Cross,
+                Features::Intercept,
+            ],
+            (Some(_), None) => &[Features::History, Features::Intercept],
+            (None, Some(_)) => &[Features::Cross, Features::Intercept],
+            (None, None) => &[Features::Intercept],
+        }
+    }
+
+    fn row(self, sample: &Sample) -> Option<Vec<f64>> {
+        Some(match self {
+            Features::Both => vec![1.0, sample.history?, sample.cross?],
+            Features::History => vec![1.0, sample.history?],
+            Features::Cross => vec![1.0, sample.cross?],
+            Features::Intercept => vec![1.0],
+        })
+    }
+}
+
+// Une case (élève, cours, période) et ses variables explicatives
+struct Sample {
+    student: usize,
+    subject: usize,
+    period: usize,
+    history: Option<f64>,
+    cross: Option<f64>,
+    // Pourcentage obtenu, None pour une cote manquante
+    target: Option<f64>,
+}
+
+// (cours, variables) ; None pour la régression de toute la classe
+type ModelKey = (Option<usize>, Features);
+
+// Moindres carrés avec une légère pénalité sur les pentes (pas sur la constante)
+fn least_squares(rows: &[Vec<f64>], targets: &[f64]) -> Option<Vec<f64>> {
+    let k = rows.first()?.len();
+    let mut a = vec![vec![0.0; k + 1]; k];
+    for (row, y) in rows.iter().zip(targets) {
+        for i in 0..k {
+            for j in 0..k {
+                a[i][j] += row[i] * row[j];
+            }
+            a[i][k] += row[i] * y;
+        }
+    }
+    for (i, line) in a.iter_mut().enumerate().skip(1) {
+        line[i] += RIDGE * rows.len() as f64;
+    }
+    // Élimination de Gauss avec pivot partiel
+    for col in 0..k {
+        let pivot = (col..k).max_by(|x, y| a[*x][col].abs().total_cmp(&a[*y][col].abs()))?;
+        if a[pivot][col].abs() < 1e-12 {
+            return None;
+        }
+        a.swap(col, pivot);
+        let pivot_row = a[col].clone();
+        for (i, line) in a.iter_mut().enumerate() {
+            if i != col {
+                let factor = line[col] / pivot_row[col];
+                for (x, p) in line.iter_mut().zip(&pivot_row).skip(col) {
+                    *x -= factor * p;
+                }
+            }
+        }
+    }
+    Some((0..k).map(|i| a[i][k] / a[i][i]).collect())
+}
+
+fn fit(samples: &[&Sample], features: Features) -> Option<Vec<f64>> {
+    let (rows, targets): (Vec<Vec<f64>>, Vec<f64>) = samples
+        .iter()
+        .filter_map(|s| Some((features.row(s)?, s.target?)))
+        .unzip();
+    if rows.len() < MIN_SAMPLES {
+        return None;
+    }
+    least_squares(&rows, &targets)
+}
+
+struct Models {
+    coefficients: HashMap<ModelKey, Vec<f64>>,
+}
+
+impl Models {
+    fn fit(samples: &[&Sample], subject_count: usize) -> Self {
+        let mut by_subject: Vec<Vec<&Sample>> = vec![Vec::new(); subject_count];
+        for sample in samples {
+            by_subject[sample.subject].push(sample);
+        }
+        let mut coefficients = HashMap::new();
+        for features in [
+            Features::Both,
+            Features::History,
+            Features::Cross,
+            Features::Intercept,
+        ] {
+            if let Some(c) = fit(samples, features) {
+                coefficients.insert((None, features), c);
+            }
+            for (subject, rows) in by_subject.iter().enumerate() {
+                if let Some(c) = fit(rows, features) {
+                    coefficients.insert((Some(subject), features), c);
+                }
+            }
+        }
+        Models { coefficients }
+    }
+
+    // Modèle le plus précis disponible pour la case : celui du cours, sinon celui de la classe
+    fn predict(&self, sample: &Sample) -> Option<(ModelKey, f64)> {
+        Features::candidates(sample).iter().find_map(|features| {
+            let row = features.row(sample)?;
+            [Some(sample.subject), None].into_iter().find_map(|scope| {
+                let key = (scope, *features);
+                let coefficients = self.coefficients.get(&key)?;
+                let value: f64 = row.iter().zip(coefficients).map(|(x, c)| x * c).sum();
+                Some((key, value.clamp(0.0, 1.0)))
+            })
+        })
+    }
+}
+
+fn mean(values: impl Iterator<Item = f64>) -> Option<f64> {
+    let (sum, count) = values.fold((0.0, 0usize), |(s, n), v| (s + v, n + 1));
+    (count > 0).then(|| sum / count as f64)
+}
+
+// Corrélation de Pearson sur les paires connues ; None si trop peu de paires ou sans variance
+fn correlation(pairs: &[(f64, f64)]) -> Option<f64> {
+    if pairs.len() < 3 {
+        return None;
+    }
+    let n = pairs.len() as f64;
+    let (mx, my) = (
+        pairs.iter().map(|p| p.0).sum::<f64>() / n,
+        pairs.iter().map(|p| p.1).sum::<f64>() / n,
+    );
+    let (mut sxy, mut sxx, mut syy) = (0.0, 0.0, 0.0);
+    for (x, y) in pairs {
+        sxy += (x - mx) * (y - my);
+        sxx += (x - mx).powi(2);
+        syy += (y - my).powi(2);
+    }
+    (sxx > 0.0 && syy > 0.0).then(|| sxy / (sxx * syy).sqrt())
+}
+
+// Cotes connues en pourcentage, [élève][cours][période] ; ni le code tricheur ni une cote
+// estimée ne renseignent sur la performance de l'élève
+fn percentages(book: &Gradebook) -> Vec<Vec<Vec<Option<f64>>>> {
+    book.students
+        .iter()
+        .map(|student| {
+            book.subjects
+                .iter()
+                .map(|subject| {
+                    book.periods
+                        .iter()
+                        .map(|period| {
+                            let max = subject.max_for(&period.code);
+                            let key = (student.id, subject.id, period.code.clone());
+                            let value = *book.grades.get(&key)?;
+                            (max > 0.0
+                                && value != GRADE_TRICHEUR_CODE
+                                && !book.estimated.contains(&key))
+                            .then(|| (value / max).clamp(0.0, 1.0))
+                        })
+                        .collect()
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn samples(book: &Gradebook, known: &[Vec<Vec<Option<f64>>>]) -> Vec<Sample> {
+    let subject_count = book.subjects.len();
+    let period_count = book.periods.len();
+
+    // Poids des autres cours : leur corrélation (positive) avec le cours prédit
+    let mut weights = vec![vec![0.0; subject_count]; subject_count];
+    for s in 0..subject_count {
+        for t in (s + 1)..subject_count {
+            let pairs: Vec<(f64, f64)> = known
+                .iter()
+                .flat_map(|grades| (0..period_count).map(move |p| (grades[s][p], grades[t][p])))
+                .filter_map(|(a, b)| Some((a?, b?)))
+                .collect();
+            let weight = correlation(&pairs).unwrap_or(0.0).max(0.0);
+            weights[s][t] = weight;
+            weights[t][s] = weight;
+        }
+    }
+
+    let mut samples = Vec::new();
+    for (student, grades) in known.iter().enumerate() {
+        for subject in 0..subject_count {
+            for period in 0..period_count {
+                if book.subjects[subject].max_for(&book.periods[period].code) <= 0.0 {
+                    continue;
+                }
+                let history = mean(grades[subject][..period].iter().flatten().copied());
+                let others: Vec<(f64, f64)> = (0..subject_count)
+                    .filter(|t| *t != subject)
+                    .filter_map(|t| Some((grades[t][period]?, weights[subject][t])))
+                    .collect();
+                let total_weight: f64 = others.iter().map(|o| o.1).sum();
+                // Aucun cours corrélé : simple moyenne des autres cours
+                let cross = if total_weight > 0.0 {
+                    Some(others.iter().map(|(v, w)| v * w).sum::<f64>() / total_weight)
+                } else {
+                    mean(others.iter().map(|o| o.0))
+                };
+                samples.push(Sample {
+                    student,
+                    subject,
+                    period,
+                    history,
+                    cross,
+                    target: grades[subject][period],
+                });
+            }
+        }
+    }
+    samples
+}
+
+fn within(residuals: &[f64]) -> Option<f64> {
+    (!residuals.is_empty()).then(|| {
+        residuals
+            .iter()
+            .filter(|r| r.abs() <= TOLERANCE + 1e-9)
+            .count() as f64
+            / residuals.len() as f64
+    })
+}
+
+fn round2(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+pub fn predict(book: &Gradebook, params: &PredictionParams) -> PredictionReport {
+    let known = percentages(book);
+    let samples = samples(book, &known);
+    let training: Vec<&Sample> = samples.iter().filter(|s| s.target.is_some()).collect();
+    let models = Models::fit(&training, book.subjects.len());
+
+    // Validation croisée : les cotes d'un groupe d'élèves sont prédites par des modèles
+    // ajustés sans elles
+    let mut residuals: HashMap<ModelKey, Vec<f64>> = HashMap::new();
+    let mut all_residuals = Vec::new();
+    for fold in 0..FOLDS {
+        let (held_out, rest): (Vec<&Sample>, Vec<&Sample>) =
+            training.iter().partition(|s| s.student % FOLDS == fold);
+        let fold_models = Models::fit(&rest, book.subjects.len());
+        for sample in held_out {
+            if let (Some((key, value)), Some(target)) = (fold_models.predict(sample), sample.target)
+            {
+                residuals.entry(key).or_default().push(value - target);
+                all_residuals.push(value - target);
+            }
+        }
+    }
+    let backtest = Backtest {
+        samples: all_residuals.len(),
+        mean_absolute_error: mean(all_residuals.iter().map(|r| r.abs() * 100.0)).map(round2),
+        within_tolerance: within(&all_residuals).map(|w| round2(w * 100.0)),
+        tolerance: TOLERANCE * 100.0,
+    };
+
+    let wanted_periods: Option<Vec<&str>> = params
+        .periods
+        .as_ref()
+        .map(|p| p.iter().map(String::as_str).collect());
+    let mut predictions = Vec::new();
+    for sample in samples.iter().filter(|s| s.target.is_none()) {
+        let student = &book.students[sample.student];
+        let subject = &book.subjects[sample.subject];
+        let period = &book.periods[sample.period].code;
+        // Une cote existante (tricheur compris) ne se prédit pas, un abandon non plus
+        if student.abandoned
+            || book
+                .grades
+                .contains_key(&(student.id, subject.id, period.clone()))
+            || params
+                .student_ids
+                .as_ref()
+                .is_some_and(|ids| !ids.contains(&(student.id as i32)))
+            || params
+                .subject_ids
+                .as_ref()
+                .is_some_and(|ids| !ids.contains(&(subject.id as i32)))
+            || wanted_periods
+                .as_ref()
+                .is_some_and(|codes| !codes.contains(&period.as_str()))
+        {
+            continue;
+        }
+        let Some((key, value)) = models.predict(sample) else {
+            continue;
+        };
+        let share = residuals
+            .get(&key)
+            .filter(|r| r.len() >= MIN_RESIDUALS)
+            .and_then(|r| within(r))
+            .or_else(|| within(&all_residuals))
+            .unwrap_or(0.0);
+        let confidence = (share * 100.0).round() as u8;
+        if confidence < params.confidence_threshold {
+            continue;
+        }
+
+        let mut reasons = Vec::new();
+        if let Some(history) = sample.history.filter(|_| key.1 != Features::Cross) {
+            reasons.push(format!("historique du cours {:.0}%", history * 100.0));
+        }
+        if let Some(cross) = sample.cross.filter(|_| key.1 != Features::History) {
+            reasons.push(format!("autres cours {:.0}%", cross * 100.0));
+        }
+        if reasons.is_empty() {
+            reasons.push("moyenne de la classe".to_string());
+        }
+        let max_grade = subject.max_for(period);
+        predictions.push(PredictionResult {
+            student_id: student.id as i32,
+            student_name: student.name.clone(),
+            subject_id: subject.id as i32,
+            subject_name: subject.name.clone(),
+            period: period.clone(),
+            predicted_grade: round2(value * max_grade),
+            max_grade,
+            confidence,
+            reasoning: format!(
+                "Régression {} : {} ; validation : {:.0}% des cotes à ±{:.0} pts",
+                if key.0.is_some() {
+                    "du cours"
+                } else {
+                    "de la classe"
+                },
+                reasons.join(", "),
+                share * 100.0,
+                TOLERANCE * 100.0
+            ),
+        });
+    }
+
+    PredictionReport {
+        class_id: params.class_id,
+        predictions,
+        backtest,
+    }
+}
+
+#[tauri::command]
+pub async fn predict_missing_grades(
+    pool: tauri::State<'_, DbPool>,
+    params: PredictionParams,
+) -> Result<PredictionReport, String> {
+    let report = pool
+        .run(move |conn| {
+            let book = Gradebook::load(conn, params.class_id as i64)?;
+            Ok::<_, String>(predict(&book, &params))
+        })
+        .await?;
+    info!(
+        "Prédiction des cotes de la classe {} : {} cote(s), erreur moyenne {:?} pts sur {} cote(s) connues",
+        report.class_id,
+        report.predictions.len(),
+        report.backtest.mean_absolute_error,
+        report.backtest.samples
+    );
+    Ok(report)
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ApplyReport {
+    // None quand aucune cote n'a été insérée
+    pub checkpoint_id: Option<i64>,
+    pub applied: usize,
+    // Cotes encodées entre la prédiction et son application : on ne les remplace pas
+    pub skipped: usize,
+}
+
+pub fn apply(
+    conn: &mut Connection,
+    predictions: &[PredictionResult],
+) -> Result<ApplyReport, String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let batch = Batch::start(
+        &tx,
+        "Application des prédictions",
+        &format!("{} cote(s) estimée(s) proposée(s)", predictions.len()),
+    )?;
+    let mut report = ApplyReport {
+        checkpoint_id: None,
+        applied: 0,
+        skipped: 0,
+    };
+    for prediction in predictions {
+        // Le frontend renvoie les prédictions telles quelles : on revérifie le couple élève / cours
+        let same_class: Option<bool> = tx
+            .query_row(
+                "SELECT st.class_id = su.class_id FROM students st, subjects su WHERE st.id = ? AND su.id = ?",
+                params![prediction.student_id, prediction.subject_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?;
+        match same_class {
+            Some(true) => {}
+            Some(false) => {
+                return Err(format!(
+                    "{} ne suit pas le cours {}",
+                    prediction.student_name, prediction.subject_name
+                ))
+            }
+            None => {
+                return Err(format!(
+                    "Élève ou cours introuvable ({}, {})",
+                    prediction.student_name, prediction.subject_name
+                ))
+            }
+        }
+        let exists: bool = tx
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM grades WHERE student_id = ? AND subject_id = ? AND period = ?)",
+                params![prediction.student_id, prediction.subject_id, prediction.period],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        if exists {
+            report.skipped += 1;
+            continue;
+        }
+        // Les triggers de grade_validation refusent une cote hors [0, maximum]
+        tx.execute(
+            "INSERT INTO grades (student_id, subject_id, period, value, is_estimated, is_dirty, last_modified_at)
+             VALUES (?, ?, ?, ?, 1, 1, datetime('now'))",
+            params![
+                prediction.student_id,
+                prediction.subject_id,
+                prediction.period,
+                prediction.predicted_grade
+            ],
+        )
+        .map_err(|e| {
+            format!(
+                "{} ({}, période {}) : {}",
+                prediction.student_name, prediction.subject_name, prediction.period, e
+            )
+        })?;
+        let grade = GradeState {
+            id: tx.last_insert_rowid(),
+            student_id: prediction.student_id as i64,
+            subject_id: prediction.subject_id as i64,
+            period: prediction.period.clone(),
+            value: prediction.predicted_grade,
+            estimated: true,
+        };
+        batch.log(
+            "grade",
+            grade.id,
+            ActionType::Create,
+            None,
+            Some(&grade),
+            &format!(
+                "Cote estimée {} pour la période {}",
+                grade.value, grade.period
+            ),
+        )?;
+        report.applied += 1;
+    }
+    // Rien d'inséré : la transaction abandonnée n'enregistre pas de point de reprise vide
+    if report.applied == 0 {
+        return Ok(report);
+    }
+    report.checkpoint_id = Some(batch.checkpoint_id);
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(report)
+}
+
+#[tauri::command]
+pub async fn apply_predictions(
+    pool: tauri::State<'_, DbPool>,
+    predictions: Vec<PredictionResult>,
+) -> Result<ApplyReport, String> {
+    let report = pool.run(move |conn| apply(conn, &predictions)).await?;
+    info!(
+        "Prédictions appliquées : {} cote(s) estimée(s), {} ignorée(s)",
+        report.applied, report.skipped
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gradebook::{ClassInfo, StudentInfo, SubjectInfo, WeightingMode};
+    use crate::periods::{Period, DEFAULT_PERIODS};
+    use std::collections::HashSet;
+    use std::time::Instant;
+
+    // Générateur pseudo-aléatoire déterministe (LCG) : pas de dépendance pour les tests
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn next(&mut self) -> f64 {
+            self.0 = self
+                .0
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (self.0 >> 11) as f64 / (1u64 << 53) as f64
+        }
+    }
+
+    // Classe synthétique : un niveau par élève, un biais par cours, du bruit ; une part
+    // des cotes manque
+    fn synthetic_class(students: i64, subjects: i64, missing: f64) -> Gradebook {
+        let mut rng = Lcg(42);
+        let periods: Vec<Period> = DEFAULT_PERIODS
+            .iter()
+            .enumerate()
+            .map(|(i, (code, label, semester, is_exam, max))| Period {
+                id: i as i64 + 1,
+                academic_year_id: 1,
+                code: code.to_string(),
+                label: label.to_string(),
+                semester: *semester,
+                is_exam: *is_exam,
+                display_order: i as i64,
+                default_max: *max,
+            })
+            .collect();
+        let subjects: Vec<SubjectInfo> = (1..=subjects)
+            .map(|id| SubjectInfo {
+                id,
+                name: format!("Cours {}", id),
+                code: format!("C{}", id),
+                category: String::new(),
+                domain_id: None,
+                domain: String::new(),
+                domain_order: 0,
+                sub_domain: String::new(),
+                coefficient: None,
+                maxima: periods
+                    .iter()
+                    .map(|p| (p.code.clone(), p.default_max as f64))
+                    .collect(),
+            })
+            .collect();
+        let mut grades = HashMap::new();
+        let biases: Vec<f64> = subjects.iter().map(|_| rng.next() * 0.2 - 0.1).collect();
+        for student in 1..=students {
+            let level = 0.3 + rng.next() * 0.6;
+            for (subject, bias) in subjects.iter().zip(&biases) {
+                for period in &periods {
+                    let noise = rng.next() * 0.16 - 0.08;
+                    if rng.next() < missing {
+                        continue;
+                    }
+                    let max = period.default_max as f64;
+                    let value = ((level + bias + noise).clamp(0.0, 1.0) * max * 2.0).round() / 2.0;
+                    grades.insert((student, subject.id, period.code.clone()), value);
+                }
+            }
+        }
+        Gradebook {
+            class: ClassInfo {
+                name: "Classe".to_string(),
+                level: "3ème".to_string(),
+                academic_year_id: 1,
+            },
+            periods,
+            subjects,
+            students: (1..=students)
+                .map(|id| StudentInfo {
+                    id,
+                    name: format!("Élève {:02}", id),
+                    gender: String::new(),
+                    abandoned: false,
+                })
+                .collect(),
+            grades,
+            estimated: HashSet::new(),
+            repechages: HashMap::new(),
+            weighting: WeightingMode::Points,
+        }
+    }
+
+    fn params(
+        student_ids: Option<Vec<i32>>,
+        subject_ids: Option<Vec<i32>>,
+        periods: Option<Vec<&str>>,
+    ) -> PredictionParams {
+        PredictionParams {
+            class_id: 1,
+            student_ids,
+            subject_ids,
+            periods: periods.map(|p| p.iter().map(|c| c.to_string()).collect()),
+            confidence_threshold: 0,
+        }
+    }
+
+    #[test]
+    fn predit_seulement_les_cotes_manquantes() {
+        let mut book = synthetic_class(20, 4, 0.0);
+        book.grades.remove(&(3, 2, "P3".to_string()));
+        // Tricheur : la cote existe, elle ne se prédit pas
+        book.grades
+            .insert((4, 1, "P2".to_string()), GRADE_TRICHEUR_CODE);
+        // Les abandons ne reçoivent pas de prédiction
+        book.grades.remove(&(5, 1, "P1".to_string()));
+        book.students[4].abandoned = true;
+
+        let report = predict(&book, &params(None, None, None));
+        let cells: Vec<(i32, i32, &str)> = report
+            .predictions
+            .iter()
+            .map(|p| (p.student_id, p.subject_id, p.period.as_str()))
+            .collect();
+        assert_eq!(cells, [(3, 2, "P3")]);
+        let prediction = &report.predictions[0];
+        assert_eq!(prediction.max_grade, 10.0);
+        assert!(prediction.predicted_grade >= 0.0 && prediction.predicted_grade <= 10.0);
+    }
+
+    #[test]
+    fn prediction_proche_du_niveau_de_l_eleve() {
+        let mut book = synthetic_class(40, 6, 0.0);
+        let actual = book.grades.remove(&(7, 3, "EXAM2".to_string())).unwrap();
+        let report = predict(&book, &params(None, None, None));
+        assert_eq!(report.predictions.len(), 1);
+        // Bruit de ±8 % et demi-points : la régression retrouve la cote à 3 points sur 20
+        assert!((report.predictions[0].predicted_grade - actual).abs() <= 3.0);
+        let within = report.backtest.within_tolerance.unwrap();
+        assert!(within >= 80.0, "{}", within);
+        assert!(report.predictions[0].confidence >= 80);
+    }
+
+    #[test]
+    fn filtres_sans_effet_sur_le_modele() {
+        let book = synthetic_class(30, 5, 0.1);
+        let all = predict(&book, &params(None, None, None));
+        let filtered = predict(
+            &book,
+            &params(
+                Some(vec![2, 9, 17]),
+                Some(vec![1, 4]),
+                Some(vec!["P2", "EXAM1"]),
+            ),
+        );
+        assert!(!filtered.predictions.is_empty());
+        assert_eq!(filtered.backtest, all.backtest);
+        for p in &filtered.predictions {
+            assert!([2, 9, 17].contains(&p.student_id));
+            assert!([1, 4].contains(&p.subject_id));
+            assert!(["P2", "EXAM1"].contains(&p.period.as_str()));
+            let same = all
+                .predictions
+                .iter()
+                .find(|q| {
+                    (q.student_id, q.subject_id, &q.period)
+                        == (p.student_id, p.subject_id, &p.period)
+                })
+                .unwrap();
+            assert_eq!(
+                (same.predicted_grade, same.confidence),
+                (p.predicted_grade, p.confidence)
+            );
+        }
+    }
+
+    #[test]
+    fn classe_vide_sans_prediction() {
+        let report = predict(&synthetic_class(0, 3, 0.0), &params(None, None, None));
+        assert!(report.predictions.is_empty());
+        assert_eq!(report.backtest.samples, 0);
+        assert_eq!(report.backtest.mean_absolute_error, None);
+    }
+
+    // cargo test --release prediction::tests::benchmark -- --ignored --nocapture
+    #[test]
+    #[ignore]
+    fn benchmark_grande_classe() {
+        let book = synthetic_class(60, 15, 0.05);
+        let start = Instant::now();
+        let report = predict(&book, &params(None, None, None));
+        let elapsed = start.elapsed();
+        println!(
+            "60 élèves x 15 cours x {} périodes : {} prédiction(s) en {:?}, erreur moyenne {:?} pts",
+            book.periods.len(),
+            report.predictions.len(),
+            elapsed,
+            report.backtest.mean_absolute_error
+        );
+        assert!(elapsed.as_secs() < 2);
+    }
+
+    fn prediction(student_id: i32, subject_id: i32, period: &str, grade: f64) -> PredictionResult {
+        PredictionResult {
+            student_id,
+            student_name: format!("Élève {}", student_id),
+            subject_id,
+            subject_name: "Maths".to_string(),
+            period: period.to_string(),
+            predicted_grade: grade,
+            max_grade: 10.0,
+            confidence: 90,
+            reasoning: String::new(),
+        }
+    }
+
+    fn count(conn: &Connection, sql: &str) -> i64 {
+        conn.query_row(sql, [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn application_puis_annulation() {
+        let db = crate::db::test_support::TestDb::open();
+        let mut conn = db.conn();
+        conn.execute_batch(
+            "INSERT INTO academic_years (id, name, start_date, end_date, is_active) VALUES (1, '2024-2025', '2024-09-01', '2025-07-01', 1);
+             INSERT INTO classes (id, name, level, option, section, academic_year_id) VALUES
+               (1, '2ème ETRO', '2ème', 'ELECTRONIQUE', 'A', 1), (2, '2ème MECA', '2ème', 'MECANIQUE', 'A', 1);
+             INSERT INTO subjects (id, name, code, class_id) VALUES (1, 'Maths', 'MATH', 1), (2, 'Maths', 'MATH', 2);
+             INSERT INTO students (id, first_name, last_name, gender, class_id) VALUES
+               (1, 'Jean', 'Kabila', 'M', 1), (2, 'Marie', 'Mbuyi', 'F', 1), (3, 'Paul', 'Ilunga', 'M', 2);
+             INSERT INTO grades (student_id, subject_id, period, value) VALUES (1, 1, 'P1', 8), (2, 1, 'P1', 5);",
+        )
+        .unwrap();
+
+        // Élève d'une autre classe, cours inconnu : rien n'est inséré
+        assert!(apply(
+            &mut conn,
+            &[prediction(1, 1, "P2", 7.0), prediction(3, 1, "P2", 6.0)]
+        )
+        .is_err());
+        assert!(apply(&mut conn, &[prediction(1, 9, "P2", 7.0)]).is_err());
+        // Hors limites : tout le lot est refusé
+        assert!(apply(
+            &mut conn,
+            &[prediction(1, 1, "P3", 7.0), prediction(2, 1, "P3", 12.0)]
+        )
+        .is_err());
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM grades"), 2);
+
+        // La cote encodée entre-temps (élève 2, P1) n'est pas remplacée
+        let report = apply(
+            &mut conn,
+            &[
+                prediction(1, 1, "P2", 7.5),
+                prediction(2, 1, "P1", 6.0),
+                prediction(2, 1, "P2", 4.0),
+            ],
+        )
+        .unwrap();
+        assert_eq!((report.applied, report.skipped), (2, 1));
+        assert_eq!(
+            count(&conn, "SELECT COUNT(*) FROM grades WHERE is_estimated = 1"),
+            2
+        );
+        assert_eq!(
+            count(
+                &conn,
+                "SELECT CAST(value AS INTEGER) FROM grades WHERE student_id = 2 AND period = 'P1'"
+            ),
+            5
+        );
+
+        // Rien à insérer : pas de point de reprise
+        let report_vide = apply(&mut conn, &[prediction(1, 1, "P1", 6.0)]).unwrap();
+        assert_eq!(report_vide.checkpoint_id, None);
+
+        let undo =
+            crate::history::undo_checkpoint(&mut conn, report.checkpoint_id.unwrap()).unwrap();
+        assert_eq!(undo.restored, 2);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM grades"), 2);
+        assert_eq!(
+            count(&conn, "SELECT COUNT(*) FROM grades WHERE is_estimated = 1"),
+            0
+        );
+
+        // L'annulation est elle-même annulable
+        crate::history::undo_checkpoint(&mut conn, undo.checkpoint_id).unwrap();
+        assert_eq!(
+            count(&conn, "SELECT COUNT(*) FROM grades WHERE is_estimated = 1"),
+            2
+        );
+    }
+
+    // Règles d'avant le modèle (une requête par élève, cours et période) : cellules à
+    // prédire et maximum de chacune. Seule différence voulue : les abandons sont exclus.
+    fn cellules_par_requetes(
+        conn: &Connection,
+        params: &PredictionParams,
+    ) -> Vec<(i32, i32, String, String, f64)> {
+        let periods: Vec<String> = match &params.periods {
+            Some(periods) => periods.clone(),
+            None => crate::periods::periods_for_class(conn, params.class_id as i64)
+                .unwrap()
+                .into_iter()
+                .map(|p| p.code)
+                .collect(),
+        };
+        let students: Vec<i32> = conn
+            .prepare("SELECT id FROM students WHERE class_id = ? AND COALESCE(is_abandoned, 0) = 0")
+            .unwrap()
+            .query_map(params![params.class_id], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let mut cells = Vec::new();
+        for student_id in students {
+            if params
+                .student_ids
+                .as_ref()
+                .is_some_and(|ids| !ids.contains(&student_id))
+            {
+                continue;
+            }
+            let subjects: Vec<(i32, String)> = conn
+                .prepare("SELECT id, name FROM subjects WHERE class_id = ?")
+                .unwrap()
+                .query_map(params![params.class_id], |row| {
+                    Ok((row.get(0)?, row.get(1)?))
+                })
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap();
+            for (subject_id, subject_name) in subjects {
+                if params
+                    .subject_ids
+                    .as_ref()
+                    .is_some_and(|ids| !ids.contains(&subject_id))
+                {
+                    continue;
+                }
+                for period in &periods {
+                    let existing: Option<f64> = conn
+                        .query_row(
+                            "SELECT value FROM grades WHERE student_id = ? AND subject_id = ? AND period = ?",
+                            params![student_id, subject_id, period],
+                            |row| row.get(0),
+                        )
+                        .ok();
+                    if existing.is_some() {
+                        continue;
+                    }
+                    let max = crate::periods::max_points(conn, subject_id as i64, period).unwrap();
+                    cells.push((
+                        student_id,
+                        subject_id,
+                        subject_name.clone(),
+                        period.clone(),
+                        max,
+                    ));
+                }
+            }
+        }
+        cells.sort_by(|a, b| (a.0, a.1, &a.3).cmp(&(b.0, b.1, &b.3)));
+        cells
+    }
+
+    #[test]
+    fn memes_cellules_que_les_regles_par_requetes() {
+        let db = crate::db::test_support::TestDb::open();
+        let conn = db.conn();
+        conn.execute_batch(
+            "INSERT INTO academic_years (id, name, start_date, end_date, is_active) VALUES (1, '2024-2025', '2024-09-01', '2025-07-01', 1);
+             INSERT INTO classes (id, name, level, option, section, academic_year_id) VALUES (1, '2ème ETRO', '2ème', 'ELECTRONIQUE', 'A', 1);
+             INSERT INTO subjects (id, name, code, class_id) VALUES (1, 'Maths', 'MATH', 1), (2, 'Français', 'FR', 1), (3, 'Physique', 'PHY', 1);
+             UPDATE subject_period_max SET max_points = max_points * 2 WHERE subject_id = 3 AND period_code IN ('P1', 'EXAM1');
+             INSERT INTO students (id, first_name, last_name, gender, class_id) VALUES
+               (1, 'Jean', 'Kabila', 'M', 1), (2, 'Marie', 'Mbuyi', 'F', 1), (3, 'Paul', 'Ilunga', 'M', 1),
+               (4, 'Ruth', 'Kasongo', 'F', 1), (5, 'Eric', 'Tshibangu', 'M', 1), (6, 'Anne', 'Mutombo', 'F', 1);
+             UPDATE students SET is_abandoned = 1 WHERE id = 6;
+             -- Une cote sur sept manque ; l'élève 2 a une cote de tricheur en P2
+             INSERT INTO grades (student_id, subject_id, period, value)
+               SELECT st.id, su.id, p.code, p.default_max * (0.4 + 0.1 * ((st.id + su.id) % 5))
+               FROM students st, subjects su, periods p
+               WHERE p.academic_year_id = 1 AND p.code != 'EXAM1' AND (st.id * 7 + su.id * 3 + p.display_order) % 7 != 0;
+             UPDATE grades SET value = -1 WHERE student_id = 2 AND subject_id = 1 AND period = 'P2';",
+        )
+        .unwrap();
+        let book = Gradebook::load(&conn, 1).unwrap();
+
+        let cases = [
+            params(None, None, None),
+            params(Some(vec![1, 2, 6]), None, None),
+            params(None, Some(vec![3]), Some(vec!["P1", "EXAM1"])),
+        ];
+        for case in &cases {
+            let expected = cellules_par_requetes(&conn, case);
+            let mut cells: Vec<(i32, i32, String, String, f64)> = predict(&book, case)
+                .predictions
+                .into_iter()
+                .map(|p| {
+                    (
+                        p.student_id,
+                        p.subject_id,
+                        p.subject_name,
+                        p.period,
+                        p.max_grade,
+                    )
+                })
+                .collect();
+            cells.sort_by(|a, b| (a.0, a.1, &a.3).cmp(&(b.0, b.1, &b.3)));
+            assert!(!expected.is_empty());
+            assert_eq!(cells, expected);
+        }
+    }
+}
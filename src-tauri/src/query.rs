@@ -0,0 +1,192 @@
+// Accès SQL générique pour le frontend quand la base est chiffrée :
+// le plugin sql ne sait pas fournir la clé SQLCipher, les requêtes passent donc par le pool.
+//
+// Frontière de confiance assumée : le webview a déjà, sans chiffrement, un accès complet aux
+// données par le plugin sql, et ces commandes lui rendent le même accès. Elles ne donnent
+// rien de plus : db_select n'accepte qu'une requête en lecture seule, db_execute qu'une seule
+// instruction INSERT, UPDATE, DELETE ou REPLACE. Schéma, PRAGMA (dont key / rekey), ATTACH
+// et transactions restent réservés au code Rust.
+
+use crate::db::{self, DbPool};
+use rusqlite::types::Value;
+use rusqlite::Connection;
+use serde::Serialize;
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecuteResult {
+    pub rows_affected: usize,
+    pub last_insert_id: i64,
+}
+
+fn to_sql_value(value: &serde_json::Value) -> Value {
+    match value {
+        serde_json::Value::Null => Value::Null,
+        serde_json::Value::Bool(b) => Value::Integer(*b as i64),
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(i) => Value::Integer(i),
+            None => Value::Real(n.as_f64().unwrap_or(0.0)),
+        },
+        serde_json::Value::String(s) => Value::Text(s.clone()),
+        other => Value::Text(other.to_string()),
+    }
+}
+
+// Premier mot autorisé pour db_execute
+const WRITE_KEYWORDS: [&str; 4] = ["INSERT", "UPDATE", "DELETE", "REPLACE"];
+
+// prepare n'exécuterait que la première instruction et ignorerait silencieusement la suite
+fn check_single(conn: &Connection, query: &str) -> Result<(), String> {
+    if rusqlite::Batch::new(conn, query).count() > 1 {
+        return Err("Une seule instruction par requête".to_string());
+    }
+    Ok(())
+}
+
+fn check_write(query: &str) -> Result<(), String> {
+    let keyword: String = query
+        .trim_start()
+        .chars()
+        .take_while(|c| c.is_ascii_alphabetic())
+        .collect::<String>()
+        .to_ascii_uppercase();
+    if WRITE_KEYWORDS.contains(&keyword.as_str()) {
+        Ok(())
+    } else {
+        Err(format!("Instruction non autorisée : {}", keyword))
+    }
+}
+
+// Même format de résultat que le plugin sql : un objet par ligne, indexé par nom de colonne
+pub fn select_rows(
+    conn: &Connection,
+    query: &str,
+    values: Vec<serde_json::Value>,
+) -> Result<Vec<serde_json::Map<String, serde_json::Value>>, String> {
+    let values: Vec<Value> = values.iter().map(to_sql_value).collect();
+    check_single(conn, query)?;
+    let mut stmt = conn.prepare_cached(query).map_err(|e| e.to_string())?;
+    if !stmt.readonly() {
+        return Err("Seules les requêtes en lecture sont acceptées".to_string());
+    }
+    let columns: Vec<String> = stmt.column_names().iter().map(|c| c.to_string()).collect();
+    stmt.query_map(rusqlite::params_from_iter(values), |row| {
+        let mut map = serde_json::Map::new();
+        for (i, name) in columns.iter().enumerate() {
+            map.insert(name.clone(), db::value_to_json(row.get_ref(i)?));
+        }
+        Ok(map)
+    })
+    .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+    .map_err(|e| e.to_string())
+}
+
+pub fn execute_statement(
+    conn: &Connection,
+    query: &str,
+    values: Vec<serde_json::Value>,
+) -> Result<ExecuteResult, String> {
+    check_write(query)?;
+    check_single(conn, query)?;
+    let values: Vec<Value> = values.iter().map(to_sql_value).collect();
+    let rows_affected = conn
+        .prepare_cached(query)
+        .and_then(|mut stmt| stmt.execute(rusqlite::params_from_iter(values)))
+        .map_err(|e| e.to_string())?;
+    Ok(ExecuteResult {
+        rows_affected,
+        last_insert_id: conn.last_insert_rowid(),
+    })
+}
+
+#[tauri::command]
+pub async fn db_select(
+    pool: tauri::State<'_, DbPool>,
+    query: String,
+    values: Option<Vec<serde_json::Value>>,
+) -> Result<Vec<serde_json::Map<String, serde_json::Value>>, String> {
+    pool.run(move |conn| select_rows(conn, &query, values.unwrap_or_default()))
+        .await
+}
+
+#[tauri::command]
+pub async fn db_execute(
+    pool: tauri::State<'_, DbPool>,
+    query: String,
+    values: Option<Vec<serde_json::Value>>,
+) -> Result<ExecuteResult, String> {
+    pool.run(move |conn| execute_statement(conn, &query, values.unwrap_or_default()))
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::TestDb;
+    use serde_json::json;
+
+    #[test]
+    fn lecture_seule_pour_db_select() {
+        let db = TestDb::open();
+        let conn = db.conn();
+        let rows = select_rows(&conn, "SELECT ? AS a, ? AS b", vec![json!(1), json!("x")]).unwrap();
+        assert_eq!(rows[0]["a"], json!(1));
+        assert_eq!(rows[0]["b"], json!("x"));
+        assert!(select_rows(&conn, "DELETE FROM settings", Vec::new()).is_err());
+        assert!(select_rows(&conn, "PRAGMA user_version = 3", Vec::new()).is_err());
+        assert!(select_rows(&conn, "SELECT 1; DELETE FROM settings", Vec::new()).is_err());
+        assert_eq!(
+            select_rows(&conn, "SELECT 1 AS a;", Vec::new())
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn ecritures_de_donnees_seulement_pour_db_execute() {
+        let db = TestDb::open();
+        let conn = db.conn();
+        let result = execute_statement(
+            &conn,
+            "  insert INTO settings (key, value) VALUES (?, ?)",
+            vec![json!("school_name"), json!("Institut Umoja")],
+        )
+        .unwrap();
+        assert_eq!(result.rows_affected, 1);
+        assert!(result.last_insert_id > 0);
+        let updated = execute_statement(
+            &conn,
+            "UPDATE settings SET value = ? WHERE key = 'school_name'",
+            vec![json!("Lycée Umoja")],
+        )
+        .unwrap();
+        assert_eq!(updated.rows_affected, 1);
+
+        let attach = format!("ATTACH DATABASE '{}' AS x", db.dir.join("x.db").display());
+        for query in [
+            "PRAGMA rekey = 'x'",
+            attach.as_str(),
+            "DROP TABLE grades",
+            "CREATE TABLE t (a)",
+            "BEGIN",
+            "SELECT 1",
+            "DELETE FROM notes; DROP TABLE grades",
+        ] {
+            assert!(
+                execute_statement(&conn, query, Vec::new()).is_err(),
+                "{}",
+                query
+            );
+        }
+        assert!(!db.dir.join("x.db").exists());
+        let tables: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE name IN ('grades', 't')",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(tables, 1);
+    }
+}
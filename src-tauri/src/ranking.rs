@@ -0,0 +1,229 @@
+// Classement d'une classe selon la convention de l'école : les ex aequo partagent la
+// place (1, 1, 3…), les élèves en abandon ne sont pas classés et le sort des élèves
+// aux cotes incomplètes dépend du paramètre ranking_incomplete_policy.
+
+use crate::bulletin::{self, BulletinColumn, Score};
+use crate::db::DbPool;
+use crate::gradebook::Gradebook;
+use log::info;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+const EPSILON: f64 = 1e-9;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IncompletePolicy {
+    // Non classé, comme la catégorie « non classés » du palmarès
+    #[default]
+    Exclude,
+    // Classé avec les autres sur le pourcentage des seules cotes encodées
+    Partial,
+    // Classé après tous les élèves complets, sur les cotes encodées
+    RankLast,
+}
+
+impl IncompletePolicy {
+    pub fn from_settings(conn: &Connection) -> Self {
+        conn.query_row(
+            "SELECT value FROM settings WHERE key = 'ranking_incomplete_policy'",
+            params![],
+            |row| row.get::<_, String>(0),
+        )
+        .ok()
+        .and_then(|v| serde_json::from_value(serde_json::Value::String(v)).ok())
+        .unwrap_or_default()
+    }
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Rank {
+    pub place: usize,
+    pub out_of: usize,
+    pub ex_aequo: bool,
+}
+
+// Élève à classer dans une colonne du bulletin
+pub struct Candidate<'a> {
+    pub abandoned: bool,
+    pub score: &'a Score,
+}
+
+// (groupe, pourcentage) : le groupe 1 passe après tous les élèves du groupe 0
+fn ranking_key(candidate: &Candidate, policy: IncompletePolicy) -> Option<(u8, f64)> {
+    let score = candidate.score;
+    let points = score.points?;
+    if candidate.abandoned {
+        return None;
+    }
+    if !score.missing {
+        return (score.max > 0.0).then(|| (0, points * 100.0 / score.max));
+    }
+    let tier = match policy {
+        IncompletePolicy::Exclude => return None,
+        IncompletePolicy::Partial => 0,
+        IncompletePolicy::RankLast => 1,
+    };
+    (score.graded_max > 0.0).then(|| (tier, points * 100.0 / score.graded_max))
+}
+
+fn ahead(other: (u8, f64), own: (u8, f64)) -> bool {
+    other.0 < own.0 || (other.0 == own.0 && other.1 > own.1 + EPSILON)
+}
+
+fn tied(other: (u8, f64), own: (u8, f64)) -> bool {
+    other.0 == own.0 && (other.1 - own.1).abs() <= EPSILON
+}
+
+// Places d'une colonne, dans l'ordre des candidats ; « sur N » compte les élèves classés
+pub fn rank_candidates(candidates: &[Candidate], policy: IncompletePolicy) -> Vec<Option<Rank>> {
+    let keys: Vec<Option<(u8, f64)>> = candidates.iter().map(|c| ranking_key(c, policy)).collect();
+    let out_of = keys.iter().flatten().count();
+    keys.iter()
+        .enumerate()
+        .map(|(index, key)| {
+            let own = (*key)?;
+            let others = keys
+                .iter()
+                .enumerate()
+                .filter(|(other, _)| *other != index)
+                .filter_map(|(_, k)| *k);
+            Some(Rank {
+                place: 1 + others.clone().filter(|k| ahead(*k, own)).count(),
+                out_of,
+                ex_aequo: others.clone().any(|k| tied(k, own)),
+            })
+        })
+        .collect()
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RankStatus {
+    Classe,
+    Incomplet,
+    SansCote,
+    Abandon,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RankingEntry {
+    pub student_id: i64,
+    pub student_name: String,
+    pub status: RankStatus,
+    pub points: Option<f64>,
+    pub max: f64,
+    // Pourcentage ayant servi au classement (cotes encodées seulement pour un incomplet)
+    pub percentage: Option<f64>,
+    pub rank: Option<Rank>,
+    pub missing_subjects: Vec<String>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassRanking {
+    pub class_id: i64,
+    pub class_name: String,
+    pub column: BulletinColumn,
+    pub policy: IncompletePolicy,
+    // Élèves hors abandon
+    pub class_size: usize,
+    pub ranked: usize,
+    // Ordre du palmarès : classés, puis non classés, puis abandons
+    pub entries: Vec<RankingEntry>,
+}
+
+pub fn rank_class(
+    book: &Gradebook,
+    class_id: i64,
+    period: &str,
+    policy: IncompletePolicy,
+) -> Result<ClassRanking, String> {
+    let result = bulletin::compute(book, class_id, policy);
+    let index = bulletin::column_index(&result.columns, period)?;
+
+    let mut entries: Vec<RankingEntry> = result
+        .students
+        .iter()
+        .map(|student| {
+            let score = &student.totals[index];
+            let status = if student.abandoned {
+                RankStatus::Abandon
+            } else if score.points.is_none() {
+                RankStatus::SansCote
+            } else if score.missing {
+                RankStatus::Incomplet
+            } else {
+                RankStatus::Classe
+            };
+            let rank = student.ranks[index];
+            let percentage = match rank {
+                Some(_) if score.missing => {
+                    Some(score.points.unwrap_or(0.0) * 100.0 / score.graded_max)
+                }
+                _ => score.percentage,
+            };
+            RankingEntry {
+                student_id: student.student_id,
+                student_name: student.student_name.clone(),
+                status,
+                points: score.points,
+                max: score.max,
+                percentage: percentage.map(|p| (p * 100.0).round() / 100.0),
+                rank,
+                missing_subjects: student
+                    .subjects
+                    .iter()
+                    .filter(|s| s.scores[index].missing)
+                    .map(|s| {
+                        if s.code.is_empty() {
+                            s.name.clone()
+                        } else {
+                            s.code.clone()
+                        }
+                    })
+                    .collect(),
+            }
+        })
+        .collect();
+
+    // Tri stable : l'ordre alphabétique du chargement départage les ex aequo
+    entries.sort_by_key(|entry| match (entry.status, entry.rank) {
+        (_, Some(rank)) => (0, rank.place),
+        (RankStatus::Abandon, None) => (2, 0),
+        (_, None) => (1, 0),
+    });
+
+    Ok(ClassRanking {
+        class_id,
+        class_name: result.class_name,
+        column: result.columns[index].clone(),
+        policy,
+        class_size: result.students.iter().filter(|s| !s.abandoned).count(),
+        ranked: entries.iter().filter(|e| e.rank.is_some()).count(),
+        entries,
+    })
+}
+
+#[tauri::command]
+pub async fn class_ranking(
+    pool: tauri::State<'_, DbPool>,
+    class_id: i64,
+    period: String,
+    policy: Option<IncompletePolicy>,
+) -> Result<ClassRanking, String> {
+    let result = pool
+        .run(move |conn| {
+            let book = Gradebook::load(conn, class_id)?;
+            let policy = policy.unwrap_or_else(|| IncompletePolicy::from_settings(conn));
+            rank_class(&book, class_id, &period, policy)
+        })
+        .await?;
+    info!(
+        "Classement de la classe {} ({}) : {} classé(s) sur {}",
+        class_id, result.column.key, result.ranked, result.class_size
+    );
+    Ok(result)
+}
@@ -0,0 +1,435 @@
+// Repêchages générés à partir de la délibération : une ligne par cours encore en échec
+// après rachat pour chaque élève admis à la 2ème session, puis saisie des résultats de
+// cette session et délibération finale. Chaque ligne touchée passe par operation_log.
+
+use crate::db::DbPool;
+use crate::deliberation::{self, ClassDeliberation, DelibMode, SubjectStatus, Verdict};
+use crate::gradebook::Gradebook;
+use crate::history::{self, ActionType};
+use log::info;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+
+// État d'une ligne de repechages tel qu'il est journalisé
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct RepechageRow {
+    pub id: i64,
+    pub student_id: i64,
+    pub subject_id: i64,
+    pub value: f64,
+    pub percentage: f64,
+    pub voir_bureau: i64,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RepechageSubject {
+    pub subject_id: i64,
+    pub code: String,
+    pub name: String,
+    pub voir_bureau: bool,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RepechageStudent {
+    pub student_id: i64,
+    pub student_name: String,
+    pub subjects: Vec<RepechageSubject>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RepechageGeneration {
+    pub created: usize,
+    // Lignes déjà présentes (résultat ou « voir bureau » conservés)
+    pub kept: usize,
+    // Lignes vides qui ne correspondent plus à un échec
+    pub removed: usize,
+    pub students: Vec<RepechageStudent>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RepechageResult {
+    pub student_id: i64,
+    pub subject_id: i64,
+    pub percentage: f64,
+}
+
+fn load_class_rows(
+    conn: &Connection,
+    class_id: i64,
+) -> Result<HashMap<(i64, i64), RepechageRow>, String> {
+    let mut stmt = conn
+        .prepare_cached(
+            "SELECT r.id, r.student_id, r.subject_id, r.value, r.percentage, r.voir_bureau
+             FROM repechages r JOIN students st ON r.student_id = st.id
+             WHERE st.class_id = ?",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![class_id], read_row)
+        .map_err(|e| e.to_string())?;
+    let mut map = HashMap::new();
+    for row in rows {
+        let row = row.map_err(|e| e.to_string())?;
+        map.insert((row.student_id, row.subject_id), row);
+    }
+    Ok(map)
+}
+
+fn read_row(row: &rusqlite::Row) -> rusqlite::Result<RepechageRow> {
+    Ok(RepechageRow {
+        id: row.get(0)?,
+        student_id: row.get(1)?,
+        subject_id: row.get(2)?,
+        value: row.get(3)?,
+        percentage: row.get(4)?,
+        voir_bureau: row.get(5)?,
+    })
+}
+
+fn find_row(
+    conn: &Connection,
+    student_id: i64,
+    subject_id: i64,
+) -> Result<Option<RepechageRow>, String> {
+    conn.query_row(
+        "SELECT id, student_id, subject_id, value, percentage, voir_bureau
+         FROM repechages WHERE student_id = ? AND subject_id = ?",
+        params![student_id, subject_id],
+        read_row,
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+pub fn generate(conn: &mut Connection, class_id: i64) -> Result<RepechageGeneration, String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    // Même délibération que l'écran : une conduite bloquante exclut la 2ème session
+    let results = deliberation::deliberate(&tx, class_id, DelibMode::FirstSession)?.students;
+    let mut existing = load_class_rows(&tx, class_id)?;
+
+    let mut report = RepechageGeneration {
+        created: 0,
+        kept: 0,
+        removed: 0,
+        students: Vec::new(),
+    };
+    let mut wanted: BTreeSet<(i64, i64)> = BTreeSet::new();
+
+    for result in results
+        .iter()
+        .filter(|r| r.verdict == Verdict::DeuxiemeSession)
+    {
+        let mut subjects = Vec::new();
+        for subject in result
+            .subjects
+            .iter()
+            .filter(|s| s.status == SubjectStatus::Echec)
+        {
+            let key = (result.student_id, subject.subject_id);
+            wanted.insert(key);
+            let voir_bureau = match existing.get(&key) {
+                Some(row) => {
+                    report.kept += 1;
+                    row.voir_bureau != 0
+                }
+                None => {
+                    tx.execute(
+                        "INSERT INTO repechages (student_id, subject_id, value, percentage, voir_bureau, is_dirty, last_modified_at)
+                         VALUES (?, ?, 0, 0, 0, 1, (datetime('now')))",
+                        params![result.student_id, subject.subject_id],
+                    )
+                    .map_err(|e| e.to_string())?;
+                    let row = RepechageRow {
+                        id: tx.last_insert_rowid(),
+                        student_id: result.student_id,
+                        subject_id: subject.subject_id,
+                        value: 0.0,
+                        percentage: 0.0,
+                        voir_bureau: 0,
+                    };
+                    history::log_operation(
+                        &tx,
+                        "repechage",
+                        row.id,
+                        ActionType::Create,
+                        None,
+                        Some(&row),
+                        &format!(
+                            "Repêchage généré : {} en {}",
+                            result.student_name,
+                            if subject.code.is_empty() {
+                                &subject.name
+                            } else {
+                                &subject.code
+                            }
+                        ),
+                    )?;
+                    report.created += 1;
+                    false
+                }
+            };
+            subjects.push(RepechageSubject {
+                subject_id: subject.subject_id,
+                code: subject.code.clone(),
+                name: subject.name.clone(),
+                voir_bureau,
+            });
+        }
+        report.students.push(RepechageStudent {
+            student_id: result.student_id,
+            student_name: result.student_name.clone(),
+            subjects,
+        });
+    }
+
+    // Une ligne vide (ni résultat ni « voir bureau ») qui ne correspond plus à un échec
+    // vient d'une génération précédente : les cotes ont été corrigées depuis
+    existing
+        .retain(|key, row| !wanted.contains(key) && row.percentage == 0.0 && row.voir_bureau == 0);
+    for row in existing.values() {
+        tx.execute("DELETE FROM repechages WHERE id = ?", params![row.id])
+            .map_err(|e| e.to_string())?;
+        history::log_operation(
+            &tx,
+            "repechage",
+            row.id,
+            ActionType::Delete,
+            Some(row),
+            None,
+            "Repêchage retiré : échec levé",
+        )?;
+        report.removed += 1;
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(report)
+}
+
+// Enregistre les résultats de la 2ème session puis rejoue la délibération finale
+pub fn save_results(
+    conn: &mut Connection,
+    class_id: i64,
+    results: &[RepechageResult],
+) -> Result<ClassDeliberation, String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let book = Gradebook::load(&tx, class_id)?;
+
+    for result in results {
+        if !(0.0..=100.0).contains(&result.percentage) {
+            return Err("Le pourcentage doit être compris entre 0 et 100".to_string());
+        }
+        let student = book
+            .students
+            .iter()
+            .find(|s| s.id == result.student_id)
+            .ok_or_else(|| format!("L'élève {} n'est pas dans cette classe", result.student_id))?;
+        let subject = book
+            .subjects
+            .iter()
+            .find(|s| s.id == result.subject_id)
+            .ok_or_else(|| format!("Le cours {} n'est pas dans cette classe", result.subject_id))?;
+        // Même conversion que repechageService.ts : pourcentage du maximum annuel
+        let annual_max: f64 = subject.maxima.values().sum();
+        let value = result.percentage * annual_max / 100.0;
+
+        let previous = find_row(&tx, result.student_id, result.subject_id)?;
+        tx.execute(
+            "INSERT INTO repechages (student_id, subject_id, value, percentage, voir_bureau, is_dirty, last_modified_at)
+             VALUES (?1, ?2, ?3, ?4, 0, 1, (datetime('now')))
+             ON CONFLICT(student_id, subject_id) DO UPDATE SET
+                value = ?3, percentage = ?4, is_dirty = 1, last_modified_at = (datetime('now'))",
+            params![result.student_id, result.subject_id, value, result.percentage],
+        )
+        .map_err(|e| e.to_string())?;
+        let current = find_row(&tx, result.student_id, result.subject_id)?
+            .ok_or_else(|| "Repêchage introuvable après enregistrement".to_string())?;
+        if previous.as_ref() == Some(&current) {
+            continue;
+        }
+        history::log_operation(
+            &tx,
+            "repechage",
+            current.id,
+            if previous.is_some() {
+                ActionType::Update
+            } else {
+                ActionType::Create
+            },
+            previous.as_ref(),
+            Some(&current),
+            &format!(
+                "Résultat de 2ème session : {} en {}, {}%",
+                student.name,
+                if subject.code.is_empty() {
+                    &subject.name
+                } else {
+                    &subject.code
+                },
+                result.percentage
+            ),
+        )?;
+    }
+
+    let deliberation = deliberation::deliberate(&tx, class_id, DelibMode::Final)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(deliberation)
+}
+
+#[tauri::command]
+pub async fn repechages_generate(
+    pool: tauri::State<'_, DbPool>,
+    class_id: i64,
+) -> Result<RepechageGeneration, String> {
+    let report = pool.run(move |conn| generate(conn, class_id)).await?;
+    info!(
+        "Repêchages de la classe {} : {} créé(s), {} conservé(s), {} retiré(s)",
+        class_id, report.created, report.kept, report.removed
+    );
+    Ok(report)
+}
+
+#[tauri::command]
+pub async fn repechage_results_save(
+    pool: tauri::State<'_, DbPool>,
+    class_id: i64,
+    results: Vec<RepechageResult>,
+) -> Result<ClassDeliberation, String> {
+    pool.run(move |conn| save_results(conn, class_id, &results))
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::TestDb;
+
+    fn count(conn: &Connection, sql: &str) -> i64 {
+        conn.query_row(sql, [], |row| row.get(0)).unwrap()
+    }
+
+    // Élève 1 : maths 90 %, physique 20 % (non rachetable), français 60 % : 2ème session
+    // en physique. Élève 2 : 70 % partout.
+    fn class_db() -> TestDb {
+        let db = TestDb::open();
+        db.conn()
+            .execute_batch(
+                "INSERT INTO academic_years (id, name, start_date, end_date, is_active) VALUES (1, '2024-2025', '2024-09-01', '2025-07-01', 1);
+                 INSERT INTO classes (id, name, level, option, section, academic_year_id) VALUES (1, '2ème ETRO', '2ème', 'ELECTRONIQUE', 'A', 1);
+                 INSERT INTO subjects (id, name, code, class_id, display_order) VALUES
+                   (1, 'Maths', 'MATH', 1, 1), (2, 'Physique', 'PHYS', 1, 2), (3, 'Français', 'FR', 1, 3);
+                 INSERT INTO students (id, first_name, last_name, gender, class_id) VALUES
+                   (1, 'Jean', 'Kabila', 'M', 1), (2, 'Marie', 'Mbuyi', 'F', 1);
+                 INSERT INTO grades (student_id, subject_id, period, value)
+                   SELECT st.id, su.id, p.code, p.default_max * CASE
+                       WHEN st.id = 2 THEN 0.7 WHEN su.id = 1 THEN 0.9 WHEN su.id = 2 THEN 0.2 ELSE 0.6 END
+                   FROM students st, subjects su, periods p WHERE p.academic_year_id = 1;",
+            )
+            .unwrap();
+        db
+    }
+
+    #[test]
+    fn creation_conservation_et_retrait() {
+        let db = class_db();
+        let mut conn = db.conn();
+        let report = generate(&mut conn, 1).unwrap();
+        assert_eq!((report.created, report.kept, report.removed), (1, 0, 0));
+        assert_eq!(report.students.len(), 1);
+        assert_eq!(report.students[0].student_id, 1);
+        assert_eq!(report.students[0].subjects[0].code, "PHYS");
+
+        // Régénérer conserve la ligne et son « voir bureau »
+        conn.execute("UPDATE repechages SET voir_bureau = 1", [])
+            .unwrap();
+        let again = generate(&mut conn, 1).unwrap();
+        assert_eq!((again.created, again.kept, again.removed), (0, 1, 0));
+        assert!(again.students[0].subjects[0].voir_bureau);
+
+        // Cotes corrigées : la ligne vide disparaît, celle marquée « voir bureau » reste
+        conn.execute_batch(
+            "INSERT INTO repechages (student_id, subject_id, value, percentage, voir_bureau) VALUES (2, 1, 0, 0, 0);
+             UPDATE repechages SET voir_bureau = 0 WHERE student_id = 1;
+             UPDATE grades SET value = value * 3 WHERE student_id = 1 AND subject_id = 2;",
+        )
+        .unwrap();
+        let corrected = generate(&mut conn, 1).unwrap();
+        assert_eq!(
+            (corrected.created, corrected.kept, corrected.removed),
+            (0, 0, 2)
+        );
+        assert!(corrected.students.is_empty());
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM repechages"), 0);
+        assert_eq!(
+            count(
+                &conn,
+                "SELECT COUNT(*) FROM operation_log WHERE entity_type = 'repechage'"
+            ),
+            3
+        );
+    }
+
+    #[test]
+    fn conduite_bloquante_sans_deuxieme_session() {
+        let db = class_db();
+        let mut conn = db.conn();
+        conn.execute_batch(
+            "INSERT INTO settings (key, value) VALUES ('delib_conduiteBloquePromotion', 'true');
+             UPDATE students SET conduite = 'Mauvais' WHERE id = 1;",
+        )
+        .unwrap();
+        let report = generate(&mut conn, 1).unwrap();
+        assert_eq!(report.created, 0);
+        assert!(report.students.is_empty());
+    }
+
+    fn result(student_id: i64, subject_id: i64, percentage: f64) -> RepechageResult {
+        RepechageResult {
+            student_id,
+            subject_id,
+            percentage,
+        }
+    }
+
+    #[test]
+    fn resultats_convertis_en_points() {
+        let db = class_db();
+        let mut conn = db.conn();
+        generate(&mut conn, 1).unwrap();
+        assert!(save_results(&mut conn, 1, &[result(1, 2, 120.0)]).is_err());
+        assert!(save_results(&mut conn, 1, &[result(9, 2, 60.0)]).is_err());
+        assert!(save_results(&mut conn, 1, &[result(1, 9, 60.0)]).is_err());
+
+        // 60 % du maximum annuel de 80 points
+        let deliberation = save_results(&mut conn, 1, &[result(1, 2, 60.0)]).unwrap();
+        assert_eq!(deliberation.mode, DelibMode::Final);
+        let student = deliberation
+            .students
+            .iter()
+            .find(|s| s.student_id == 1)
+            .unwrap();
+        assert!(student.remaining_failures.is_empty());
+        let value: f64 = conn
+            .query_row(
+                "SELECT value FROM repechages WHERE student_id = 1",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(value, 48.0);
+
+        // Le même résultat n'est pas journalisé deux fois
+        save_results(&mut conn, 1, &[result(1, 2, 60.0)]).unwrap();
+        assert_eq!(
+            count(
+                &conn,
+                "SELECT COUNT(*) FROM operation_log WHERE entity_type = 'repechage'"
+            ),
+            2
+        );
+    }
+}
@@ -0,0 +1,441 @@
+// Alerte précoce : repérer les élèves en danger d'échec avant l'examen plutôt qu'à la
+// délibération. Le niveau de risque additionne des facteurs pondérés : pourcentage de
+// l'année sous le seuil, cours sous 50 %, baisse des pourcentages de période, conduite
+// des périodes et repêchages des années précédentes. Chaque facteur retenu est renvoyé
+// pour que le titulaire voie pourquoi l'élève est signalé.
+
+use crate::bulletin::{self, ColumnKind, Score};
+use crate::conduct;
+use crate::db::DbPool;
+use crate::gradebook::Gradebook;
+use crate::ranking::IncompletePolicy;
+use log::info;
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+// Seuil de réussite d'un cours et de l'année
+pub const PASS_PERCENTAGE: f64 = 50.0;
+// Au-dessus du seuil mais trop près pour être rassurant
+pub const WARNING_PERCENTAGE: f64 = 55.0;
+// Baisse par période, en points de pourcentage
+pub const DECLINE_WARNING: f64 = 5.0;
+pub const DECLINE_ALERT: f64 = 10.0;
+// Score à partir duquel l'élève passe en risque moyen, puis élevé
+pub const MEDIUM_SCORE: u32 = 2;
+pub const HIGH_SCORE: u32 = 5;
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RiskLevel {
+    Faible,
+    Moyen,
+    Eleve,
+}
+
+impl RiskLevel {
+    fn from_score(score: u32) -> Self {
+        if score >= HIGH_SCORE {
+            RiskLevel::Eleve
+        } else if score >= MEDIUM_SCORE {
+            RiskLevel::Moyen
+        } else {
+            RiskLevel::Faible
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RiskFactorKind {
+    PourcentageInsuffisant,
+    CoursEnEchec,
+    BaisseDesResultats,
+    Conduite,
+    RepechagesAnterieurs,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RiskFactor {
+    pub kind: RiskFactorKind,
+    pub weight: u32,
+    pub detail: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct StudentRisk {
+    pub student_id: i64,
+    pub student_name: String,
+    pub level: RiskLevel,
+    pub score: u32,
+    // Pourcentage de l'année sur les seules cotes encodées
+    pub percentage: Option<f64>,
+    // Variation moyenne d'une période à l'autre, en points de pourcentage
+    pub trend: Option<f64>,
+    pub failing_subjects: Vec<String>,
+    pub factors: Vec<RiskFactor>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassRisk {
+    pub class_id: i64,
+    pub class_name: String,
+    // Élèves en risque moyen ou élevé
+    pub at_risk: usize,
+    // Du plus exposé au moins exposé ; les abandons ne figurent pas
+    pub students: Vec<StudentRisk>,
+}
+
+fn round2(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+// Pourcentage sur les cotes encodées, même quand la colonne est incomplète
+fn partial_percentage(score: &Score) -> Option<f64> {
+    let points = score.points?;
+    (score.graded_max > 0.0).then(|| points * 100.0 / score.graded_max)
+}
+
+// Pente des moindres carrés, en points par période
+fn slope(points: &[(f64, f64)]) -> Option<f64> {
+    if points.len() < 2 {
+        return None;
+    }
+    let n = points.len() as f64;
+    let mx = points.iter().map(|p| p.0).sum::<f64>() / n;
+    let my = points.iter().map(|p| p.1).sum::<f64>() / n;
+    let sxx: f64 = points.iter().map(|p| (p.0 - mx).powi(2)).sum();
+    let sxy: f64 = points.iter().map(|p| (p.0 - mx) * (p.1 - my)).sum();
+    (sxx > 0.0).then(|| sxy / sxx)
+}
+
+// élève -> (période, niveau, poids) des conduites encodées
+type ConductByStudent = HashMap<i64, Vec<(String, String, u32)>>;
+
+// Conduites lues selon l'échelle de conduct.rs ; une saisie hors échelle ne compte pas
+fn load_conduct(conn: &Connection, class_id: i64) -> Result<ConductByStudent, String> {
+    let report = conduct::class_conduct(conn, class_id)?;
+    let scale = &report.scale;
+    Ok(report
+        .students
+        .into_iter()
+        .map(|student| {
+            let values = student
+                .periods
+                .iter()
+                .filter_map(|period| {
+                    let rank = scale.parse(&period.raw)?;
+                    Some((
+                        period.period.clone(),
+                        scale.levels[rank].label.clone(),
+                        scale.risk_weight(rank),
+                    ))
+                })
+                .collect();
+            (student.student_id, values)
+        })
+        .collect())
+}
+
+// Repêchages des années antérieures. Un élève réinscrit est une nouvelle ligne de students
+// (voir rollover) sans lien vers l'ancienne : on le retrouve par ses noms dans les classes
+// des années précédentes, et par sa date de naissance quand elle est connue des deux côtés.
+// Limite assumée : deux homonymes sans date de naissance sont confondus, et un nom
+// corrigé d'une année à l'autre fait perdre l'historique.
+fn load_past_repechages(
+    conn: &Connection,
+    class_id: i64,
+) -> Result<HashMap<i64, (u32, String)>, String> {
+    let mut stmt = conn
+        .prepare_cached(
+            "SELECT cur.id, COUNT(r.id), GROUP_CONCAT(DISTINCT py.name)
+             FROM students cur
+             JOIN classes cc ON cc.id = cur.class_id
+             JOIN academic_years cy ON cy.id = cc.academic_year_id
+             JOIN students prev ON prev.id != cur.id
+                AND prev.last_name = cur.last_name
+                AND COALESCE(prev.post_name, '') = COALESCE(cur.post_name, '')
+                AND COALESCE(prev.first_name, '') = COALESCE(cur.first_name, '')
+                AND (COALESCE(prev.birth_date, '') = '' OR COALESCE(cur.birth_date, '') = ''
+                     OR prev.birth_date = cur.birth_date)
+             JOIN classes pc ON pc.id = prev.class_id
+             JOIN academic_years py ON py.id = pc.academic_year_id AND py.start_date < cy.start_date
+             JOIN repechages r ON r.student_id = prev.id
+             WHERE cur.class_id = ?
+             GROUP BY cur.id",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![class_id], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                (
+                    row.get(1)?,
+                    row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+                ),
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<HashMap<_, _>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+pub fn class_risk_for(
+    book: &Gradebook,
+    class_id: i64,
+    conduct: &ConductByStudent,
+    past_repechages: &HashMap<i64, (u32, String)>,
+) -> ClassRisk {
+    let result = bulletin::compute(book, class_id, IncompletePolicy::default());
+    let annual = result.columns.len() - 1;
+    let periods: Vec<usize> = result
+        .columns
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| c.kind == ColumnKind::Period)
+        .map(|(i, _)| i)
+        .collect();
+
+    let mut students: Vec<StudentRisk> = result
+        .students
+        .iter()
+        .filter(|student| !student.abandoned)
+        .map(|student| {
+            let mut factors = Vec::new();
+
+            let percentage = partial_percentage(&student.totals[annual]);
+            match percentage {
+                Some(p) if p < PASS_PERCENTAGE => factors.push(RiskFactor {
+                    kind: RiskFactorKind::PourcentageInsuffisant,
+                    weight: 3,
+                    detail: format!("{:.1}% sur les cotes encodées", p),
+                }),
+                Some(p) if p < WARNING_PERCENTAGE => factors.push(RiskFactor {
+                    kind: RiskFactorKind::PourcentageInsuffisant,
+                    weight: 1,
+                    detail: format!("{:.1}%, proche du seuil de {}%", p, PASS_PERCENTAGE),
+                }),
+                _ => {}
+            }
+
+            let failing_subjects: Vec<String> = student
+                .subjects
+                .iter()
+                .filter(|line| {
+                    partial_percentage(&line.scores[annual]).is_some_and(|p| p < PASS_PERCENTAGE)
+                })
+                .map(|line| {
+                    if line.code.is_empty() {
+                        line.name.clone()
+                    } else {
+                        line.code.clone()
+                    }
+                })
+                .collect();
+            if !failing_subjects.is_empty() {
+                factors.push(RiskFactor {
+                    kind: RiskFactorKind::CoursEnEchec,
+                    weight: failing_subjects.len().min(3) as u32,
+                    detail: format!(
+                        "{} cours sous {}% : {}",
+                        failing_subjects.len(),
+                        PASS_PERCENTAGE,
+                        failing_subjects.join(", ")
+                    ),
+                });
+            }
+
+            // Tendance sur les périodes déjà cotées, dans l'ordre de l'année
+            let series: Vec<(f64, f64)> = periods
+                .iter()
+                .enumerate()
+                .filter_map(|(position, column)| {
+                    Some((
+                        position as f64,
+                        partial_percentage(&student.totals[*column])?,
+                    ))
+                })
+                .collect();
+            let trend = slope(&series);
+            if let Some(t) = trend.filter(|t| *t <= -DECLINE_WARNING) {
+                factors.push(RiskFactor {
+                    kind: RiskFactorKind::BaisseDesResultats,
+                    weight: if t <= -DECLINE_ALERT { 2 } else { 1 },
+                    detail: format!("{:.1} points par période", t),
+                });
+            }
+
+            let worst_conduct = conduct
+                .get(&student.student_id)
+                .into_iter()
+                .flatten()
+                .filter(|c| c.2 > 0)
+                .max_by_key(|c| c.2);
+            if let Some((period, label, weight)) = worst_conduct {
+                factors.push(RiskFactor {
+                    kind: RiskFactorKind::Conduite,
+                    weight: *weight,
+                    detail: format!("{} en {}", label, period),
+                });
+            }
+
+            if let Some((count, years)) =
+                past_repechages.get(&student.student_id).filter(|r| r.0 > 0)
+            {
+                factors.push(RiskFactor {
+                    kind: RiskFactorKind::RepechagesAnterieurs,
+                    weight: (*count).min(2),
+                    detail: format!("{} repêchage(s) en {}", count, years),
+                });
+            }
+
+            let score = factors.iter().map(|f| f.weight).sum();
+            StudentRisk {
+                student_id: student.student_id,
+                student_name: student.student_name.clone(),
+                level: RiskLevel::from_score(score),
+                score,
+                percentage: percentage.map(round2),
+                trend: trend.map(round2),
+                failing_subjects,
+                factors,
+            }
+        })
+        .collect();
+
+    // Tri stable : l'ordre alphabétique du chargement départage les scores égaux
+    students.sort_by_key(|s| Reverse(s.score));
+
+    ClassRisk {
+        class_id,
+        class_name: result.class_name,
+        at_risk: students
+            .iter()
+            .filter(|s| s.level != RiskLevel::Faible)
+            .count(),
+        students,
+    }
+}
+
+pub fn class_risk(conn: &Connection, class_id: i64) -> Result<ClassRisk, String> {
+    let book = Gradebook::load(conn, class_id)?;
+    let conduct = load_conduct(conn, class_id)?;
+    let past_repechages = load_past_repechages(conn, class_id)?;
+    Ok(class_risk_for(&book, class_id, &conduct, &past_repechages))
+}
+
+#[tauri::command]
+pub async fn class_risk_report(
+    pool: tauri::State<'_, DbPool>,
+    class_id: i64,
+) -> Result<ClassRisk, String> {
+    let report = pool.run(move |conn| class_risk(conn, class_id)).await?;
+    info!(
+        "Élèves à risque de la classe {} : {} sur {}",
+        class_id,
+        report.at_risk,
+        report.students.len()
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::TestDb;
+
+    #[test]
+    fn pente_des_moindres_carres() {
+        assert_eq!(slope(&[]), None);
+        assert_eq!(slope(&[(0.0, 60.0)]), None);
+        assert_eq!(slope(&[(0.0, 60.0), (1.0, 50.0)]), Some(-10.0));
+        assert_eq!(slope(&[(0.0, 40.0), (1.0, 50.0), (2.0, 60.0)]), Some(10.0));
+        // Points sur une même abscisse : pas de pente
+        assert_eq!(slope(&[(1.0, 40.0), (1.0, 60.0)]), None);
+        let fitted = slope(&[(0.0, 70.0), (1.0, 60.0), (2.0, 65.0), (3.0, 50.0)]).unwrap();
+        assert!((fitted - -5.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn seuils_des_niveaux() {
+        assert_eq!(RiskLevel::from_score(0), RiskLevel::Faible);
+        assert_eq!(RiskLevel::from_score(MEDIUM_SCORE - 1), RiskLevel::Faible);
+        assert_eq!(RiskLevel::from_score(MEDIUM_SCORE), RiskLevel::Moyen);
+        assert_eq!(RiskLevel::from_score(HIGH_SCORE - 1), RiskLevel::Moyen);
+        assert_eq!(RiskLevel::from_score(HIGH_SCORE), RiskLevel::Eleve);
+    }
+
+    fn weights(risk: &StudentRisk) -> Vec<(RiskFactorKind, u32)> {
+        risk.factors.iter().map(|f| (f.kind, f.weight)).collect()
+    }
+
+    #[test]
+    fn poids_des_facteurs() {
+        let db = TestDb::open();
+        let conn = db.conn();
+        conn.execute_batch(
+            "INSERT INTO academic_years (id, name, start_date, end_date, is_active) VALUES
+               (1, '2023-2024', '2023-09-01', '2024-07-01', 0), (2, '2024-2025', '2024-09-01', '2025-07-01', 1);
+             INSERT INTO classes (id, name, level, option, section, academic_year_id) VALUES
+               (1, '1ère ETRO', '1ère', 'ELECTRONIQUE', 'A', 1), (2, '2ème ETRO', '2ème', 'ELECTRONIQUE', 'A', 2);
+             INSERT INTO subjects (id, name, code, class_id) VALUES (1, 'Maths', 'MATH', 1), (2, 'Maths', 'MATH', 2), (3, 'Français', 'FR', 2);
+             -- L'an passé : Jean Kabila repêché ; un homonyme né un autre jour aussi
+             INSERT INTO students (id, first_name, last_name, gender, birth_date, class_id) VALUES
+               (1, 'Jean', 'Kabila', 'M', '2008-03-01', 1), (6, 'Paul', 'Ilunga', 'M', '2007-01-01', 1);
+             INSERT INTO repechages (student_id, subject_id, value, percentage) VALUES (1, 1, 30, 60), (6, 1, 30, 60);
+             INSERT INTO students (id, first_name, last_name, gender, birth_date, class_id, conduite_p2) VALUES
+               (2, 'Jean', 'Kabila', 'M', '2008-03-01', 2, 'mauvais'), (3, 'Marie', 'Mbuyi', 'F', '', 2, 'bon'),
+               (4, 'Ruth', 'Kasongo', 'F', '', 2, ''), (5, 'Paul', 'Ilunga', 'M', '2008-05-05', 2, 'AB');
+             INSERT INTO grades (student_id, subject_id, period, value) VALUES
+               (2, 2, 'P1', 7), (2, 2, 'P2', 4), (2, 3, 'P1', 6), (2, 3, 'P2', 3),
+               (3, 2, 'P1', 8), (3, 2, 'P2', 8), (3, 3, 'P1', 7), (3, 3, 'P2', 7),
+               (4, 2, 'P1', 6), (4, 2, 'P2', 5), (4, 3, 'P1', 5), (4, 3, 'P2', 5),
+               (5, 2, 'P1', 6), (5, 2, 'P2', 6), (5, 3, 'P1', 6), (5, 3, 'P2', 6);",
+        )
+        .unwrap();
+        let report = class_risk(&conn, 2).unwrap();
+        let by_id = |id: i64| report.students.iter().find(|s| s.student_id == id).unwrap();
+
+        // 50 % en baisse de 30 points, le français en échec, conduite mauvaise, 1 repêchage
+        let jean = by_id(2);
+        assert_eq!(
+            weights(jean),
+            [
+                (RiskFactorKind::PourcentageInsuffisant, 1),
+                (RiskFactorKind::CoursEnEchec, 1),
+                (RiskFactorKind::BaisseDesResultats, 2),
+                (RiskFactorKind::Conduite, 2),
+                (RiskFactorKind::RepechagesAnterieurs, 1),
+            ]
+        );
+        assert_eq!((jean.score, jean.level), (7, RiskLevel::Eleve));
+        assert_eq!(jean.failing_subjects, ["FR"]);
+        assert_eq!(jean.trend, Some(-30.0));
+        assert_eq!(report.students[0].student_id, 2);
+
+        // 52,5 % et une baisse de 5 points : deux alertes légères
+        let ruth = by_id(4);
+        assert_eq!(
+            weights(ruth),
+            [
+                (RiskFactorKind::PourcentageInsuffisant, 1),
+                (RiskFactorKind::BaisseDesResultats, 1),
+            ]
+        );
+        assert_eq!(ruth.level, RiskLevel::Moyen);
+
+        // Homonyme de l'an passé né un autre jour : pas de repêchage antérieur
+        let paul = by_id(5);
+        assert_eq!(weights(paul), [(RiskFactorKind::Conduite, 1)]);
+        assert_eq!(paul.level, RiskLevel::Faible);
+
+        let marie = by_id(3);
+        assert!(marie.factors.is_empty());
+        assert_eq!(report.students.last().unwrap().student_id, 3);
+        assert_eq!(report.at_risk, 2);
+    }
+}
@@ -0,0 +1,631 @@
+// Passage à l'année scolaire suivante : création de la nouvelle année, copie des classes
+// et de leurs cours (pondérations, domaines, ordre), puis promotion des élèves selon
+// leur résultat de fin d'année (mêmes catégories que le palmarès final).
+
+use crate::backup::{self, BackupEntry, BackupKind};
+use crate::conduct;
+use crate::db::DbPool;
+use crate::delib_rules;
+use crate::deliberation::{self, DelibMode, StudentDeliberation, Verdict};
+use crate::gradebook::Gradebook;
+use crate::periods;
+use log::info;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+// Ordre des niveaux (voir LEVELS dans school.ts)
+pub const LEVELS: [&str; 6] = ["7ème", "8ème", "1ère", "2ème", "3ème", "4ème"];
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Decision {
+    Promote,
+    Repeat,
+    Leave,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DecisionOverride {
+    pub student_id: i64,
+    pub decision: Decision,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RolloverParams {
+    pub source_year_id: i64,
+    pub name: String,
+    pub start_date: String,
+    pub end_date: String,
+    pub class_ids: Vec<i64>,
+    #[serde(default)]
+    pub set_active: bool,
+    // Décisions corrigées à la main depuis l'aperçu
+    #[serde(default)]
+    pub overrides: Vec<DecisionOverride>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PlannedClass {
+    pub source_class_id: i64,
+    pub name: String,
+    pub level: String,
+    pub option: String,
+    pub section: String,
+    pub subject_count: i64,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct StudentPlacement {
+    pub student_id: i64,
+    pub student_name: String,
+    pub from_class: String,
+    pub decision: Decision,
+    pub percentage: Option<f64>,
+    pub target_class: String,
+    pub target_source_class_id: i64,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UnplacedStudent {
+    pub student_id: i64,
+    pub student_name: String,
+    pub from_class: String,
+    pub decision: Option<Decision>,
+    pub reason: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RolloverPreview {
+    pub classes: Vec<PlannedClass>,
+    pub placements: Vec<StudentPlacement>,
+    pub unplaced: Vec<UnplacedStudent>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RolloverReport {
+    pub academic_year_id: i64,
+    pub classes_created: usize,
+    pub subjects_created: usize,
+    pub students_placed: usize,
+    pub unplaced: Vec<UnplacedStudent>,
+    pub backup: BackupEntry,
+}
+
+struct ClassRow {
+    id: i64,
+    name: String,
+    level: String,
+    option: String,
+    section: String,
+}
+
+struct Outcome {
+    decision: Option<Decision>,
+    percentage: Option<f64>,
+    reason: String,
+}
+
+fn next_level(level: &str) -> Option<&'static str> {
+    let index = LEVELS.iter().position(|l| *l == level)?;
+    LEVELS.get(index + 1).copied()
+}
+
+// Résultat annuel de la délibération finale (2ème session comprise)
+fn end_of_year_outcome(result: &StudentDeliberation) -> Outcome {
+    let decision = match result.verdict {
+        Verdict::Admis | Verdict::DeuxiemeSession => Some(Decision::Promote),
+        Verdict::Echec => Some(Decision::Repeat),
+        Verdict::Abandon => Some(Decision::Leave),
+        Verdict::Incomplet => None,
+    };
+    Outcome {
+        decision,
+        percentage: result.percentage,
+        reason: result.verdict_label.clone(),
+    }
+}
+
+fn load_source_classes(
+    conn: &Connection,
+    params: &RolloverParams,
+) -> Result<Vec<ClassRow>, String> {
+    if params.class_ids.is_empty() {
+        return Err("Aucune classe sélectionnée".to_string());
+    }
+    let mut stmt = conn
+        .prepare_cached(
+            "SELECT id, name, level, option, section FROM classes WHERE academic_year_id = ? ORDER BY level, option, section",
+        )
+        .map_err(|e| e.to_string())?;
+    let classes: Vec<ClassRow> = stmt
+        .query_map(params![params.source_year_id], |row| {
+            Ok(ClassRow {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                level: row.get(2)?,
+                option: row.get(3)?,
+                section: row.get(4)?,
+            })
+        })
+        .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| e.to_string())?;
+
+    for id in &params.class_ids {
+        if !classes.iter().any(|c| c.id == *id) {
+            return Err(format!(
+                "La classe {} n'appartient pas à l'année source",
+                id
+            ));
+        }
+    }
+    Ok(classes)
+}
+
+// Classe de destination parmi les classes copiées : même option et section,
+// ou la seule classe de ce niveau et de cette option.
+fn find_target<'a>(
+    cloned: &[&'a ClassRow],
+    level: &str,
+    option: &str,
+    section: &str,
+) -> Option<&'a ClassRow> {
+    if let Some(exact) = cloned
+        .iter()
+        .find(|c| c.level == level && c.option == option && c.section == section)
+    {
+        return Some(exact);
+    }
+    let candidates: Vec<&'a ClassRow> = cloned
+        .iter()
+        .copied()
+        .filter(|c| c.level == level && c.option == option)
+        .collect();
+    match candidates.as_slice() {
+        [only] => Some(only),
+        _ => None,
+    }
+}
+
+pub fn build_plan(conn: &Connection, params: &RolloverParams) -> Result<RolloverPreview, String> {
+    let classes = load_source_classes(conn, params)?;
+    let cloned: Vec<&ClassRow> = classes
+        .iter()
+        .filter(|c| params.class_ids.contains(&c.id))
+        .collect();
+
+    let mut planned = Vec::new();
+    for class in &cloned {
+        let subject_count: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM subjects WHERE class_id = ?",
+                params![class.id],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        planned.push(PlannedClass {
+            source_class_id: class.id,
+            name: class.name.clone(),
+            level: class.level.clone(),
+            option: class.option.clone(),
+            section: class.section.clone(),
+            subject_count,
+        });
+    }
+
+    let mut placements = Vec::new();
+    let mut unplaced = Vec::new();
+
+    // Tous les élèves de l'année source : une classe non copiée peut alimenter une classe copiée
+    for class in &classes {
+        let book = Gradebook::load(conn, class.id)?;
+        let rules = delib_rules::rules_for_class(conn, class.id)?;
+        let mut results = deliberation::deliberate_gradebook(&book, &rules.rules, DelibMode::Final);
+        conduct::apply_to_deliberation(
+            conn,
+            class.id,
+            &rules.rules,
+            DelibMode::Final,
+            &mut results,
+        )?;
+
+        for result in results {
+            let student_id = result.student_id;
+            let student_name = result.student_name.clone();
+            let outcome = end_of_year_outcome(&result);
+            let overridden = params
+                .overrides
+                .iter()
+                .find(|o| o.student_id == student_id)
+                .map(|o| o.decision);
+            let decision = overridden.or(outcome.decision);
+
+            let unplaced_entry = |reason: String| UnplacedStudent {
+                student_id,
+                student_name: student_name.clone(),
+                from_class: class.name.clone(),
+                decision,
+                reason,
+            };
+
+            let target = match decision {
+                None => {
+                    unplaced.push(unplaced_entry(outcome.reason));
+                    continue;
+                }
+                Some(Decision::Leave) => {
+                    let reason = if overridden.is_some() {
+                        "Départ décidé manuellement".to_string()
+                    } else {
+                        outcome.reason
+                    };
+                    unplaced.push(unplaced_entry(reason));
+                    continue;
+                }
+                Some(Decision::Repeat) => (class.level.as_str(), "redoublement"),
+                Some(Decision::Promote) => match next_level(&class.level) {
+                    Some(level) => (level, "promotion"),
+                    None => {
+                        unplaced.push(unplaced_entry("Finaliste (fin de cycle)".to_string()));
+                        continue;
+                    }
+                },
+            };
+
+            match find_target(&cloned, target.0, &class.option, &class.section) {
+                Some(target_class) => placements.push(StudentPlacement {
+                    student_id,
+                    student_name,
+                    from_class: class.name.clone(),
+                    decision: decision.unwrap_or(Decision::Repeat),
+                    percentage: outcome.percentage,
+                    target_class: target_class.name.clone(),
+                    target_source_class_id: target_class.id,
+                }),
+                None => unplaced.push(unplaced_entry(format!(
+                    "Aucune classe de {} {} {} pour la {}",
+                    target.0, class.option, class.section, target.1
+                ))),
+            }
+        }
+    }
+
+    Ok(RolloverPreview {
+        classes: planned,
+        placements,
+        unplaced,
+    })
+}
+
+pub fn apply_rollover(
+    conn: &mut Connection,
+    db_path: &Path,
+    params: &RolloverParams,
+) -> Result<RolloverReport, String> {
+    if params.name.trim().is_empty() {
+        return Err("Le nom de la nouvelle année est obligatoire".to_string());
+    }
+    let existing: Option<i64> = conn
+        .query_row(
+            "SELECT id FROM academic_years WHERE name = ?",
+            params![params.name.trim()],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    if existing.is_some() {
+        return Err(format!("L'année {} existe déjà", params.name.trim()));
+    }
+
+    let plan = build_plan(conn, params)?;
+    let backup = backup::create_backup(db_path, BackupKind::PreRollover)?;
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    tx.execute(
+        "INSERT INTO academic_years (name, start_date, end_date, is_active, is_dirty, last_modified_at) VALUES (?, ?, ?, 0, 1, (datetime('now')))",
+        params![params.name.trim(), params.start_date, params.end_date],
+    )
+    .map_err(|e| e.to_string())?;
+    let year_id = tx.last_insert_rowid();
+
+    if params.set_active {
+        tx.execute(
+            "UPDATE academic_years SET is_active = (id = ?), is_dirty = 1, last_modified_at = (datetime('now'))",
+            params![year_id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    // Même découpage en périodes que l'année source
+    periods::copy_periods(&tx, params.source_year_id, year_id)?;
+    delib_rules::copy_rule_sets(&tx, params.source_year_id, year_id)?;
+
+    let mut class_map: HashMap<i64, i64> = HashMap::new();
+    let mut subjects_created = 0;
+    for class in &plan.classes {
+        tx.execute(
+            "INSERT INTO classes (name, level, option, section, academic_year_id, is_dirty, last_modified_at) VALUES (?, ?, ?, ?, ?, 1, (datetime('now')))",
+            params![class.name, class.level, class.option, class.section, year_id],
+        )
+        .map_err(|e| e.to_string())?;
+        let new_class_id = tx.last_insert_rowid();
+        class_map.insert(class.source_class_id, new_class_id);
+
+        subjects_created += tx
+            .execute(
+                "INSERT INTO subjects (name, code, max_p1, max_p2, max_exam1, max_p3, max_p4, max_exam2, category, sub_domain, domain_id, display_order, coefficient, class_id, is_dirty, last_modified_at)
+                 SELECT name, code, max_p1, max_p2, max_exam1, max_p3, max_p4, max_exam2, category, sub_domain, domain_id, display_order, coefficient, ?, 1, (datetime('now'))
+                 FROM subjects WHERE class_id = ? ORDER BY display_order, id",
+                params![new_class_id, class.source_class_id],
+            )
+            .map_err(|e| e.to_string())?;
+        tx.execute(
+            "INSERT INTO subject_period_max (subject_id, period_code, max_points)
+             SELECT ns.id, m.period_code, m.max_points
+             FROM subjects ns
+             JOIN subjects os ON os.class_id = ?2 AND os.name = ns.name AND os.code = ns.code
+             JOIN subject_period_max m ON m.subject_id = os.id
+             WHERE ns.class_id = ?1
+             ON CONFLICT(subject_id, period_code) DO UPDATE SET max_points = excluded.max_points",
+            params![new_class_id, class.source_class_id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    let mut unplaced = plan.unplaced;
+    let mut students_placed = 0;
+    for placement in &plan.placements {
+        let target = class_map[&placement.target_source_class_id];
+        let inserted = tx
+            .execute(
+                "INSERT OR IGNORE INTO students (first_name, last_name, post_name, gender, birth_date, birthplace, class_id, is_dirty, last_modified_at)
+                 SELECT first_name, last_name, post_name, gender, birth_date, birthplace, ?, 1, (datetime('now'))
+                 FROM students WHERE id = ?",
+                params![target, placement.student_id],
+            )
+            .map_err(|e| e.to_string())?;
+        if inserted == 0 {
+            unplaced.push(UnplacedStudent {
+                student_id: placement.student_id,
+                student_name: placement.student_name.clone(),
+                from_class: placement.from_class.clone(),
+                decision: Some(placement.decision),
+                reason: format!("Homonyme déjà inscrit en {}", placement.target_class),
+            });
+        } else {
+            students_placed += 1;
+        }
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    info!(
+        "Rollover to {}: {} classes, {} subjects, {} students placed, {} unplaced",
+        params.name,
+        class_map.len(),
+        subjects_created,
+        students_placed,
+        unplaced.len()
+    );
+
+    Ok(RolloverReport {
+        academic_year_id: year_id,
+        classes_created: class_map.len(),
+        subjects_created,
+        students_placed,
+        unplaced,
+        backup,
+    })
+}
+
+#[tauri::command]
+pub async fn rollover_preview(
+    pool: tauri::State<'_, DbPool>,
+    params: RolloverParams,
+) -> Result<RolloverPreview, String> {
+    pool.run(move |conn| build_plan(conn, &params)).await
+}
+
+#[tauri::command]
+pub async fn rollover_apply(
+    pool: tauri::State<'_, DbPool>,
+    params: RolloverParams,
+) -> Result<RolloverReport, String> {
+    let db_path = pool.path().to_path_buf();
+    pool.run(move |conn| apply_rollover(conn, &db_path, &params))
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::TestDb;
+
+    fn count(conn: &Connection, sql: &str) -> i64 {
+        conn.query_row(sql, [], |row| row.get(0)).unwrap()
+    }
+
+    // 7ème : un bon élève, un faible, un sans cotes et un abandon ; 8ème : un admis
+    fn school_db() -> TestDb {
+        let db = TestDb::open();
+        let conn = db.conn();
+        conn.execute_batch(
+            "INSERT INTO academic_years (id, name, start_date, end_date, is_active) VALUES (1, '2024-2025', '2024-09-01', '2025-07-01', 1);
+             INSERT INTO classes (id, name, level, option, section, academic_year_id) VALUES
+               (1, '7ème EB A', '7ème', 'EB', 'A', 1), (2, '8ème EB A', '8ème', 'EB', 'A', 1);
+             INSERT INTO subjects (id, name, code, class_id, display_order, coefficient) VALUES
+               (1, 'Maths', 'MATH', 1, 1, 3), (2, 'Français', 'FR', 1, 2, NULL), (3, 'Maths', 'MATH', 2, 1, 2);
+             INSERT INTO students (id, first_name, last_name, gender, class_id) VALUES
+               (1, 'Jean', 'Kabila', 'M', 1), (2, 'Marie', 'Mbuyi', 'F', 1), (3, 'Paul', 'Ilunga', 'M', 1),
+               (4, 'Ruth', 'Kasongo', 'F', 1), (5, 'Eve', 'Tshala', 'F', 2);
+             UPDATE students SET is_abandoned = 1 WHERE id = 4;
+             UPDATE subject_period_max SET max_points = 15 WHERE subject_id = 1 AND period_code = 'P1';",
+        )
+        .unwrap();
+        for (student_id, ratio, subjects) in [
+            (1, 0.8, vec![1, 2]),
+            (2, 0.3, vec![1, 2]),
+            (5, 0.7, vec![3]),
+        ] {
+            for subject_id in subjects {
+                for period in periods::load_periods(&conn, 1).unwrap() {
+                    let max = periods::max_points(&conn, subject_id, &period.code).unwrap();
+                    conn.execute(
+                        "INSERT INTO grades (student_id, subject_id, period, value) VALUES (?, ?, ?, ?)",
+                        params![student_id, subject_id, period.code, max * ratio],
+                    )
+                    .unwrap();
+                }
+            }
+        }
+        db
+    }
+
+    fn rollover_params(overrides: serde_json::Value) -> RolloverParams {
+        serde_json::from_value(serde_json::json!({
+            "sourceYearId": 1, "name": "2025-2026", "startDate": "2025-09-01", "endDate": "2026-07-01",
+            "classIds": [1, 2], "setActive": true, "overrides": overrides
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn plan_de_passage() {
+        let db = school_db();
+        let plan = build_plan(&db.conn(), &rollover_params(serde_json::json!([]))).unwrap();
+
+        let subjects: Vec<_> = plan
+            .classes
+            .iter()
+            .map(|c| (c.source_class_id, c.subject_count))
+            .collect();
+        assert_eq!(subjects, [(1, 2), (2, 1)]);
+
+        let placed: Vec<_> = plan
+            .placements
+            .iter()
+            .map(|p| (p.student_id, p.decision, p.target_source_class_id))
+            .collect();
+        // Sans cotes, l'élève redouble (cotes manquantes en délibération finale)
+        assert_eq!(
+            placed,
+            [
+                (3, Decision::Repeat, 1),
+                (1, Decision::Promote, 2),
+                (2, Decision::Repeat, 1)
+            ]
+        );
+
+        let mut unplaced: Vec<_> = plan
+            .unplaced
+            .iter()
+            .map(|u| (u.student_id, u.decision))
+            .collect();
+        unplaced.sort_by_key(|u| u.0);
+        // Abandon, et pas de 1ère copiée pour l'élève de 8ème
+        assert_eq!(
+            unplaced,
+            [(4, Some(Decision::Leave)), (5, Some(Decision::Promote))]
+        );
+        assert!(plan
+            .unplaced
+            .iter()
+            .any(|u| u.student_id == 5 && u.reason.contains("1ère")));
+
+        // Une décision corrigée à la main l'emporte
+        let plan = build_plan(
+            &db.conn(),
+            &rollover_params(serde_json::json!([{"studentId": 3, "decision": "PROMOTE"}, {"studentId": 1, "decision": "LEAVE"}])),
+        )
+        .unwrap();
+        let placed: Vec<_> = plan
+            .placements
+            .iter()
+            .map(|p| (p.student_id, p.target_source_class_id))
+            .collect();
+        assert_eq!(placed, [(3, 2), (2, 1)]);
+        assert!(plan
+            .unplaced
+            .iter()
+            .any(|u| u.student_id == 1 && u.reason == "Départ décidé manuellement"));
+
+        let mut params = rollover_params(serde_json::json!([]));
+        params.class_ids = vec![9];
+        assert!(build_plan(&db.conn(), &params).is_err());
+    }
+
+    #[test]
+    fn nouvelle_annee_creee() {
+        let db = school_db();
+        let mut conn = db.conn();
+        let params = rollover_params(serde_json::json!([{"studentId": 3, "decision": "PROMOTE"}]));
+        let report = apply_rollover(&mut conn, &db.path, &params).unwrap();
+
+        assert_eq!(report.classes_created, 2);
+        assert_eq!(report.subjects_created, 3);
+        assert_eq!(report.students_placed, 3);
+        assert_eq!(report.backup.kind, "pre-rollover");
+        let year = report.academic_year_id;
+        assert_eq!(
+            count(&conn, "SELECT id FROM academic_years WHERE is_active = 1"),
+            year
+        );
+
+        // Cours copiés avec leur coefficient et leurs maxima
+        let copied: Vec<(String, Option<f64>, i64)> = conn
+            .prepare(
+                "SELECT c.level, s.coefficient, m.max_points FROM subjects s
+                 JOIN classes c ON c.id = s.class_id
+                 JOIN subject_period_max m ON m.subject_id = s.id AND m.period_code = 'P1'
+                 WHERE c.academic_year_id = ? ORDER BY c.level, s.display_order",
+            )
+            .unwrap()
+            .query_map(params![year], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            copied,
+            [
+                ("7ème".to_string(), Some(3.0), 15),
+                ("7ème".to_string(), None, 10),
+                ("8ème".to_string(), Some(2.0), 10),
+            ]
+        );
+        assert_eq!(
+            count(
+                &conn,
+                &format!("SELECT COUNT(*) FROM periods WHERE academic_year_id = {year}")
+            ),
+            6
+        );
+
+        // Élèves inscrits sans leurs cotes
+        let enrolled: Vec<(i64, String)> = conn
+            .prepare(
+                "SELECT COUNT(*), c.level FROM students st JOIN classes c ON c.id = st.class_id
+                 WHERE c.academic_year_id = ? GROUP BY c.level ORDER BY c.level",
+            )
+            .unwrap()
+            .query_map(params![year], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(enrolled, [(1, "7ème".to_string()), (2, "8ème".to_string())]);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM grades g JOIN subjects s ON s.id = g.subject_id WHERE s.class_id > 2"), 0);
+
+        // Une seconde fois : l'année existe déjà
+        assert!(apply_rollover(&mut conn, &db.path, &params).is_err());
+    }
+}
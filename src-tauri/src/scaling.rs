@@ -0,0 +1,428 @@
+// Changement du maximum d'un cours pour une période alors que des cotes existent
+// (mise à l'échelle, plafonnement ou refus), et correction en bloc des cotes d'une
+// période (décalage ou facteur). Chaque opération est journalisée sous un point de
+// reprise et s'annule d'un bloc avec history_undo_checkpoint.
+
+use crate::db::DbPool;
+use crate::gradebook::GRADE_TRICHEUR_CODE;
+use crate::history::{ActionType, Batch, GradeState, SubjectMaxState};
+use crate::periods;
+use log::info;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MaxChangePolicy {
+    // Cotes ramenées proportionnellement au nouveau maximum
+    Rescale,
+    // Cotes inchangées, sauf celles au-delà du nouveau maximum
+    Clamp,
+    // Changement refusé dès qu'une cote existe
+    Refuse,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Curve {
+    // Points ajoutés (ou retirés) à chaque cote
+    Shift { points: f64 },
+    // Chaque cote multipliée par le facteur
+    Scale { factor: f64 },
+}
+
+impl Curve {
+    fn label(&self) -> Result<String, String> {
+        match *self {
+            Curve::Shift { points } if points.is_finite() => {
+                Ok(format!("Décalage de {} point(s)", points))
+            }
+            Curve::Scale { factor } if factor.is_finite() && factor > 0.0 => {
+                Ok(format!("Facteur {}", factor))
+            }
+            _ => Err("Correction invalide".to_string()),
+        }
+    }
+
+    fn apply(&self, value: f64) -> f64 {
+        match *self {
+            Curve::Shift { points } => value + points,
+            Curve::Scale { factor } => value * factor,
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ScalingReport {
+    // None quand rien n'a changé
+    pub checkpoint_id: Option<i64>,
+    pub subject_id: i64,
+    pub period_code: String,
+    pub max_points: f64,
+    pub changed: usize,
+    pub unchanged: usize,
+}
+
+fn round2(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+// Cotes d'un cours pour une période, sans le code tricheur qui n'est pas une note
+fn period_grades(
+    conn: &Connection,
+    subject_id: i64,
+    period_code: &str,
+) -> Result<Vec<GradeState>, String> {
+    conn.prepare_cached(
+        "SELECT id, student_id, subject_id, period, value, COALESCE(is_estimated, 0) FROM grades
+         WHERE subject_id = ? AND period = ? AND value != ? ORDER BY student_id",
+    )
+    .and_then(|mut stmt| {
+        stmt.query_map(
+            params![subject_id, period_code, GRADE_TRICHEUR_CODE],
+            |row| {
+                Ok(GradeState {
+                    id: row.get(0)?,
+                    student_id: row.get(1)?,
+                    subject_id: row.get(2)?,
+                    period: row.get(3)?,
+                    value: row.get(4)?,
+                    estimated: row.get(5)?,
+                })
+            },
+        )?
+        .collect::<Result<Vec<_>, _>>()
+    })
+    .map_err(|e| e.to_string())
+}
+
+// Applique une nouvelle valeur à chaque cote et journalise celles qui changent
+fn rewrite_grades(
+    batch: &Batch,
+    conn: &Connection,
+    grades: Vec<GradeState>,
+    description: &str,
+    new_value: impl Fn(f64) -> f64,
+) -> Result<(usize, usize), String> {
+    let (mut changed, mut unchanged) = (0, 0);
+    for previous in grades {
+        let value = new_value(previous.value);
+        if value == previous.value {
+            unchanged += 1;
+            continue;
+        }
+        conn.execute(
+            "UPDATE grades SET value = ?, is_dirty = 1, last_modified_at = datetime('now') WHERE id = ?",
+            params![value, previous.id],
+        )
+        .map_err(|e| e.to_string())?;
+        let current = GradeState {
+            value,
+            ..previous.clone()
+        };
+        batch.log(
+            "grade",
+            previous.id,
+            ActionType::Update,
+            Some(&previous),
+            Some(&current),
+            &format!(
+                "{} ({} -> {}) pour la période {}",
+                description, previous.value, value, previous.period
+            ),
+        )?;
+        changed += 1;
+    }
+    Ok((changed, unchanged))
+}
+
+pub fn change_subject_max(
+    conn: &mut Connection,
+    subject_id: i64,
+    period_code: &str,
+    new_max: i64,
+    policy: MaxChangePolicy,
+) -> Result<ScalingReport, String> {
+    if new_max <= 0 {
+        return Err("Le maximum doit être positif".to_string());
+    }
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let old_max = periods::max_points(&tx, subject_id, period_code)?;
+    let grades = period_grades(&tx, subject_id, period_code)?;
+    let mut report = ScalingReport {
+        checkpoint_id: None,
+        subject_id,
+        period_code: period_code.to_string(),
+        max_points: new_max as f64,
+        changed: 0,
+        unchanged: grades.len(),
+    };
+    if old_max == new_max as f64 {
+        return Ok(report);
+    }
+    // Sans maximum de départ, il n'y a pas de proportion à conserver
+    if policy == MaxChangePolicy::Rescale && old_max <= 0.0 && !grades.is_empty() {
+        return Err(format!(
+            "Maximum actuel de {} nul : mise à l'échelle impossible, choisissez le plafonnement",
+            period_code
+        ));
+    }
+    if policy == MaxChangePolicy::Refuse && !grades.is_empty() {
+        return Err(format!(
+            "{} cote(s) déjà encodée(s) sur {} : changement du maximum refusé",
+            grades.len(),
+            old_max
+        ));
+    }
+
+    let batch = Batch::start(
+        &tx,
+        &format!("Maximum {} : {} -> {}", period_code, old_max, new_max),
+        match policy {
+            MaxChangePolicy::Rescale => "Cotes mises à l'échelle",
+            MaxChangePolicy::Clamp => "Cotes plafonnées au nouveau maximum",
+            MaxChangePolicy::Refuse => "",
+        },
+    )?;
+    let write_max = || -> Result<(), String> {
+        periods::set_subject_max(&tx, subject_id, period_code, new_max)?;
+        let row_id: i64 = tx
+            .query_row(
+                "SELECT id FROM subject_period_max WHERE subject_id = ? AND period_code = ?",
+                params![subject_id, period_code],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        let state = |max_points| SubjectMaxState {
+            subject_id,
+            period_code: period_code.to_string(),
+            max_points,
+        };
+        batch.log(
+            "subject_period_max",
+            row_id,
+            ActionType::Update,
+            Some(&state(old_max as i64)),
+            Some(&state(new_max)),
+            &format!(
+                "Maximum de la période {} : {} -> {}",
+                period_code, old_max, new_max
+            ),
+        )?;
+        Ok(())
+    };
+    let write_grades = || -> Result<(usize, usize), String> {
+        let new_max = new_max as f64;
+        match policy {
+            MaxChangePolicy::Rescale => {
+                rewrite_grades(&batch, &tx, grades, "Mise à l'échelle", |v| {
+                    round2(v * new_max / old_max)
+                })
+            }
+            MaxChangePolicy::Clamp => {
+                rewrite_grades(&batch, &tx, grades, "Plafonnement", |v| v.min(new_max))
+            }
+            MaxChangePolicy::Refuse => Ok((0, 0)),
+        }
+    };
+    // Les triggers de grade_validation exigent une cote sous le maximum à chaque étape :
+    // on baisse les cotes avant le maximum, on monte le maximum avant les cotes. L'annulation
+    // rejoue le journal à l'envers et reste donc valide elle aussi.
+    let (changed, unchanged) = if (new_max as f64) < old_max {
+        let counts = write_grades()?;
+        write_max()?;
+        counts
+    } else {
+        write_max()?;
+        write_grades()?
+    };
+    report.checkpoint_id = Some(batch.checkpoint_id);
+    report.changed = changed;
+    report.unchanged = unchanged;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(report)
+}
+
+pub fn curve_grades(
+    conn: &mut Connection,
+    subject_id: i64,
+    period_code: &str,
+    curve: Curve,
+) -> Result<ScalingReport, String> {
+    let label = curve.label()?;
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let max = periods::max_points(&tx, subject_id, period_code)?;
+    let grades = period_grades(&tx, subject_id, period_code)?;
+    let batch = Batch::start(&tx, &format!("Correction {} : {}", period_code, label), "")?;
+    // Une cote corrigée reste entre 0 et le maximum de la période
+    let (changed, unchanged) = rewrite_grades(&batch, &tx, grades, &label, |v| {
+        round2(curve.apply(v).clamp(0.0, max))
+    })?;
+    let mut report = ScalingReport {
+        checkpoint_id: None,
+        subject_id,
+        period_code: period_code.to_string(),
+        max_points: max,
+        changed,
+        unchanged,
+    };
+    // Rien n'a bougé : la transaction abandonnée n'enregistre pas de point de reprise vide
+    if changed == 0 {
+        return Ok(report);
+    }
+    report.checkpoint_id = Some(batch.checkpoint_id);
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(report)
+}
+
+#[tauri::command]
+pub async fn subject_max_change(
+    pool: tauri::State<'_, DbPool>,
+    subject_id: i64,
+    period_code: String,
+    max_points: i64,
+    policy: MaxChangePolicy,
+) -> Result<ScalingReport, String> {
+    let report = pool
+        .run(move |conn| change_subject_max(conn, subject_id, &period_code, max_points, policy))
+        .await?;
+    info!(
+        "Maximum du cours {} ({}) porté à {} : {} cote(s) modifiée(s)",
+        subject_id, report.period_code, report.max_points, report.changed
+    );
+    Ok(report)
+}
+
+#[tauri::command]
+pub async fn grades_curve(
+    pool: tauri::State<'_, DbPool>,
+    subject_id: i64,
+    period_code: String,
+    curve: Curve,
+) -> Result<ScalingReport, String> {
+    let report = pool
+        .run(move |conn| curve_grades(conn, subject_id, &period_code, curve))
+        .await?;
+    info!(
+        "Correction des cotes du cours {} ({}) : {} cote(s) modifiée(s)",
+        subject_id, report.period_code, report.changed
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::TestDb;
+    use crate::history;
+
+    fn class_db() -> TestDb {
+        let db = TestDb::open();
+        db.conn()
+            .execute_batch(
+                "INSERT INTO academic_years (id, name, start_date, end_date, is_active) VALUES (1, '2024-2025', '2024-09-01', '2025-07-01', 1);
+                 INSERT INTO classes (id, name, level, option, section, academic_year_id) VALUES (1, '2ème ETRO', '2ème', 'ELECTRONIQUE', 'A', 1);
+                 INSERT INTO subjects (id, name, code, class_id) VALUES (1, 'Maths', 'MATH', 1);
+                 INSERT INTO students (id, first_name, last_name, gender, class_id) VALUES
+                   (1, 'Jean', 'Kabila', 'M', 1), (2, 'Marie', 'Mbuyi', 'F', 1), (3, 'Paul', 'Ilunga', 'M', 1);
+                 INSERT INTO grades (student_id, subject_id, period, value) VALUES (1, 1, 'P1', 8), (2, 1, 'P1', 5), (3, 1, 'P1', -1);",
+            )
+            .unwrap();
+        db
+    }
+
+    // Cote de l'élève et maximum hérité (max_p1) tenu à jour par les triggers de periods
+    fn state(conn: &Connection, student_id: i64) -> (f64, i64) {
+        conn.query_row(
+            "SELECT g.value, s.max_p1 FROM grades g JOIN subjects s ON s.id = g.subject_id
+             WHERE g.student_id = ? AND g.period = 'P1'",
+            params![student_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn mise_a_l_echelle_et_plafonnement() {
+        let db = class_db();
+        let mut conn = db.conn();
+        assert!(change_subject_max(&mut conn, 1, "P1", 0, MaxChangePolicy::Clamp).is_err());
+        assert!(change_subject_max(&mut conn, 1, "P1", 20, MaxChangePolicy::Refuse).is_err());
+        let unchanged =
+            change_subject_max(&mut conn, 1, "P1", 10, MaxChangePolicy::Refuse).unwrap();
+        assert_eq!(unchanged.checkpoint_id, None);
+
+        // Hausse : le maximum passe avant les cotes, le tricheur n'est pas touché
+        let rescale = change_subject_max(&mut conn, 1, "P1", 20, MaxChangePolicy::Rescale).unwrap();
+        assert_eq!((rescale.changed, rescale.unchanged), (2, 0));
+        assert_eq!(state(&conn, 1), (16.0, 20));
+        assert_eq!(state(&conn, 2), (10.0, 20));
+        assert_eq!(state(&conn, 3).0, GRADE_TRICHEUR_CODE);
+
+        // Baisse : les cotes descendent avant le maximum, sinon les triggers refusent
+        let clamp = change_subject_max(&mut conn, 1, "P1", 12, MaxChangePolicy::Clamp).unwrap();
+        assert_eq!((clamp.changed, clamp.unchanged), (1, 1));
+        assert_eq!(state(&conn, 1), (12.0, 12));
+        let rescale_down =
+            change_subject_max(&mut conn, 1, "P1", 6, MaxChangePolicy::Rescale).unwrap();
+        assert_eq!(rescale_down.changed, 2);
+        assert_eq!(state(&conn, 1), (6.0, 6));
+        assert_eq!(state(&conn, 2), (5.0, 6));
+
+        // Annulation dans l'ordre inverse : retour à l'état de départ
+        for report in [&rescale_down, &clamp, &rescale] {
+            history::undo_checkpoint(&mut conn, report.checkpoint_id.unwrap()).unwrap();
+        }
+        assert_eq!(state(&conn, 1), (8.0, 10));
+        assert_eq!(state(&conn, 2), (5.0, 10));
+        // Une seconde annulation ne correspond plus à l'état courant
+        assert!(history::undo_checkpoint(&mut conn, rescale.checkpoint_id.unwrap()).is_err());
+    }
+
+    #[test]
+    fn mise_a_l_echelle_depuis_un_maximum_nul() {
+        let db = class_db();
+        let mut conn = db.conn();
+        conn.execute_batch(
+            "DELETE FROM grades WHERE student_id = 1;
+             UPDATE grades SET value = 0 WHERE student_id = 2;
+             UPDATE subject_period_max SET max_points = 0 WHERE subject_id = 1 AND period_code = 'P1';",
+        )
+        .unwrap();
+        let error =
+            change_subject_max(&mut conn, 1, "P1", 10, MaxChangePolicy::Rescale).unwrap_err();
+        assert!(error.contains("nul"), "{}", error);
+        let clamp = change_subject_max(&mut conn, 1, "P1", 10, MaxChangePolicy::Clamp).unwrap();
+        assert_eq!((clamp.changed, clamp.unchanged), (0, 1));
+        assert_eq!(state(&conn, 2), (0.0, 10));
+    }
+
+    #[test]
+    fn correction_bornee_et_annulable() {
+        let db = class_db();
+        let mut conn = db.conn();
+        let invalid: Curve =
+            serde_json::from_value(serde_json::json!({"kind": "scale", "factor": 0})).unwrap();
+        assert!(curve_grades(&mut conn, 1, "P1", invalid).is_err());
+
+        // 8 + 3 plafonné à 10, 5 + 3 = 8
+        let shift: Curve =
+            serde_json::from_value(serde_json::json!({"kind": "shift", "points": 3})).unwrap();
+        let report = curve_grades(&mut conn, 1, "P1", shift).unwrap();
+        assert_eq!((report.changed, report.unchanged), (2, 0));
+        assert_eq!((state(&conn, 1).0, state(&conn, 2).0), (10.0, 8.0));
+        let noop = curve_grades(&mut conn, 1, "P1", Curve::Scale { factor: 1.0 }).unwrap();
+        assert_eq!(noop.checkpoint_id, None);
+
+        let scaled = curve_grades(&mut conn, 1, "P1", Curve::Scale { factor: 0.5 }).unwrap();
+        assert_eq!((state(&conn, 1).0, state(&conn, 2).0), (5.0, 4.0));
+        assert_eq!(state(&conn, 3).0, GRADE_TRICHEUR_CODE);
+
+        let undo = history::undo_checkpoint(&mut conn, scaled.checkpoint_id.unwrap()).unwrap();
+        assert_eq!(undo.restored, 2);
+        history::undo_checkpoint(&mut conn, report.checkpoint_id.unwrap()).unwrap();
+        assert_eq!((state(&conn, 1).0, state(&conn, 2).0), (8.0, 5.0));
+    }
+}
@@ -1,6 +1,7 @@
 // Module serveur web pour le Marking Board (Version Optimisée tiny-http)
 // Ce serveur permet aux appareils mobiles de saisir les notes
 
+use std::collections::BTreeMap;
 use std::fs::File;
 use std::io::{self, Write};
 use std::path::PathBuf;
@@ -8,7 +9,7 @@ use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use lazy_static::lazy_static;
-use rusqlite::{params, Connection};
+use rusqlite::params;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use tauri::{Emitter, Manager};
@@ -16,6 +17,12 @@ use tiny_http::{Header, Method, Response, Server, StatusCode};
 
 use tauri::path::BaseDirectory;
 
+use crate::conduct;
+use crate::db::DbPool;
+use crate::evaluations;
+use crate::periods;
+use crate::risk;
+
 // Structure d'information du serveur
 #[derive(Clone, Serialize, Debug)]
 pub struct ServerInfo {
@@ -33,7 +40,7 @@ lazy_static! {
 
 // État partagé passé au thread de gestion
 pub struct AppState {
-    pub db_path: PathBuf,
+    pub pool: DbPool,
     pub app_handle: tauri::AppHandle,
 }
 
@@ -195,7 +202,7 @@ fn serve_static_file(
 
 // GET /api/classes
 fn handle_get_classes(state: &AppState) -> Response<io::Cursor<Vec<u8>>> {
-    let conn = match Connection::open(&state.db_path) {
+    let conn = match state.pool.get() {
         Ok(c) => c,
         Err(_) => return error_response(500, "Database connection failed"),
     };
@@ -260,7 +267,7 @@ fn handle_get_classes(state: &AppState) -> Response<io::Cursor<Vec<u8>>> {
 
 // GET /api/classes/:id/full
 fn handle_get_class_full(id: i64, state: &AppState) -> Response<io::Cursor<Vec<u8>>> {
-    let conn = match Connection::open(&state.db_path) {
+    let conn = match state.pool.get() {
         Ok(c) => c,
         Err(_) => return error_response(500, "Database connection failed"),
     };
@@ -282,22 +289,43 @@ fn handle_get_class_full(id: i64, state: &AppState) -> Response<io::Cursor<Vec<u
         Err(_) => Vec::new(),
     };
 
+    // Periods (découpage de l'année de la classe)
+    let periods: Vec<PeriodResponse> = match periods::periods_for_class(&conn, id) {
+        Ok(list) => list
+            .into_iter()
+            .map(|p| PeriodResponse {
+                code: p.code,
+                label: p.label,
+                semester: p.semester,
+                is_exam: p.is_exam,
+            })
+            .collect(),
+        Err(_) => return error_response(500, "Failed to load periods"),
+    };
+
     // Subjects
-    let mut stmt = match conn.prepare(
-        "SELECT id, name, code, max_p1, max_p2, max_exam1, max_p3, max_p4, max_exam2 FROM subjects WHERE class_id = ?"
-    ) { Ok(s) => s, Err(_) => return error_response(500, "Failed prep subjects") };
+    let mut maxima = match periods::class_maxima(&conn, id) {
+        Ok(m) => m,
+        Err(_) => return error_response(500, "Failed to load subject maxima"),
+    };
+
+    let mut stmt = match conn.prepare("SELECT id, name, code, coefficient FROM subjects WHERE class_id = ?") {
+        Ok(s) => s,
+        Err(_) => return error_response(500, "Failed prep subjects"),
+    };
 
     let subjects: Vec<SubjectResponse> = match stmt.query_map([id], |row| {
+        let subject_id: i64 = row.get(0)?;
         Ok(SubjectResponse {
-            id: row.get(0)?,
+            id: subject_id,
             name: row.get(1)?,
             code: row.get(2)?,
-            max_p1: row.get(3)?,
-            max_p2: row.get(4)?,
-            max_exam1: row.get(5)?,
-            max_p3: row.get(6)?,
-            max_p4: row.get(7)?,
-            max_exam2: row.get(8)?,
+            max_points: maxima
+                .remove(&subject_id)
+                .unwrap_or_default()
+                .into_iter()
+                .collect(),
+            coefficient: row.get(3)?,
         })
     }) {
         Ok(iter) => iter.filter_map(Result::ok).collect(),
@@ -322,6 +350,39 @@ fn handle_get_class_full(id: i64, state: &AppState) -> Response<io::Cursor<Vec<u
         Err(_) => Vec::new(),
     };
 
+    // Evaluations (interrogations) et points des élèves
+    let mut stmt = match conn.prepare(
+        "SELECT e.id, e.subject_id, e.period_code, e.name, e.max_points FROM evaluations e JOIN subjects s ON e.subject_id = s.id WHERE s.class_id = ? ORDER BY e.display_order, e.id"
+    ) { Ok(s) => s, Err(_) => return error_response(500, "Failed prep evaluations") };
+
+    let evaluations: Vec<EvaluationResponse> = match stmt.query_map([id], |row| {
+        Ok(EvaluationResponse {
+            id: row.get(0)?,
+            subject_id: row.get(1)?,
+            period_code: row.get(2)?,
+            name: row.get(3)?,
+            max_points: row.get(4)?,
+        })
+    }) {
+        Ok(iter) => iter.filter_map(Result::ok).collect(),
+        Err(_) => Vec::new(),
+    };
+
+    let mut stmt = match conn.prepare(
+        "SELECT sc.evaluation_id, sc.student_id, sc.value FROM evaluation_scores sc JOIN evaluations e ON sc.evaluation_id = e.id JOIN subjects s ON e.subject_id = s.id WHERE s.class_id = ?"
+    ) { Ok(s) => s, Err(_) => return error_response(500, "Failed prep evaluation scores") };
+
+    let evaluation_scores: Vec<EvaluationScoreUpdate> = match stmt.query_map([id], |row| {
+        Ok(EvaluationScoreUpdate {
+            evaluation_id: row.get(0)?,
+            student_id: row.get(1)?,
+            value: row.get(2)?,
+        })
+    }) {
+        Ok(iter) => iter.filter_map(Result::ok).collect(),
+        Err(_) => Vec::new(),
+    };
+
     // Custom Sorts
     let mut stmt =
         match conn.prepare("SELECT id, name, student_order FROM custom_sorts WHERE class_id = ?") {
@@ -341,13 +402,72 @@ fn handle_get_class_full(id: i64, state: &AppState) -> Response<io::Cursor<Vec<u
     };
 
     json_response(ClassFullResponse {
+        periods,
         students,
         subjects,
         grades,
+        evaluations,
+        evaluation_scores,
         custom_sorts,
     })
 }
 
+// GET /api/classes/:id/at-risk : élèves à risque, pour le téléphone du préfet
+fn handle_get_class_risk(id: i64, state: &AppState) -> Response<io::Cursor<Vec<u8>>> {
+    let conn = match state.pool.get() {
+        Ok(c) => c,
+        Err(_) => return error_response(500, "Database connection failed"),
+    };
+    match risk::class_risk(&conn, id) {
+        Ok(report) => json_response(report),
+        Err(e) => error_response(500, &e),
+    }
+}
+
+// GET /api/classes/:id/conduct : conduites de la classe et échelle, pour le titulaire
+fn handle_get_class_conduct(id: i64, state: &AppState) -> Response<io::Cursor<Vec<u8>>> {
+    let conn = match state.pool.get() {
+        Ok(c) => c,
+        Err(_) => return error_response(500, "Database connection failed"),
+    };
+    match conduct::class_conduct(&conn, id) {
+        Ok(report) => json_response(report),
+        Err(e) => error_response(500, &e),
+    }
+}
+
+// POST /api/conduct/batch : saisie contrôlée contre l'échelle, tout ou rien
+fn handle_save_conduct(
+    request: &mut tiny_http::Request,
+    state: &AppState,
+) -> Response<io::Cursor<Vec<u8>>> {
+    let mut content = String::new();
+    if request.as_reader().read_to_string(&mut content).is_err() {
+        return error_response(400, "Failed to read body");
+    }
+    let payload: BatchConductRequest = match serde_json::from_str(&content) {
+        Ok(p) => p,
+        Err(_) => return error_response(400, "Invalid JSON"),
+    };
+    let mut conn = match state.pool.get() {
+        Ok(c) => c,
+        Err(_) => return error_response(500, "DB connection failed"),
+    };
+    let saved = match conduct::set_conduct_batch(&mut conn, &payload.updates) {
+        Ok(saved) => saved,
+        Err(e) => return error_response(400, &e),
+    };
+
+    let event_payload = json!({
+        "type": "conduct_update",
+        "updates": saved
+    });
+    let _ = state.app_handle.emit("db:changed", &event_payload);
+    broadcast_msg(event_payload);
+
+    json_response(json!({"success": true, "updates": saved}))
+}
+
 // POST /api/grades/batch
 fn handle_save_grades(
     request: &mut tiny_http::Request,
@@ -363,7 +483,7 @@ fn handle_save_grades(
         Err(_) => return error_response(400, "Invalid JSON"),
     };
 
-    let mut conn = match Connection::open(&state.db_path) {
+    let mut conn = match state.pool.get() {
         Ok(c) => c,
         Err(_) => return error_response(500, "DB connection failed"),
     };
@@ -374,12 +494,27 @@ fn handle_save_grades(
     };
 
     for update in &payload.updates {
+        // La période doit exister pour l'année de la classe du cours
+        let known = tx
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM subjects s JOIN classes c ON s.class_id = c.id
+                 JOIN periods p ON p.academic_year_id = c.academic_year_id
+                 WHERE s.id = ? AND p.code = ?)",
+                params![update.subject_id, update.period],
+                |row| row.get::<_, bool>(0),
+            )
+            .unwrap_or(false);
+        if !known {
+            let _ = tx.rollback();
+            return error_response(400, "Unknown period");
+        }
+
         // Upsert
         let res = tx.execute(
             "INSERT INTO grades (student_id, subject_id, period, value, is_dirty, last_modified_at)
              VALUES (?1, ?2, ?3, ?4, 1, datetime('now'))
              ON CONFLICT(student_id, subject_id, period)
-             DO UPDATE SET value = ?4, is_dirty = 1, last_modified_at = datetime('now')",
+             DO UPDATE SET value = ?4, is_estimated = 0, is_computed = 0, is_dirty = 1, last_modified_at = datetime('now')",
             params![
                 update.student_id,
                 update.subject_id,
@@ -387,9 +522,44 @@ fn handle_save_grades(
                 update.value
             ],
         );
-        if res.is_err() {
+        // Les triggers de grade_validation refusent une cote hors [0, maximum]
+        if let Err(e) = res {
             let _ = tx.rollback();
-            return error_response(500, "Failed to update grades");
+            return match e.sqlite_error_code() {
+                Some(rusqlite::ErrorCode::ConstraintViolation) => {
+                    error_response(400, &e.to_string())
+                }
+                _ => error_response(500, "Failed to update grades"),
+            };
+        }
+    }
+
+    // Points d'interrogations : la cote de période recalculée rejoint les mises à jour diffusées
+    let mut updates = payload.updates;
+    let mut by_evaluation: BTreeMap<i64, Vec<evaluations::EvaluationScore>> = BTreeMap::new();
+    for score in &payload.evaluation_scores {
+        by_evaluation
+            .entry(score.evaluation_id)
+            .or_default()
+            .push(evaluations::EvaluationScore {
+                student_id: score.student_id,
+                value: score.value,
+            });
+    }
+    for (evaluation_id, scores) in &by_evaluation {
+        let computed = evaluations::write_scores(&tx, *evaluation_id, scores)
+            .and_then(|(subject_id, period)| evaluations::recompute(&tx, subject_id, &period));
+        match computed {
+            Ok(grades) => updates.extend(grades.into_iter().map(|g| GradeUpdate {
+                student_id: g.student_id,
+                subject_id: g.subject_id,
+                period: g.period,
+                value: g.value,
+            })),
+            Err(e) => {
+                let _ = tx.rollback();
+                return error_response(400, &e);
+            }
         }
     }
 
@@ -400,13 +570,14 @@ fn handle_save_grades(
     // Notify Desktop (Batch granular update)
     let event_payload = json!({
         "type": "grade_update",
-        "updates": payload.updates
+        "updates": updates,
+        "evaluationScores": payload.evaluation_scores
     });
     let _ = state.app_handle.emit("db:changed", &event_payload);
 
     // Broadcast to Mobile Clients (Keep individual updates if that's what they expect, or batch if supported)
     // For safety, let's just broadcast individual updates as per previous logic which likely works for mobile sync
-    for update in payload.updates {
+    for update in updates {
         let msg = serde_json::to_value(&update).unwrap();
         broadcast_msg(msg);
     }
@@ -418,10 +589,7 @@ fn handle_save_grades(
 
 // --- Main Server Function ---
 
-pub fn start_web_server(
-    app_handle: tauri::AppHandle,
-    db_path: PathBuf,
-) -> Result<ServerInfo, String> {
+pub fn start_web_server(app_handle: tauri::AppHandle, pool: DbPool) -> Result<ServerInfo, String> {
     // Guard: Don't start if already running
     {
         let existing = SERVER_INFO.lock().unwrap();
@@ -463,10 +631,7 @@ pub fn start_web_server(
     let _ = app_handle.emit("server-ready", ());
     println!("Tiny Server running at http://{}:{}", ip, port_val);
 
-    let app_state = Arc::new(AppState {
-        db_path,
-        app_handle,
-    });
+    let app_state = Arc::new(AppState { pool, app_handle });
 
     // Spawn the request handling loop
     std::thread::spawn(move || {
@@ -505,6 +670,37 @@ pub fn start_web_server(
                     }
                 }
 
+                // GET /api/classes/:id/at-risk
+                if method == Method::Get
+                    && path.starts_with("/api/classes/")
+                    && path.ends_with("/at-risk")
+                {
+                    let parts: Vec<&str> = path.split('/').collect();
+                    if let Some(Ok(id)) = parts.get(3).map(|s| s.parse::<i64>()) {
+                        let _ = request.respond(handle_get_class_risk(id, &state));
+                        return;
+                    }
+                }
+
+                // GET /api/classes/:id/conduct
+                if method == Method::Get
+                    && path.starts_with("/api/classes/")
+                    && path.ends_with("/conduct")
+                {
+                    let parts: Vec<&str> = path.split('/').collect();
+                    if let Some(Ok(id)) = parts.get(3).map(|s| s.parse::<i64>()) {
+                        let _ = request.respond(handle_get_class_conduct(id, &state));
+                        return;
+                    }
+                }
+
+                // POST /api/conduct/batch
+                if method == Method::Post && path == "/api/conduct/batch" {
+                    let response = handle_save_conduct(&mut request, &state);
+                    let _ = request.respond(response);
+                    return;
+                }
+
                 // POST /api/grades/batch
                 if method == Method::Post && path == "/api/grades/batch" {
                     let response = handle_save_grades(&mut request, &state);
@@ -631,17 +827,21 @@ struct StudentResponse {
     post_name: String,
 }
 
+#[derive(Serialize)]
+struct PeriodResponse {
+    code: String,
+    label: String,
+    semester: i64,
+    is_exam: bool,
+}
+
 #[derive(Serialize)]
 struct SubjectResponse {
     id: i64,
     name: String,
     code: String,
-    max_p1: f64,
-    max_p2: f64,
-    max_exam1: f64,
-    max_p3: f64,
-    max_p4: f64,
-    max_exam2: f64,
+    max_points: BTreeMap<String, f64>,
+    coefficient: Option<f64>,
 }
 
 #[derive(Serialize)]
@@ -652,6 +852,23 @@ struct GradeResponse {
     value: f64,
 }
 
+#[derive(Serialize)]
+struct EvaluationResponse {
+    id: i64,
+    subject_id: i64,
+    period_code: String,
+    name: String,
+    max_points: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct EvaluationScoreUpdate {
+    evaluation_id: i64,
+    student_id: i64,
+    // null efface les points de l'élève
+    value: Option<f64>,
+}
+
 #[derive(Serialize)]
 struct CustomSortResponse {
     id: i64,
@@ -661,9 +878,12 @@ struct CustomSortResponse {
 
 #[derive(Serialize)]
 struct ClassFullResponse {
+    periods: Vec<PeriodResponse>,
     students: Vec<StudentResponse>,
     subjects: Vec<SubjectResponse>,
     grades: Vec<GradeResponse>,
+    evaluations: Vec<EvaluationResponse>,
+    evaluation_scores: Vec<EvaluationScoreUpdate>,
     custom_sorts: Vec<CustomSortResponse>,
 }
 
@@ -675,7 +895,15 @@ struct GradeUpdate {
     value: f64,
 }
 
+#[derive(Deserialize, Debug)]
+struct BatchConductRequest {
+    updates: Vec<conduct::ConductUpdate>,
+}
+
 #[derive(Deserialize, Debug)]
 struct BatchGradeRequest {
+    #[serde(default)]
     updates: Vec<GradeUpdate>,
+    #[serde(default)]
+    evaluation_scores: Vec<EvaluationScoreUpdate>,
 }
@@ -0,0 +1,345 @@
+// Statistiques d'une classe pour une période, un semestre ou l'année : moyenne, médiane,
+// écart type, extrêmes, taux de réussite et histogramme, par cours et pour la classe
+// entière, avec la répartition par genre. Les totaux viennent du moteur de bulletins.
+
+use crate::bulletin::{self, BulletinColumn, Score, StudentBulletin};
+use crate::db::DbPool;
+use crate::gradebook::Gradebook;
+use crate::ranking::IncompletePolicy;
+use log::info;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+// Réussite : au moins la moitié du maximum de la période
+pub const PASS_PERCENTAGE: f64 = 50.0;
+// Tranches de 10 % ; 100 % tombe dans la dernière
+pub const HISTOGRAM_BUCKETS: usize = 10;
+
+const EPSILON: f64 = 1e-9;
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HistogramBucket {
+    pub from_percentage: f64,
+    pub to_percentage: f64,
+    pub count: usize,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Summary {
+    // Élèves dont la colonne est complète ; les autres ne comptent pas
+    pub count: usize,
+    pub max_points: f64,
+    pub mean: Option<f64>,
+    pub median: Option<f64>,
+    pub std_dev: Option<f64>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub mean_percentage: Option<f64>,
+    pub passed: usize,
+    pub pass_rate: Option<f64>,
+    pub histogram: Vec<HistogramBucket>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct GenderSummary {
+    pub gender: String,
+    pub summary: Summary,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SubjectStatistics {
+    pub subject_id: i64,
+    pub code: String,
+    pub name: String,
+    // Élèves (hors abandons) auxquels il manque une cote
+    pub incomplete: usize,
+    pub overall: Summary,
+    pub by_gender: Vec<GenderSummary>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassStatistics {
+    pub class_id: i64,
+    pub class_name: String,
+    pub level: String,
+    pub column: BulletinColumn,
+    // Élèves hors abandons
+    pub students: usize,
+    pub incomplete: usize,
+    pub overall: Summary,
+    pub by_gender: Vec<GenderSummary>,
+    pub subjects: Vec<SubjectStatistics>,
+}
+
+fn round2(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+// (points, maximum) des colonnes complètes
+pub fn summarize(samples: &[(f64, f64)]) -> Summary {
+    let mut histogram: Vec<HistogramBucket> = (0..HISTOGRAM_BUCKETS)
+        .map(|i| HistogramBucket {
+            from_percentage: (i * 100 / HISTOGRAM_BUCKETS) as f64,
+            to_percentage: ((i + 1) * 100 / HISTOGRAM_BUCKETS) as f64,
+            count: 0,
+        })
+        .collect();
+    let count = samples.len();
+    let max_points = samples.iter().map(|s| s.1).fold(0.0, f64::max);
+    if count == 0 {
+        return Summary {
+            count,
+            max_points,
+            mean: None,
+            median: None,
+            std_dev: None,
+            min: None,
+            max: None,
+            mean_percentage: None,
+            passed: 0,
+            pass_rate: None,
+            histogram,
+        };
+    }
+
+    let mut points: Vec<f64> = samples.iter().map(|s| s.0).collect();
+    points.sort_by(f64::total_cmp);
+    let n = count as f64;
+    let mean = points.iter().sum::<f64>() / n;
+    let median = if count % 2 == 1 {
+        points[count / 2]
+    } else {
+        (points[count / 2 - 1] + points[count / 2]) / 2.0
+    };
+    // Écart type de la population : la classe entière, pas un échantillon
+    let variance = points.iter().map(|p| (p - mean).powi(2)).sum::<f64>() / n;
+
+    let percentages: Vec<f64> = samples
+        .iter()
+        .map(|(p, max)| if *max > 0.0 { p * 100.0 / max } else { 0.0 })
+        .collect();
+    let passed = percentages
+        .iter()
+        .filter(|p| **p + EPSILON >= PASS_PERCENTAGE)
+        .count();
+    for percentage in &percentages {
+        let bucket = ((percentage / 100.0 * HISTOGRAM_BUCKETS as f64)
+            .floor()
+            .max(0.0) as usize)
+            .min(HISTOGRAM_BUCKETS - 1);
+        histogram[bucket].count += 1;
+    }
+
+    Summary {
+        count,
+        max_points,
+        mean: Some(round2(mean)),
+        median: Some(round2(median)),
+        std_dev: Some(round2(variance.sqrt())),
+        min: points.first().copied(),
+        max: points.last().copied(),
+        mean_percentage: Some(round2(percentages.iter().sum::<f64>() / n)),
+        passed,
+        pass_rate: Some(round2(passed as f64 * 100.0 / n)),
+        histogram,
+    }
+}
+
+fn sample(score: &Score) -> Option<(f64, f64)> {
+    match score.points {
+        Some(points) if !score.missing && score.max > 0.0 => Some((points, score.max)),
+        _ => None,
+    }
+}
+
+// Résumé global puis par genre d'une colonne de scores
+fn breakdown<'a>(
+    students: &[(&'a StudentBulletin, &'a str)],
+    score_of: impl Fn(&'a StudentBulletin) -> &'a Score,
+) -> (Summary, Vec<GenderSummary>, usize) {
+    let mut all = Vec::new();
+    let mut by_gender: BTreeMap<&str, Vec<(f64, f64)>> = BTreeMap::new();
+    let mut incomplete = 0;
+    for (student, gender) in students {
+        let entry = by_gender.entry(gender).or_default();
+        match sample(score_of(student)) {
+            Some(s) => {
+                all.push(s);
+                entry.push(s);
+            }
+            None => incomplete += 1,
+        }
+    }
+    let by_gender = by_gender
+        .into_iter()
+        .map(|(gender, samples)| GenderSummary {
+            gender: gender.to_string(),
+            summary: summarize(&samples),
+        })
+        .collect();
+    (summarize(&all), by_gender, incomplete)
+}
+
+pub fn class_statistics_for(
+    book: &Gradebook,
+    class_id: i64,
+    period: &str,
+) -> Result<ClassStatistics, String> {
+    let result = bulletin::compute(book, class_id, IncompletePolicy::default());
+    let index = bulletin::column_index(&result.columns, period)?;
+
+    // Les abandons ne comptent dans aucune statistique
+    let students: Vec<(&StudentBulletin, &str)> = result
+        .students
+        .iter()
+        .zip(&book.students)
+        .filter(|(student, _)| !student.abandoned)
+        .map(|(student, info)| (student, info.gender.as_str()))
+        .collect();
+
+    let subjects = book
+        .subjects
+        .iter()
+        .enumerate()
+        // Un cours sans maximum sur la colonne n'y est pas noté
+        .filter(|(_, subject)| {
+            result.columns[index]
+                .period_codes
+                .iter()
+                .any(|code| subject.max_for(code) > 0.0)
+        })
+        .map(|(position, subject)| {
+            let (overall, by_gender, incomplete) =
+                breakdown(&students, |s| &s.subjects[position].scores[index]);
+            SubjectStatistics {
+                subject_id: subject.id,
+                code: subject.code.clone(),
+                name: subject.name.clone(),
+                incomplete,
+                overall,
+                by_gender,
+            }
+        })
+        .collect();
+
+    let (overall, by_gender, incomplete) = breakdown(&students, |s| &s.totals[index]);
+    Ok(ClassStatistics {
+        class_id,
+        class_name: result.class_name.clone(),
+        level: result.level.clone(),
+        column: result.columns[index].clone(),
+        students: students.len(),
+        incomplete,
+        overall,
+        by_gender,
+        subjects,
+    })
+}
+
+#[tauri::command]
+pub async fn class_statistics(
+    pool: tauri::State<'_, DbPool>,
+    class_id: i64,
+    period: String,
+) -> Result<ClassStatistics, String> {
+    let result = pool
+        .run(move |conn| {
+            let book = Gradebook::load(conn, class_id)?;
+            class_statistics_for(&book, class_id, &period)
+        })
+        .await?;
+    info!(
+        "Statistiques de la classe {} ({}) : {} élève(s), {} cours",
+        class_id,
+        result.column.key,
+        result.students,
+        result.subjects.len()
+    );
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::TestDb;
+
+    #[test]
+    fn moyenne_mediane_et_ecart_type() {
+        let summary = summarize(&[(4.0, 10.0), (5.0, 10.0), (10.0, 10.0), (7.0, 10.0)]);
+        assert_eq!(summary.count, 4);
+        assert_eq!(summary.mean, Some(6.5));
+        // Effectif pair : moyenne des deux valeurs centrales
+        assert_eq!(summary.median, Some(6.0));
+        // Population : racine de 21 / 4, pas de 21 / 3
+        assert_eq!(summary.std_dev, Some(2.29));
+        assert_eq!((summary.min, summary.max), (Some(4.0), Some(10.0)));
+        assert_eq!(summary.mean_percentage, Some(65.0));
+        assert_eq!(
+            summarize(&[(3.0, 10.0), (9.0, 10.0), (4.0, 10.0)]).median,
+            Some(4.0)
+        );
+
+        let empty = summarize(&[]);
+        assert_eq!((empty.count, empty.mean, empty.pass_rate), (0, None, None));
+        assert_eq!(empty.histogram.len(), HISTOGRAM_BUCKETS);
+    }
+
+    #[test]
+    fn reussite_a_cinquante_pour_cent_exactement() {
+        let summary = summarize(&[(5.0, 10.0), (4.99, 10.0), (10.0, 20.0), (12.0, 20.0)]);
+        assert_eq!(summary.passed, 3);
+        assert_eq!(summary.pass_rate, Some(75.0));
+        assert_eq!(summary.histogram[4].count, 1);
+        assert_eq!(summary.histogram[5].count, 2);
+    }
+
+    #[test]
+    fn cent_pour_cent_dans_la_derniere_tranche() {
+        let summary = summarize(&[(10.0, 10.0), (9.5, 10.0), (0.0, 10.0)]);
+        let last = summary.histogram.last().unwrap();
+        assert_eq!((last.from_percentage, last.to_percentage), (90.0, 100.0));
+        assert_eq!(last.count, 2);
+        assert_eq!(summary.histogram[0].count, 1);
+        assert_eq!(summary.histogram.iter().map(|b| b.count).sum::<usize>(), 3);
+    }
+
+    #[test]
+    fn repartition_par_genre_sans_les_abandons() {
+        let db = TestDb::open();
+        let conn = db.conn();
+        conn.execute_batch(
+            "INSERT INTO academic_years (id, name, start_date, end_date, is_active) VALUES (1, '2024-2025', '2024-09-01', '2025-07-01', 1);
+             INSERT INTO classes (id, name, level, option, section, academic_year_id) VALUES (1, '2ème ETRO', '2ème', 'ELECTRONIQUE', 'A', 1);
+             INSERT INTO subjects (id, name, code, class_id) VALUES (1, 'Maths', 'MATH', 1);
+             INSERT INTO students (id, first_name, last_name, gender, class_id) VALUES
+               (1, 'Jean', 'Kabila', 'M', 1), (2, 'Paul', 'Ilunga', 'M', 1), (3, 'Marie', 'Mbuyi', 'F', 1),
+               (4, 'Ruth', 'Kasongo', 'F', 1), (5, 'Anne', 'Mutombo', 'F', 1);
+             UPDATE students SET abandon_reason = 'Déménagement' WHERE id = 5;
+             INSERT INTO grades (student_id, subject_id, period, value) VALUES
+               (1, 1, 'P1', 4), (2, 1, 'P1', 6), (3, 1, 'P1', 8), (5, 1, 'P1', 1);",
+        )
+        .unwrap();
+        let book = Gradebook::load(&conn, 1).unwrap();
+        assert!(class_statistics_for(&book, 1, "P9").is_err());
+
+        let statistics = class_statistics_for(&book, 1, "P1").unwrap();
+        assert_eq!((statistics.students, statistics.incomplete), (4, 1));
+        assert_eq!(statistics.overall.count, 3);
+        assert_eq!(statistics.overall.mean, Some(6.0));
+        let genders: Vec<(&str, usize, Option<f64>)> = statistics
+            .by_gender
+            .iter()
+            .map(|g| (g.gender.as_str(), g.summary.count, g.summary.mean))
+            .collect();
+        assert_eq!(genders, [("F", 1, Some(8.0)), ("M", 2, Some(5.0))]);
+
+        let maths = &statistics.subjects[0];
+        assert_eq!((maths.code.as_str(), maths.incomplete), ("MATH", 1));
+        assert_eq!(maths.overall.pass_rate, Some(66.67));
+    }
+}
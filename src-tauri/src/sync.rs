@@ -1,8 +1,12 @@
-use crate::{get_cloud_url, get_db_path, get_hwid_internal, SchoolInfo};
+use crate::archive;
+use crate::backup::{self, BackupKind};
+use crate::db::DbPool;
+use crate::{get_cloud_url, get_hwid_internal, SchoolInfo};
 use log::{error, info};
 use reqwest::header::{AUTHORIZATION, CONTENT_TYPE};
-use rusqlite::{params, Connection};
+use rusqlite::{params, Connection, Transaction};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct SyncResult {
@@ -23,6 +27,10 @@ pub struct SyncData {
     pub grades: Vec<GradePush>,
     pub repechages: Vec<RepechagePush>,
     pub notes: Vec<NotePush>,
+    pub periods: Vec<PeriodPush>,
+    pub evaluations: Vec<EvaluationPush>,
+    #[serde(rename = "evaluationScores")]
+    pub evaluation_scores: Vec<EvaluationScorePush>,
     pub deletions: Vec<SyncDeletionEntry>,
 }
 
@@ -112,10 +120,15 @@ pub struct SubjectPush {
     pub maxP3: i64,
     pub maxP4: i64,
     pub maxExam2: i64,
+    // Maximum par code de période (table subject_period_max)
+    #[serde(default)]
+    pub maxPoints: BTreeMap<String, i64>,
     pub category: String,
     pub subDomain: String,
     pub domainLocalId: Option<i64>,
     pub classLocalId: i64,
+    #[serde(default)]
+    pub coefficient: Option<f64>,
     #[serde(alias = "lastModifiedAt")]
     pub last_modified_at: String,
 }
@@ -131,6 +144,8 @@ pub struct GradePush {
     pub points: f64,
     #[serde(alias = "lastModifiedAt")]
     pub last_modified_at: String,
+    #[serde(default)]
+    pub isEstimated: bool,
 }
 #[allow(non_snake_case)]
 #[derive(Serialize, Deserialize, Debug)]
@@ -159,6 +174,52 @@ pub struct RepechagePush {
     pub last_modified_at: String,
 }
 
+#[allow(non_snake_case)]
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PeriodPush {
+    pub localId: i64,
+    #[serde(alias = "id")]
+    pub serverId: Option<String>,
+    pub academicYearLocalId: i64,
+    pub code: String,
+    pub label: String,
+    pub semester: i64,
+    pub isExam: bool,
+    pub displayOrder: i64,
+    pub defaultMax: i64,
+    #[serde(alias = "lastModifiedAt")]
+    pub last_modified_at: String,
+}
+
+#[allow(non_snake_case)]
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EvaluationPush {
+    pub localId: i64,
+    #[serde(alias = "id")]
+    pub serverId: Option<String>,
+    pub subjectLocalId: i64,
+    pub periodCode: String,
+    pub name: String,
+    pub maxPoints: f64,
+    pub evalDate: Option<String>,
+    pub displayOrder: i64,
+    #[serde(alias = "lastModifiedAt")]
+    pub last_modified_at: String,
+}
+
+#[allow(non_snake_case)]
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EvaluationScorePush {
+    pub localId: i64,
+    #[serde(alias = "id")]
+    pub serverId: Option<String>,
+    pub evaluationLocalId: i64,
+    pub studentLocalId: i64,
+    pub value: f64,
+    #[serde(alias = "lastModifiedAt")]
+    pub last_modified_at: String,
+}
+
 #[derive(Deserialize, Debug)]
 pub struct PushResponse {
     pub success: bool,
@@ -175,6 +236,9 @@ pub struct PushResults {
     pub grades: Option<Vec<ResultItem>>,
     pub repechages: Option<Vec<ResultItem>>,
     pub notes: Option<Vec<ResultItem>>,
+    pub periods: Option<Vec<ResultItem>>,
+    pub evaluations: Option<Vec<ResultItem>>,
+    pub evaluationScores: Option<Vec<ResultItem>>,
     pub deletions: Option<Vec<DeletionResultItem>>,
 }
 
@@ -221,12 +285,85 @@ fn collect_push_data(conn: &Connection) -> Result<SyncData, String> {
         })
         .map_err(|e| e.to_string())?;
 
-    let subjects_dirty: Vec<SubjectPush> = conn.prepare("SELECT id, name, COALESCE(code, '') AS code, max_p1, max_p2, max_exam1, max_p3, max_p4, max_exam2, COALESCE(category, '') AS category, COALESCE(sub_domain, '') AS sub_domain, domain_id, class_id, server_id, last_modified_at FROM subjects WHERE is_dirty = 1")
-        .and_then(|mut stmt| stmt.query_map([], |row| Ok(SubjectPush { localId: row.get(0)?, serverId: row.get(13)?, name: row.get(1)?, code: row.get(2)?, maxP1: row.get(3)?, maxP2: row.get(4)?, maxExam1: row.get(5)?, maxP3: row.get(6)?, maxP4: row.get(7)?, maxExam2: row.get(8)?, category: row.get(9)?, subDomain: row.get(10)?, domainLocalId: row.get(11)?, classLocalId: row.get(12)?, last_modified_at: row.get(14)? }))?.collect::<Result<Vec<_>, _>>())
+    let mut subjects_dirty: Vec<SubjectPush> = conn.prepare("SELECT id, name, COALESCE(code, '') AS code, max_p1, max_p2, max_exam1, max_p3, max_p4, max_exam2, COALESCE(category, '') AS category, COALESCE(sub_domain, '') AS sub_domain, domain_id, class_id, server_id, last_modified_at, coefficient FROM subjects WHERE is_dirty = 1")
+        .and_then(|mut stmt| stmt.query_map([], |row| Ok(SubjectPush { localId: row.get(0)?, serverId: row.get(13)?, name: row.get(1)?, code: row.get(2)?, maxP1: row.get(3)?, maxP2: row.get(4)?, maxExam1: row.get(5)?, maxP3: row.get(6)?, maxP4: row.get(7)?, maxExam2: row.get(8)?, maxPoints: BTreeMap::new(), category: row.get(9)?, subDomain: row.get(10)?, domainLocalId: row.get(11)?, classLocalId: row.get(12)?, coefficient: row.get(15)?, last_modified_at: row.get(14)? }))?.collect::<Result<Vec<_>, _>>())
         .map_err(|e| e.to_string())?;
 
-    let grades_dirty: Vec<GradePush> = conn.prepare("SELECT id, student_id, subject_id, period, value, server_id, last_modified_at FROM grades WHERE is_dirty = 1")
-        .and_then(|mut stmt| stmt.query_map([], |row| Ok(GradePush { localId: row.get(0)?, serverId: row.get(5)?, studentLocalId: row.get(1)?, subjectLocalId: row.get(2)?, period: row.get(3)?, points: row.get(4)?, last_modified_at: row.get(6)? }))?.collect::<Result<Vec<_>, _>>())
+    // Maxima par période de chaque cours modifié
+    {
+        let mut stmt = conn
+            .prepare("SELECT period_code, max_points FROM subject_period_max WHERE subject_id = ?")
+            .map_err(|e| e.to_string())?;
+        for subject in subjects_dirty.iter_mut() {
+            subject.maxPoints = stmt
+                .query_map(params![subject.localId], |row| {
+                    Ok((row.get(0)?, row.get(1)?))
+                })
+                .and_then(|rows| rows.collect::<Result<BTreeMap<_, _>, _>>())
+                .map_err(|e| e.to_string())?;
+        }
+    }
+
+    let periods_dirty: Vec<PeriodPush> = conn
+        .prepare("SELECT id, academic_year_id, code, label, semester, is_exam, display_order, default_max, server_id, last_modified_at FROM periods WHERE is_dirty = 1")
+        .and_then(|mut stmt| {
+            stmt.query_map([], |row| {
+                Ok(PeriodPush {
+                    localId: row.get(0)?,
+                    serverId: row.get(8)?,
+                    academicYearLocalId: row.get(1)?,
+                    code: row.get(2)?,
+                    label: row.get(3)?,
+                    semester: row.get(4)?,
+                    isExam: row.get::<_, i32>(5)? != 0,
+                    displayOrder: row.get(6)?,
+                    defaultMax: row.get(7)?,
+                    last_modified_at: row.get(9)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()
+        })
+        .map_err(|e| e.to_string())?;
+
+    let evaluations_dirty: Vec<EvaluationPush> = conn
+        .prepare("SELECT id, subject_id, period_code, name, max_points, eval_date, display_order, server_id, last_modified_at FROM evaluations WHERE is_dirty = 1")
+        .and_then(|mut stmt| {
+            stmt.query_map([], |row| {
+                Ok(EvaluationPush {
+                    localId: row.get(0)?,
+                    serverId: row.get(7)?,
+                    subjectLocalId: row.get(1)?,
+                    periodCode: row.get(2)?,
+                    name: row.get(3)?,
+                    maxPoints: row.get(4)?,
+                    evalDate: row.get(5)?,
+                    displayOrder: row.get(6)?,
+                    last_modified_at: row.get(8)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()
+        })
+        .map_err(|e| e.to_string())?;
+
+    let evaluation_scores_dirty: Vec<EvaluationScorePush> = conn
+        .prepare("SELECT id, evaluation_id, student_id, value, server_id, last_modified_at FROM evaluation_scores WHERE is_dirty = 1")
+        .and_then(|mut stmt| {
+            stmt.query_map([], |row| {
+                Ok(EvaluationScorePush {
+                    localId: row.get(0)?,
+                    serverId: row.get(4)?,
+                    evaluationLocalId: row.get(1)?,
+                    studentLocalId: row.get(2)?,
+                    value: row.get(3)?,
+                    last_modified_at: row.get(5)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()
+        })
+        .map_err(|e| e.to_string())?;
+
+    let grades_dirty: Vec<GradePush> = conn.prepare("SELECT id, student_id, subject_id, period, value, server_id, last_modified_at, COALESCE(is_estimated, 0) FROM grades WHERE is_dirty = 1")
+        .and_then(|mut stmt| stmt.query_map([], |row| Ok(GradePush { localId: row.get(0)?, serverId: row.get(5)?, studentLocalId: row.get(1)?, subjectLocalId: row.get(2)?, period: row.get(3)?, points: row.get(4)?, last_modified_at: row.get(6)?, isEstimated: row.get(7)? }))?.collect::<Result<Vec<_>, _>>())
         .map_err(|e| e.to_string())?;
 
     let repechages_dirty: Vec<RepechagePush> = conn.prepare("SELECT id, student_id, subject_id, value, percentage, server_id, last_modified_at FROM repechages WHERE is_dirty = 1")
@@ -260,6 +397,9 @@ fn collect_push_data(conn: &Connection) -> Result<SyncData, String> {
         grades: grades_dirty,
         repechages: repechages_dirty,
         notes: notes_dirty,
+        periods: periods_dirty,
+        evaluations: evaluations_dirty,
+        evaluation_scores: evaluation_scores_dirty,
         deletions,
     };
 
@@ -269,6 +409,9 @@ fn collect_push_data(conn: &Connection) -> Result<SyncData, String> {
     info!("- Domains: {}", data.domains.len());
     info!("- Students: {}", data.students.len());
     info!("- Subjects: {}", data.subjects.len());
+    info!("- Periods: {}", data.periods.len());
+    info!("- Evaluations: {}", data.evaluations.len());
+    info!("- Evaluation scores: {}", data.evaluation_scores.len());
     info!("- Deletions: {}", data.deletions.len());
 
     Ok(data)
@@ -393,6 +536,30 @@ fn process_push_response(conn: &Connection, response: PushResponse) -> Result<()
                 );
             }
         }
+        if let Some(per_res) = response.results.periods {
+            for item in per_res {
+                let _ = conn.execute(
+                    "UPDATE periods SET server_id = ?, is_dirty = 0 WHERE id = ?",
+                    params![item.serverId, item.localId],
+                );
+            }
+        }
+        if let Some(ev_res) = response.results.evaluations {
+            for item in ev_res {
+                let _ = conn.execute(
+                    "UPDATE evaluations SET server_id = ?, is_dirty = 0 WHERE id = ?",
+                    params![item.serverId, item.localId],
+                );
+            }
+        }
+        if let Some(sc_res) = response.results.evaluationScores {
+            for item in sc_res {
+                let _ = conn.execute(
+                    "UPDATE evaluation_scores SET server_id = ?, is_dirty = 0 WHERE id = ?",
+                    params![item.serverId, item.localId],
+                );
+            }
+        }
         if let Some(del_res) = response.results.deletions {
             for item in del_res {
                 if item.success {
@@ -431,6 +598,13 @@ pub struct PullData {
     pub grades: Vec<GradePush>,
     pub repechages: Vec<RepechagePush>,
     pub notes: Vec<NotePush>,
+    // Absent des réponses d'un cloud antérieur aux périodes configurables
+    #[serde(default)]
+    pub periods: Vec<PeriodPush>,
+    #[serde(default)]
+    pub evaluations: Vec<EvaluationPush>,
+    #[serde(default)]
+    pub evaluationScores: Vec<EvaluationScorePush>,
 }
 
 async fn pull_from_cloud(school_id: &str, token: &str) -> Result<PullData, String> {
@@ -461,42 +635,128 @@ async fn pull_from_cloud(school_id: &str, token: &str) -> Result<PullData, Strin
     info!("- Repechages: {}", response.data.repechages.len());
     info!("- Subjects: {}", response.data.subjects.len());
     info!("- Notes: {}", response.data.notes.len());
+    info!("- Periods: {}", response.data.periods.len());
+    info!("- Evaluations: {}", response.data.evaluations.len());
 
     Ok(response.data)
 }
 
-fn process_pull_data(mut conn: Connection, data: PullData) -> Result<i32, String> {
+// Une ligne déjà synchronisée (is_dirty = 0) mise à jour par le pull passe par le trigger
+// trg_{table}_updated_at, qui la remet en file : elle vient du cloud, elle est à jour.
+fn mark_pulled(tx: &Transaction, table: &str, id: i64) {
+    let _ = tx.execute(
+        &format!("UPDATE {} SET is_dirty = 0 WHERE id = ?", table),
+        params![id],
+    );
+}
+
+// Les lignes existantes sont mises à jour sur place (ON CONFLICT(id) DO UPDATE) : un
+// INSERT OR REPLACE supprimerait la ligne avant de la réinsérer et, avec les clés
+// étrangères actives, emporterait en cascade les cotes, repêchages, périodes... locaux.
+fn process_pull_data(conn: &mut Connection, data: PullData) -> Result<i32, String> {
     let mut total_added = 0;
     info!("Processing pulled data into local database...");
 
-    // Set busy timeout to handle contention
-    let _ = conn.execute("PRAGMA busy_timeout = 5000", []);
-
     let tx = conn
         .transaction()
         .map_err(|e| format!("Transaction Error: {}", e))?;
 
+    // Années archivées et retirées de la base : le cloud les renvoie, elles sont ignorées
+    // avec tout ce qu'elles contiennent
+    let (mut skipped_years, archived_server_ids) = archive::archived_years(&tx)?;
+    let mut skipped_classes = HashSet::new();
+    let mut skipped_students = HashSet::new();
+    let mut skipped_subjects = HashSet::new();
+    let mut skipped_evaluations = HashSet::new();
+    let mut skipped_rows = 0;
+
     let mut ay_count = 0;
     // Academic Years
     for ay in data.academicYears {
+        if skipped_years.contains(&ay.localId)
+            || ay
+                .serverId
+                .as_ref()
+                .is_some_and(|id| archived_server_ids.contains(id))
+        {
+            skipped_years.insert(ay.localId);
+            skipped_rows += 1;
+            continue;
+        }
         match tx.execute(
-            "INSERT OR REPLACE INTO academic_years (id, name, start_date, end_date, is_active, server_id, is_dirty) VALUES (?, ?, ?, ?, ?, ?, 0)",
+            "INSERT INTO academic_years (id, name, start_date, end_date, is_active, server_id, is_dirty) VALUES (?, ?, ?, ?, ?, ?, 0)
+             ON CONFLICT(id) DO UPDATE SET name = excluded.name, start_date = excluded.start_date, end_date = excluded.end_date, is_active = excluded.is_active, server_id = excluded.server_id, is_dirty = excluded.is_dirty",
             params![ay.localId, ay.name, ay.startDate, ay.endDate, ay.isCurrent as i32, ay.serverId],
         ) {
-            Ok(_) => ay_count += 1,
+            Ok(_) => {
+                mark_pulled(&tx, "academic_years", ay.localId);
+                ay_count += 1;
+            }
             Err(e) => error!("Failed to insert AY {}: {}", ay.localId, e),
         }
     }
     info!("Successfully processed {} academic years", ay_count);
 
+    // Periods (avant les cours, dont les maxima en dépendent). Le code identifie la période dans l'année.
+    let mut period_count = 0;
+    let mut pulled_codes: BTreeMap<i64, Vec<String>> = BTreeMap::new();
+    for p in data.periods {
+        if skipped_years.contains(&p.academicYearLocalId) {
+            skipped_rows += 1;
+            continue;
+        }
+        match tx.execute(
+            "INSERT INTO periods (academic_year_id, code, label, semester, is_exam, display_order, default_max, server_id, is_dirty) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, 0)
+             ON CONFLICT(academic_year_id, code) DO UPDATE SET label = ?3, semester = ?4, is_exam = ?5, display_order = ?6, default_max = ?7, server_id = ?8, is_dirty = 0",
+            params![p.academicYearLocalId, p.code, p.label, p.semester, p.isExam as i32, p.displayOrder, p.defaultMax, p.serverId],
+        ) {
+            Ok(_) => {
+                let _ = tx.execute(
+                    "UPDATE periods SET is_dirty = 0 WHERE academic_year_id = ? AND code = ?",
+                    params![p.academicYearLocalId, p.code],
+                );
+                period_count += 1;
+                pulled_codes.entry(p.academicYearLocalId).or_default().push(p.code);
+            }
+            Err(e) => error!("Failed to insert Period {}: {}", p.localId, e),
+        }
+    }
+    // Retire le découpage par défaut créé localement pour une année dont le cloud a fourni le sien
+    for (year_id, codes) in &pulled_codes {
+        let mut stmt = tx
+            .prepare("SELECT code FROM periods WHERE academic_year_id = ? AND server_id IS NULL")
+            .map_err(|e| e.to_string())?;
+        let local_only: Vec<String> = stmt
+            .query_map(params![year_id], |row| row.get(0))
+            .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| e.to_string())?;
+        for code in local_only.iter().filter(|c| !codes.contains(c)) {
+            let _ = tx.execute(
+                "DELETE FROM periods WHERE academic_year_id = ?1 AND code = ?2
+                 AND NOT EXISTS (SELECT 1 FROM grades g JOIN subjects s ON g.subject_id = s.id JOIN classes c ON s.class_id = c.id WHERE c.academic_year_id = ?1 AND g.period = ?2)",
+                params![year_id, code],
+            );
+        }
+    }
+    info!("Successfully processed {} periods", period_count);
+
     // Classes
     let mut class_count = 0;
     for c in data.classes {
+        if skipped_years.contains(&c.academicYearLocalId) {
+            skipped_classes.insert(c.localId);
+            skipped_rows += 1;
+            continue;
+        }
         match tx.execute(
-            "INSERT OR REPLACE INTO classes (id, name, level, option, section, academic_year_id, server_id, is_dirty) VALUES (?, ?, ?, ?, ?, ?, ?, 0)",
+            "INSERT INTO classes (id, name, level, option, section, academic_year_id, server_id, is_dirty) VALUES (?, ?, ?, ?, ?, ?, ?, 0)
+             ON CONFLICT(id) DO UPDATE SET name = excluded.name, level = excluded.level, option = excluded.option, section = excluded.section, academic_year_id = excluded.academic_year_id, server_id = excluded.server_id, is_dirty = excluded.is_dirty",
             params![c.localId, c.name, c.level, c.option, c.section, c.academicYearLocalId, c.serverId],
         ) {
-            Ok(_) => class_count += 1,
+            Ok(_) => {
+                mark_pulled(&tx, "classes", c.localId);
+                class_count += 1;
+            }
             Err(e) => error!("Failed to insert Class {}: {}", c.localId, e),
         }
     }
@@ -506,10 +766,14 @@ fn process_pull_data(mut conn: Connection, data: PullData) -> Result<i32, String
     let mut domain_count = 0;
     for d in data.domains {
         match tx.execute(
-            "INSERT OR REPLACE INTO domains (id, name, display_order, server_id, is_dirty) VALUES (?, ?, ?, ?, 0)",
+            "INSERT INTO domains (id, name, display_order, server_id, is_dirty) VALUES (?, ?, ?, ?, 0)
+             ON CONFLICT(id) DO UPDATE SET name = excluded.name, display_order = excluded.display_order, server_id = excluded.server_id, is_dirty = excluded.is_dirty",
             params![d.localId, d.name, d.displayOrder, d.serverId],
         ) {
-            Ok(_) => domain_count += 1,
+            Ok(_) => {
+                mark_pulled(&tx, "domains", d.localId);
+                domain_count += 1;
+            }
             Err(e) => error!("Failed to insert Domain {}: {}", d.localId, e),
         }
     }
@@ -518,11 +782,20 @@ fn process_pull_data(mut conn: Connection, data: PullData) -> Result<i32, String
     // Students
     let mut student_count = 0;
     for s in data.students {
+        if skipped_classes.contains(&s.classLocalId) {
+            skipped_students.insert(s.localId);
+            skipped_rows += 1;
+            continue;
+        }
         match tx.execute(
-            "INSERT OR REPLACE INTO students (id, first_name, last_name, post_name, gender, birth_date, birthplace, conduite, conduite_p1, conduite_p2, conduite_p3, conduite_p4, is_abandoned, abandon_reason, class_id, server_id, is_dirty) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 0)",
+            "INSERT INTO students (id, first_name, last_name, post_name, gender, birth_date, birthplace, conduite, conduite_p1, conduite_p2, conduite_p3, conduite_p4, is_abandoned, abandon_reason, class_id, server_id, is_dirty) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 0)
+             ON CONFLICT(id) DO UPDATE SET first_name = excluded.first_name, last_name = excluded.last_name, post_name = excluded.post_name, gender = excluded.gender, birth_date = excluded.birth_date, birthplace = excluded.birthplace, conduite = excluded.conduite, conduite_p1 = excluded.conduite_p1, conduite_p2 = excluded.conduite_p2, conduite_p3 = excluded.conduite_p3, conduite_p4 = excluded.conduite_p4, is_abandoned = excluded.is_abandoned, abandon_reason = excluded.abandon_reason, class_id = excluded.class_id, server_id = excluded.server_id, is_dirty = excluded.is_dirty",
             params![s.localId, s.firstName, s.lastName, s.postName, s.gender, s.birthDate, s.birthplace, s.conduite, s.conduiteP1, s.conduiteP2, s.conduiteP3, s.conduiteP4, s.isAbandoned as i32, s.abandonReason, s.classLocalId, s.serverId],
         ) {
-            Ok(_) => student_count += 1,
+            Ok(_) => {
+                mark_pulled(&tx, "students", s.localId);
+                student_count += 1;
+            }
             Err(e) => error!("Failed to insert Student {}: {}", s.localId, e),
         }
     }
@@ -531,11 +804,28 @@ fn process_pull_data(mut conn: Connection, data: PullData) -> Result<i32, String
     // Subjects
     let mut subject_count = 0;
     for sub in data.subjects {
+        if skipped_classes.contains(&sub.classLocalId) {
+            skipped_subjects.insert(sub.localId);
+            skipped_rows += 1;
+            continue;
+        }
         match tx.execute(
-            "INSERT OR REPLACE INTO subjects (id, name, code, max_p1, max_p2, max_exam1, max_p3, max_p4, max_exam2, category, sub_domain, domain_id, class_id, server_id, is_dirty) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 0)",
-            params![sub.localId, sub.name, sub.code, sub.maxP1, sub.maxP2, sub.maxExam1, sub.maxP3, sub.maxP4, sub.maxExam2, sub.category, sub.subDomain, sub.domainLocalId, sub.classLocalId, sub.serverId],
+            "INSERT INTO subjects (id, name, code, max_p1, max_p2, max_exam1, max_p3, max_p4, max_exam2, category, sub_domain, domain_id, class_id, coefficient, server_id, is_dirty) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 0)
+             ON CONFLICT(id) DO UPDATE SET name = excluded.name, code = excluded.code, max_p1 = excluded.max_p1, max_p2 = excluded.max_p2, max_exam1 = excluded.max_exam1, max_p3 = excluded.max_p3, max_p4 = excluded.max_p4, max_exam2 = excluded.max_exam2, category = excluded.category, sub_domain = excluded.sub_domain, domain_id = excluded.domain_id, class_id = excluded.class_id, coefficient = excluded.coefficient, server_id = excluded.server_id, is_dirty = excluded.is_dirty",
+            params![sub.localId, sub.name, sub.code, sub.maxP1, sub.maxP2, sub.maxExam1, sub.maxP3, sub.maxP4, sub.maxExam2, sub.category, sub.subDomain, sub.domainLocalId, sub.classLocalId, sub.coefficient, sub.serverId],
         ) {
-            Ok(_) => subject_count += 1,
+            Ok(_) => {
+                subject_count += 1;
+                for (code, max) in &sub.maxPoints {
+                    let _ = tx.execute(
+                        "INSERT INTO subject_period_max (subject_id, period_code, max_points) VALUES (?1, ?2, ?3)
+                         ON CONFLICT(subject_id, period_code) DO UPDATE SET max_points = ?3",
+                        params![sub.localId, code, max],
+                    );
+                }
+                // Les triggers de subject_period_max ont remis le cours en file de sync
+                mark_pulled(&tx, "subjects", sub.localId);
+            }
             Err(e) => error!("Failed to insert Subject {}: {}", sub.localId, e),
         }
     }
@@ -544,24 +834,89 @@ fn process_pull_data(mut conn: Connection, data: PullData) -> Result<i32, String
     // Grades
     let mut grade_count = 0;
     for g in data.grades {
+        if skipped_students.contains(&g.studentLocalId)
+            || skipped_subjects.contains(&g.subjectLocalId)
+        {
+            skipped_rows += 1;
+            continue;
+        }
         match tx.execute(
-            "INSERT OR REPLACE INTO grades (id, student_id, subject_id, period, value, server_id, is_dirty) VALUES (?, ?, ?, ?, ?, ?, 0)",
-            params![g.localId, g.studentLocalId, g.subjectLocalId, g.period, g.points, g.serverId],
+            "INSERT INTO grades (id, student_id, subject_id, period, value, server_id, is_estimated, is_dirty) VALUES (?, ?, ?, ?, ?, ?, ?, 0)
+             ON CONFLICT(id) DO UPDATE SET student_id = excluded.student_id, subject_id = excluded.subject_id, period = excluded.period, value = excluded.value, server_id = excluded.server_id, is_estimated = excluded.is_estimated, is_dirty = excluded.is_dirty",
+            params![g.localId, g.studentLocalId, g.subjectLocalId, g.period, g.points, g.serverId, g.isEstimated],
         ) {
-            Ok(_) => grade_count += 1,
+            Ok(_) => {
+                mark_pulled(&tx, "grades", g.localId);
+                grade_count += 1;
+            }
             Err(e) => error!("Failed to insert Grade {}: {}", g.localId, e),
         }
     }
     info!("Successfully processed {} grades", grade_count);
 
+    // Evaluations
+    let mut evaluation_count = 0;
+    for e in data.evaluations {
+        if skipped_subjects.contains(&e.subjectLocalId) {
+            skipped_evaluations.insert(e.localId);
+            skipped_rows += 1;
+            continue;
+        }
+        match tx.execute(
+            "INSERT INTO evaluations (id, subject_id, period_code, name, max_points, eval_date, display_order, server_id, is_dirty) VALUES (?, ?, ?, ?, ?, ?, ?, ?, 0)
+             ON CONFLICT(id) DO UPDATE SET subject_id = excluded.subject_id, period_code = excluded.period_code, name = excluded.name, max_points = excluded.max_points, eval_date = excluded.eval_date, display_order = excluded.display_order, server_id = excluded.server_id, is_dirty = excluded.is_dirty",
+            params![e.localId, e.subjectLocalId, e.periodCode, e.name, e.maxPoints, e.evalDate, e.displayOrder, e.serverId],
+        ) {
+            Ok(_) => {
+                mark_pulled(&tx, "evaluations", e.localId);
+                evaluation_count += 1;
+            }
+            Err(err) => error!("Failed to insert Evaluation {}: {}", e.localId, err),
+        }
+    }
+    info!("Successfully processed {} evaluations", evaluation_count);
+
+    // Evaluation scores (les cotes de période calculées arrivent déjà par grades)
+    let mut score_count = 0;
+    for sc in data.evaluationScores {
+        if skipped_evaluations.contains(&sc.evaluationLocalId)
+            || skipped_students.contains(&sc.studentLocalId)
+        {
+            skipped_rows += 1;
+            continue;
+        }
+        match tx.execute(
+            "INSERT INTO evaluation_scores (id, evaluation_id, student_id, value, server_id, is_dirty) VALUES (?, ?, ?, ?, ?, 0)
+             ON CONFLICT(id) DO UPDATE SET evaluation_id = excluded.evaluation_id, student_id = excluded.student_id, value = excluded.value, server_id = excluded.server_id, is_dirty = excluded.is_dirty",
+            params![sc.localId, sc.evaluationLocalId, sc.studentLocalId, sc.value, sc.serverId],
+        ) {
+            Ok(_) => {
+                mark_pulled(&tx, "evaluation_scores", sc.localId);
+                score_count += 1;
+            }
+            Err(e) => error!("Failed to insert Evaluation score {}: {}", sc.localId, e),
+        }
+    }
+    info!("Successfully processed {} evaluation scores", score_count);
+
     // Repechages
     let mut rep_count = 0;
     for r in data.repechages {
+        if skipped_students.contains(&r.studentLocalId)
+            || skipped_subjects.contains(&r.subjectLocalId)
+        {
+            skipped_rows += 1;
+            continue;
+        }
         match tx.execute(
-            "INSERT OR REPLACE INTO repechages (id, student_id, subject_id, value, percentage, server_id, is_dirty) VALUES (?, ?, ?, ?, ?, ?, 0)",
+            "INSERT INTO repechages (id, student_id, subject_id, value, percentage, server_id, is_dirty) VALUES (?, ?, ?, ?, ?, ?, 0)
+             ON CONFLICT(id) DO UPDATE SET student_id = excluded.student_id, subject_id = excluded.subject_id, value = excluded.value, percentage = excluded.percentage, server_id = excluded.server_id, is_dirty = excluded.is_dirty",
             params![r.localId, r.studentLocalId, r.subjectLocalId, r.value, r.percentage, r.serverId],
         ) {
-            Ok(_) => rep_count += 1,
+            Ok(_) => {
+                mark_pulled(&tx, "repechages", r.localId);
+                rep_count += 1;
+            }
             Err(e) => error!("Failed to insert Repechage {}: {}", r.localId, e),
         }
     }
@@ -570,15 +925,26 @@ fn process_pull_data(mut conn: Connection, data: PullData) -> Result<i32, String
     // Notes
     let mut note_count = 0;
     for n in data.notes {
+        if n.academicYearLocalId.is_some_and(|id| skipped_years.contains(&id)) {
+            skipped_rows += 1;
+            continue;
+        }
         match tx.execute(
-            "INSERT OR REPLACE INTO notes (id, title, content, academic_year_id, server_id, is_dirty) VALUES (?, ?, ?, ?, ?, 0)",
+            "INSERT INTO notes (id, title, content, academic_year_id, server_id, is_dirty) VALUES (?, ?, ?, ?, ?, 0)
+             ON CONFLICT(id) DO UPDATE SET title = excluded.title, content = excluded.content, academic_year_id = excluded.academic_year_id, server_id = excluded.server_id, is_dirty = excluded.is_dirty",
             params![n.localId, n.title, n.content, n.academicYearLocalId, n.serverId],
         ) {
-            Ok(_) => note_count += 1,
+            Ok(_) => {
+                mark_pulled(&tx, "notes", n.localId);
+                note_count += 1;
+            }
             Err(e) => error!("Failed to insert Note {}: {}", n.localId, e),
         }
     }
     info!("Successfully processed {} notes", note_count);
+    if skipped_rows > 0 {
+        info!("Skipped {} rows of archived years", skipped_rows);
+    }
 
     // Enregistre l'heure du dernier pull réussi
     let now = chrono::Utc::now().to_rfc3339();
@@ -596,88 +962,98 @@ fn process_pull_data(mut conn: Connection, data: PullData) -> Result<i32, String
         + grade_count
         + rep_count
         + note_count
-        + domain_count;
+        + domain_count
+        + period_count
+        + evaluation_count
+        + score_count;
     Ok(total_added)
 }
 
-#[tauri::command]
-pub async fn sync_push(app_handle: tauri::AppHandle) -> Result<SyncResult, String> {
+// Identifiants cloud enregistrés lors de l'activation
+fn read_credentials(conn: &mut Connection) -> Result<(String, String), String> {
+    let s_id: String = conn
+        .query_row(
+            "SELECT value FROM settings WHERE key = 'school_id'",
+            [],
+            |r| r.get(0),
+        )
+        .map_err(|_| "NOT_LINKED")?;
+    let tok: String = conn
+        .query_row(
+            "SELECT value FROM settings WHERE key = 'license_token'",
+            [],
+            |r| r.get(0),
+        )
+        .map_err(|_| "NOT_LINKED")?;
+    Ok((s_id, tok))
+}
+
+async fn push(pool: &DbPool) -> Result<SyncResult, String> {
     info!("Starting sync push process...");
-    let db_path = get_db_path(&app_handle);
-
-    let (school_id, token) = {
-        let conn = Connection::open(&db_path).map_err(|e| e.to_string())?;
-        let s_id: String = conn
-            .query_row(
-                "SELECT value FROM settings WHERE key = 'school_id'",
-                [],
-                |r| r.get(0),
-            )
-            .map_err(|_| "NOT_LINKED")?;
-        let tok: String = conn
-            .query_row(
-                "SELECT value FROM settings WHERE key = 'license_token'",
-                [],
-                |r| r.get(0),
-            )
-            .map_err(|_| "NOT_LINKED")?;
-        (s_id, tok)
-    };
 
-    let (sync_data, school_info, push_total) = {
-        let conn = Connection::open(&db_path).map_err(|e| e.to_string())?;
-
-        let s_name = conn
-            .query_row(
-                "SELECT value FROM settings WHERE key = 'school_name'",
-                [],
-                |r| r.get::<_, String>(0),
-            )
-            .unwrap_or_else(|_| "Unknown School".to_string());
-        let s_city = conn
-            .query_row(
-                "SELECT value FROM settings WHERE key = 'school_city'",
-                [],
-                |r| r.get::<_, String>(0),
-            )
-            .unwrap_or_else(|_| "Unknown City".to_string());
-        let s_pobox = conn
-            .query_row(
-                "SELECT value FROM settings WHERE key = 'school_pobox'",
-                [],
-                |r| r.get::<_, String>(0),
-            )
-            .ok();
-
-        let s_info = SchoolInfo {
-            id: school_id.clone(),
-            name: s_name,
-            city: s_city,
-            pobox: s_pobox,
-        };
-
-        let data = collect_push_data(&conn)?;
-        let total = data.academic_years.len()
-            + data.classes.len()
-            + data.domains.len()
-            + data.students.len()
-            + data.subjects.len()
-            + data.grades.len()
-            + data.repechages.len()
-            + data.notes.len()
-            + data.deletions.len();
-        (data, s_info, total as i32)
-    };
+    let (school_id, token) = pool.run(read_credentials).await?;
+
+    let s_id = school_id.clone();
+    let (sync_data, school_info, push_total) = pool
+        .run(move |conn| {
+            let s_name = conn
+                .query_row(
+                    "SELECT value FROM settings WHERE key = 'school_name'",
+                    [],
+                    |r| r.get::<_, String>(0),
+                )
+                .unwrap_or_else(|_| "Unknown School".to_string());
+            let s_city = conn
+                .query_row(
+                    "SELECT value FROM settings WHERE key = 'school_city'",
+                    [],
+                    |r| r.get::<_, String>(0),
+                )
+                .unwrap_or_else(|_| "Unknown City".to_string());
+            let s_pobox = conn
+                .query_row(
+                    "SELECT value FROM settings WHERE key = 'school_pobox'",
+                    [],
+                    |r| r.get::<_, String>(0),
+                )
+                .ok();
+
+            let s_info = SchoolInfo {
+                id: s_id,
+                name: s_name,
+                city: s_city,
+                pobox: s_pobox,
+            };
+
+            let data = collect_push_data(conn)?;
+            let total = data.academic_years.len()
+                + data.classes.len()
+                + data.domains.len()
+                + data.students.len()
+                + data.subjects.len()
+                + data.grades.len()
+                + data.repechages.len()
+                + data.notes.len()
+                + data.periods.len()
+                + data.evaluations.len()
+                + data.evaluation_scores.len()
+                + data.deletions.len();
+            Ok((data, s_info, total as i32))
+        })
+        .await?;
 
     if push_total == 0 {
         // Enregistre l'heure de la synchronisation même si rien n'était à envoyer
-        if let Ok(conn) = Connection::open(&db_path) {
+        pool.run(|conn| {
             let now = chrono::Utc::now().to_rfc3339();
             let _ = conn.execute(
                 "INSERT OR REPLACE INTO settings (key, value) VALUES ('last_sync_time', ?)",
                 params![now],
             );
-        }
+            Ok(())
+        })
+        .await
+        .ok();
         return Ok(SyncResult {
             success: true,
             error: None,
@@ -687,10 +1063,8 @@ pub async fn sync_push(app_handle: tauri::AppHandle) -> Result<SyncResult, Strin
 
     let push_response = send_push_data(&school_id, &token, sync_data, school_info).await?;
 
-    {
-        let conn = Connection::open(&db_path).map_err(|e| e.to_string())?;
-        process_push_response(&conn, push_response)?;
-    }
+    pool.run(move |conn| process_push_response(conn, push_response))
+        .await?;
 
     Ok(SyncResult {
         success: true,
@@ -699,35 +1073,20 @@ pub async fn sync_push(app_handle: tauri::AppHandle) -> Result<SyncResult, Strin
     })
 }
 
-#[tauri::command]
-pub async fn sync_pull(app_handle: tauri::AppHandle) -> Result<SyncResult, String> {
+async fn pull(pool: &DbPool) -> Result<SyncResult, String> {
     info!("Starting sync pull process...");
-    let db_path = get_db_path(&app_handle);
-
-    let (school_id, token) = {
-        let conn = Connection::open(&db_path).map_err(|e| e.to_string())?;
-        let s_id: String = conn
-            .query_row(
-                "SELECT value FROM settings WHERE key = 'school_id'",
-                [],
-                |r| r.get(0),
-            )
-            .map_err(|_| "NOT_LINKED")?;
-        let tok: String = conn
-            .query_row(
-                "SELECT value FROM settings WHERE key = 'license_token'",
-                [],
-                |r| r.get(0),
-            )
-            .map_err(|_| "NOT_LINKED")?;
-        (s_id, tok)
-    };
+
+    let (school_id, token) = pool.run(read_credentials).await?;
 
     let pull_data = pull_from_cloud(&school_id, &token).await?;
-    let pull_total = {
-        let conn = Connection::open(&db_path).map_err(|e| e.to_string())?;
-        process_pull_data(conn, pull_data)?
-    };
+
+    // Les données du cloud écrasent les lignes locales : on garde une copie avant
+    pool.run_with_path(|path| backup::create_backup(path, BackupKind::PreSync))
+        .await?;
+
+    let pull_total = pool
+        .run(move |conn| process_pull_data(conn, pull_data))
+        .await?;
 
     Ok(SyncResult {
         success: true,
@@ -737,9 +1096,19 @@ pub async fn sync_pull(app_handle: tauri::AppHandle) -> Result<SyncResult, Strin
 }
 
 #[tauri::command]
-pub async fn sync_start(app_handle: tauri::AppHandle) -> Result<SyncResult, String> {
-    let push = sync_push(app_handle.clone()).await?;
-    let pull = sync_pull(app_handle).await?;
+pub async fn sync_push(pool: tauri::State<'_, DbPool>) -> Result<SyncResult, String> {
+    push(&pool).await
+}
+
+#[tauri::command]
+pub async fn sync_pull(pool: tauri::State<'_, DbPool>) -> Result<SyncResult, String> {
+    pull(&pool).await
+}
+
+#[tauri::command]
+pub async fn sync_start(pool: tauri::State<'_, DbPool>) -> Result<SyncResult, String> {
+    let push = push(&pool).await?;
+    let pull = pull(&pool).await?;
 
     Ok(SyncResult {
         success: push.success && pull.success,
@@ -755,3 +1124,124 @@ pub async fn sync_start(app_handle: tauri::AppHandle) -> Result<SyncResult, Stri
         )),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::TestDb;
+
+    fn count(conn: &Connection, sql: &str) -> i64 {
+        conn.query_row(sql, [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn pull_met_a_jour_sans_supprimer_les_lignes_filles() {
+        let db = TestDb::open();
+        let mut conn = db.conn();
+        conn.execute_batch(
+            "INSERT INTO academic_years (id, name, start_date, end_date, server_id) VALUES (1, '2024-2025', '2024-09-01', '2025-07-01', 'ay-1');
+             INSERT INTO classes (id, name, level, option, section, academic_year_id, server_id) VALUES (1, '7ème A', '7ème', 'EB', 'A', 1, 'c-1');
+             INSERT INTO students (id, first_name, last_name, gender, class_id, server_id) VALUES (1, 'Jean', 'Kabila', 'M', 1, 's-1');
+             INSERT INTO subjects (id, name, code, class_id, server_id) VALUES (1, 'Maths', 'MATH', 1, 'sub-1');
+             UPDATE academic_years SET is_dirty = 0;
+             UPDATE classes SET is_dirty = 0;
+             UPDATE students SET is_dirty = 0;
+             UPDATE subjects SET is_dirty = 0;
+             -- Cotes saisies localement, jamais poussées
+             INSERT INTO grades (student_id, subject_id, period, value) VALUES (1, 1, 'P1', 7), (1, 1, 'P2', 8);
+             INSERT INTO repechages (student_id, subject_id, value, percentage) VALUES (1, 1, 30, 60);",
+        )
+        .unwrap();
+        let periods = count(&conn, "SELECT COUNT(*) FROM periods");
+        let maxima = count(&conn, "SELECT COUNT(*) FROM subject_period_max");
+        assert!(periods > 0 && maxima > 0);
+
+        let data: PullData = serde_json::from_value(serde_json::json!({
+            "academicYears": [{"localId": 1, "serverId": "ay-1", "name": "2024-2025", "startDate": "2024-09-01", "endDate": "2025-07-01", "isCurrent": true, "lastModifiedAt": ""}],
+            "classes": [{"localId": 1, "serverId": "c-1", "name": "7ème B", "level": "7ème", "option": "EB", "section": "B", "academicYearLocalId": 1, "lastModifiedAt": ""}],
+            "domains": [],
+            "students": [{"localId": 1, "serverId": "s-1", "firstName": "Jean", "lastName": "Kabila", "postName": "", "gender": "M", "birthDate": null, "birthplace": "", "conduite": "", "conduiteP1": "", "conduiteP2": "", "conduiteP3": "", "conduiteP4": "", "isAbandoned": false, "abandonReason": "", "classLocalId": 1, "lastModifiedAt": ""}],
+            "subjects": [{"localId": 1, "serverId": "sub-1", "name": "Mathématiques", "code": "MATH", "maxP1": 10, "maxP2": 10, "maxExam1": 20, "maxP3": 10, "maxP4": 10, "maxExam2": 20, "category": "", "subDomain": "", "domainLocalId": null, "classLocalId": 1, "lastModifiedAt": ""}],
+            "grades": [],
+            "repechages": [],
+            "notes": []
+        }))
+        .unwrap();
+        assert_eq!(process_pull_data(&mut conn, data).unwrap(), 4);
+
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM grades"), 2);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM grades WHERE is_dirty = 1"), 2);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM repechages"), 1);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM periods"), periods);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM subject_period_max"), maxima);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM sync_deletions"), 0);
+
+        let (name, dirty): (String, i64) = conn
+            .query_row("SELECT name, is_dirty FROM classes WHERE id = 1", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!((name.as_str(), dirty), ("7ème B", 0));
+        let dirty_parents = count(
+            &conn,
+            "SELECT (SELECT COUNT(*) FROM academic_years WHERE is_dirty = 1)
+                  + (SELECT COUNT(*) FROM students WHERE is_dirty = 1)
+                  + (SELECT COUNT(*) FROM subjects WHERE is_dirty = 1)",
+        );
+        assert_eq!(dirty_parents, 0);
+    }
+
+    #[test]
+    fn pull_ignore_les_annees_archivees() {
+        let db = TestDb::open();
+        let mut conn = db.conn();
+        conn.execute_batch(
+            "INSERT INTO academic_years (id, name, start_date, end_date, is_active, server_id) VALUES
+               (1, '2023-2024', '2023-09-01', '2024-07-01', 0, 'ay-1'), (2, '2024-2025', '2024-09-01', '2025-07-01', 1, 'ay-2');
+             INSERT INTO classes (id, name, level, option, section, academic_year_id, server_id) VALUES (1, '7ème A', '7ème', 'EB', 'A', 1, 'c-1');
+             INSERT INTO students (id, first_name, last_name, gender, class_id, server_id) VALUES (1, 'Jean', 'Kabila', 'M', 1, 's-1');
+             INSERT INTO subjects (id, name, code, class_id, server_id) VALUES (1, 'Maths', 'MATH', 1, 'sub-1');
+             INSERT INTO grades (id, student_id, subject_id, period, value, server_id) VALUES (1, 1, 1, 'P1', 7, 'g-1');",
+        )
+        .unwrap();
+        crate::archive::export_year(&mut conn, &db.path, 1, true, false).unwrap();
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM academic_years"), 1);
+
+        // Le cloud renvoie toujours l'année archivée, à côté d'une nouvelle classe de l'année en cours
+        let data: PullData = serde_json::from_value(serde_json::json!({
+            "academicYears": [
+                {"localId": 1, "serverId": "ay-1", "name": "2023-2024", "startDate": "2023-09-01", "endDate": "2024-07-01", "isCurrent": false, "lastModifiedAt": ""},
+                {"localId": 2, "serverId": "ay-2", "name": "2024-2025", "startDate": "2024-09-01", "endDate": "2025-07-01", "isCurrent": true, "lastModifiedAt": ""}
+            ],
+            "classes": [
+                {"localId": 1, "serverId": "c-1", "name": "7ème A", "level": "7ème", "option": "EB", "section": "A", "academicYearLocalId": 1, "lastModifiedAt": ""},
+                {"localId": 2, "serverId": "c-2", "name": "8ème A", "level": "8ème", "option": "EB", "section": "A", "academicYearLocalId": 2, "lastModifiedAt": ""}
+            ],
+            "domains": [],
+            "students": [{"localId": 1, "serverId": "s-1", "firstName": "Jean", "lastName": "Kabila", "postName": "", "gender": "M", "birthDate": null, "birthplace": "", "conduite": "", "conduiteP1": "", "conduiteP2": "", "conduiteP3": "", "conduiteP4": "", "isAbandoned": false, "abandonReason": "", "classLocalId": 1, "lastModifiedAt": ""}],
+            "subjects": [{"localId": 1, "serverId": "sub-1", "name": "Maths", "code": "MATH", "maxP1": 10, "maxP2": 10, "maxExam1": 20, "maxP3": 10, "maxP4": 10, "maxExam2": 20, "category": "", "subDomain": "", "domainLocalId": null, "classLocalId": 1, "lastModifiedAt": ""}],
+            "grades": [{"localId": 1, "serverId": "g-1", "studentLocalId": 1, "subjectLocalId": 1, "period": "P1", "points": 7, "lastModifiedAt": ""}],
+            "repechages": [],
+            "notes": [{"localId": 1, "serverId": "n-1", "title": "Rentrée", "content": "", "academicYearLocalId": 1, "lastModifiedAt": ""}]
+        }))
+        .unwrap();
+        assert_eq!(process_pull_data(&mut conn, data).unwrap(), 2);
+
+        assert_eq!(
+            count(&conn, "SELECT COUNT(*) FROM academic_years WHERE id = 1"),
+            0
+        );
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM classes"), 1);
+        assert_eq!(
+            count(&conn, "SELECT COUNT(*) FROM classes WHERE academic_year_id = 2"),
+            1
+        );
+        assert_eq!(
+            count(&conn, "SELECT COUNT(*) FROM students")
+                + count(&conn, "SELECT COUNT(*) FROM subjects")
+                + count(&conn, "SELECT COUNT(*) FROM grades")
+                + count(&conn, "SELECT COUNT(*) FROM notes"),
+            0
+        );
+    }
+}
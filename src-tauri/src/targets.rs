@@ -0,0 +1,553 @@
+// « Que me faut-il ? » : à partir des cotes connues et des maxima, la cote minimale à
+// obtenir dans chaque période ou examen restant pour atteindre un objectif (réussite,
+// aucun échec, une place au classement). Chaque proposition est vérifiée par la
+// délibération avec les règles de la classe, sans 2ème session.
+//
+// On cherche la plus petite part du maximum, la même pour toutes les cotes restantes,
+// qui atteint l'objectif. Pour « aucun échec », chaque cours reçoit d'abord la part
+// qui le ramène au seuil d'échec : un bon cours n'a pas à porter l'effort d'un faible.
+
+use crate::db::DbPool;
+use crate::delib_rules;
+use crate::deliberation::{self, DelibConfig, DelibMode, SubjectScore, Verdict};
+use crate::gradebook::Gradebook;
+use log::info;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+const EPSILON: f64 = 1e-9;
+// Pas de la recherche par dichotomie : bien en deçà du centième de point
+const ITERATIONS: usize = 40;
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Target {
+    // Pourcentage annuel au seuil de réussite des règles (50 % en général)
+    Pass,
+    // Admis sans échec : chaque cours au seuil d'échec et le seuil global atteint
+    NoFailures,
+    // Place au classement annuel, ex aequo compris
+    Rank { place: usize },
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PeriodNeed {
+    pub subject_id: i64,
+    pub period: String,
+    pub max_points: f64,
+    pub needed_points: f64,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SubjectNeed {
+    pub subject_id: i64,
+    pub code: String,
+    pub name: String,
+    pub known_points: f64,
+    pub known_max: f64,
+    pub remaining_max: f64,
+    pub needed_points: f64,
+    // Part du maximum restant à obtenir
+    pub needed_percentage: f64,
+    pub periods: Vec<PeriodNeed>,
+}
+
+// Total à obtenir sur une période, tous cours confondus
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PeriodSummary {
+    pub period: String,
+    pub label: String,
+    pub is_exam: bool,
+    pub max_points: f64,
+    pub needed_points: f64,
+    pub needed_percentage: f64,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TargetSimulation {
+    pub student_id: i64,
+    pub student_name: String,
+    pub class_id: i64,
+    pub target: Target,
+    pub reachable: bool,
+    // Atteint même avec zéro à toutes les cotes restantes
+    pub already_reached: bool,
+    // Pourcentage sur les seules cotes encodées
+    pub current_percentage: Option<f64>,
+    // Avec les cotes proposées (ou le maximum partout si l'objectif est hors de portée)
+    pub projected_percentage: Option<f64>,
+    pub best_percentage: Option<f64>,
+    pub projected_rank: usize,
+    pub verdict: Verdict,
+    pub verdict_label: String,
+    pub subjects: Vec<SubjectNeed>,
+    pub periods: Vec<PeriodSummary>,
+    pub reasoning: Vec<String>,
+}
+
+fn round2(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+// Arrondi au centième supérieur : une cote proposée ne doit jamais manquer l'objectif
+fn ceil2(value: f64) -> f64 {
+    ((value * 100.0) - 1e-6).ceil().max(0.0) / 100.0
+}
+
+// Cotes connues et cotes restantes d'un cours pour l'élève
+struct SubjectState {
+    score: SubjectScore,
+    known_points: f64,
+    known_max: f64,
+    remaining: Vec<(String, f64)>,
+}
+
+impl SubjectState {
+    fn remaining_max(&self) -> f64 {
+        self.remaining.iter().map(|(_, max)| max).sum()
+    }
+}
+
+fn subject_states(book: &Gradebook, student_id: i64) -> Vec<SubjectState> {
+    book.subjects
+        .iter()
+        .map(|subject| {
+            let (mut known_points, mut known_max) = (0.0, 0.0);
+            let mut remaining = Vec::new();
+            for period in &book.periods {
+                let max = subject.max_for(&period.code);
+                if max == 0.0 {
+                    continue;
+                }
+                match book.grade(student_id, subject.id, &period.code) {
+                    Some(value) => {
+                        known_points += value;
+                        known_max += max;
+                    }
+                    None => remaining.push((period.code.clone(), max)),
+                }
+            }
+            SubjectState {
+                score: SubjectScore {
+                    subject_id: subject.id,
+                    code: subject.code.clone(),
+                    name: subject.name.clone(),
+                    category: subject.category.clone(),
+                    domain: subject.domain.clone(),
+                    points: known_points,
+                    max_points: known_max,
+                    missing: false,
+                    repechage: None,
+                    weight: book.weight(subject),
+                },
+                known_points,
+                known_max,
+                remaining,
+            }
+        })
+        .filter(|s| s.known_max + s.remaining_max() > 0.0)
+        .collect()
+}
+
+// Pourcentage pondéré sur les cotes encodées
+fn partial_percentage(states: &[SubjectState]) -> Option<f64> {
+    let points: f64 = states.iter().map(|s| s.known_points * s.score.weight).sum();
+    let max: f64 = states.iter().map(|s| s.known_max * s.score.weight).sum();
+    (max > 0.0).then(|| points / max * 100.0)
+}
+
+// Totaux annuels quand chaque cours obtient la part donnée de ses cotes restantes
+fn filled_scores(states: &[SubjectState], shares: &[f64]) -> Vec<SubjectScore> {
+    states
+        .iter()
+        .zip(shares)
+        .map(|(state, share)| {
+            let remaining = state.remaining_max();
+            SubjectScore {
+                points: state.known_points + share * remaining,
+                max_points: state.known_max + remaining,
+                ..state.score.clone()
+            }
+        })
+        .collect()
+}
+
+// Pourcentage annuel non arrondi : celui de la délibération l'est au centième
+fn filled_percentage(states: &[SubjectState], shares: &[f64]) -> f64 {
+    let (points, max) = filled_scores(states, shares)
+        .iter()
+        .fold((0.0, 0.0), |(points, max), s| {
+            (points + s.points * s.weight, max + s.max_points * s.weight)
+        });
+    points / max * 100.0
+}
+
+// Part minimale des cotes restantes qui amène chaque cours au seuil d'échec.
+// Infinie quand le cours ne peut plus y arriver faute de cotes restantes.
+fn failure_floors(config: &DelibConfig, states: &[SubjectState]) -> Vec<f64> {
+    let ratio = config.seuil_echec_matiere / 100.0;
+    states
+        .iter()
+        .map(|state| {
+            let remaining = state.remaining_max();
+            let needed = (state.known_max + remaining) * ratio - state.known_points;
+            if needed <= EPSILON {
+                0.0
+            } else if remaining == 0.0 {
+                f64::INFINITY
+            } else {
+                needed / remaining
+            }
+        })
+        .collect()
+}
+
+// Place annuelle de l'élève face aux autres, projetés à leur pourcentage actuel
+fn projected_rank(percentage: f64, others: &[f64]) -> usize {
+    1 + others.iter().filter(|p| **p > percentage + EPSILON).count()
+}
+
+pub fn simulate_for(
+    book: &Gradebook,
+    config: &DelibConfig,
+    class_id: i64,
+    student_id: i64,
+    target: Target,
+) -> Result<TargetSimulation, String> {
+    let student = book
+        .students
+        .iter()
+        .find(|s| s.id == student_id)
+        .ok_or_else(|| "Élève introuvable dans la classe".to_string())?;
+    if student.abandoned {
+        return Err(format!(
+            "{} a abandonné : pas d'objectif à simuler",
+            student.name
+        ));
+    }
+    if let Target::Rank { place } = target {
+        if place == 0 {
+            return Err("La place visée doit être au moins 1".to_string());
+        }
+    }
+
+    let states = subject_states(book, student_id);
+    if states.is_empty() {
+        return Err("Aucun cours noté pour cette classe".to_string());
+    }
+    let others: Vec<f64> = book
+        .students
+        .iter()
+        .filter(|s| s.id != student_id && !s.abandoned)
+        .filter_map(|s| partial_percentage(&subject_states(book, s.id)))
+        .collect();
+
+    let floors = match target {
+        Target::NoFailures => failure_floors(config, &states),
+        Target::Pass | Target::Rank { .. } => vec![0.0; states.len()],
+    };
+    let shares_for = |uniform: f64| -> Vec<f64> {
+        floors
+            .iter()
+            .map(|floor| floor.max(uniform).min(1.0))
+            .collect()
+    };
+    let deliberate = |shares: &[f64]| {
+        deliberation::deliberate_student(
+            config,
+            DelibMode::FirstSession,
+            student_id,
+            &student.name,
+            false,
+            &filled_scores(&states, shares),
+        )
+    };
+    let reached = |shares: &[f64]| -> bool {
+        let percentage = filled_percentage(&states, shares);
+        match target {
+            Target::Pass => percentage + EPSILON >= config.seuil_reussite_global,
+            Target::NoFailures => deliberate(shares).verdict == Verdict::Admis,
+            Target::Rank { place } => projected_rank(percentage, &others) <= place,
+        }
+    };
+
+    let mut reasoning = Vec::new();
+    for (state, floor) in states.iter().zip(&floors) {
+        if *floor > 1.0 {
+            reasoning.push(format!(
+                "{} : {} / {} déjà perdus, le seuil de {} % n'est plus atteignable",
+                state.score.name,
+                round2(state.known_max - state.known_points),
+                state.known_max + state.remaining_max(),
+                config.seuil_echec_matiere
+            ));
+        }
+    }
+    let best = shares_for(1.0);
+    let reachable = floors.iter().all(|f| *f <= 1.0) && reached(&best);
+    let already_reached =
+        reachable && floors.iter().all(|f| *f == 0.0) && reached(&shares_for(0.0));
+
+    let shares = if !reachable {
+        best
+    } else if already_reached {
+        shares_for(0.0)
+    } else {
+        let (mut low, mut high) = (0.0, 1.0);
+        for _ in 0..ITERATIONS {
+            let middle = (low + high) / 2.0;
+            if reached(&shares_for(middle)) {
+                high = middle;
+            } else {
+                low = middle;
+            }
+        }
+        shares_for(high)
+    };
+    let projection = deliberate(&shares);
+
+    let subjects: Vec<SubjectNeed> = states
+        .iter()
+        .zip(&shares)
+        .map(|(state, share)| {
+            let periods: Vec<PeriodNeed> = state
+                .remaining
+                .iter()
+                .map(|(period, max)| PeriodNeed {
+                    subject_id: state.score.subject_id,
+                    period: period.clone(),
+                    max_points: *max,
+                    needed_points: ceil2(share * max).min(*max),
+                })
+                .collect();
+            let needed_points: f64 = periods.iter().map(|p| p.needed_points).sum();
+            SubjectNeed {
+                subject_id: state.score.subject_id,
+                code: state.score.code.clone(),
+                name: state.score.name.clone(),
+                known_points: state.known_points,
+                known_max: state.known_max,
+                remaining_max: state.remaining_max(),
+                needed_points: round2(needed_points),
+                needed_percentage: round2(share * 100.0),
+                periods,
+            }
+        })
+        .collect();
+
+    let periods: Vec<PeriodSummary> = book
+        .periods
+        .iter()
+        .filter_map(|period| {
+            let needs: Vec<&PeriodNeed> = subjects
+                .iter()
+                .flat_map(|s| s.periods.iter())
+                .filter(|p| p.period == period.code)
+                .collect();
+            if needs.is_empty() {
+                return None;
+            }
+            let max_points: f64 = needs.iter().map(|p| p.max_points).sum();
+            let needed_points: f64 = needs.iter().map(|p| p.needed_points).sum();
+            Some(PeriodSummary {
+                period: period.code.clone(),
+                label: period.label.clone(),
+                is_exam: period.is_exam,
+                max_points,
+                needed_points: round2(needed_points),
+                needed_percentage: round2(needed_points / max_points * 100.0),
+            })
+        })
+        .collect();
+
+    if periods.is_empty() {
+        reasoning.push("Toutes les cotes de l'année sont encodées".to_string());
+    } else if already_reached {
+        reasoning.push("Objectif atteint quelles que soient les cotes restantes".to_string());
+    } else if reachable {
+        for period in &periods {
+            reasoning.push(format!(
+                "{} : au moins {} / {} ({} %)",
+                period.label, period.needed_points, period.max_points, period.needed_percentage
+            ));
+        }
+    } else {
+        reasoning.push("Objectif hors de portée, même avec le maximum partout".to_string());
+    }
+    if let Target::Rank { .. } = target {
+        reasoning.push(
+            "Les autres élèves sont projetés à leur pourcentage actuel sur les cotes encodées"
+                .to_string(),
+        );
+    }
+
+    let projected_rank = projected_rank(filled_percentage(&states, &shares), &others);
+    Ok(TargetSimulation {
+        student_id,
+        student_name: student.name.clone(),
+        class_id,
+        target,
+        reachable,
+        already_reached,
+        current_percentage: partial_percentage(&states).map(round2),
+        projected_percentage: projection.percentage.map(round2),
+        best_percentage: deliberate(&shares_for(1.0)).percentage.map(round2),
+        projected_rank,
+        verdict: projection.verdict,
+        verdict_label: projection.verdict_label,
+        subjects,
+        periods,
+        reasoning,
+    })
+}
+
+pub fn simulate(
+    conn: &Connection,
+    student_id: i64,
+    target: Target,
+) -> Result<TargetSimulation, String> {
+    let class_id: i64 = conn
+        .query_row(
+            "SELECT class_id FROM students WHERE id = ?",
+            params![student_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Élève introuvable".to_string())?;
+    let book = Gradebook::load(conn, class_id)?;
+    let resolved = delib_rules::rules_for_class(conn, class_id)?;
+    simulate_for(&book, &resolved.rules, class_id, student_id, target)
+}
+
+#[tauri::command]
+pub async fn student_target_simulation(
+    pool: tauri::State<'_, DbPool>,
+    student_id: i64,
+    target: Target,
+) -> Result<TargetSimulation, String> {
+    let result = pool
+        .run(move |conn| simulate(conn, student_id, target))
+        .await?;
+    info!(
+        "Objectif {:?} de l'élève {} : {}",
+        target,
+        student_id,
+        if result.reachable {
+            "atteignable"
+        } else {
+            "hors de portée"
+        }
+    );
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::TestDb;
+
+    // Deux cours sur six périodes (80 points chacun) ; la classe 2 n'a que les maths
+    fn class_db() -> TestDb {
+        let db = TestDb::open();
+        db.conn()
+            .execute_batch(
+                "INSERT INTO academic_years (id, name, start_date, end_date, is_active) VALUES (1, '2024-2025', '2024-09-01', '2025-07-01', 1);
+                 INSERT INTO classes (id, name, level, option, section, academic_year_id) VALUES
+                   (1, '2ème ETRO', '2ème', 'ELECTRONIQUE', 'A', 1), (2, '2ème MECA', '2ème', 'MECANIQUE', 'A', 1);
+                 INSERT INTO subjects (id, name, code, class_id) VALUES (1, 'Maths', 'MATH', 1), (2, 'Français', 'FR', 1), (3, 'Maths', 'MATH', 2);
+                 INSERT INTO students (id, first_name, last_name, gender, class_id) VALUES
+                   (1, 'Jean', 'Kabila', 'M', 1), (2, 'Marie', 'Mbuyi', 'F', 1), (3, 'Paul', 'Ilunga', 'M', 1),
+                   (4, 'Ruth', 'Kasongo', 'F', 2), (5, 'Eric', 'Tshibangu', 'M', 2);
+                 INSERT INTO grades (student_id, subject_id, period, value) VALUES
+                   (1, 1, 'P1', 10), (1, 1, 'P2', 10), (1, 1, 'EXAM1', 20),
+                   (1, 2, 'P1', 10), (1, 2, 'P2', 10), (1, 2, 'EXAM1', 20),
+                   (2, 1, 'P1', 0), (2, 1, 'P2', 0), (2, 1, 'EXAM1', 0), (2, 1, 'P3', 0), (2, 1, 'P4', 0),
+                   (3, 1, 'P1', 2), (3, 1, 'P2', 3), (3, 2, 'P1', 9), (3, 2, 'P2', 9),
+                   (4, 3, 'P1', 6), (4, 3, 'P2', 0), (5, 3, 'P1', 6), (5, 3, 'P2', 6);",
+            )
+            .unwrap();
+        db
+    }
+
+    fn subject<'a>(simulation: &'a TargetSimulation, code: &str) -> &'a SubjectNeed {
+        simulation.subjects.iter().find(|s| s.code == code).unwrap()
+    }
+
+    #[test]
+    fn objectif_deja_atteint() {
+        let db = class_db();
+        let simulation = simulate(&db.conn(), 1, Target::Pass).unwrap();
+        assert!(simulation.reachable && simulation.already_reached);
+        assert_eq!(simulation.current_percentage, Some(100.0));
+        assert!(simulation.subjects.iter().all(|s| s.needed_points == 0.0));
+        assert_eq!(simulation.projected_percentage, Some(50.0));
+        assert_eq!(simulation.verdict, Verdict::Admis);
+        assert_eq!(
+            simulation.reasoning,
+            ["Objectif atteint quelles que soient les cotes restantes"]
+        );
+    }
+
+    #[test]
+    fn points_deja_perdus() {
+        let db = class_db();
+        let conn = db.conn();
+        // 60 points perdus sur 80 : l'examen restant ne ramène pas les maths à 40
+        let simulation = simulate(&conn, 2, Target::NoFailures).unwrap();
+        assert!(!simulation.reachable && !simulation.already_reached);
+        assert_ne!(simulation.verdict, Verdict::Admis);
+        assert_eq!(subject(&simulation, "MATH").needed_points, 20.0);
+        assert!(simulation.reasoning[0].starts_with("Maths : 60 / 80 déjà perdus"));
+        assert_eq!(
+            simulation.reasoning.last().map(String::as_str),
+            Some("Objectif hors de portée, même avec le maximum partout")
+        );
+        // La réussite globale reste possible grâce au français
+        assert!(simulate(&conn, 2, Target::Pass).unwrap().reachable);
+    }
+
+    #[test]
+    fn aucun_echec_avec_un_cours_faible() {
+        let db = class_db();
+        let simulation = simulate(&db.conn(), 3, Target::NoFailures).unwrap();
+        assert!(simulation.reachable && !simulation.already_reached);
+        assert_eq!(simulation.verdict, Verdict::Admis);
+        // Chaque cours remonte juste à 40 / 80 : 35 points en maths, 22 en français
+        let maths = subject(&simulation, "MATH");
+        let french = subject(&simulation, "FR");
+        assert_eq!(
+            (maths.needed_percentage, french.needed_percentage),
+            (58.33, 36.67)
+        );
+        assert!(maths.needed_points >= 35.0 && maths.needed_points < 35.1);
+        assert!(french.needed_points >= 22.0 && french.needed_points < 22.1);
+        assert_eq!(maths.periods.len(), 4);
+        assert_eq!(simulation.periods.len(), 4);
+        assert_eq!(simulation.periods[0].period, "EXAM1");
+    }
+
+    #[test]
+    fn place_au_classement_ex_aequo() {
+        assert_eq!(projected_rank(60.0, &[60.0, 50.0]), 1);
+        assert_eq!(projected_rank(59.99, &[60.0, 50.0]), 2);
+
+        let db = class_db();
+        let conn = db.conn();
+        assert!(simulate(&conn, 4, Target::Rank { place: 0 }).is_err());
+        // L'autre élève est projeté à 60 % : l'égaler suffit pour la première place
+        let simulation = simulate(&conn, 4, Target::Rank { place: 1 }).unwrap();
+        assert!(simulation.reachable && !simulation.already_reached);
+        assert_eq!(simulation.projected_rank, 1);
+        assert_eq!(simulation.projected_percentage, Some(60.0));
+        assert_eq!(subject(&simulation, "MATH").needed_percentage, 70.0);
+
+        let second = simulate(&conn, 4, Target::Rank { place: 2 }).unwrap();
+        assert!(second.already_reached);
+        assert_eq!(second.projected_rank, 2);
+    }
+}
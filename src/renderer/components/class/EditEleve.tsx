@@ -1,5 +1,6 @@
 import React, { useState, useEffect } from 'react';
 import { studentService, Student } from '../../services/studentService';
+import { conductService, ConductLevel, DEFAULT_CONDUCT_LEVELS } from '../../services/conductService';
 import { useToast } from '../../context/ToastContext';
 import { User, Calendar, MapPin, Award, ShieldAlert, X, Save, GraduationCap, Info, AlertTriangle, CheckCircle2 } from '../iconsSvg';
 
@@ -51,6 +52,14 @@ export default function EditEleve({ studentId, initialData, onClose}: EditEleveP
         };
     });
 
+    // Niveaux de l'échelle de conduite configurée
+    const [conductLevels, setConductLevels] = useState<ConductLevel[]>(DEFAULT_CONDUCT_LEVELS);
+    useEffect(() => {
+        conductService.getScale()
+            .then(scale => { if (scale) setConductLevels(scale.levels); })
+            .catch(err => console.error('Échelle de conduite indisponible :', err));
+    }, []);
+
     // Si on a initialData, on n'est pas en loading
     const [loading, setLoading] = useState(!initialData);
     const [error, setError] = useState<string | null>(null);
@@ -307,11 +316,13 @@ export default function EditEleve({ studentId, initialData, onClose}: EditEleveP
                                         className="w-full px-3 py-3 bg-white dark:bg-slate-800 border border-slate-100 dark:border-white/5 rounded-xl text-xs font-bold text-slate-700 dark:text-slate-200 focus:ring-2 focus:ring-blue-500/20 outline-none transition-all shadow-sm"
                                     >
                                         <option value="">--</option>
-                                        <option value="elute">Élute</option>
-                                        <option value="tres bon">T. Bon</option>
-                                        <option value="bon">Bon</option>
-                                        <option value="mediocre">Médiocre</option>
-                                        <option value="mauvais">Mauvais</option>
+                                        {conductLevels.map(level => (
+                                            <option key={level.code} value={level.code}>{level.label}</option>
+                                        ))}
+                                        {/* Ancienne saisie hors échelle : conservée tant qu'elle n'est pas corrigée */}
+                                        {(formData as any)[key] && !conductLevels.some(l => l.code === (formData as any)[key]) && (
+                                            <option value={(formData as any)[key]}>{(formData as any)[key]}</option>
+                                        )}
                                     </select>
                                 </div>
                             ))}
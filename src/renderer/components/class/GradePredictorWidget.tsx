@@ -1,9 +1,9 @@
 import React, { useState } from 'react';
 import { GradePredictorButton } from './GradePredictorButton';
 import { GradePredictorModal } from './GradePredictorModal';
+import { RotateCcw } from '../iconsSvg';
 import { useGradePredictor } from '../../hooks/useGradePredictor';
-import { PredictionResult } from '../../services/predictionService';
-import { gradeService } from '../../services/gradeService';
+import { ApplyPredictionsReport, PredictionResult, predictionService } from '../../services/predictionService';
 
 interface GradePredictorWidgetProps {
   classId: number;
@@ -23,6 +23,9 @@ export const GradePredictorWidget: React.FC<GradePredictorWidgetProps> = ({
   onError
 }) => {
   const [isModalOpen, setIsModalOpen] = useState(false);
+  // Dernier import appliqué, gardé pour pouvoir l'annuler d'un bloc
+  const [lastApplied, setLastApplied] = useState<ApplyPredictionsReport | null>(null);
+  const [undoing, setUndoing] = useState(false);
   const { loading, error, results, predict, reset } = useGradePredictor();
 
   /**
@@ -45,10 +48,9 @@ export const GradePredictorWidget: React.FC<GradePredictorWidgetProps> = ({
 
   const handleApply = async (applicableResults: PredictionResult[]) => {
     try {
-      // Importer les notes prédites dans la base de données
-      for (const pred of applicableResults) {
-        await gradeService.updateGrade(pred.studentId, pred.subjectId, pred.period, pred.predictedGrade);
-      }
+      // Importer les notes prédites comme cotes estimées, en un seul lot annulable
+      const report = await predictionService.applyPredictions(applicableResults);
+      setLastApplied(report?.checkpointId != null ? report : null);
       onSuccess?.();
       setIsModalOpen(false);
       reset();
@@ -59,6 +61,21 @@ export const GradePredictorWidget: React.FC<GradePredictorWidgetProps> = ({
     }
   };
 
+  const handleUndo = async () => {
+    if (lastApplied?.checkpointId == null) return;
+    setUndoing(true);
+    try {
+      await predictionService.undoApplied(lastApplied.checkpointId);
+      setLastApplied(null);
+      onSuccess?.();
+    } catch (err) {
+      const message = err instanceof Error ? err.message : "Erreur lors de l'annulation";
+      onError?.(message);
+    } finally {
+      setUndoing(false);
+    }
+  };
+
   const handleClose = () => {
     setIsModalOpen(false);
     // Réinitialiser les résultats uniquement si on ferme sans appliquer
@@ -71,6 +88,17 @@ export const GradePredictorWidget: React.FC<GradePredictorWidgetProps> = ({
         onClick={() => setIsModalOpen(true)}
         disabled={loading}
       />
+      {lastApplied && (
+        <button
+          onClick={handleUndo}
+          disabled={undoing}
+          className="flex items-center gap-1.5 px-2 py-1.5 rounded text-[11px] font-medium text-amber-700 hover:bg-amber-50 dark:text-amber-400 dark:hover:bg-slate-800 transition-colors disabled:opacity-50 disabled:pointer-events-none"
+          title={`Retirer les ${lastApplied.applied} cote(s) estimée(s) importée(s)${lastApplied.skipped ? ` (${lastApplied.skipped} ignorée(s) car déjà encodée(s))` : ''}`}
+        >
+          <RotateCcw size={14} />
+          <span>Annuler l'import</span>
+        </button>
+      )}
       <GradePredictorModal
         isOpen={isModalOpen}
         isLoading={loading}
@@ -89,9 +89,11 @@ export default function BulletinHumanitesContent({
 
   const abregeConduite = (conduite?: string | null) => {
     switch(conduite?.toUpperCase()){ 
+      case 'ELITE':
       case 'ELUTE': return 'E';
       case 'TRES BON': return 'TB';
       case 'BON': return 'B';
+      case 'ASSEZ BON': return 'AB';
       case 'MAUVAIS': return 'Ma';
       case 'MEDIOCRE': return 'Me';
       case 'INSUFFISANT': return 'I';
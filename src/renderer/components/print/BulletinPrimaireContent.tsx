@@ -39,6 +39,7 @@ const abregeConduite = (conduite?: string | null) => {
     case 'ÉLUTE': return 'E';
     case 'TRES BON': return 'TB';
     case 'BON': return 'B';
+    case 'ASSEZ BON': return 'AB';
     case 'MAUVAIS': return 'Ma';
     case 'MEDIOCRE': return 'Me';
     default: return conduite.charAt(0);
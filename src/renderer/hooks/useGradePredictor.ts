@@ -1,34 +1,37 @@
 import { useState } from 'react';
-import { predictionService, PredictionResult, PredictionParams } from '../services/predictionService';
+import { predictionService, PredictionResult, PredictionParams, PredictionBacktest } from '../services/predictionService';
 
 interface UsePredictorState {
   loading: boolean;
   error: string | null;
   results: PredictionResult[];
+  backtest: PredictionBacktest | null;
 }
 
 export const useGradePredictor = () => {
   const [state, setState] = useState<UsePredictorState>({
     loading: false,
     error: null,
-    results: []
+    results: [],
+    backtest: null
   });
 
   const predict = async (params: PredictionParams) => {
-    setState({ loading: true, error: null, results: [] });
+    setState({ loading: true, error: null, results: [], backtest: null });
     try {
-      const results = await predictionService.predictMissingGrades(params);
-      setState({ loading: false, error: null, results });
+      const report = await predictionService.predictMissingGrades(params);
+      const results = report?.predictions ?? [];
+      setState({ loading: false, error: null, results, backtest: report?.backtest ?? null });
       return results;
     } catch (err) {
       const error = err instanceof Error ? err.message : 'Erreur lors de la prédiction';
-      setState({ loading: false, error, results: [] });
+      setState({ loading: false, error, results: [], backtest: null });
       throw err;
     }
   };
 
   const reset = () => {
-    setState({ loading: false, error: null, results: [] });
+    setState({ loading: false, error: null, results: [], backtest: null });
   };
 
   return { ...state, predict, reset };
@@ -2,6 +2,13 @@ import { gradeService, Grade } from './gradeService';
 import { studentService } from './studentService';
 import { Subject } from './classService';
 import { getMathValue } from '../components/class/classDetails/gradeUtils';
+import { getTauriAPI } from './tauriBridge';
+
+export interface PdfExport {
+  path: string;
+  pages: number;
+  students: number;
+}
 
 export interface StudentRanks {
   p1: number;
@@ -337,6 +344,20 @@ class BulletinService {
   getConduite(percentage: number): string {
     return this.getApplication(percentage);
   }
+
+  /**
+   * Génère les bulletins officiels en PDF côté Rust et les écrit dans `path`.
+   * Sans `studentId`, toute la classe est imprimée dans un seul fichier.
+   */
+  async exportPdf(
+    classId: number,
+    path: string,
+    studentId?: number,
+    mode: 'FIRST_SESSION' | 'FINAL' = 'FIRST_SESSION'
+  ): Promise<PdfExport | null> {
+    const api = await getTauriAPI();
+    return await api?.invoke('bulletins_pdf', { classId, studentId: studentId ?? null, mode, path }) ?? null;
+  }
 }
 
 export const bulletinService = new BulletinService();
@@ -23,6 +23,7 @@ export interface Subject {
   domain_id?: number;
   category?: string;
   display_order?: number;  // Ordre d'affichage dans le bulletin et la grille
+  coefficient?: number | null;  // Pondération des totaux (paramètre grade_weighting_mode)
 }
 
 /**
@@ -0,0 +1,86 @@
+import { getTauriAPI } from './tauriBridge';
+
+export interface ConductLevel {
+  // Valeur enregistrée dans les colonnes conduite_* de students
+  code: string;
+  label: string;
+  abbreviation: string;
+  aliases?: string[];
+  blocksPromotion?: boolean;
+}
+
+// Niveaux du meilleur au moins bon
+export interface ConductScale {
+  name: string;
+  levels: ConductLevel[];
+}
+
+export interface PeriodConduct {
+  period: string;
+  raw: string;
+  code: string | null;
+  label: string | null;
+}
+
+export interface StudentConduct {
+  studentId: number;
+  studentName: string;
+  periods: PeriodConduct[];
+  computed: string | null;
+  annual: string | null;
+  annualLabel: string | null;
+  overridden: boolean;
+  blocksPromotion: boolean;
+  invalid: string[];
+}
+
+export interface ClassConduct {
+  classId: number;
+  className: string;
+  scale: ConductScale;
+  students: StudentConduct[];
+}
+
+export interface ConductUpdate {
+  studentId: number;
+  // P1 à P4, ou ANNUEL pour la décision du conseil de classe
+  period: string;
+  value: string | null;
+}
+
+// Échelle par défaut, tant que le backend n'a pas répondu
+export const DEFAULT_CONDUCT_LEVELS: ConductLevel[] = [
+  { code: 'elite', label: 'Élite', abbreviation: 'E' },
+  { code: 'tres bon', label: 'Très bon', abbreviation: 'TB' },
+  { code: 'bon', label: 'Bon', abbreviation: 'B' },
+  { code: 'assez bon', label: 'Assez bon', abbreviation: 'AB' },
+  { code: 'mauvais', label: 'Mauvais', abbreviation: 'Ma' },
+];
+
+export const conductService = {
+  getScale: async (): Promise<ConductScale | null> => {
+    const api = await getTauriAPI();
+    return await api?.invoke('conduct_scale_get') ?? null;
+  },
+
+  getPresets: async (): Promise<ConductScale[]> => {
+    const api = await getTauriAPI();
+    return await api?.invoke('conduct_scale_presets') ?? [];
+  },
+
+  setScale: async (scale: ConductScale): Promise<ConductScale | null> => {
+    const api = await getTauriAPI();
+    return await api?.invoke('conduct_scale_set', { scale }) ?? null;
+  },
+
+  getClassConduct: async (classId: number): Promise<ClassConduct | null> => {
+    const api = await getTauriAPI();
+    return await api?.invoke('class_conduct_report', { classId }) ?? null;
+  },
+
+  // Refusé en bloc si une valeur est absente de l'échelle
+  setConduct: async (updates: ConductUpdate[]): Promise<ConductUpdate[]> => {
+    const api = await getTauriAPI();
+    return await api?.invoke('student_conduct_set', { updates }) ?? [];
+  },
+};
@@ -12,6 +12,27 @@ export interface DatabaseService {
 
 class DatabaseServiceImpl implements DatabaseService {
   private tauriDb: any = null;
+  // Base chiffrée : le plugin sql ne peut pas fournir la clé, les requêtes passent par le backend
+  private useBackend: boolean | null = null;
+
+  private async isEncrypted(): Promise<boolean> {
+    if (this.useBackend !== null) return this.useBackend;
+    const api = await getTauriAPI();
+    const status = await api.invoke<{ enabled: boolean }>('db_encryption_status');
+    this.useBackend = status.enabled;
+    return this.useBackend;
+  }
+
+  /**
+   * Ferme la connexion du plugin sql (obligatoire avant db_encrypt, qui remplace le fichier).
+   */
+  async reset(): Promise<void> {
+    if (this.tauriDb) {
+      await this.tauriDb.close();
+      this.tauriDb = null;
+    }
+    this.useBackend = null;
+  }
 
   private async getTauriDb() {
     if (this.tauriDb) return this.tauriDb;
@@ -41,6 +62,10 @@ class DatabaseServiceImpl implements DatabaseService {
 
   async query<T>(sql: string, params: any[] = []): Promise<T[]> {
     try {
+      if (await this.isEncrypted()) {
+        const api = await getTauriAPI();
+        return await api.invoke<T[]>('db_select', { query: sql, values: params });
+      }
       const db = await this.getTauriDb();
       return await db.select(sql, params);
     } catch (error) {
@@ -51,6 +76,10 @@ class DatabaseServiceImpl implements DatabaseService {
 
   async execute(sql: string, params: any[] = []): Promise<any> {
     try {
+      if (await this.isEncrypted()) {
+        const api = await getTauriAPI();
+        return await api.invoke('db_execute', { query: sql, values: params });
+      }
       const db = await this.getTauriDb();
       const result = await db.execute(sql, params);
       return result; // Tauri plugin-sql retourne un objet avec lastInsertId et rowsAffected
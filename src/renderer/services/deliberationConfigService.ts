@@ -49,9 +49,17 @@ export interface DeliberationConfig {
 
   // --- Règles structurelles ---
   maxEchecsRepechage: number;
+  // Branches principales (codes de cours) : 7ème et 8ème
+  branchesPrincipales: string[];
+  maxEchecsPrincipales: number;
+  // Branches spécifiques (catégories de cours) : humanités
+  categoriesSpecifiques: string[];
+  maxEchecsSpecifiques: number;
   coursCompletObligatoire: boolean;
   ordreTransfertPeriodes: string;
   manqueCotesDoubleEnFinal: boolean;
+  // Une conduite annuelle bloquante (Mauvais par défaut) refuse la promotion
+  conduiteBloquePromotion: boolean;
 }
 
 // Valeurs par défaut de référence
@@ -88,9 +96,14 @@ export const DEFAULT_DELIBERATION_CONFIG: DeliberationConfig = {
   categorie_5_label: 'V. Non classés',
 
   maxEchecsRepechage: 5,
+  branchesPrincipales: ['FR', 'MATH', 'ALG', 'ARITH', 'GEO', 'STAT'],
+  maxEchecsPrincipales: 1,
+  categoriesSpecifiques: ['Techniques'],
+  maxEchecsSpecifiques: 3,
   coursCompletObligatoire: true,
   ordreTransfertPeriodes: 'EXAM2,P4,EXAM1,P2,P1,P3',
   manqueCotesDoubleEnFinal: true,
+  conduiteBloquePromotion: false,
 };
 
 const KEY_PREFIX = 'delib_';
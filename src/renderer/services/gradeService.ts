@@ -6,6 +6,8 @@ export interface Grade {
   subject_id: number;
   period: string;
   value: number;
+  // 1 : cote estimée insérée depuis une prédiction
+  is_estimated?: number;
 }
 
 /**
@@ -59,9 +61,9 @@ class GradeService {
           [studentId, subjectId, period]
         );
       } else {
-        // Mettre à jour
+        // Mettre à jour (une saisie remplace une cote estimée ou calculée)
         await dbService.execute(
-          'UPDATE grades SET value = ?, is_dirty = 1, last_modified_at = (datetime(\'now\')) WHERE student_id = ? AND subject_id = ? AND period = ?',
+          'UPDATE grades SET value = ?, is_estimated = 0, is_computed = 0, is_dirty = 1, last_modified_at = (datetime(\'now\')) WHERE student_id = ? AND subject_id = ? AND period = ?',
           [value, studentId, subjectId, period]
         );
       }
@@ -20,12 +20,44 @@ export interface PredictionParams {
   confidenceThreshold: number;
 }
 
+// Validation croisée du modèle sur les cotes connues de la classe
+export interface PredictionBacktest {
+  samples: number;
+  meanAbsoluteError: number | null;
+  withinTolerance: number | null;
+  tolerance: number;
+}
+
+export interface PredictionReport {
+  classId: number;
+  predictions: PredictionResult[];
+  backtest: PredictionBacktest;
+}
+
+export interface ApplyPredictionsReport {
+  // Point de reprise à passer à history_undo_checkpoint pour tout annuler
+  checkpointId: number | null;
+  applied: number;
+  skipped: number;
+}
+
 export const predictionService = {
-  predictMissingGrades: async (params: PredictionParams): Promise<PredictionResult[]> => {
+  predictMissingGrades: async (params: PredictionParams): Promise<PredictionReport | null> => {
     const api = await getTauriAPI();
     // IMPORTANT : Tauri v2 attend un objet dont les clés correspondent aux noms des paramètres Rust.
     // La commande Rust est : predict_missing_grades(app_handle, params: PredictionParams)
     // Donc on doit passer { params } et non params directement.
-    return await api?.invoke('predict_missing_grades', { params }) ?? [];
+    return await api?.invoke('predict_missing_grades', { params }) ?? null;
+  },
+
+  // Insère les prédictions acceptées comme cotes estimées, en un seul lot annulable
+  applyPredictions: async (predictions: PredictionResult[]): Promise<ApplyPredictionsReport | null> => {
+    const api = await getTauriAPI();
+    return await api?.invoke('apply_predictions', { predictions }) ?? null;
+  },
+
+  undoApplied: async (checkpointId: number): Promise<void> => {
+    const api = await getTauriAPI();
+    await api?.invoke('history_undo_checkpoint', { checkpointId });
   }
 };
@@ -4,7 +4,7 @@ import ClassSelector from './components/ClassSelector';
 import SubjectSelector from './components/SubjectSelector';
 import GradingTable from './components/GradingTable';
 import { api } from './services/api';
-import { Class, Subject, Student, Grade, CustomSort } from './types';
+import { Class, Period, Subject, Student, Grade, CustomSort } from './types';
 
 export default function App() {
   const [classes, setClasses] = useState<Class[]>([]);
@@ -16,6 +16,7 @@ export default function App() {
 
   const clientId = React.useMemo(() => Math.random().toString(36).substring(7), []);
 
+  const [periods, setPeriods] = useState<Period[]>([]);
   const [students, setStudents] = useState<Student[]>([]);
   const [subjects, setSubjects] = useState<Subject[]>([]);
   const [grades, setGrades] = useState<Grade[]>([]);
@@ -26,6 +27,9 @@ export default function App() {
   const loadClassData = async (clsId: number) => {
     try {
       const data = await api.fetchClassData(clsId);
+      setPeriods(data.periods);
+      // Conserve la période active si elle existe dans l'année de la classe
+      setPeriod(prev => data.periods.some(p => p.code === prev) ? prev : (data.periods[0]?.code ?? prev));
       setStudents(data.students);
       setSubjects(data.subjects);
       setGrades(data.grades);
@@ -90,15 +94,7 @@ export default function App() {
   const getCurrentMax = (sub?: Subject | null) => {
     const s = sub || selectedSubject;
     if (!s) return 10;
-    switch(period) {
-      case 'P1': return s.max_p1;
-      case 'P2': return s.max_p2;
-      case 'EXAM1': return s.max_exam1;
-      case 'P3': return s.max_p3;
-      case 'P4': return s.max_p4;
-      case 'EXAM2': return s.max_exam2;
-      default: return 10;
-    }
+    return s.max_points[period] ?? 0;
   };
 
   const handleGradeChange = async (studentId: number, subjectId: number, value: number) => {
@@ -154,6 +150,7 @@ export default function App() {
             selectedClass={selectedClass}
             selectedSubject={selectedSubject}
             subjects={subjects}
+            periods={periods}
             period={period}
             setPeriod={setPeriod}
             students={students}
@@ -1,12 +1,13 @@
 import React, { useState, useEffect, useRef, useMemo } from 'react';
 import { ChevronLeft, CheckCircle2, Search } from './iconsSvg';
-import { Class, Subject, Student, Grade, CustomSort } from '../types';
+import { Class, Period, Subject, Student, Grade, CustomSort } from '../types';
 
 interface GradingTableProps {
   selectedClass: Class;
   selectedSubject: Subject;
   // Liste complète des matières pour les onglets multi-matières
   subjects: Subject[];
+  periods: Period[];
   period: string;
   setPeriod: (period: string) => void;
   students: Student[];
@@ -139,6 +140,7 @@ export default function GradingTable({
   selectedClass,
   selectedSubject,
   subjects,
+  periods,
   period,
   setPeriod,
   students,
@@ -265,7 +267,7 @@ export default function GradingTable({
 
         {/* Sélecteur de période */}
         <div className="flex bg-white p-1 rounded-xl mt-2 border border-slate-200 overflow-x-auto no-scrollbar">
-          {['P1', 'P2', 'EXAM1', 'P3', 'P4', 'EXAM2'].map(p => (
+          {periods.map(({ code: p, is_exam }) => (
             <button
               key={p}
               onClick={() => setPeriod(p)}
@@ -275,7 +277,7 @@ export default function GradingTable({
                   : 'text-slate-400 hover:text-slate-600 hover:bg-slate-50'
               }`}
             >
-              {p.replace('EXAM', 'Ex.')}
+              {is_exam ? p.replace('EXAM', 'Ex.') : p}
             </button>
           ))}
         </div>
@@ -1,10 +1,13 @@
-import { Class, Grade, Subject, Student, CustomSort } from '../types';
+import { Class, Evaluation, EvaluationScore, Grade, Period, Subject, Student, CustomSort } from '../types';
 
 // Structure complète des données d'une classe retournée par l'API
 interface FullClassData {
+  periods: Period[];
   students: Student[];
   subjects: Subject[];
   grades: Grade[];
+  evaluations: Evaluation[];
+  evaluation_scores: EvaluationScore[];
   custom_sorts: CustomSort[];
 }
 
@@ -33,6 +36,19 @@ export const api = {
     if (!res.ok) throw new Error('Failed to save grades');
   },
 
+  // Les cotes de période recalculées sont renvoyées par SSE comme des mises à jour de notes
+  saveEvaluationScores: async (scores: EvaluationScore[], clientId: string): Promise<void> => {
+    const res = await fetch('/api/grades/batch', {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({
+        evaluation_scores: scores,
+        senderId: clientId
+      })
+    });
+    if (!res.ok) throw new Error('Failed to save evaluation scores');
+  },
+
   getEventSource: (): EventSource => {
     return new EventSource('/api/events');
   }
@@ -13,16 +13,20 @@ export interface Student {
   post_name: string;
 }
 
+// Période d'évaluation définie pour l'année de la classe
+export interface Period {
+  code: string;
+  label: string;
+  semester: number;
+  is_exam: boolean;
+}
+
 export interface Subject {
   id: number;
   name: string;
   code: string;
-  max_p1: number;
-  max_p2: number;
-  max_exam1: number;
-  max_p3: number;
-  max_p4: number;
-  max_exam2: number;
+  // Maximum par code de période
+  max_points: Record<string, number>;
 }
 
 export interface Grade {
@@ -32,6 +36,22 @@ export interface Grade {
   value: number;
 }
 
+// Interrogation notée à l'intérieur d'une période
+export interface Evaluation {
+  id: number;
+  subject_id: number;
+  period_code: string;
+  name: string;
+  max_points: number;
+}
+
+export interface EvaluationScore {
+  evaluation_id: number;
+  student_id: number;
+  // null efface les points de l'élève
+  value: number | null;
+}
+
 export interface CustomSort {
   id: number;
   name: string;